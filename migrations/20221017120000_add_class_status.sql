@@ -0,0 +1,40 @@
+CREATE TYPE class_status AS ENUM ('real_time', 'closed', 'finished', 'adjusted', 'transcoded');
+
+ALTER TABLE class ADD COLUMN status class_status NOT NULL DEFAULT 'real_time';
+
+UPDATE class
+SET status = (
+    CASE
+        WHEN r.transcoded_at IS NOT NULL THEN 'transcoded'
+        WHEN r.adjusted_at IS NOT NULL THEN 'adjusted'
+        WHEN r.id IS NOT NULL THEN 'finished'
+        WHEN UPPER(class.time) < NOW() THEN 'closed'
+        ELSE 'real_time'
+    END
+)::class_status
+FROM class AS c
+LEFT JOIN LATERAL (
+    SELECT id, adjusted_at, transcoded_at
+    FROM recording
+    WHERE recording.class_id = c.id
+    AND deleted_at IS NULL
+    ORDER BY transcoded_at DESC NULLS LAST, adjusted_at DESC NULLS LAST
+    LIMIT 1
+) AS r ON TRUE
+WHERE class.id = c.id;
+
+CREATE INDEX IF NOT EXISTS class_status_idx ON class (status);
+
+CREATE TABLE IF NOT EXISTS class_status_transition (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    class_id uuid NOT NULL,
+    from_status class_status NOT NULL,
+    to_status class_status NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+
+    FOREIGN KEY (class_id) REFERENCES class (id) ON DELETE CASCADE,
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX IF NOT EXISTS class_status_transition_class_id_idx
+    ON class_status_transition (class_id, created_at);
@@ -0,0 +1,25 @@
+CREATE TYPE tq_priority AS ENUM ('low', 'normal', 'high');
+CREATE TYPE postprocessing_job_status AS ENUM ('pending', 'succeeded', 'failed');
+
+CREATE TABLE IF NOT EXISTS postprocessing_job (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    class_id uuid NOT NULL,
+    template TEXT NOT NULL,
+    stream_id uuid,
+    priority tq_priority NOT NULL,
+    status postprocessing_job_status NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 1,
+    last_error JSONB,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    completed_at TIMESTAMPTZ,
+
+    FOREIGN KEY (class_id) REFERENCES class (id) ON DELETE CASCADE,
+    PRIMARY KEY (id)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS postprocessing_job_task_idx ON postprocessing_job (
+    class_id,
+    template,
+    COALESCE(stream_id, '00000000-0000-0000-0000-000000000000'::uuid)
+);
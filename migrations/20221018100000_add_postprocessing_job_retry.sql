@@ -0,0 +1,6 @@
+ALTER TABLE postprocessing_job ADD COLUMN task JSONB;
+ALTER TABLE postprocessing_job ADD COLUMN retry_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS postprocessing_job_retry_at_idx
+    ON postprocessing_job (retry_at)
+    WHERE retry_at IS NOT NULL;
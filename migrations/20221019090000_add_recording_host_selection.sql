@@ -0,0 +1,8 @@
+CREATE TYPE recording_host_selection AS ENUM (
+    'host_event',
+    'class_host',
+    'longest_recording',
+    'first_recording'
+);
+
+ALTER TABLE recording ADD COLUMN host_selection recording_host_selection;
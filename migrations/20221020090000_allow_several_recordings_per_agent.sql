@@ -0,0 +1,4 @@
+-- An agent may drop and rejoin getting a new rtc so there may be several recordings of them.
+DROP INDEX IF EXISTS uniq_recording_per_class;
+
+CREATE UNIQUE INDEX uniq_recording_per_class ON recording (class_id, rtc_id) WHERE deleted_at IS NULL;
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS outbox (
+    id BIGSERIAL NOT NULL,
+    label TEXT NOT NULL,
+    path TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    sent_at TIMESTAMPTZ,
+
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX IF NOT EXISTS outbox_unsent_idx
+    ON outbox (created_at)
+    WHERE sent_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS outbox_sent_at_idx
+    ON outbox (sent_at)
+    WHERE sent_at IS NOT NULL;
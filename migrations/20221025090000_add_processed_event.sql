@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS processed_event (
+    key TEXT NOT NULL,
+    label TEXT NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+
+    PRIMARY KEY (key)
+);
+
+CREATE INDEX IF NOT EXISTS processed_event_expires_at_idx ON processed_event (expires_at);
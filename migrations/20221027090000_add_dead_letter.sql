@@ -0,0 +1,21 @@
+-- Incoming events whose handling failed. `key` is the same as in processed_event
+-- so that a redelivery or a replay of the event updates the same row.
+CREATE TABLE IF NOT EXISTS dead_letter (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    key TEXT NOT NULL,
+    label TEXT NOT NULL,
+    topic TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    properties JSONB NOT NULL,
+    error TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    replayed_at TIMESTAMPTZ,
+    resolved_at TIMESTAMPTZ,
+
+    UNIQUE (key),
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX IF NOT EXISTS dead_letter_updated_at_idx ON dead_letter (updated_at);
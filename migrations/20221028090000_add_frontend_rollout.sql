@@ -0,0 +1,12 @@
+-- Sends `percent` of the scopes of the tenant app without their own binding to the frontend.
+CREATE TABLE IF NOT EXISTS frontend_rollout (
+    id BIGSERIAL PRIMARY KEY,
+    tenant TEXT NOT NULL,
+    app TEXT NOT NULL,
+    frontend_id BIGINT NOT NULL,
+    percent SMALLINT NOT NULL CHECK (percent BETWEEN 0 AND 100),
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+
+    UNIQUE (tenant, app),
+    FOREIGN KEY (frontend_id) REFERENCES frontend(id) ON DELETE CASCADE
+);
@@ -0,0 +1,17 @@
+-- Mutating API requests, who made them and how they ended.
+CREATE TABLE IF NOT EXISTS audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    account_id account_id,
+    method TEXT NOT NULL,
+    route TEXT NOT NULL,
+    path TEXT NOT NULL,
+    class_id UUID,
+    scope TEXT,
+    payload JSONB,
+    status SMALLINT NOT NULL,
+    error TEXT,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_class_id_idx ON audit_log (class_id, id) WHERE class_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS audit_log_account_id_idx ON audit_log (account_id, id);
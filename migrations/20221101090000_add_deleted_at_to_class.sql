@@ -0,0 +1,6 @@
+-- A deleted class is kept along with its recordings, postprocessing jobs and status history.
+ALTER TABLE class ADD COLUMN deleted_at TIMESTAMPTZ;
+
+-- The scope of a deleted class may be taken by a new one.
+DROP INDEX IF EXISTS uniq_audience_scope;
+CREATE UNIQUE INDEX uniq_audience_scope ON class (audience, scope) WHERE deleted_at IS NULL;
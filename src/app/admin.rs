@@ -0,0 +1,322 @@
+//! One-off operations for the `dispatcher-admin` binary. These run next to the service against
+//! the same database, broker and config and print their results as JSON.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use serde_json::{json, Value as JsonValue};
+use sqlx::Acquire;
+use uuid::Uuid;
+
+use crate::app::webhooks::{self, WebhookEvent};
+use crate::app::AppContext;
+use crate::clients::tq::Priority;
+use crate::db::class::{ClassStatus, ClassType, Object as Class};
+
+/// Operational tasks on dispatcher classes, rooms and scopes.
+#[derive(Debug, Parser)]
+#[command(name = "dispatcher-admin", version)]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Class operations
+    Class {
+        #[command(subcommand)]
+        command: ClassCommand,
+    },
+    /// Event room operations
+    EventRoom {
+        #[command(subcommand)]
+        command: EventRoomCommand,
+    },
+    /// Lists scopes
+    Scopes,
+    /// Lists frontends
+    Frontends,
+}
+
+#[derive(Debug, Subcommand)]
+enum ClassCommand {
+    /// Prints the class along with its recordings
+    Show { id: Uuid },
+    /// Sends the class recordings to transcoding again
+    RestartTranscoding {
+        id: Uuid,
+        #[arg(long, default_value = "normal", value_parser = parse_priority)]
+        priority: Priority,
+    },
+    /// Closes the class right away as if its conference room was closed
+    Close { id: Uuid },
+}
+
+#[derive(Debug, Subcommand)]
+enum EventRoomCommand {
+    /// Asks event to dump the room events again, the class picks the dump up on `room.dump_events`
+    Dump { id: Uuid },
+}
+
+pub async fn run(cli: Cli) -> Result<()> {
+    let db = crate::create_db().await;
+
+    // Concurrent runs would kick each other off the broker under the same label.
+    let label = format!("admin-{}", Uuid::new_v4().simple());
+    let ctx = super::standalone_context(db, &label)?;
+
+    if let Some(output) = execute(ctx, cli.command).await? {
+        let output = serde_json::to_string_pretty(&output).context("Failed to serialize output")?;
+        println!("{}", output);
+    }
+
+    Ok(())
+}
+
+async fn execute(ctx: Arc<dyn AppContext>, command: Command) -> Result<Option<JsonValue>> {
+    match command {
+        Command::Class {
+            command: ClassCommand::Show { id },
+        } => show_class(ctx.as_ref(), id).await.map(Some),
+        Command::Class {
+            command: ClassCommand::RestartTranscoding { id, priority },
+        } => restart_transcoding(ctx, id, priority).await.map(|_| None),
+        Command::Class {
+            command: ClassCommand::Close { id },
+        } => close_class(ctx.as_ref(), id).await.map(Some),
+        Command::EventRoom {
+            command: EventRoomCommand::Dump { id },
+        } => ctx
+            .event_client()
+            .dump_room(id)
+            .await
+            .context("Dump room request failed")
+            .map(|_| None),
+        Command::Scopes => {
+            let mut conn = ctx.get_conn().await?;
+            let scopes = crate::db::scope::ListQuery::new()
+                .execute(&mut conn)
+                .await
+                .context("Failed to list scopes")?;
+
+            Ok(Some(json!(scopes)))
+        }
+        Command::Frontends => {
+            let mut conn = ctx.get_conn().await?;
+            let frontends = crate::db::frontend::ListQuery::new()
+                .execute(&mut conn)
+                .await
+                .context("Failed to list frontends")?;
+
+            Ok(Some(json!(frontends)))
+        }
+    }
+}
+
+async fn read_class(ctx: &dyn AppContext, id: Uuid) -> Result<Class> {
+    let mut conn = ctx.get_conn().await?;
+
+    crate::db::class::ReadQuery::by_id(id)
+        .execute(&mut conn)
+        .await
+        .context("Failed to read class")?
+        .ok_or_else(|| anyhow!("Class not found, id = {}", id))
+}
+
+async fn show_class(ctx: &dyn AppContext, id: Uuid) -> Result<JsonValue> {
+    let class = read_class(ctx, id).await?;
+
+    let recordings = {
+        let mut conn = ctx.get_conn().await?;
+        crate::db::recording::RecordingListQuery::new(id)
+            .execute(&mut conn)
+            .await
+            .context("Failed to list recordings")?
+    };
+
+    let kind = match class.kind() {
+        ClassType::Webinar => "webinar",
+        ClassType::Minigroup => "minigroup",
+        ClassType::P2P => "p2p",
+    };
+
+    Ok(json!({
+        "kind": kind,
+        "class": class,
+        "recordings": recordings,
+    }))
+}
+
+async fn restart_transcoding(ctx: Arc<dyn AppContext>, id: Uuid, priority: Priority) -> Result<()> {
+    let class = read_class(ctx.as_ref(), id).await?;
+
+    match class.kind() {
+        ClassType::Webinar => {
+            crate::app::postprocessing_strategy::restart_webinar_transcoding(ctx, class, priority)
+                .await
+        }
+        ClassType::Minigroup => {
+            crate::app::postprocessing_strategy::restart_minigroup_transcoding(ctx, class, priority)
+                .await
+        }
+        ClassType::P2P => bail!("P2P classes aren't transcoded"),
+    }
+}
+
+// The stop event is left in the outbox for the service relay to publish,
+// this process may exit before the broker gets it.
+async fn close_class(ctx: &dyn AppContext, id: Uuid) -> Result<JsonValue> {
+    let class = read_class(ctx, id).await?;
+
+    if class.status() >= ClassStatus::Closed {
+        bail!("Class is already closed, id = {}", id);
+    }
+
+    let class = {
+        let mut conn = ctx.get_conn().await?;
+        let mut txn = conn
+            .begin()
+            .await
+            .context("Failed to begin sqlx db transaction")?;
+
+        let class = crate::app::services::close_class(&mut txn, &class, false).await?;
+        crate::app::services::push_class_stop(&mut txn, &class).await?;
+
+        txn.commit()
+            .await
+            .context("Failed to commit sqlx db transaction")?;
+
+        class
+    };
+
+    webhooks::enqueue(ctx, &class, WebhookEvent::Stopped).await;
+    Ok(json!(class))
+}
+
+fn parse_priority(value: &str) -> Result<Priority> {
+    serde_json::from_value(json!(value)).context("Expected low, normal or high")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use svc_agent::{AccountId, AgentId};
+
+    use super::*;
+    use crate::test_helpers::prelude::*;
+
+    async fn insert_webinar(state: &TestState) -> Class {
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+        let now = Utc::now();
+
+        factory::Webinar::new(
+            random_string(),
+            USR_AUDIENCE.to_string(),
+            (
+                Bound::Included(now - Duration::hours(1)),
+                Bound::Excluded(now + Duration::hours(1)),
+            )
+                .into(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        )
+        .insert(&mut conn)
+        .await
+    }
+
+    #[test]
+    fn parses_commands() {
+        let id = Uuid::new_v4().to_string();
+
+        let cli = Cli::try_parse_from([
+            "dispatcher-admin",
+            "class",
+            "restart-transcoding",
+            &id,
+            "--priority",
+            "high",
+        ])
+        .expect("Failed to parse restart transcoding");
+
+        assert!(matches!(
+            cli.command,
+            Command::Class {
+                command: ClassCommand::RestartTranscoding {
+                    priority: Priority::High,
+                    ..
+                }
+            }
+        ));
+
+        Cli::try_parse_from(["dispatcher-admin", "class", "show", "not-a-uuid"])
+            .expect_err("Parsed invalid class id");
+    }
+
+    #[tokio::test]
+    async fn shows_class_with_recordings() {
+        let state = TestState::new(TestAuthz::new()).await;
+        let webinar = insert_webinar(&state).await;
+
+        {
+            let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+            let agent_id = AgentId::new("web", AccountId::new("user1", USR_AUDIENCE));
+
+            factory::Recording::new(webinar.id(), Uuid::new_v4(), agent_id)
+                .insert(&mut conn)
+                .await;
+        }
+
+        let output = show_class(&state, webinar.id())
+            .await
+            .expect("Failed to show class");
+
+        assert_eq!(output["kind"], "webinar");
+        assert_eq!(output["class"]["id"], webinar.id().to_string());
+        assert_eq!(output["recordings"].as_array().map(Vec::len), Some(1));
+    }
+
+    #[tokio::test]
+    async fn closes_class_once() {
+        let state = TestState::new(TestAuthz::new()).await;
+        let webinar = insert_webinar(&state).await;
+
+        close_class(&state, webinar.id())
+            .await
+            .expect("Failed to close class");
+
+        let class = read_class(&state, webinar.id())
+            .await
+            .expect("Failed to read class");
+        assert_eq!(class.status(), ClassStatus::Closed);
+        assert!(!class.timed_out());
+
+        close_class(&state, webinar.id())
+            .await
+            .expect_err("Closed class twice");
+    }
+
+    #[tokio::test]
+    async fn dumps_event_room() {
+        let mut state = TestState::new(TestAuthz::new()).await;
+        let event_room_id = Uuid::new_v4();
+
+        state
+            .event_client_mock()
+            .expect_dump_room()
+            .withf(move |id| *id == event_room_id)
+            .returning(|_| Ok(()));
+
+        let output = execute(
+            Arc::new(state),
+            Command::EventRoom {
+                command: EventRoomCommand::Dump { id: event_room_id },
+            },
+        )
+        .await
+        .expect("Failed to dump room");
+
+        assert!(output.is_none());
+    }
+}
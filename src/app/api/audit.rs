@@ -0,0 +1,461 @@
+//! Audit log of the mutating API requests. [`middleware`] wraps every route and records the
+//! requests listed in [`AUDITED`] once they are handled: who made the request, the class or
+//! the scope it targets, its payload with secrets redacted and how it ended.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::body::{boxed, Full};
+use axum::extract::{Extension, MatchedPath, Path};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use hyper::body::{Bytes, HttpBody};
+use hyper::{Body, Request};
+use serde_json::Value as JsonValue;
+use svc_utils::extractors::AccountIdExtractor;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::app::error::{Error as AppError, ErrorKind as AppErrorKind};
+use crate::app::AppContext;
+use crate::db::audit_log::InsertQuery;
+
+const REDACTED: &str = "[redacted]";
+const MAX_TEXT_LEN: usize = 1000;
+// Bodies are buffered up to the size axum's extractors accept by default.
+const MAX_BODY_LEN: usize = 2 * 1024 * 1024;
+
+// Payload fields whose name contains any of these are redacted.
+const SECRETS: &[&str] = &["password", "secret", "token", "authorization", "api_key"];
+
+#[derive(Debug, Clone, Copy)]
+enum Target {
+    /// The class from the `id` path parameter
+    Class,
+    /// The class created by the request, its id comes in the response
+    CreatedClass,
+    /// The `scope` path parameter
+    Scope,
+    None,
+}
+
+/// Requests to record by method and route.
+const AUDITED: &[(&str, &str, Target)] = &[
+    ("POST", "/api/v1/scopes/:scope/rollback", Target::Scope),
+    ("PUT", "/api/v1/scopes/:scope/apps/:app", Target::Scope),
+    ("DELETE", "/api/v1/scopes/:scope/apps/:app", Target::Scope),
+    ("POST", "/api/v1/frontends", Target::None),
+    ("PUT", "/api/v1/frontends/:frontend_id", Target::None),
+    ("DELETE", "/api/v1/frontends/:frontend_id", Target::None),
+    (
+        "PUT",
+        "/api/v1/rollouts/tenants/:tenant/apps/:app",
+        Target::None,
+    ),
+    (
+        "DELETE",
+        "/api/v1/rollouts/tenants/:tenant/apps/:app",
+        Target::None,
+    ),
+    ("POST", "/api/v1/webinars", Target::CreatedClass),
+    ("PUT", "/api/v1/webinars/:id", Target::Class),
+    ("DELETE", "/api/v1/webinars/:id", Target::Class),
+    (
+        "PUT",
+        "/api/v1/webinars/:id/properties/:property_id",
+        Target::Class,
+    ),
+    (
+        "POST",
+        "/api/v1/webinars/:id/replicas",
+        Target::CreatedClass,
+    ),
+    ("POST", "/api/v1/webinars/convert", Target::CreatedClass),
+    ("POST", "/api/v1/webinars/:id/recreate", Target::Class),
+    ("POST", "/api/v1/p2p", Target::CreatedClass),
+    ("DELETE", "/api/v1/p2p/:id", Target::Class),
+    (
+        "PUT",
+        "/api/v1/p2p/:id/properties/:property_id",
+        Target::Class,
+    ),
+    ("POST", "/api/v1/p2p/convert", Target::CreatedClass),
+    ("POST", "/api/v1/minigroups", Target::CreatedClass),
+    ("PUT", "/api/v1/minigroups/:id", Target::Class),
+    ("DELETE", "/api/v1/minigroups/:id", Target::Class),
+    (
+        "PUT",
+        "/api/v1/audiences/:audience/minigroups/:scope",
+        Target::Scope,
+    ),
+    (
+        "PUT",
+        "/api/v1/minigroups/:id/properties/:property_id",
+        Target::Class,
+    ),
+    ("POST", "/api/v1/minigroups/:id/recreate", Target::Class),
+    ("POST", "/api/v1/minigroups/:id/whiteboard", Target::Class),
+    (
+        "POST",
+        "/api/v1/audiences/:audience/classes/:scope/editions/:id",
+        Target::Scope,
+    ),
+    (
+        "PUT",
+        "/api/v1/account/properties/:property_id",
+        Target::None,
+    ),
+    (
+        "POST",
+        "/api/v1/transcoding/minigroup/:id/restart",
+        Target::Class,
+    ),
+    (
+        "POST",
+        "/api/v1/transcoding/webinar/:id/restart",
+        Target::Class,
+    ),
+    ("POST", "/api/v1/dead_letters/:id/replay", Target::None),
+];
+
+pub async fn middleware(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    route: Option<MatchedPath>,
+    account: Option<AccountIdExtractor>,
+    params: Option<Path<HashMap<String, String>>>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let audited = route.and_then(|route| {
+        AUDITED
+            .iter()
+            .find(|(method, path, _)| {
+                *method == request.method().as_str() && *path == route.as_str()
+            })
+            .map(|(_, path, target)| (*path, *target))
+    });
+
+    let (route, target) = match audited {
+        Some(audited) => audited,
+        None => return next.run(request).await,
+    };
+
+    let method = request.method().to_string();
+    let path = request.uri().path().to_owned();
+
+    let (parts, body) = request.into_parts();
+    let body = match read_request_body(body).await {
+        Ok(Some(body)) => body,
+        Ok(None) => {
+            let e = anyhow!("Request body is larger than {} bytes", MAX_BODY_LEN);
+            return AppError::new(AppErrorKind::PayloadTooLarge, e).into_response();
+        }
+        Err(e) => {
+            let e = anyhow::Error::from(e).context("Failed to read request body");
+            return AppError::new(AppErrorKind::InvalidPayload, e).into_response();
+        }
+    };
+
+    let payload = payload(&body);
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    let status = response.status();
+
+    // The response body is read only when there's something to take from it
+    // and it's known to fit the limit, otherwise it's passed through as is.
+    let readable = response
+        .body()
+        .size_hint()
+        .upper()
+        .map_or(false, |len| len <= MAX_BODY_LEN as u64);
+
+    let (response, body) =
+        if readable && (!status.is_success() || matches!(target, Target::CreatedClass)) {
+            let (parts, body) = response.into_parts();
+            let body = hyper::body::to_bytes(body).await.unwrap_or_else(|e| {
+                error!("Failed to read response body, err = {:?}", e);
+                Bytes::new()
+            });
+
+            (
+                Response::from_parts(parts, boxed(Full::from(body.clone()))),
+                body,
+            )
+        } else {
+            (response, Bytes::new())
+        };
+
+    let mut query = InsertQuery::new(method, route.to_owned(), path, status.as_u16() as i16);
+
+    if let Some(AccountIdExtractor(account_id)) = account {
+        query = query.account_id(account_id);
+    }
+
+    let params = params.map(|Path(params)| params).unwrap_or_default();
+
+    match target {
+        Target::Class => {
+            if let Some(id) = params.get("id").and_then(|id| id.parse::<Uuid>().ok()) {
+                query = query.class_id(id);
+            }
+        }
+        Target::CreatedClass if status.is_success() => {
+            let id = serde_json::from_slice::<JsonValue>(&body)
+                .ok()
+                .and_then(|body| body["id"].as_str()?.parse::<Uuid>().ok());
+
+            if let Some(id) = id {
+                query = query.class_id(id);
+            }
+        }
+        Target::Scope => {
+            if let Some(scope) = params.get("scope") {
+                query = query.scope(scope.to_owned());
+            }
+        }
+        Target::CreatedClass | Target::None => {}
+    }
+
+    if let Some(payload) = payload {
+        query = query.payload(payload);
+    }
+
+    if !status.is_success() {
+        query = query.error(error_text(&body));
+    }
+
+    if let Err(e) = record(ctx.as_ref(), query).await {
+        error!("Failed to record audit log entry, err = {:?}", e);
+    }
+
+    response
+}
+
+/// Buffers the request body, `None` when it's larger than `MAX_BODY_LEN`.
+async fn read_request_body(mut body: Body) -> Result<Option<Bytes>, hyper::Error> {
+    if body.size_hint().lower() > MAX_BODY_LEN as u64 {
+        return Ok(None);
+    }
+
+    let mut bytes = Vec::new();
+
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+
+        if bytes.len() + chunk.len() > MAX_BODY_LEN {
+            return Ok(None);
+        }
+
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(Some(bytes.into()))
+}
+
+async fn record(ctx: &dyn AppContext, query: InsertQuery) -> anyhow::Result<()> {
+    let mut conn = ctx.get_conn().await?;
+    query.execute(&mut conn).await?;
+    Ok(())
+}
+
+fn payload(body: &[u8]) -> Option<JsonValue> {
+    if body.is_empty() {
+        return None;
+    }
+
+    let payload = match serde_json::from_slice::<JsonValue>(body) {
+        Ok(mut payload) => {
+            redact(&mut payload);
+            payload
+        }
+        Err(_) => JsonValue::String(truncate(body)),
+    };
+
+    Some(payload)
+}
+
+fn redact(value: &mut JsonValue) {
+    match value {
+        JsonValue::Object(fields) => {
+            for (name, value) in fields.iter_mut() {
+                let name = name.to_lowercase();
+
+                if SECRETS.iter().any(|secret| name.contains(secret)) {
+                    *value = JsonValue::String(REDACTED.to_owned());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        JsonValue::Array(values) => values.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+// Errors come either as `svc_error` JSON or as plain text.
+fn error_text(body: &[u8]) -> String {
+    match serde_json::from_slice::<JsonValue>(body) {
+        Ok(error) => match (error["type"].as_str(), error["detail"].as_str()) {
+            (Some(kind), Some(detail)) => format!("{}: {}", kind, detail),
+            (Some(kind), None) => kind.to_owned(),
+            _ => error.to_string(),
+        },
+        Err(_) => truncate(body),
+    }
+}
+
+fn truncate(body: &[u8]) -> String {
+    String::from_utf8_lossy(body)
+        .chars()
+        .take(MAX_TEXT_LEN)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::app::api::v1::openapi;
+    use crate::app::readiness::Readiness;
+    use crate::app::{http, EventSender};
+    use crate::db::audit_log::ListQuery;
+    use crate::test_helpers::prelude::*;
+
+    // Writes that aren't worth an audit entry: participants' events and timestamps and
+    // the authorization checks proxied for other services.
+    const EXEMPT: &[(&str, &str)] = &[
+        ("POST", "/api/v1/webinars/:id/events"),
+        ("POST", "/api/v1/p2p/:id/events"),
+        ("POST", "/api/v1/minigroups/:id/events"),
+        ("POST", "/api/v1/webinars/:id/timestamps"),
+        ("POST", "/api/v1/minigroups/:id/timestamps"),
+        ("POST", "/api/v1/authz/:audience"),
+    ];
+
+    #[test]
+    fn audits_every_write() {
+        let document = openapi::document();
+
+        let writes = document.paths.paths.iter().flat_map(|(path, item)| {
+            let path = path.replace('{', ":").replace('}', "");
+
+            item.operations.keys().filter_map(move |item_type| {
+                let method = serde_json::to_value(item_type).unwrap();
+                let method = method.as_str().unwrap().to_uppercase();
+                (method != "GET").then(|| (method, path.clone()))
+            })
+        });
+
+        for (method, path) in writes {
+            let known = AUDITED
+                .iter()
+                .map(|(method, path, _)| (method, path))
+                .chain(EXEMPT.iter().map(|(method, path)| (method, path)))
+                .any(|(m, p)| *m == method && *p == path);
+
+            assert!(known, "Neither audited nor exempt: {} {}", method, path);
+        }
+    }
+
+    #[test]
+    fn redacts_secrets() {
+        let mut payload = json!({
+            "scope": "foo",
+            "password": "qwerty",
+            "properties": {
+                "API_KEY": "123",
+                "recordings": [{"access_token": "abc", "id": 1}]
+            }
+        });
+
+        redact(&mut payload);
+
+        assert_eq!(
+            payload,
+            json!({
+                "scope": "foo",
+                "password": REDACTED,
+                "properties": {
+                    "API_KEY": REDACTED,
+                    "recordings": [{"access_token": REDACTED, "id": 1}]
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn records_rejected_update() {
+        let state = Arc::new(TestState::new(TestAuthz::new()).await);
+        let ctx = state.clone() as Arc<dyn AppContext>;
+        let (events, _) = EventSender::channel(Default::default(), 1);
+        let readiness = Readiness::new(None, &ctx.config().readiness);
+        let router = http::router(ctx, events, readiness, HashMap::new());
+        let class_id = Uuid::new_v4();
+
+        let request = Request::builder()
+            .method("PUT")
+            .uri(format!("/api/v1/webinars/{}", class_id))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"reason":"test","token":"secret"}"#))
+            .unwrap();
+
+        let response = router.oneshot(request).await.unwrap();
+        assert!(!response.status().is_success());
+
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+
+        let entries = ListQuery::new(10)
+            .class_id(class_id)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to list audit log");
+
+        assert_eq!(entries.len(), 1);
+
+        let entry = serde_json::to_value(&entries[0]).unwrap();
+        assert_eq!(entry["route"], "/api/v1/webinars/:id");
+        assert_eq!(entry["status"], response.status().as_u16());
+        assert_eq!(
+            entry["payload"],
+            json!({"reason": "test", "token": REDACTED})
+        );
+        assert!(entry["error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn rejects_too_large_payload() {
+        let state = Arc::new(TestState::new(TestAuthz::new()).await);
+        let ctx = state.clone() as Arc<dyn AppContext>;
+        let (events, _) = EventSender::channel(Default::default(), 1);
+        let readiness = Readiness::new(None, &ctx.config().readiness);
+        let router = http::router(ctx, events, readiness, HashMap::new());
+
+        // Streamed so that the size is only found out while reading the body.
+        let (mut sender, body) = Body::channel();
+
+        tokio::spawn(async move {
+            for chunk in [vec![b' '; MAX_BODY_LEN], vec![b' ']] {
+                if sender.send_data(chunk.into()).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let request = Request::builder()
+            .method("PUT")
+            .uri(format!("/api/v1/webinars/{}", Uuid::new_v4()))
+            .header("content-type", "application/json")
+            .body(body)
+            .unwrap();
+
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), 413);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = serde_json::from_slice::<JsonValue>(&body).unwrap();
+        assert_eq!(body["type"], "payload_too_large");
+    }
+}
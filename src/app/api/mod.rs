@@ -5,12 +5,8 @@ use axum::extract::{Extension, Path};
 use http::{StatusCode, Uri};
 use hyper::{Body, Request, Response};
 use serde_derive::Deserialize;
-use svc_agent::{
-    mqtt::{
-        IntoPublishableMessage, OutgoingEvent, OutgoingEventProperties, ShortTermTimingProperties,
-    },
-    Authenticable,
-};
+use sqlx::Acquire;
+use svc_agent::Authenticable;
 use svc_utils::extractors::AccountIdExtractor;
 use tracing::error;
 
@@ -18,6 +14,7 @@ use crate::app::api::v1::AppResult;
 use crate::app::authz::AuthzObject;
 use crate::app::error::ErrorExt;
 use crate::app::error::{Error as AppError, ErrorKind as AppErrorKind};
+use crate::app::outbox;
 use crate::app::AppContext;
 
 use super::metrics::AuthorizeMetrics;
@@ -54,47 +51,45 @@ pub async fn rollback(
             .unwrap();
     }
 
-    match ctx.get_conn().await {
+    let event = match do_rollback(ctx.as_ref(), &scope).await {
+        Ok(event) => event,
         Err(err) => {
-            error!("Failed to get db conn, reason = {:?}", err);
+            error!("Failed to rollback scope, reason = {:?}", err);
 
             return Response::builder()
                 .status(500)
-                .body(Body::from(format!("Failed to acquire conn: {}", err)))
+                .body(Body::from(format!("Failed to rollback scope: {}", err)))
                 .unwrap();
         }
-        Ok(mut conn) => {
-            let r = crate::db::scope::DeleteQuery::new(scope.clone())
-                .execute(&mut conn)
-                .await;
-
-            if let Err(err) = r {
-                error!("Failed to delete scope from db, reason = {:?}", err);
-
-                return Response::builder()
-                    .status(500)
-                    .body(Body::from(format!("Failed to delete scope: {}", err)))
-                    .unwrap();
-            }
-
-            let timing = ShortTermTimingProperties::new(chrono::Utc::now());
-            let props = OutgoingEventProperties::new("scope.frontend.rollback", timing);
-            let path = format!("scopes/{}/events", scope);
-            let event = OutgoingEvent::broadcast("", props, &path);
-            let e = Box::new(event) as Box<dyn IntoPublishableMessage + Send>;
-
-            if let Err(err) = ctx.publisher().publish(e) {
-                error!(
-                    "Failed to publish scope.frontend.rollback event, reason = {:?}",
-                    err
-                );
-            }
-        }
-    }
+    };
+
+    outbox::publish(ctx.as_ref(), vec![event]).await;
 
     Response::builder().body(Body::from("Ok")).unwrap()
 }
 
+async fn do_rollback(ctx: &dyn AppContext, scope: &str) -> anyhow::Result<outbox::Event> {
+    let mut conn = ctx.get_conn().await?;
+    let mut txn = conn
+        .begin()
+        .await
+        .context("Failed to begin sqlx db transaction")?;
+
+    crate::db::scope::DeleteQuery::new(scope.to_owned())
+        .execute(&mut txn)
+        .await
+        .context("Failed to delete scope")?;
+
+    let path = format!("scopes/{}/events", scope);
+    let event = outbox::push(&mut txn, "scope.frontend.rollback", &path, &"").await?;
+
+    txn.commit()
+        .await
+        .context("Failed to commit sqlx db transaction")?;
+
+    Ok(event)
+}
+
 fn build_back_url<B>(request: &Request<B>) -> Result<Uri, AppError> {
     let path = request
         .uri()
@@ -148,4 +143,5 @@ where
     }
 }
 
+pub mod audit;
 pub mod v1;
@@ -0,0 +1,174 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::{Extension, Query};
+use serde_derive::{Deserialize, Serialize};
+use svc_authn::AccountId;
+use svc_utils::extractors::AccountIdExtractor;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use super::AppResult;
+use crate::app::api::IntoJsonResponse;
+use crate::app::authz::authorize_service;
+use crate::app::error::ErrorExt;
+use crate::app::error::ErrorKind as AppErrorKind;
+use crate::app::AppContext;
+use crate::db::audit_log::{ListQuery, Object as AuditLogEntry};
+
+const DEFAULT_LIMIT: i64 = 25;
+const MAX_LIMIT: i64 = 100;
+
+#[derive(Default, Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListFilters {
+    class_id: Option<Uuid>,
+    #[param(value_type = Option<String>)]
+    account_id: Option<AccountId>,
+    /// `next_cursor` of the previous page
+    before: Option<i64>,
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[schema(as = AuditLogList)]
+pub struct ListResponseBody {
+    entries: Vec<AuditLogEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<i64>,
+}
+
+pub async fn list(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    Query(filters): Query<ListFilters>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+) -> AppResult {
+    do_list(ctx.as_ref(), &account_id, filters).await
+}
+
+async fn do_list(
+    state: &dyn AppContext,
+    account_id: &AccountId,
+    filters: ListFilters,
+) -> AppResult {
+    authorize_service(state, account_id, &["audit_log"], "list").await?;
+
+    let limit = filters.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    // Fetch one extra entry to find out whether there is a next page.
+    let mut query = ListQuery::new(limit + 1);
+
+    if let Some(class_id) = filters.class_id {
+        query = query.class_id(class_id);
+    }
+
+    if let Some(account_id) = filters.account_id {
+        query = query.account_id(account_id);
+    }
+
+    if let Some(before) = filters.before {
+        query = query.before(before);
+    }
+
+    let mut conn = state
+        .get_conn()
+        .await
+        .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+    let mut entries = query
+        .execute(&mut conn)
+        .await
+        .context("Failed to list audit log")
+        .error(AppErrorKind::DbQueryFailed)?;
+
+    let next_cursor = if entries.len() as i64 > limit {
+        entries.truncate(limit as usize);
+        entries.last().map(|entry| entry.id())
+    } else {
+        None
+    };
+
+    ListResponseBody {
+        entries,
+        next_cursor,
+    }
+    .into_json_response("Failed to serialize audit log", http::StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value as JsonValue;
+
+    use super::*;
+    use crate::db::audit_log::InsertQuery;
+    use crate::test_helpers::prelude::*;
+
+    #[tokio::test]
+    async fn list_audit_log_unauthorized() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let state = TestState::new(TestAuthz::new()).await;
+
+        do_list(&state, agent.account_id(), Default::default())
+            .await
+            .expect_err("Unexpectedly succeeded");
+    }
+
+    #[tokio::test]
+    async fn list_audit_log_by_class_and_account() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let admin = TestAgent::new("web", "admin", USR_AUDIENCE);
+        let mut authz = TestAuthz::new();
+        authz.set_audience(SVC_AUDIENCE);
+        authz.allow(admin.account_id(), vec!["audit_log"], "list");
+        let state = TestState::new(authz).await;
+        let class_id = Uuid::new_v4();
+
+        {
+            let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+
+            for (account, status) in [(&agent, 200), (&admin, 403), (&agent, 204)] {
+                InsertQuery::new(
+                    "PUT".into(),
+                    "/api/v1/webinars/:id".into(),
+                    format!("/api/v1/webinars/{}", class_id),
+                    status,
+                )
+                .account_id(account.account_id().to_owned())
+                .class_id(class_id)
+                .execute(&mut conn)
+                .await
+                .expect("Failed to insert audit log entry");
+            }
+        }
+
+        let filters = |before| ListFilters {
+            class_id: Some(class_id),
+            account_id: Some(agent.account_id().to_owned()),
+            before,
+            limit: Some(1),
+        };
+
+        let r = do_list(&state, admin.account_id(), filters(None))
+            .await
+            .expect("Failed to list audit log");
+        let r = hyper::body::to_bytes(r.into_body()).await.unwrap();
+        let v = serde_json::from_slice::<JsonValue>(&r[..]).expect("Failed to parse json");
+        assert_eq!(v["entries"].as_array().map(Vec::len), Some(1));
+        assert_eq!(v["entries"][0]["status"], 204);
+        assert_eq!(
+            v["entries"][0]["account_id"],
+            agent.account_id().to_string()
+        );
+
+        let r = do_list(
+            &state,
+            admin.account_id(),
+            filters(v["next_cursor"].as_i64()),
+        )
+        .await
+        .expect("Failed to list audit log");
+        let r = hyper::body::to_bytes(r.into_body()).await.unwrap();
+        let v = serde_json::from_slice::<JsonValue>(&r[..]).expect("Failed to parse json");
+        assert_eq!(v["entries"][0]["status"], 200);
+        assert!(v.get("next_cursor").is_none());
+    }
+}
@@ -9,6 +9,7 @@ use serde_json::json;
 use svc_authn::{AccountId, Authenticable};
 use svc_utils::extractors::AccountIdExtractor;
 use tracing::info;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::app::http::Json;
@@ -20,27 +21,30 @@ use crate::db::authz::{AuthzClass, AuthzReadQuery};
 
 use super::{AppError, AppResult};
 
-#[derive(Deserialize, Debug, Serialize)]
+#[derive(Deserialize, Debug, Serialize, ToSchema)]
 pub struct AuthzRequest {
+    #[schema(inline)]
     subject: Subject,
+    #[schema(inline)]
     object: Object,
     action: String,
 }
 
-#[derive(Deserialize, Debug, Serialize, Clone)]
+#[derive(Deserialize, Debug, Serialize, Clone, ToSchema)]
 struct Subject {
     namespace: String,
+    #[schema(inline)]
     value: SubjectValue,
 }
 
-#[derive(Deserialize, Debug, Serialize, Clone)]
+#[derive(Deserialize, Debug, Serialize, Clone, ToSchema)]
 #[serde(untagged)]
 enum SubjectValue {
     New(String),
     Old(Vec<String>),
 }
 
-#[derive(Deserialize, Debug, Serialize)]
+#[derive(Deserialize, Debug, Serialize, ToSchema)]
 struct Object {
     namespace: String,
     value: Vec<String>,
@@ -5,6 +5,7 @@ use axum::extract::{Extension, Path};
 use hyper::{Body, Response};
 use serde_derive::Deserialize;
 use svc_utils::extractors::AccountIdExtractor;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 use super::{find, AppResult};
@@ -15,9 +16,10 @@ use crate::app::AppContext;
 use crate::app::{authz::AuthzObject, metrics::AuthorizeMetrics};
 use crate::db::class::AsClassType;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct TimestampPayload {
     #[serde(with = "crate::serde::duration_seconds")]
+    #[schema(value_type = u64)]
     position: chrono::Duration,
 }
 
@@ -68,7 +70,10 @@ pub async fn create_timestamp<T: AsClassType>(
 #[cfg(test)]
 mod create_timestamp_tests {
     use super::*;
-    use crate::{db::class::WebinarType, test_helpers::prelude::*};
+    use crate::{
+        db::class::{ClassStatus, WebinarType},
+        test_helpers::prelude::*,
+    };
     use chrono::{Duration, Utc};
     use serde_json::Value as JsonValue;
     use std::ops::Bound;
@@ -130,6 +135,7 @@ mod create_timestamp_tests {
                 Uuid::new_v4(),
                 Uuid::new_v4(),
             )
+            .status(ClassStatus::Transcoded)
             .insert(&mut conn)
             .await;
 
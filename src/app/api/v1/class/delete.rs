@@ -0,0 +1,502 @@
+use std::ops::Bound;
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::{Extension, Path};
+use chrono::{Duration, Utc};
+use hyper::{Body, Response};
+use sqlx::Acquire;
+use svc_authn::AccountId;
+use svc_utils::extractors::AccountIdExtractor;
+use tracing::error;
+use uuid::Uuid;
+
+use super::{find, AppResult};
+use crate::app::error::ErrorExt;
+use crate::app::error::ErrorKind as AppErrorKind;
+use crate::app::outbox;
+use crate::app::services::ClassStop;
+use crate::app::webhooks::WebhookEvent;
+use crate::app::AppContext;
+use crate::app::{authz::AuthzObject, metrics::AuthorizeMetrics};
+use crate::clients::{
+    conference::RoomUpdate as ConfRoomUpdate, event::RoomUpdate as EventRoomUpdate,
+};
+use crate::db::class::{AsClassType, BoundedDateTimeTuple, ClassType, Object as Class, ReadQuery};
+
+pub async fn delete<T: AsClassType>(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    Path(id): Path<Uuid>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+) -> AppResult {
+    let class = find::<T>(ctx.as_ref(), id)
+        .await
+        .error(AppErrorKind::ClassNotFound)?;
+
+    do_delete(ctx.as_ref(), &account_id, class).await
+}
+
+async fn do_delete(state: &dyn AppContext, account_id: &AccountId, class: Class) -> AppResult {
+    let object = AuthzObject::new(&["classrooms", &class.id().to_string()]).into();
+    state
+        .authz()
+        .authorize(
+            class.audience().to_owned(),
+            account_id.clone(),
+            object,
+            "delete".into(),
+        )
+        .await
+        .measure()?;
+
+    let label = match class.kind() {
+        ClassType::P2P => "p2p.deleted",
+        ClassType::Minigroup => "minigroup.deleted",
+        ClassType::Webinar => "webinar.deleted",
+    };
+
+    let path = format!("audiences/{}/events", class.audience());
+
+    let payload = ClassStop::new(&class);
+
+    let (event, event_room_shared) = {
+        let mut conn = state
+            .get_conn()
+            .await
+            .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+        let mut txn = conn
+            .begin()
+            .await
+            .context("Failed to begin sqlx db transaction")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        crate::db::recording::DeleteQuery::new(class.id())
+            .execute(&mut txn)
+            .await
+            .context("Failed to delete recordings")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        crate::db::record_timestamp::DeleteQuery::new(class.id())
+            .execute(&mut txn)
+            .await
+            .context("Failed to delete record timestamps")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        crate::db::class::DeleteQuery::new(class.id())
+            .execute(&mut txn)
+            .await
+            .context("Failed to delete class")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        // Replicas share the event room of the original class so it stays open
+        // while any class using it is left.
+        let event_room_shared = ReadQuery::by_event_room(class.event_room_id())
+            .execute(&mut txn)
+            .await
+            .context("Failed to find classes sharing the event room")
+            .error(AppErrorKind::DbQueryFailed)?
+            .is_some();
+
+        let event = outbox::push(&mut txn, label, &path, &payload)
+            .await
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        txn.commit()
+            .await
+            .context("Failed to commit sqlx db transaction")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        (event, event_room_shared)
+    };
+
+    outbox::publish(state, vec![event]).await;
+    close_rooms(state, &class, !event_room_shared).await;
+    crate::app::webhooks::enqueue(state, &class, WebhookEvent::Deleted).await;
+
+    let response = Response::builder().status(204).body(Body::empty()).unwrap();
+
+    Ok(response)
+}
+
+/// The class is already deleted at this point so a failure to close a room is only logged,
+/// the room gets closed by its own time anyway.
+async fn close_rooms(state: &dyn AppContext, class: &Class, close_event_room: bool) {
+    let time = closed_time(class);
+
+    let event_fut = async {
+        if !close_event_room {
+            return;
+        }
+
+        let update = EventRoomUpdate {
+            time: Some(time),
+            classroom_id: None,
+        };
+
+        if let Err(e) = state
+            .event_client()
+            .update_room(class.event_room_id(), update)
+            .await
+        {
+            error!(
+                class_id = ?class.id(),
+                "Failed to close event room, err = {:?}", e
+            );
+        }
+    };
+
+    let conference_fut = async {
+        let update = ConfRoomUpdate {
+            time: Some(time),
+            reserve: None,
+            classroom_id: None,
+            host: None,
+        };
+
+        if let Err(e) = state
+            .conference_client()
+            .update_room(class.conference_room_id(), update)
+            .await
+        {
+            error!(
+                class_id = ?class.id(),
+                "Failed to close conference room, err = {:?}", e
+            );
+        }
+    };
+
+    tokio::join!(event_fut, conference_fut);
+}
+
+/// Cuts the class time at the current moment so the rooms get closed.
+/// A class that hasn't started yet gets a second long time from now since rooms
+/// don't accept an empty time range.
+fn closed_time(class: &Class) -> BoundedDateTimeTuple {
+    let now = Utc::now();
+
+    match BoundedDateTimeTuple::from(class.time().to_owned()).0 {
+        Bound::Included(t) | Bound::Excluded(t) if t < now => {
+            (Bound::Included(t), Bound::Excluded(now))
+        }
+        Bound::Unbounded => (Bound::Unbounded, Bound::Excluded(now)),
+        _ => (
+            Bound::Included(now),
+            Bound::Excluded(now + Duration::seconds(1)),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::record_timestamp::{FindQuery, UpsertQuery};
+    use crate::test_helpers::prelude::*;
+
+    #[tokio::test]
+    async fn delete_webinar_unauthorized() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let state = TestState::new(TestAuthz::new()).await;
+
+        let webinar = {
+            let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+            factory::Webinar::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                (Bound::Unbounded, Bound::Unbounded).into(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+            )
+            .insert(&mut conn)
+            .await
+        };
+
+        do_delete(&state, agent.account_id(), webinar)
+            .await
+            .expect_err("Unexpectedly succeeded");
+    }
+
+    #[tokio::test]
+    async fn delete_webinar() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let db_pool = TestDb::new().await;
+        let event_room_id = Uuid::new_v4();
+        let conference_room_id = Uuid::new_v4();
+
+        let webinar = {
+            let mut conn = db_pool.get_conn().await;
+            let webinar = factory::Webinar::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                (Bound::Included(Utc::now()), Bound::Unbounded).into(),
+                conference_room_id,
+                event_room_id,
+            )
+            .insert(&mut conn)
+            .await;
+
+            factory::Recording::new(webinar.id(), Uuid::new_v4(), agent.agent_id().to_owned())
+                .insert(&mut conn)
+                .await;
+
+            UpsertQuery::new(
+                webinar.id(),
+                agent.account_id().to_owned(),
+                chrono::Duration::seconds(10),
+            )
+            .execute(&mut conn)
+            .await
+            .expect("Failed to insert record timestamp");
+
+            webinar
+        };
+
+        let mut authz = TestAuthz::new();
+        authz.allow(
+            agent.account_id(),
+            vec!["classrooms", &webinar.id().to_string()],
+            "delete",
+        );
+
+        let mut state = TestState::new_with_pool(db_pool, authz);
+
+        state
+            .event_client_mock()
+            .expect_update_room()
+            .withf(move |id, update| {
+                *id == event_room_id && matches!(update.time, Some((_, Bound::Excluded(_))))
+            })
+            .returning(|_, _| Ok(()));
+
+        state
+            .conference_client_mock()
+            .expect_update_room()
+            .withf(move |id, update| {
+                *id == conference_room_id && matches!(update.time, Some((_, Bound::Excluded(_))))
+            })
+            .returning(|_, _| Ok(()));
+
+        let r = do_delete(&state, agent.account_id(), webinar.clone())
+            .await
+            .expect("Failed to delete webinar");
+        assert_eq!(r.status(), 204);
+
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+
+        let class = ReadQuery::by_id(webinar.id())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to read class");
+        assert!(class.is_none());
+
+        // The class is kept as soft-deleted so that the rows referencing it stay valid.
+        let deleted_at = sqlx::query_scalar::<_, Option<chrono::DateTime<Utc>>>(
+            "SELECT deleted_at FROM class WHERE id = $1",
+        )
+        .bind(webinar.id())
+        .fetch_one(&mut conn)
+        .await
+        .expect("Failed to find class");
+        assert!(deleted_at.is_some());
+
+        // Recordings are kept as soft-deleted.
+        let deleted_at = sqlx::query_scalar::<_, Option<chrono::DateTime<Utc>>>(
+            "SELECT deleted_at FROM recording WHERE class_id = $1",
+        )
+        .bind(webinar.id())
+        .fetch_all(&mut conn)
+        .await
+        .expect("Failed to find recordings");
+        assert_eq!(deleted_at.len(), 1);
+        assert!(deleted_at[0].is_some());
+
+        let timestamp = FindQuery::new(webinar.id(), agent.account_id().to_owned())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to find record timestamp");
+        assert!(timestamp.is_none());
+
+        let messages = state.test_publisher().flush();
+        assert_eq!(messages.len(), 1);
+        match messages[0].properties() {
+            OutgoingEnvelopeProperties::Event(props) => {
+                assert_eq!(props.label(), "webinar.deleted")
+            }
+            _ => panic!("Expected an event"),
+        }
+    }
+
+    #[tokio::test]
+    async fn delete_not_started_webinar() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let db_pool = TestDb::new().await;
+
+        let webinar = {
+            let mut conn = db_pool.get_conn().await;
+            factory::Webinar::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                (
+                    Bound::Included(Utc::now() + chrono::Duration::hours(1)),
+                    Bound::Unbounded,
+                )
+                    .into(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+            )
+            .insert(&mut conn)
+            .await
+        };
+
+        let mut authz = TestAuthz::new();
+        authz.allow(
+            agent.account_id(),
+            vec!["classrooms", &webinar.id().to_string()],
+            "delete",
+        );
+
+        let mut state = TestState::new_with_pool(db_pool, authz);
+
+        // Rooms don't accept an empty time range so the closed time must not be one.
+        fn non_empty(time: &Option<BoundedDateTimeTuple>) -> bool {
+            matches!(time, Some((Bound::Included(start), Bound::Excluded(end))) if start < end)
+        }
+
+        state
+            .event_client_mock()
+            .expect_update_room()
+            .withf(|_, update| non_empty(&update.time))
+            .returning(|_, _| Ok(()));
+
+        state
+            .conference_client_mock()
+            .expect_update_room()
+            .withf(|_, update| non_empty(&update.time))
+            .returning(|_, _| Ok(()));
+
+        let r = do_delete(&state, agent.account_id(), webinar)
+            .await
+            .expect("Failed to delete webinar");
+        assert_eq!(r.status(), 204);
+    }
+
+    #[tokio::test]
+    async fn delete_webinar_replica() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let db_pool = TestDb::new().await;
+        let event_room_id = Uuid::new_v4();
+        let replica_conference_room_id = Uuid::new_v4();
+
+        let (original, replica) = {
+            let mut conn = db_pool.get_conn().await;
+
+            let original = factory::Webinar::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                (Bound::Included(Utc::now()), Bound::Unbounded).into(),
+                Uuid::new_v4(),
+                event_room_id,
+            )
+            .insert(&mut conn)
+            .await;
+
+            // A replica shares the event room of its original.
+            let replica = factory::Webinar::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                (Bound::Included(Utc::now()), Bound::Unbounded).into(),
+                replica_conference_room_id,
+                event_room_id,
+            )
+            .insert(&mut conn)
+            .await;
+
+            (original, replica)
+        };
+
+        let mut authz = TestAuthz::new();
+        authz.allow(
+            agent.account_id(),
+            vec!["classrooms", &replica.id().to_string()],
+            "delete",
+        );
+
+        let mut state = TestState::new_with_pool(db_pool, authz);
+        state.event_client_mock().expect_update_room().never();
+
+        state
+            .conference_client_mock()
+            .expect_update_room()
+            .withf(move |id, _| *id == replica_conference_room_id)
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let r = do_delete(&state, agent.account_id(), replica)
+            .await
+            .expect("Failed to delete replica");
+        assert_eq!(r.status(), 204);
+
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+
+        let class = ReadQuery::by_id(original.id())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to read class");
+        assert!(class.is_some());
+    }
+
+    #[tokio::test]
+    async fn delete_webinar_when_room_close_fails() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let db_pool = TestDb::new().await;
+        let conference_room_id = Uuid::new_v4();
+
+        let webinar = {
+            let mut conn = db_pool.get_conn().await;
+            factory::Webinar::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                (Bound::Included(Utc::now()), Bound::Unbounded).into(),
+                conference_room_id,
+                Uuid::new_v4(),
+            )
+            .insert(&mut conn)
+            .await
+        };
+
+        let mut authz = TestAuthz::new();
+        authz.allow(
+            agent.account_id(),
+            vec!["classrooms", &webinar.id().to_string()],
+            "delete",
+        );
+
+        let mut state = TestState::new_with_pool(db_pool, authz);
+
+        state
+            .event_client_mock()
+            .expect_update_room()
+            .returning(|_, _| Err(crate::clients::ClientError::Timeout));
+
+        // The other room is closed regardless.
+        state
+            .conference_client_mock()
+            .expect_update_room()
+            .withf(move |id, _| *id == conference_room_id)
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let r = do_delete(&state, agent.account_id(), webinar.clone())
+            .await
+            .expect("Failed to delete webinar");
+        assert_eq!(r.status(), 204);
+
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+
+        let class = ReadQuery::by_id(webinar.id())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to read class");
+        assert!(class.is_none());
+    }
+}
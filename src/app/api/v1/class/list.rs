@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+use std::ops::Bound;
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::RawQuery;
+use axum::extract::{Extension, Path};
+use chrono::{DateTime, Utc};
+use hyper::{Body, Response};
+use serde::{Deserialize, Serialize};
+use svc_authn::AccountId;
+use svc_utils::extractors::AccountIdExtractor;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use super::AppResult;
+use crate::app::error::ErrorExt;
+use crate::app::error::ErrorKind as AppErrorKind;
+use crate::app::AppContext;
+use crate::app::{authz::AuthzObject, metrics::AuthorizeMetrics};
+use crate::db::class::{ClassStatus, ClassType, ListQuery, Object as Class, ReadQuery};
+
+const DEFAULT_LIMIT: i64 = 25;
+const MAX_LIMIT: i64 = 100;
+
+#[derive(Default, Debug, PartialEq, Eq, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListFilters {
+    kind: Option<ClassType>,
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    #[param(value_type = Option<i64>)]
+    time_from: Option<DateTime<Utc>>,
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    #[param(value_type = Option<i64>)]
+    time_to: Option<DateTime<Utc>>,
+    timed_out: Option<bool>,
+    original_class_id: Option<Uuid>,
+    content_id: Option<String>,
+    #[param(style = DeepObject, value_type = Option<Object>)]
+    tags: Option<HashMap<String, String>>,
+    status: Option<ClassStatus>,
+    after: Option<Uuid>,
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[schema(as = ClassList)]
+pub struct ListResponseBody {
+    classes: Vec<Class>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<Uuid>,
+}
+
+pub async fn list(
+    ctx: Extension<Arc<dyn AppContext>>,
+    Path(audience): Path<String>,
+    RawQuery(raw_q): RawQuery,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+) -> AppResult {
+    let filters = serde_qs::from_str(raw_q.unwrap_or_default().as_str())
+        .context("Failed to parse qs")
+        .error(AppErrorKind::InvalidQueryString)?;
+
+    do_list(ctx.0.as_ref(), &account_id, audience, filters).await
+}
+
+async fn do_list(
+    state: &dyn AppContext,
+    account_id: &AccountId,
+    audience: String,
+    filters: ListFilters,
+) -> AppResult {
+    let object = AuthzObject::new(&["classrooms"]).into();
+    state
+        .authz()
+        .authorize(audience.clone(), account_id.clone(), object, "list".into())
+        .await
+        .measure()?;
+
+    if let (Some(time_from), Some(time_to)) = (filters.time_from, filters.time_to) {
+        if time_from > time_to {
+            return Err(anyhow!("time_from is after time_to"))
+                .error(AppErrorKind::InvalidQueryString);
+        }
+    }
+
+    let mut conn = state
+        .get_conn()
+        .await
+        .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+    // The cursor must point to a listed class, otherwise the page would come out empty.
+    if let Some(after) = filters.after {
+        let cursor = ReadQuery::by_id(after)
+            .execute(&mut conn)
+            .await
+            .context("Failed to find cursor class")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        match cursor {
+            Some(class) if class.audience() == audience => (),
+            _ => {
+                return Err(anyhow!("Unknown cursor, after = {}", after))
+                    .error(AppErrorKind::InvalidQueryString)
+            }
+        }
+    }
+
+    let limit = filters.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    // Fetch one extra row to find out whether there is a next page.
+    let mut query = ListQuery::new(audience, limit + 1);
+
+    if let Some(kind) = filters.kind {
+        query = query.kind(kind);
+    }
+
+    if filters.time_from.is_some() || filters.time_to.is_some() {
+        let bound = |t: Option<DateTime<Utc>>| t.map_or(Bound::Unbounded, Bound::Included);
+        query = query.time((bound(filters.time_from), bound(filters.time_to)));
+    }
+
+    if let Some(timed_out) = filters.timed_out {
+        query = query.timed_out(timed_out);
+    }
+
+    if let Some(original_class_id) = filters.original_class_id {
+        query = query.original_class_id(original_class_id);
+    }
+
+    if let Some(content_id) = filters.content_id {
+        query = query.content_id(content_id);
+    }
+
+    if let Some(tags) = filters.tags {
+        query = query.tags(serde_json::json!(tags));
+    }
+
+    if let Some(status) = filters.status {
+        query = query.status(status);
+    }
+
+    if let Some(after) = filters.after {
+        query = query.after(after);
+    }
+
+    let mut classes = query
+        .execute(&mut conn)
+        .await
+        .context("Failed to list classes")
+        .error(AppErrorKind::DbQueryFailed)?;
+
+    let next_cursor = if classes.len() as i64 > limit {
+        classes.truncate(limit as usize);
+        classes.last().map(|class| class.id())
+    } else {
+        None
+    };
+
+    let body = serde_json::to_string(&ListResponseBody {
+        classes,
+        next_cursor,
+    })
+    .context("Failed to serialize classes")
+    .error(AppErrorKind::SerializationFailed)?;
+
+    let response = Response::builder().body(Body::from(body)).unwrap();
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::prelude::*;
+    use serde_json::Value;
+
+    async fn list_ids(state: &TestState, account_id: &AccountId, filters: ListFilters) -> Value {
+        let r = do_list(state, account_id, USR_AUDIENCE.to_owned(), filters)
+            .await
+            .expect("Failed to list classes");
+
+        let r = hyper::body::to_bytes(r.into_body()).await.unwrap();
+        serde_json::from_slice::<Value>(&r[..]).expect("Failed to parse json")
+    }
+
+    fn ids(v: &Value) -> Vec<String> {
+        v["classes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["id"].as_str().unwrap().to_owned())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn list_classes_unauthorized() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let state = TestState::new(TestAuthz::new()).await;
+
+        do_list(
+            &state,
+            agent.account_id(),
+            USR_AUDIENCE.to_owned(),
+            ListFilters::default(),
+        )
+        .await
+        .expect_err("Unexpectedly succeeded");
+    }
+
+    #[tokio::test]
+    async fn list_classes_with_filters_and_pagination() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let db_pool = TestDb::new().await;
+        // Other tests share the audience so every query is narrowed down by this tag.
+        let run = random_string();
+
+        let (webinar1, webinar2, minigroup) = {
+            let mut conn = db_pool.get_conn().await;
+            let now = Utc::now();
+
+            let webinar1 = factory::Webinar::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                (
+                    Bound::Included(now - chrono::Duration::hours(3)),
+                    Bound::Excluded(now - chrono::Duration::hours(2)),
+                )
+                    .into(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+            )
+            .tags(serde_json::json!({ "run": run }))
+            .insert(&mut conn)
+            .await;
+
+            let webinar2 = factory::Webinar::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                (Bound::Included(now), Bound::Unbounded).into(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+            )
+            .tags(serde_json::json!({ "run": run }))
+            .insert(&mut conn)
+            .await;
+
+            let minigroup = factory::Minigroup::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                (Bound::Included(now), Bound::Unbounded).into(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+            )
+            .tags(serde_json::json!({ "run": run, "foo": "bar" }))
+            .insert(&mut conn)
+            .await;
+
+            (webinar1, webinar2, minigroup)
+        };
+
+        let mut authz = TestAuthz::new();
+        authz.allow(agent.account_id(), vec!["classrooms"], "list");
+        let state = TestState::new_with_pool(db_pool, authz);
+
+        let tags = |extra: &[(&str, &str)]| {
+            let mut tags = HashMap::from([("run".to_owned(), run.clone())]);
+            tags.extend(extra.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+            Some(tags)
+        };
+
+        let v = list_ids(
+            &state,
+            agent.account_id(),
+            ListFilters {
+                kind: Some(ClassType::Webinar),
+                tags: tags(&[]),
+                ..Default::default()
+            },
+        )
+        .await;
+        assert_eq!(
+            ids(&v),
+            vec![webinar2.id().to_string(), webinar1.id().to_string()]
+        );
+
+        let v = list_ids(
+            &state,
+            agent.account_id(),
+            ListFilters {
+                time_to: Some(Utc::now() - chrono::Duration::hours(1)),
+                tags: tags(&[]),
+                ..Default::default()
+            },
+        )
+        .await;
+        assert_eq!(ids(&v), vec![webinar1.id().to_string()]);
+
+        let v = list_ids(
+            &state,
+            agent.account_id(),
+            ListFilters {
+                tags: tags(&[("foo", "bar")]),
+                ..Default::default()
+            },
+        )
+        .await;
+        assert_eq!(ids(&v), vec![minigroup.id().to_string()]);
+
+        let v = list_ids(
+            &state,
+            agent.account_id(),
+            ListFilters {
+                tags: tags(&[]),
+                limit: Some(2),
+                ..Default::default()
+            },
+        )
+        .await;
+        let first_page = ids(&v);
+        assert_eq!(first_page.len(), 2);
+        let cursor = v["next_cursor"].as_str().expect("Missing next cursor");
+        assert_eq!(cursor, first_page[1]);
+
+        let v = list_ids(
+            &state,
+            agent.account_id(),
+            ListFilters {
+                tags: tags(&[]),
+                limit: Some(2),
+                after: Some(cursor.parse().unwrap()),
+                ..Default::default()
+            },
+        )
+        .await;
+        let second_page = ids(&v);
+        assert_eq!(second_page.len(), 1);
+        assert!(!first_page.contains(&second_page[0]));
+        assert!(v.get("next_cursor").is_none());
+    }
+
+    #[tokio::test]
+    async fn list_classes_with_invalid_filters() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let mut authz = TestAuthz::new();
+        authz.allow(agent.account_id(), vec!["classrooms"], "list");
+        let state = TestState::new(authz).await;
+
+        let deleted = {
+            let mut conn = state.get_conn().await.expect("Failed to get conn");
+
+            let webinar = factory::Webinar::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                (Bound::Unbounded, Bound::Unbounded).into(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+            )
+            .insert(&mut conn)
+            .await;
+
+            crate::db::class::DeleteQuery::new(webinar.id())
+                .execute(&mut conn)
+                .await
+                .expect("Failed to delete class");
+
+            webinar
+        };
+
+        let now = Utc::now();
+        let invalid_filters = vec![
+            ListFilters {
+                after: Some(Uuid::new_v4()),
+                ..Default::default()
+            },
+            ListFilters {
+                after: Some(deleted.id()),
+                ..Default::default()
+            },
+            ListFilters {
+                time_from: Some(now),
+                time_to: Some(now - chrono::Duration::hours(1)),
+                ..Default::default()
+            },
+        ];
+
+        for filters in invalid_filters {
+            let err = do_list(&state, agent.account_id(), USR_AUDIENCE.to_owned(), filters)
+                .await
+                .expect_err("Unexpectedly succeeded");
+            let body = serde_json::to_value(err.to_svc_error()).unwrap();
+            assert_eq!(body["type"], "invalid_query_string");
+        }
+    }
+
+    #[test]
+    fn parse_list_filters() {
+        let filters: ListFilters = serde_qs::from_str(
+            "kind=minigroup&time_from=1600000000&tags[foo]=bar&status=real-time&limit=10",
+        )
+        .expect("Failed to parse filters");
+
+        assert_eq!(filters.kind, Some(ClassType::Minigroup));
+        assert_eq!(filters.time_from.map(|t| t.timestamp()), Some(1600000000));
+        assert_eq!(
+            filters.tags,
+            Some(HashMap::from([("foo".to_owned(), "bar".to_owned())]))
+        );
+        assert_eq!(filters.status, Some(ClassStatus::RealTime));
+        assert_eq!(filters.limit, Some(10));
+    }
+}
@@ -2,29 +2,36 @@ use uuid::Uuid;
 
 use crate::{
     app::turn_host::TurnHost,
-    db::class::{self, KeyValueProperties},
+    db::class::{self, ClassStatus, KeyValueProperties},
 };
 
 use super::{find, find_by_scope, find_class_by_scope, AppResult};
 
 pub use commit_edition::commit_edition;
-pub use create_timestamp::create_timestamp;
+pub use create_timestamp::{create_timestamp, TimestampPayload};
+pub use delete::delete;
+pub use list::{list, ListFilters, ListResponseBody};
+pub use postprocessing::list_postprocessing_jobs;
 pub use properties::{read_property, update_property};
-pub use read::{read, read_by_scope};
-pub use recreate::recreate;
+pub use read::{read, read_by_scope, PropertyFilters};
+pub use recreate::{recreate, ClassRecreatePayload};
 use serde::Serialize;
 use serde_json::Value;
-pub use update::{update, update_by_scope};
+pub use update::{update, update_by_scope, ClassUpdate};
+use utoipa::ToSchema;
 
 mod commit_edition;
 mod create_timestamp;
+mod delete;
+mod list;
+mod postprocessing;
 mod properties;
 mod read;
 mod recreate;
 mod update;
 
-#[derive(Serialize)]
-struct ClassResponseBody {
+#[derive(Serialize, ToSchema)]
+pub struct ClassResponseBody {
     class_id: Uuid,
     id: String,
     real_time: RealTimeObject,
@@ -35,6 +42,7 @@ struct ClassResponseBody {
     timed_out: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     position: Option<i32>,
+    #[schema(value_type = String)]
     turn_host: TurnHost,
     content_id: String,
     properties: KeyValueProperties,
@@ -108,7 +116,7 @@ impl ClassResponseBody {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ClassroomVersion {
     version: &'static str,
     event_room_id: Uuid,
@@ -121,7 +129,7 @@ pub struct ClassroomVersion {
     room_events_uri: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct RealTimeObject {
     conference_room_id: Uuid,
     event_room_id: Uuid,
@@ -131,16 +139,6 @@ pub struct RealTimeObject {
     rtc_id: Option<Uuid>,
 }
 
-#[derive(Serialize, Clone, Copy)]
-#[serde(rename_all = "kebab-case")]
-enum ClassStatus {
-    Transcoded,
-    Adjusted,
-    Finished,
-    RealTime,
-    Closed,
-}
-
 impl RealTimeObject {
     pub fn set_rtc_id(&mut self, rtc_id: Uuid) {
         self.rtc_id = Some(rtc_id);
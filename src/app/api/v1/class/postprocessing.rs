@@ -0,0 +1,175 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::{Extension, Path};
+use hyper::{Body, Response};
+use svc_authn::AccountId;
+use svc_utils::extractors::AccountIdExtractor;
+use uuid::Uuid;
+
+use super::AppResult;
+use crate::app::error::ErrorExt;
+use crate::app::error::ErrorKind as AppErrorKind;
+use crate::app::AppContext;
+use crate::app::{authz::AuthzObject, metrics::AuthorizeMetrics};
+
+pub async fn list_postprocessing_jobs(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    Path(id): Path<Uuid>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+) -> AppResult {
+    do_list_postprocessing_jobs(ctx.as_ref(), &account_id, id).await
+}
+
+async fn do_list_postprocessing_jobs(
+    state: &dyn AppContext,
+    account_id: &AccountId,
+    id: Uuid,
+) -> AppResult {
+    let mut conn = state
+        .get_conn()
+        .await
+        .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+    let class = crate::db::class::ReadQuery::by_id(id)
+        .execute(&mut conn)
+        .await
+        .context("Failed to find class")
+        .error(AppErrorKind::DbQueryFailed)?
+        .ok_or_else(|| anyhow!("Class not found, id = {}", id))
+        .error(AppErrorKind::ClassNotFound)?;
+
+    let object = AuthzObject::new(&["classrooms", &class.id().to_string()]).into();
+    state
+        .authz()
+        .authorize(
+            class.audience().to_owned(),
+            account_id.clone(),
+            object,
+            "read".into(),
+        )
+        .await
+        .measure()?;
+
+    let jobs = crate::db::postprocessing_job::ListQuery::new(class.id())
+        .execute(&mut conn)
+        .await
+        .context("Failed to list postprocessing jobs")
+        .error(AppErrorKind::DbQueryFailed)?;
+
+    let body = serde_json::to_string(&jobs)
+        .context("Failed to serialize postprocessing jobs")
+        .error(AppErrorKind::SerializationFailed)?;
+
+    let response = Response::builder().body(Body::from(body)).unwrap();
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use serde_json::Value as JsonValue;
+
+    use super::*;
+    use crate::clients::tq::Priority;
+    use crate::db::postprocessing_job::{CompleteQuery, UpsertQuery};
+    use crate::test_helpers::prelude::*;
+
+    #[tokio::test]
+    async fn list_postprocessing_jobs_unauthorized() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let db_pool = TestDb::new().await;
+
+        let webinar = {
+            let mut conn = db_pool.get_conn().await;
+            factory::Webinar::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                (Bound::Unbounded, Bound::Unbounded).into(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+            )
+            .insert(&mut conn)
+            .await
+        };
+
+        let state = TestState::new_with_pool(db_pool, TestAuthz::new());
+
+        do_list_postprocessing_jobs(&state, agent.account_id(), webinar.id())
+            .await
+            .expect_err("Unexpectedly succeeded");
+    }
+
+    #[tokio::test]
+    async fn list_postprocessing_jobs() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let db_pool = TestDb::new().await;
+        let stream_id = Uuid::new_v4();
+
+        let webinar = {
+            let mut conn = db_pool.get_conn().await;
+            let webinar = factory::Webinar::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                (Bound::Unbounded, Bound::Unbounded).into(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+            )
+            .insert(&mut conn)
+            .await;
+
+            for _ in 0..2 {
+                UpsertQuery::new(
+                    webinar.id(),
+                    "convert-mjr-dumps-to-stream".to_owned(),
+                    Some(stream_id),
+                    Priority::Normal,
+                )
+                .retry()
+                .execute(&mut conn)
+                .await
+                .expect("Failed to insert postprocessing job");
+            }
+
+            let failed = CompleteQuery::failed(
+                webinar.id(),
+                "convert-mjr-dumps-to-stream".to_owned(),
+                Some(stream_id),
+                serde_json::json!({"kind": "oops"}),
+            )
+            .execute(&mut conn)
+            .await
+            .expect("Failed to complete postprocessing job")
+            .expect("Missing postprocessing job");
+            assert_eq!(failed.template(), "convert-mjr-dumps-to-stream");
+            assert_eq!(failed.stream_id(), Some(stream_id));
+
+            webinar
+        };
+
+        let mut authz = TestAuthz::new();
+        authz.allow(
+            agent.account_id(),
+            vec!["classrooms", &webinar.id().to_string()],
+            "read",
+        );
+
+        let state = TestState::new_with_pool(db_pool, authz);
+
+        let r = do_list_postprocessing_jobs(&state, agent.account_id(), webinar.id())
+            .await
+            .expect("Failed to list postprocessing jobs");
+
+        let r = hyper::body::to_bytes(r.into_body()).await.unwrap();
+        let v = serde_json::from_slice::<JsonValue>(&r[..]).expect("Failed to parse json");
+        let jobs = v.as_array().expect("Expected an array");
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0]["template"], "convert-mjr-dumps-to-stream");
+        assert_eq!(jobs[0]["attempts"], 2);
+        assert_eq!(jobs[0]["status"], "failed");
+        assert_eq!(jobs[0]["priority"], "normal");
+        assert_eq!(jobs[0]["last_error"]["kind"], "oops");
+        assert!(jobs[0]["completed_at"].is_i64());
+    }
+}
@@ -3,12 +3,12 @@ use std::sync::Arc;
 use anyhow::Context;
 use axum::extract::RawQuery;
 use axum::extract::{Extension, Path};
-use chrono::Utc;
 use hyper::{Body, Response};
 use serde::Deserialize;
 use svc_authn::AccountId;
 use svc_utils::extractors::AccountIdExtractor;
 use tracing::error;
+use utoipa::IntoParams;
 use uuid::Uuid;
 
 use super::*;
@@ -18,7 +18,8 @@ use crate::app::AppContext;
 use crate::app::{authz::AuthzObject, metrics::AuthorizeMetrics};
 use crate::db::class::{AsClassType, Object as Class};
 
-#[derive(Default, Debug, PartialEq, Eq, Deserialize)]
+#[derive(Default, Debug, PartialEq, Eq, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PropertyFilters {
     class_keys: Option<Vec<String>>,
     account_keys: Option<Vec<String>>,
@@ -142,8 +143,7 @@ async fn do_read_inner<T: AsClassType>(
         );
     }
 
-    let class_end = class.time().end();
-    if let Some(recording) = recordings.first() {
+    if let Some(recording) = crate::db::recording::main_recording(&recordings) {
         // BEWARE: the order is significant
         // as of now its expected that modified version is second
         if let Some(og_event_id) = class.original_event_room_id() {
@@ -158,7 +158,7 @@ async fn do_read_inner<T: AsClassType>(
 
         class_body.set_rtc_id(recording.rtc_id());
 
-        if recording.transcoded_at().is_some() {
+        if class.status() == ClassStatus::Transcoded {
             if let Some(md_event_id) = class.modified_event_room_id() {
                 class_body.add_version(ClassroomVersion {
                     version: "modified",
@@ -185,19 +185,11 @@ async fn do_read_inner<T: AsClassType>(
                     class_body.set_position(pos);
                 }
             }
-
-            class_body.set_status(ClassStatus::Transcoded);
-        } else if recording.adjusted_at().is_some() {
-            class_body.set_status(ClassStatus::Adjusted);
-        } else {
-            class_body.set_status(ClassStatus::Finished);
         }
-    } else if class_end.map(|t| Utc::now() > *t).unwrap_or(false) {
-        class_body.set_status(ClassStatus::Closed);
-    } else {
-        class_body.set_status(ClassStatus::RealTime);
     }
 
+    class_body.set_status(class.status());
+
     let body = serde_json::to_string(&class_body)
         .context("Failed to serialize minigroup")
         .error(AppErrorKind::SerializationFailed)?;
@@ -214,6 +206,7 @@ mod tests {
         db::{
             account::UpsertQuery,
             class::{P2PType, WebinarType},
+            recording::{AdjustMinigroupUpdateQuery, HostSelection},
         },
         test_helpers::prelude::*,
     };
@@ -282,6 +275,81 @@ mod tests {
         assert_eq!(v.get("turn_host").unwrap().as_str(), Some("turn0"));
     }
 
+    #[tokio::test]
+    async fn read_webinar_responds_with_host_recording() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let host = TestAgent::new("web", "host", USR_AUDIENCE);
+        let co_presenter = TestAgent::new("web", "co-presenter", USR_AUDIENCE);
+        let db_pool = TestDb::new().await;
+        let now = Utc::now();
+        let host_rtc_id = Uuid::new_v4();
+
+        let webinar = {
+            let mut conn = db_pool.get_conn().await;
+            let webinar = factory::Webinar::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                (Bound::Unbounded, Bound::Unbounded).into(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+            )
+            .insert(&mut conn)
+            .await;
+
+            // The co-presenter has started publishing first.
+            factory::Recording::new(
+                webinar.id(),
+                Uuid::new_v4(),
+                co_presenter.agent_id().to_owned(),
+            )
+            .started_at(now - chrono::Duration::hours(1))
+            .insert(&mut conn)
+            .await;
+
+            factory::Recording::new(webinar.id(), host_rtc_id, host.agent_id().to_owned())
+                .started_at(now - chrono::Duration::minutes(50))
+                .insert(&mut conn)
+                .await;
+
+            AdjustMinigroupUpdateQuery::new(
+                webinar.id(),
+                vec![(Bound::Included(0), Bound::Excluded(1000))].into(),
+                host.agent_id().to_owned(),
+            )
+            .host_selection(HostSelection::HostEvent)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to adjust recordings");
+
+            webinar
+        };
+
+        let mut authz = TestAuthz::new();
+        authz.allow(
+            agent.account_id(),
+            vec!["classrooms", &webinar.id().to_string()],
+            "read",
+        );
+
+        let state = TestState::new_with_pool(db_pool, authz);
+
+        let r = do_read::<WebinarType>(
+            &state,
+            agent.account_id(),
+            webinar.id(),
+            PropertyFilters::default(),
+        )
+        .await
+        .expect("Failed to read webinar");
+
+        let r = hyper::body::to_bytes(r.into_body()).await.unwrap();
+        let v = serde_json::from_slice::<Value>(&r[..]).expect("Failed to parse json");
+        assert_eq!(
+            v["real_time"]["rtc_id"].as_str(),
+            Some(host_rtc_id.to_string().as_str())
+        );
+    }
+
     #[tokio::test]
     async fn read_p2p() {
         let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
@@ -8,6 +8,7 @@ use serde_derive::Deserialize;
 use sqlx::Acquire;
 use svc_authn::AccountId;
 use svc_utils::extractors::AccountIdExtractor;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 use super::{find, AppResult};
@@ -19,12 +20,13 @@ use crate::app::AppContext;
 use crate::app::{authz::AuthzObject, metrics::AuthorizeMetrics};
 use crate::clients::event::LockedTypes;
 use crate::db::class;
-use crate::db::class::AsClassType;
 use crate::db::class::BoundedDateTimeTuple;
+use crate::db::class::{AsClassType, ClassStatus};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct ClassRecreatePayload {
     #[serde(default, with = "crate::serde::ts_seconds_option_bound_tuple")]
+    #[schema(value_type = Option<BoundedTime>)]
     time: Option<BoundedDateTimeTuple>,
     #[serde(default = "class::default_locked_chat")]
     locked_chat: bool,
@@ -95,6 +97,14 @@ async fn do_recreate<T: AsClassType>(
             .context("Failed to acquire transaction")
             .error(AppErrorKind::DbQueryFailed)?;
 
+        // Recreated class starts its lifecycle over.
+        crate::db::class::UpdateStatusQuery::new(webinar.id(), ClassStatus::RealTime)
+            .force()
+            .execute(&mut txn)
+            .await
+            .context("Failed to reset class status")
+            .error(AppErrorKind::DbQueryFailed)?;
+
         let webinar = query
             .execute(&mut txn)
             .await
@@ -9,11 +9,13 @@ use serde_derive::Deserialize;
 use svc_agent::AgentId;
 use svc_authn::AccountId;
 use svc_utils::extractors::AccountIdExtractor;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 use super::{find, AppResult, ClassResponseBody};
 use crate::app::error::ErrorKind as AppErrorKind;
 use crate::app::http::Json;
+use crate::app::webhooks::WebhookEvent;
 use crate::app::{api::v1::find_by_scope, error::ErrorExt};
 use crate::app::{authz::AuthzObject, metrics::AuthorizeMetrics};
 use crate::app::{error, AppContext};
@@ -23,11 +25,13 @@ use crate::clients::{
 use crate::db::class;
 use crate::db::class::{AsClassType, BoundedDateTimeTuple};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct ClassUpdate {
     #[serde(default, with = "crate::serde::ts_seconds_option_bound_tuple")]
+    #[schema(value_type = Option<BoundedTime>)]
     time: Option<BoundedDateTimeTuple>,
     reserve: Option<i32>,
+    #[schema(value_type = Option<String>)]
     host: Option<AgentId>,
 }
 
@@ -125,6 +129,8 @@ async fn do_update<T: AsClassType>(
         .context("Failed to update webinar")
         .error(AppErrorKind::DbQueryFailed)?;
 
+    crate::app::webhooks::enqueue(state, &class, WebhookEvent::Updated).await;
+
     Ok(class)
 }
 
@@ -0,0 +1,308 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::{Extension, Path, Query};
+use hyper::{Body, Response};
+use serde_derive::{Deserialize, Serialize};
+use svc_authn::AccountId;
+use svc_utils::extractors::AccountIdExtractor;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use super::{AppError, AppResult};
+use crate::app::authz::authorize_service;
+use crate::app::dead_letters;
+use crate::app::error::ErrorExt;
+use crate::app::error::ErrorKind as AppErrorKind;
+use crate::app::{AppContext, EventSender};
+use crate::db::dead_letter::{ListQuery, MarkReplayedQuery, Object as DeadLetter, ReadQuery};
+
+const DEFAULT_LIMIT: i64 = 25;
+const MAX_LIMIT: i64 = 100;
+
+#[derive(Default, Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListFilters {
+    label: Option<String>,
+    resolved: Option<bool>,
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[schema(as = DeadLetterList)]
+pub struct ListResponseBody {
+    dead_letters: Vec<DeadLetter>,
+}
+
+pub async fn list(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    Query(filters): Query<ListFilters>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+) -> AppResult {
+    do_list(ctx.as_ref(), &account_id, filters).await
+}
+
+async fn do_list(
+    state: &dyn AppContext,
+    account_id: &AccountId,
+    filters: ListFilters,
+) -> AppResult {
+    authorize_service(state, account_id, &["dead_letters"], "list").await?;
+
+    let limit = filters.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let mut query = ListQuery::new(limit);
+
+    if let Some(label) = filters.label {
+        query = query.label(label);
+    }
+
+    if let Some(resolved) = filters.resolved {
+        query = query.resolved(resolved);
+    }
+
+    let mut conn = state
+        .get_conn()
+        .await
+        .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+    let dead_letters = query
+        .execute(&mut conn)
+        .await
+        .context("Failed to list dead letters")
+        .error(AppErrorKind::DbQueryFailed)?;
+
+    let body = serde_json::to_string(&ListResponseBody { dead_letters })
+        .context("Failed to serialize dead letters")
+        .error(AppErrorKind::SerializationFailed)?;
+
+    let response = Response::builder().body(Body::from(body)).unwrap();
+    Ok(response)
+}
+
+pub async fn read(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    Path(id): Path<Uuid>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+) -> AppResult {
+    do_read(ctx.as_ref(), &account_id, id).await
+}
+
+async fn do_read(state: &dyn AppContext, account_id: &AccountId, id: Uuid) -> AppResult {
+    authorize_service(state, account_id, &["dead_letters"], "read").await?;
+
+    let dead_letter = find_dead_letter(state, id).await?;
+
+    let body = serde_json::to_string(&dead_letter)
+        .context("Failed to serialize dead letter")
+        .error(AppErrorKind::SerializationFailed)?;
+
+    let response = Response::builder().body(Body::from(body)).unwrap();
+    Ok(response)
+}
+
+pub async fn replay(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    Extension(events): Extension<EventSender>,
+    Path(id): Path<Uuid>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+) -> AppResult {
+    do_replay(ctx.as_ref(), &events, &account_id, id).await
+}
+
+async fn do_replay(
+    state: &dyn AppContext,
+    events: &EventSender,
+    account_id: &AccountId,
+    id: Uuid,
+) -> AppResult {
+    authorize_service(state, account_id, &["dead_letters"], "replay").await?;
+
+    let dead_letter = find_dead_letter(state, id).await?;
+
+    // A resolved event would be skipped as a duplicate anyway.
+    if dead_letter.is_resolved() {
+        return Err(anyhow!("Dead letter is already resolved, id = {}", id))
+            .error(AppErrorKind::InvalidParameter);
+    }
+
+    let (event, topic) =
+        dead_letters::to_event(&dead_letter).error(AppErrorKind::InvalidPayload)?;
+
+    // The event goes through the same lanes as the live ones so it's ordered with
+    // the other events of its class. The outcome is recorded on the dead letter.
+    events
+        .send(event, topic)
+        .await
+        .error(AppErrorKind::InternalFailure)?;
+
+    let mut conn = state
+        .get_conn()
+        .await
+        .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+    MarkReplayedQuery::new(dead_letter.id())
+        .execute(&mut conn)
+        .await
+        .context("Failed to mark dead letter replayed")
+        .error(AppErrorKind::DbQueryFailed)?;
+
+    let response = Response::builder()
+        .status(http::StatusCode::ACCEPTED)
+        .body(Body::empty())
+        .unwrap();
+
+    Ok(response)
+}
+
+async fn find_dead_letter(state: &dyn AppContext, id: Uuid) -> Result<DeadLetter, AppError> {
+    let mut conn = state
+        .get_conn()
+        .await
+        .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+    ReadQuery::by_id(id)
+        .execute(&mut conn)
+        .await
+        .context("Failed to find dead letter")
+        .error(AppErrorKind::DbQueryFailed)?
+        .ok_or_else(|| anyhow!("Dead letter not found, id = {}", id))
+        .error(AppErrorKind::DeadLetterNotFound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::task_tracker::TaskTracker;
+    use crate::test_helpers::prelude::*;
+    use serde_json::Value as JsonValue;
+
+    async fn insert_dead_letter(state: &TestState, key: &str, label: &str) -> DeadLetter {
+        let event = incoming_event(label, &serde_json::json!({ "id": Uuid::new_v4() }));
+        let error = AppError::new(
+            AppErrorKind::TranscodingFlowFailed,
+            anyhow!("Class not found"),
+        );
+
+        dead_letters::record(
+            state,
+            key,
+            &event,
+            "apps/conference.dev.svc.example.org/api/v1/events",
+            &error,
+        )
+        .await
+        .expect("Failed to record dead letter")
+    }
+
+    fn allow(agent: &TestAgent, action: &str) -> TestAuthz {
+        let mut authz = TestAuthz::new();
+        authz.set_audience(SVC_AUDIENCE);
+        authz.allow(agent.account_id(), vec!["dead_letters"], action);
+        authz
+    }
+
+    #[tokio::test]
+    async fn list_dead_letters_unauthorized() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let state = TestState::new(TestAuthz::new()).await;
+
+        do_list(&state, agent.account_id(), ListFilters::default())
+            .await
+            .expect_err("Unexpectedly succeeded");
+    }
+
+    #[tokio::test]
+    async fn list_dead_letters_by_label() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let db_pool = TestDb::new().await;
+        let state = TestState::new_with_pool(db_pool, allow(&agent, "list"));
+
+        // Other tests share the database so a random label keeps them apart.
+        let label = random_string();
+        let dead_letter = insert_dead_letter(&state, &random_string(), &label).await;
+        insert_dead_letter(&state, &random_string(), "room.upload").await;
+
+        let r = do_list(
+            &state,
+            agent.account_id(),
+            ListFilters {
+                label: Some(label.clone()),
+                resolved: Some(false),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Failed to list dead letters");
+
+        let r = hyper::body::to_bytes(r.into_body()).await.unwrap();
+        let v = serde_json::from_slice::<JsonValue>(&r[..]).expect("Failed to parse json");
+        let dead_letters = v["dead_letters"].as_array().expect("Expected an array");
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0]["id"], dead_letter.id().to_string());
+        assert_eq!(dead_letters[0]["label"], label);
+        assert_eq!(dead_letters[0]["attempts"], 1);
+        assert_eq!(
+            dead_letters[0]["error"],
+            "Transcoding flow failed: Class not found"
+        );
+    }
+
+    #[tokio::test]
+    async fn read_missing_dead_letter() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let state = TestState::new(allow(&agent, "read")).await;
+
+        do_read(&state, agent.account_id(), Uuid::new_v4())
+            .await
+            .expect_err("Unexpectedly succeeded");
+    }
+
+    #[tokio::test]
+    async fn replay_dead_letter() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let db_pool = TestDb::new().await;
+        let state = TestState::new_with_pool(db_pool, allow(&agent, "replay"));
+        let dead_letter = insert_dead_letter(&state, &random_string(), "room.upload").await;
+        let (tx, mut rx) = EventSender::channel(TaskTracker::default(), 1);
+
+        let r = do_replay(&state, &tx, agent.account_id(), dead_letter.id())
+            .await
+            .expect("Failed to replay dead letter");
+        assert_eq!(r.status(), http::StatusCode::ACCEPTED);
+
+        let (event, topic, _) = rx.try_recv().expect("Event wasn't replayed");
+        assert_eq!(event.payload(), dead_letter.payload());
+        assert_eq!(event.properties().label(), Some("room.upload"));
+        assert_eq!(topic, dead_letter.topic());
+
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+        let dead_letter = ReadQuery::by_id(dead_letter.id())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to read dead letter")
+            .expect("Dead letter not found");
+
+        let v = serde_json::to_value(dead_letter).unwrap();
+        assert!(v["replayed_at"].is_i64());
+    }
+
+    #[tokio::test]
+    async fn replay_resolved_dead_letter() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let db_pool = TestDb::new().await;
+        let state = TestState::new_with_pool(db_pool, allow(&agent, "replay"));
+        let key = random_string();
+        let dead_letter = insert_dead_letter(&state, &key, "room.upload").await;
+
+        dead_letters::resolve(&state, &key)
+            .await
+            .expect("Failed to resolve dead letter");
+
+        let (tx, mut rx) = EventSender::channel(TaskTracker::default(), 1);
+
+        do_replay(&state, &tx, agent.account_id(), dead_letter.id())
+            .await
+            .expect_err("Unexpectedly succeeded");
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,503 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::{Extension, Path};
+use hyper::{Body, Response};
+use serde_derive::{Deserialize, Serialize};
+use sqlx::{Acquire, PgConnection};
+use svc_authn::AccountId;
+use svc_utils::extractors::AccountIdExtractor;
+use url::Url;
+use utoipa::ToSchema;
+
+use super::{AppError, AppResult};
+use crate::app::api::IntoJsonResponse;
+use crate::app::authz::authorize_service;
+use crate::app::error::ErrorExt;
+use crate::app::error::ErrorKind as AppErrorKind;
+use crate::app::http::Json;
+use crate::app::outbox;
+use crate::app::AppContext;
+use crate::db::frontend::{self, Object as Frontend};
+use crate::db::scope::{self, Object as Scope};
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct FrontendPayload {
+    url: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ScopeBindingPayload {
+    frontend_id: i64,
+}
+
+/// Payload of the `scope.frontend.update` event, `frontend` is null once the app is unbound.
+#[derive(Serialize)]
+struct FrontendUpdate<'a> {
+    scope: &'a str,
+    app: &'a str,
+    frontend: Option<&'a Frontend>,
+}
+
+pub async fn create(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+    Json(payload): Json<FrontendPayload>,
+) -> AppResult {
+    do_create(ctx.as_ref(), &account_id, payload).await
+}
+
+async fn do_create(
+    state: &dyn AppContext,
+    account_id: &AccountId,
+    payload: FrontendPayload,
+) -> AppResult {
+    authorize_service(state, account_id, &["frontends"], "create").await?;
+    validate_url(&payload.url)?;
+
+    let mut conn = state
+        .get_conn()
+        .await
+        .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+    // No scope is bound to a new frontend so there's nobody to notify.
+    let frontend = frontend::InsertQuery::new(payload.url)
+        .execute(&mut conn)
+        .await
+        .context("Failed to insert frontend")
+        .error(AppErrorKind::DbQueryFailed)?;
+
+    frontend.into_json_response("Failed to serialize frontend", http::StatusCode::CREATED)
+}
+
+pub async fn update(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    Path(id): Path<i64>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+    Json(payload): Json<FrontendPayload>,
+) -> AppResult {
+    do_update(ctx.as_ref(), &account_id, id, payload).await
+}
+
+async fn do_update(
+    state: &dyn AppContext,
+    account_id: &AccountId,
+    id: i64,
+    payload: FrontendPayload,
+) -> AppResult {
+    authorize_service(state, account_id, &["frontends"], "update").await?;
+    validate_url(&payload.url)?;
+
+    let (frontend, events) = {
+        let mut conn = state
+            .get_conn()
+            .await
+            .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+        let mut txn = conn
+            .begin()
+            .await
+            .context("Failed to begin sqlx db transaction")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        let frontend = frontend::UpdateQuery::new(id, payload.url)
+            .execute(&mut txn)
+            .await
+            .context("Failed to update frontend")
+            .error(AppErrorKind::DbQueryFailed)?
+            .ok_or_else(|| anyhow!("Frontend not found, id = {}", id))
+            .error(AppErrorKind::FrontendNotFound)?;
+
+        let scopes = scope::ListQuery::new()
+            .frontend_id(id)
+            .execute(&mut txn)
+            .await
+            .context("Failed to list frontend scopes")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        let events = push_updates(&mut txn, &scopes, Some(&frontend)).await?;
+
+        txn.commit()
+            .await
+            .context("Failed to commit sqlx db transaction")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        (frontend, events)
+    };
+
+    outbox::publish(state, events).await;
+
+    frontend.into_json_response("Failed to serialize frontend", http::StatusCode::OK)
+}
+
+pub async fn delete(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    Path(id): Path<i64>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+) -> AppResult {
+    do_delete(ctx.as_ref(), &account_id, id).await
+}
+
+async fn do_delete(state: &dyn AppContext, account_id: &AccountId, id: i64) -> AppResult {
+    authorize_service(state, account_id, &["frontends"], "delete").await?;
+
+    let events = {
+        let mut conn = state
+            .get_conn()
+            .await
+            .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+        let mut txn = conn
+            .begin()
+            .await
+            .context("Failed to begin sqlx db transaction")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        // The scopes bound to the frontend go away along with it.
+        let scopes = scope::ListQuery::new()
+            .frontend_id(id)
+            .execute(&mut txn)
+            .await
+            .context("Failed to list frontend scopes")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        frontend::DeleteQuery::new(id)
+            .execute(&mut txn)
+            .await
+            .context("Failed to delete frontend")
+            .error(AppErrorKind::DbQueryFailed)?
+            .ok_or_else(|| anyhow!("Frontend not found, id = {}", id))
+            .error(AppErrorKind::FrontendNotFound)?;
+
+        let events = push_updates(&mut txn, &scopes, None).await?;
+
+        txn.commit()
+            .await
+            .context("Failed to commit sqlx db transaction")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        events
+    };
+
+    outbox::publish(state, events).await;
+
+    Ok(no_content())
+}
+
+pub async fn bind(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    Path((scope, app)): Path<(String, String)>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+    Json(payload): Json<ScopeBindingPayload>,
+) -> AppResult {
+    do_bind(ctx.as_ref(), &account_id, scope, app, payload).await
+}
+
+async fn do_bind(
+    state: &dyn AppContext,
+    account_id: &AccountId,
+    scope: String,
+    app: String,
+    payload: ScopeBindingPayload,
+) -> AppResult {
+    authorize_service(state, account_id, &["scopes"], "update").await?;
+
+    let (binding, events) = {
+        let mut conn = state
+            .get_conn()
+            .await
+            .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+        let mut txn = conn
+            .begin()
+            .await
+            .context("Failed to begin sqlx db transaction")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        let binding = scope::UpsertQuery::new(scope, app, payload.frontend_id)
+            .execute(&mut txn)
+            .await
+            .context("Failed to bind scope")
+            .error(AppErrorKind::DbQueryFailed)?
+            .ok_or_else(|| anyhow!("Frontend not found, id = {}", payload.frontend_id))
+            .error(AppErrorKind::FrontendNotFound)?;
+
+        let frontend =
+            frontend::FrontendByScopeQuery::new(binding.scope.clone(), binding.app.clone())
+                .execute(&mut txn)
+                .await
+                .context("Failed to find frontend by scope")
+                .error(AppErrorKind::DbQueryFailed)?;
+
+        let events = push_updates(&mut txn, &[binding.clone()], frontend.as_ref()).await?;
+
+        txn.commit()
+            .await
+            .context("Failed to commit sqlx db transaction")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        (binding, events)
+    };
+
+    outbox::publish(state, events).await;
+
+    binding.into_json_response("Failed to serialize scope", http::StatusCode::OK)
+}
+
+pub async fn unbind(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    Path((scope, app)): Path<(String, String)>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+) -> AppResult {
+    do_unbind(ctx.as_ref(), &account_id, scope, app).await
+}
+
+async fn do_unbind(
+    state: &dyn AppContext,
+    account_id: &AccountId,
+    scope: String,
+    app: String,
+) -> AppResult {
+    authorize_service(state, account_id, &["scopes"], "delete").await?;
+
+    let events = {
+        let mut conn = state
+            .get_conn()
+            .await
+            .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+        let mut txn = conn
+            .begin()
+            .await
+            .context("Failed to begin sqlx db transaction")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        let bindings = scope::DeleteQuery::new(scope.clone())
+            .app(app.clone())
+            .execute(&mut txn)
+            .await
+            .context("Failed to unbind scope")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        if bindings.is_empty() {
+            return Err(anyhow!("Scope not found, scope = {}, app = {}", scope, app))
+                .error(AppErrorKind::ScopeNotFound);
+        }
+
+        let events = push_updates(&mut txn, &bindings, None).await?;
+
+        txn.commit()
+            .await
+            .context("Failed to commit sqlx db transaction")
+            .error(AppErrorKind::DbQueryFailed)?;
+
+        events
+    };
+
+    outbox::publish(state, events).await;
+
+    Ok(no_content())
+}
+
+fn validate_url(url: &str) -> Result<(), AppError> {
+    Url::parse(url)
+        .with_context(|| format!("Invalid frontend url = {}", url))
+        .error(AppErrorKind::InvalidPayload)?;
+
+    Ok(())
+}
+
+async fn push_updates(
+    conn: &mut PgConnection,
+    scopes: &[Scope],
+    frontend: Option<&Frontend>,
+) -> Result<Vec<outbox::Event>, AppError> {
+    let mut events = Vec::with_capacity(scopes.len());
+
+    for scope in scopes {
+        let path = format!("scopes/{}/events", scope.scope);
+        let update = FrontendUpdate {
+            scope: &scope.scope,
+            app: &scope.app,
+            frontend,
+        };
+
+        let event = outbox::push(conn, "scope.frontend.update", &path, &update)
+            .await
+            .error(AppErrorKind::DbQueryFailed)?;
+        events.push(event);
+    }
+
+    Ok(events)
+}
+
+fn no_content() -> Response<Body> {
+    Response::builder()
+        .status(http::StatusCode::NO_CONTENT)
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value as JsonValue;
+
+    use super::*;
+    use crate::test_helpers::prelude::*;
+
+    fn allow(agent: &TestAgent, object: &str, action: &str) -> TestAuthz {
+        let mut authz = TestAuthz::new();
+        authz.set_audience(SVC_AUDIENCE);
+        authz.allow(agent.account_id(), vec![object], action);
+        authz
+    }
+
+    async fn insert_frontend(state: &TestState) -> Frontend {
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+
+        factory::Frontend::new("https://beta.example.org".into())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to seed frontend")
+    }
+
+    fn published_update(state: &TestState, scope: &str) -> JsonValue {
+        let messages = state.test_publisher().flush();
+        assert_eq!(messages.len(), 1);
+
+        match messages[0].properties() {
+            OutgoingEnvelopeProperties::Event(props) => {
+                assert_eq!(props.label(), "scope.frontend.update")
+            }
+            _ => panic!("Expected an event"),
+        }
+
+        assert!(messages[0]
+            .topic()
+            .ends_with(&format!("scopes/{}/events", scope)));
+
+        messages[0].payload::<JsonValue>()
+    }
+
+    #[tokio::test]
+    async fn create_frontend_unauthorized() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let state = TestState::new(TestAuthz::new()).await;
+
+        let payload = FrontendPayload {
+            url: "https://beta.example.org".into(),
+        };
+
+        do_create(&state, agent.account_id(), payload)
+            .await
+            .expect_err("Unexpectedly succeeded");
+    }
+
+    #[tokio::test]
+    async fn create_frontend_with_invalid_url() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let state = TestState::new(allow(&agent, "frontends", "create")).await;
+
+        let payload = FrontendPayload { url: "beta".into() };
+
+        do_create(&state, agent.account_id(), payload)
+            .await
+            .expect_err("Unexpectedly succeeded");
+    }
+
+    #[tokio::test]
+    async fn bind_scope_to_frontend() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let state = TestState::new(allow(&agent, "scopes", "update")).await;
+        let frontend = insert_frontend(&state).await;
+        let scope = random_string();
+
+        let r = do_bind(
+            &state,
+            agent.account_id(),
+            scope.clone(),
+            "webinar".into(),
+            ScopeBindingPayload {
+                frontend_id: frontend.id,
+            },
+        )
+        .await
+        .expect("Failed to bind scope");
+        assert_eq!(r.status(), http::StatusCode::OK);
+
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+        let bound = frontend::FrontendByScopeQuery::new(scope.clone(), "webinar".into())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to find frontend")
+            .expect("Scope isn't bound");
+        assert_eq!(bound.id, frontend.id);
+
+        let update = published_update(&state, &scope);
+        assert_eq!(update["app"], "webinar");
+        assert_eq!(update["frontend"]["url"], "https://beta.example.org");
+    }
+
+    #[tokio::test]
+    async fn bind_scope_to_missing_frontend() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let state = TestState::new(allow(&agent, "scopes", "update")).await;
+
+        do_bind(
+            &state,
+            agent.account_id(),
+            random_string(),
+            "webinar".into(),
+            ScopeBindingPayload { frontend_id: -1 },
+        )
+        .await
+        .expect_err("Unexpectedly succeeded");
+
+        assert!(state.test_publisher().flush().is_empty());
+    }
+
+    #[tokio::test]
+    async fn delete_frontend_unbinds_its_scopes() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let state = TestState::new(allow(&agent, "frontends", "delete")).await;
+        let frontend = insert_frontend(&state).await;
+        let scope = random_string();
+
+        {
+            let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+
+            factory::Scope::new(scope.clone(), frontend.id, "minigroup".into())
+                .execute(&mut conn)
+                .await
+                .expect("Failed to seed scope");
+        }
+
+        let r = do_delete(&state, agent.account_id(), frontend.id)
+            .await
+            .expect("Failed to delete frontend");
+        assert_eq!(r.status(), http::StatusCode::NO_CONTENT);
+
+        let update = published_update(&state, &scope);
+        assert_eq!(update["app"], "minigroup");
+        assert!(update["frontend"].is_null());
+
+        do_delete(&state, agent.account_id(), frontend.id)
+            .await
+            .expect_err("Deleted frontend twice");
+    }
+
+    #[tokio::test]
+    async fn unbind_missing_scope() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let state = TestState::new(allow(&agent, "scopes", "delete")).await;
+
+        do_unbind(
+            &state,
+            agent.account_id(),
+            random_string(),
+            "webinar".into(),
+        )
+        .await
+        .expect_err("Unexpectedly succeeded");
+    }
+}
@@ -9,6 +9,7 @@ use serde_derive::Deserialize;
 use svc_agent::AccountId;
 use svc_utils::extractors::AccountIdExtractor;
 use tracing::{error, info, instrument};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::app::authz::AuthzObject;
@@ -18,6 +19,7 @@ use crate::app::http::Json;
 use crate::app::metrics::AuthorizeMetrics;
 use crate::app::services;
 use crate::app::services::lock_interaction;
+use crate::app::webhooks::{self, WebhookEvent};
 use crate::app::AppContext;
 use crate::clients::event::LockedTypes;
 use crate::clients::tq::Priority;
@@ -28,11 +30,12 @@ use crate::db::class::{self, BoundedDateTimeTuple};
 use super::AppError;
 use super::AppResult;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct MinigroupCreatePayload {
     scope: String,
     audience: String,
     #[serde(default, with = "crate::serde::ts_seconds_option_bound_tuple")]
+    #[schema(value_type = Option<BoundedTime>)]
     time: Option<BoundedDateTimeTuple>,
     tags: Option<serde_json::Value>,
     #[serde(default)]
@@ -107,11 +110,13 @@ async fn do_create(
         Ok((event_id, conference_id)) => {
             info!(?event_id, ?conference_id, "Created rooms",);
 
-            class::EstablishQuery::new(dummy.id(), event_id, conference_id)
+            let class = class::EstablishQuery::new(dummy.id(), event_id, conference_id)
                 .execute(&mut conn)
                 .await
                 .context("Failed to establish webinar dummy")
                 .error(AppErrorKind::DbQueryFailed)?;
+
+            webhooks::enqueue(state, &class, WebhookEvent::Created).await;
             event_id
         }
         Err(e) => {
@@ -182,7 +187,7 @@ async fn insert_minigroup_dummy(
         .ok_or_else(|| AppError::from(AppErrorKind::ClassAlreadyEstablished))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct RestartTranscodingPayload {
     priority: Priority,
 }
@@ -2,13 +2,14 @@ use std::sync::Arc;
 
 use anyhow::Context;
 use axum::extract::{Extension, Path, Query};
+use http::StatusCode;
 use hyper::{Body, Request, Response};
 use percent_encoding::{percent_encode, NON_ALPHANUMERIC};
 use serde_derive::Deserialize;
 use serde_json::Value as JsonValue;
 use svc_utils::extractors::AccountIdExtractor;
 use tracing::error;
-use url::Url;
+use utoipa::IntoParams;
 use uuid::Uuid;
 
 use super::FEATURE_POLICY;
@@ -16,6 +17,9 @@ use super::FEATURE_POLICY;
 use crate::app::error::ErrorExt;
 use crate::app::error::ErrorKind as AppErrorKind;
 use crate::app::http::Json;
+use crate::app::metrics::RedirectMetrics;
+use crate::app::readiness::Readiness;
+use crate::app::rollout;
 use crate::app::AppContext;
 use crate::app::{authz::AuthzObject, metrics::AuthorizeMetrics};
 use crate::db::class::AsClassType;
@@ -27,6 +31,30 @@ pub async fn healthz() -> &'static str {
     "Ok"
 }
 
+pub async fn readyz(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    Extension(readiness): Extension<Readiness>,
+) -> AppResult {
+    let report = readiness.check(ctx.as_ref()).await;
+
+    let status = if report.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    let body = serde_json::to_string(&report)
+        .context("Failed to serialize readiness report")
+        .error(AppErrorKind::SerializationFailed)?;
+
+    let response = Response::builder()
+        .status(status)
+        .body(Body::from(body))
+        .unwrap();
+
+    Ok(response)
+}
+
 pub async fn create_event(
     Extension(ctx): Extension<Arc<dyn AppContext>>,
     Path(id): Path<Uuid>,
@@ -99,7 +127,8 @@ async fn find_class_by_scope(
     Ok(webinar)
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct RedirQuery {
     pub scope: String,
 }
@@ -110,35 +139,32 @@ pub async fn redirect_to_frontend(
     Query(query): Query<RedirQuery>,
     request: Request<Body>,
 ) -> AppResult {
-    let conn = ctx.get_conn().await;
-    let base_url = match conn {
+    let frontend = match ctx.get_conn().await {
         Err(e) => {
             error!("Failed to acquire conn: {:?}", e);
             None
         }
         Ok(mut conn) => {
-            let fe =
-                crate::db::frontend::FrontendByScopeQuery::new(query.scope.clone(), app.clone())
-                    .execute(&mut conn)
-                    .await;
-            match fe {
+            match rollout::find_frontend(&mut conn, &tenant, &app, &query.scope).await {
                 Err(e) => {
                     error!("Failed to find frontend: {:?}", e);
                     None
                 }
-                Ok(Some(frontend)) => {
-                    let u = Url::parse(&frontend.url);
-                    u.ok()
-                }
-                Ok(None) => None,
+                Ok(frontend) => frontend,
             }
         }
     };
 
-    let mut url = base_url
-        .map(Result::Ok)
-        .unwrap_or_else(|| ctx.build_default_frontend_url(&tenant, &app))
-        .map_err(|e| AppError::new(AppErrorKind::MissingTenant, e))?;
+    let (source, url) = match frontend {
+        Some((source, url)) => (source, Ok(url)),
+        None => (
+            rollout::Source::Default,
+            ctx.build_default_frontend_url(&tenant, &app),
+        ),
+    };
+
+    let mut url = url.map_err(|e| AppError::new(AppErrorKind::MissingTenant, e))?;
+    RedirectMetrics::observe(source.as_str());
 
     url.set_query(request.uri().query());
 
@@ -194,10 +220,16 @@ async fn find_by_scope<T: AsClassType>(
 }
 
 pub mod account;
+pub mod audit_log;
 pub mod authz;
 pub mod class;
+pub mod dead_letters;
+pub mod frontends;
 pub mod minigroup;
+pub mod openapi;
 pub mod p2p;
+pub mod rollouts;
 #[cfg(test)]
 mod tests;
+pub mod webhooks;
 pub mod webinar;
@@ -0,0 +1,774 @@
+//! OpenAPI document of the HTTP API. Schemas are derived from the handler payload and response
+//! types, operations are listed below in the same groups `app::http` registers the routes in.
+//! `tests::documents_every_route` fails for a route that is registered but not listed here.
+
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+
+use hyper::{Body, Response};
+use once_cell::sync::Lazy;
+use utoipa::openapi::path::{
+    Operation, OperationBuilder, Parameter, ParameterBuilder, ParameterIn, PathItem, PathItemType,
+};
+use utoipa::openapi::request_body::RequestBodyBuilder;
+use utoipa::openapi::{
+    ArrayBuilder, ContentBuilder, KnownFormat, ObjectBuilder, OpenApi as Document, Ref, RefOr,
+    Required, ResponseBuilder, Schema, SchemaFormat, SchemaType,
+};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+use super::{
+    audit_log, authz, class, dead_letters, frontends, minigroup, p2p, rollouts, webhooks, webinar,
+};
+use crate::app::error::ErrorKind;
+use crate::app::{info, readiness};
+use crate::clients::tq::Priority;
+use crate::db;
+use crate::serde::BoundedTime;
+
+static DOCUMENT: Lazy<String> =
+    Lazy::new(|| serde_json::to_string(&document()).expect("Failed to serialize OpenAPI document"));
+
+pub async fn openapi() -> Response<Body> {
+    Response::builder()
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(DOCUMENT.as_str()))
+        .unwrap()
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "dispatcher"),
+    components(schemas(
+        BoundedTime,
+        Priority,
+        db::class::KeyValueProperties,
+        db::class::ClassType,
+        db::class::ClassStatus,
+        db::class::Object,
+        db::class::Dummy,
+        db::scope::Object,
+        db::frontend::Object,
+        db::frontend_rollout::Object,
+        db::postprocessing_job::Object,
+        db::postprocessing_job::Status,
+        db::webhook_delivery::Object,
+        db::webhook_delivery::Attempt,
+        db::webhook_delivery::Status,
+        db::dead_letter::Object,
+        db::audit_log::Object,
+        readiness::Report,
+        readiness::Component,
+        class::ClassResponseBody,
+        class::ClassroomVersion,
+        class::RealTimeObject,
+        class::ClassUpdate,
+        class::ClassRecreatePayload,
+        class::TimestampPayload,
+        class::ListResponseBody,
+        webinar::WebinarCreatePayload,
+        webinar::ReplicaCreatePayload,
+        webinar::WebinarConvertObject,
+        webinar::RestartTranscodingPayload,
+        minigroup::MinigroupCreatePayload,
+        p2p::P2PCreatePayload,
+        p2p::P2PConvertObject,
+        authz::AuthzRequest,
+        webhooks::ListResponseBody,
+        dead_letters::ListResponseBody,
+        audit_log::ListResponseBody,
+        frontends::FrontendPayload,
+        frontends::ScopeBindingPayload,
+        rollouts::RolloutPayload,
+        rollouts::ListResponseBody,
+        info::ScopeList,
+        info::FrontendList,
+    ))
+)]
+struct ApiDoc;
+
+pub(crate) fn document() -> Document {
+    let mut document = ApiDoc::openapi();
+    let mut paths = BTreeMap::<String, PathItem>::new();
+
+    let groups = [
+        ("redirects", redirects_routes()),
+        ("webinars", webinars_routes()),
+        ("p2p", p2p_routes()),
+        ("minigroups", minigroups_routes()),
+        ("authz", authz_routes()),
+        ("utils", utils_routes()),
+    ];
+
+    for (tag, routes) in groups {
+        for route in routes {
+            let (method, path, operation) = route.into_operation(tag);
+
+            match paths.entry(path) {
+                Entry::Occupied(mut item) => {
+                    item.get_mut().operations.insert(method, operation);
+                }
+                Entry::Vacant(item) => {
+                    item.insert(PathItem::new(method, operation));
+                }
+            }
+        }
+    }
+
+    document.paths.paths = paths;
+
+    let schemas = &mut document
+        .components
+        .get_or_insert_with(Default::default)
+        .schemas;
+    schemas.insert("Error".to_owned(), error_schema().into());
+    schemas.insert("Download".to_owned(), download_schema().into());
+
+    document
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+fn redirects_routes() -> Vec<Route> {
+    use PathItemType::*;
+
+    vec![
+        Route::new(Get, "/info/scopes", "List scopes")
+            .query::<info::ScopeFilters>()
+            .returns(200, Content::json::<info::ScopeList>())
+            .returns(200, Content::Text),
+        Route::new(Get, "/info/frontends", "List frontends")
+            .query::<info::FrontendFilters>()
+            .returns(200, Content::json::<info::FrontendList>())
+            .returns(200, Content::Text),
+        Route::new(Get, "/healthz", "Liveness probe")
+            .returns(200, Content::Text)
+            .text_errors(),
+        Route::new(Get, "/readyz", "Readiness probe")
+            .returns(200, Content::json::<readiness::Report>())
+            .returns(503, Content::json::<readiness::Report>()),
+        Route::new(
+            Post,
+            "/api/v1/scopes/{scope}/rollback",
+            "Roll the scope back",
+        )
+        .returns(200, Content::Text)
+        .text_errors(),
+        Route::new(
+            Put,
+            "/api/v1/scopes/{scope}/apps/{app}",
+            "Bind the scope app to a frontend",
+        )
+        .body(Content::json::<frontends::ScopeBindingPayload>())
+        .returns(200, Content::json::<db::scope::Object>()),
+        Route::new(
+            Delete,
+            "/api/v1/scopes/{scope}/apps/{app}",
+            "Unbind the scope app",
+        )
+        .returns(204, Content::Empty),
+        Route::new(Post, "/api/v1/frontends", "Create frontend")
+            .body(Content::json::<frontends::FrontendPayload>())
+            .returns(201, Content::json::<db::frontend::Object>()),
+        Route::new(Put, "/api/v1/frontends/{frontend_id}", "Update frontend")
+            .body(Content::json::<frontends::FrontendPayload>())
+            .returns(200, Content::json::<db::frontend::Object>()),
+        Route::new(
+            Delete,
+            "/api/v1/frontends/{frontend_id}",
+            "Delete frontend with its scopes",
+        )
+        .returns(204, Content::Empty),
+        Route::new(Get, "/api/v1/rollouts", "List frontend rollouts")
+            .returns(200, Content::json::<rollouts::ListResponseBody>()),
+        Route::new(
+            Put,
+            "/api/v1/rollouts/tenants/{tenant}/apps/{app}",
+            "Roll the frontend out to a percent of the tenant app scopes",
+        )
+        .body(Content::json::<rollouts::RolloutPayload>())
+        .returns(200, Content::json::<db::frontend_rollout::Object>()),
+        Route::new(
+            Delete,
+            "/api/v1/rollouts/tenants/{tenant}/apps/{app}",
+            "Stop the frontend rollout",
+        )
+        .returns(204, Content::Empty),
+        Route::new(Get, "/api/v1/redirs", "Redirect to the scope frontend")
+            .query::<super::RedirQuery>()
+            .returns(307, Content::Empty),
+        Route::new(
+            Get,
+            "/api/v1/redirs/tenants/{tenant}/apps/{app}",
+            "Redirect to the scope frontend",
+        )
+        .query::<super::RedirQuery>()
+        .returns(307, Content::Empty),
+        Route::new(Get, "/api/v1/openapi.json", "This document")
+            .returns(200, Content::Any)
+            .text_errors(),
+    ]
+}
+
+fn webinars_routes() -> Vec<Route> {
+    use PathItemType::*;
+
+    let mut routes = class_routes("webinars");
+    routes.extend(vec![
+        Route::new(Put, "/api/v1/webinars/{id}", "Update webinar")
+            .body(Content::json::<class::ClassUpdate>())
+            .returns(200, Content::json::<db::class::Object>()),
+        Route::new(Post, "/api/v1/webinars/{id}/timestamps", "Save position")
+            .body(Content::json::<class::TimestampPayload>())
+            .returns(201, Content::Empty),
+        Route::new(Post, "/api/v1/webinars", "Create webinar")
+            .body(Content::json::<webinar::WebinarCreatePayload>())
+            .returns(201, Content::json::<db::class::Dummy>()),
+        Route::new(
+            Post,
+            "/api/v1/webinars/{id}/replicas",
+            "Create webinar replica",
+        )
+        .body(Content::json::<webinar::ReplicaCreatePayload>())
+        .returns(201, Content::json::<db::class::Dummy>()),
+        Route::new(Post, "/api/v1/webinars/convert", "Convert webinar")
+            .body(Content::json::<webinar::WebinarConvertObject>())
+            .returns(201, Content::json::<db::class::Object>()),
+        Route::new(Post, "/api/v1/webinars/{id}/recreate", "Recreate webinar")
+            .body(Content::json::<class::ClassRecreatePayload>())
+            .returns(200, Content::json::<db::class::Object>()),
+    ]);
+
+    routes
+}
+
+fn p2p_routes() -> Vec<Route> {
+    use PathItemType::*;
+
+    let mut routes = class_routes("p2p");
+    routes.extend(vec![
+        Route::new(Post, "/api/v1/p2p", "Create p2p")
+            .body(Content::json::<p2p::P2PCreatePayload>())
+            .returns(201, Content::json::<db::class::Dummy>()),
+        Route::new(Post, "/api/v1/p2p/convert", "Convert p2p")
+            .body(Content::json::<p2p::P2PConvertObject>())
+            .returns(201, Content::json::<db::class::Object>()),
+    ]);
+
+    routes
+}
+
+fn minigroups_routes() -> Vec<Route> {
+    use PathItemType::*;
+
+    let mut routes = class_routes("minigroups");
+    routes.extend(vec![
+        Route::new(Put, "/api/v1/minigroups/{id}", "Update minigroup")
+            .body(Content::json::<class::ClassUpdate>())
+            .returns(200, Content::json::<db::class::Object>()),
+        Route::new(
+            Put,
+            "/api/v1/audiences/{audience}/minigroups/{scope}",
+            "Update minigroup by scope",
+        )
+        .body(Content::json::<class::ClassUpdate>())
+        .returns(200, Content::json::<class::ClassResponseBody>()),
+        Route::new(Post, "/api/v1/minigroups/{id}/timestamps", "Save position")
+            .body(Content::json::<class::TimestampPayload>())
+            .returns(201, Content::Empty),
+        Route::new(
+            Post,
+            "/api/v1/minigroups/{id}/recreate",
+            "Recreate minigroup",
+        )
+        .body(Content::json::<class::ClassRecreatePayload>())
+        .returns(200, Content::json::<db::class::Object>()),
+        Route::new(Post, "/api/v1/minigroups", "Create minigroup")
+            .body(Content::json::<minigroup::MinigroupCreatePayload>())
+            .returns(201, Content::json::<db::class::Dummy>()),
+        Route::new(
+            Post,
+            "/api/v1/minigroups/{id}/whiteboard",
+            "Create whiteboard",
+        )
+        .returns(201, Content::Empty),
+    ]);
+
+    routes
+}
+
+// Routes every class kind has under its own prefix.
+fn class_routes(kind: &str) -> Vec<Route> {
+    use PathItemType::*;
+
+    let path = |suffix: &str| format!("/api/v1/{}{}", kind, suffix);
+    let by_scope = format!("/api/v1/audiences/{{audience}}/{}/{{scope}}", kind);
+
+    vec![
+        Route::new(Get, path("/{id}"), "Read class")
+            .query::<class::PropertyFilters>()
+            .returns(200, Content::json::<class::ClassResponseBody>()),
+        Route::new(Delete, path("/{id}"), "Delete class").returns(204, Content::Empty),
+        Route::new(Get, by_scope, "Read class by scope")
+            .query::<class::PropertyFilters>()
+            .returns(200, Content::json::<class::ClassResponseBody>())
+            .returns(404, Content::Text),
+        Route::new(Post, path("/{id}/events"), "Create event in the class room")
+            .body(Content::Any)
+            .returns(201, Content::Any),
+        Route::new(
+            Get,
+            path("/{id}/properties/{property_id}"),
+            "Read class property",
+        )
+        .returns(200, Content::Any),
+        Route::new(
+            Put,
+            path("/{id}/properties/{property_id}"),
+            "Update class property",
+        )
+        .body(Content::Any)
+        .returns(200, Content::json::<db::class::KeyValueProperties>()),
+        Route::new(Get, path("/{id}/download"), "Recording download link")
+            .returns(200, Content::Json("Download")),
+    ]
+}
+
+fn authz_routes() -> Vec<Route> {
+    vec![Route::new(
+        PathItemType::Post,
+        "/api/v1/authz/{audience}",
+        "Authorize on behalf of the class",
+    )
+    .body(Content::json::<authz::AuthzRequest>())
+    .returns(200, Content::Any)]
+}
+
+fn utils_routes() -> Vec<Route> {
+    use PathItemType::*;
+
+    vec![
+        Route::new(Get, "/api/v1/audiences/{audience}/classes", "List classes")
+            .query::<class::ListFilters>()
+            .returns(200, Content::json::<class::ListResponseBody>()),
+        Route::new(
+            Get,
+            "/api/v1/audiences/{audience}/webhooks",
+            "List webhook deliveries",
+        )
+        .query::<webhooks::ListFilters>()
+        .returns(200, Content::json::<webhooks::ListResponseBody>()),
+        Route::new(
+            Post,
+            "/api/v1/audiences/{audience}/classes/{scope}/editions/{id}",
+            "Commit edition",
+        )
+        .returns(202, Content::Empty)
+        .returns(404, Content::Text),
+        Route::new(
+            Get,
+            "/api/v1/account/properties/{property_id}",
+            "Read account property",
+        )
+        .returns(200, Content::Any),
+        Route::new(
+            Put,
+            "/api/v1/account/properties/{property_id}",
+            "Update account property",
+        )
+        .body(Content::Any)
+        .returns(200, Content::json::<db::class::KeyValueProperties>()),
+        Route::new(
+            Get,
+            "/api/v1/classes/{id}/postprocessing",
+            "List postprocessing jobs",
+        )
+        .returns(200, Content::list::<db::postprocessing_job::Object>()),
+        Route::new(
+            Post,
+            "/api/v1/transcoding/minigroup/{id}/restart",
+            "Restart minigroup transcoding",
+        )
+        .optional_body(Content::json::<minigroup::RestartTranscodingPayload>())
+        .returns(200, Content::Empty),
+        Route::new(
+            Post,
+            "/api/v1/transcoding/webinar/{id}/restart",
+            "Restart webinar transcoding",
+        )
+        .optional_body(Content::json::<webinar::RestartTranscodingPayload>())
+        .returns(200, Content::Empty),
+        Route::new(Get, "/api/v1/dead_letters", "List dead letters")
+            .query::<dead_letters::ListFilters>()
+            .returns(200, Content::json::<dead_letters::ListResponseBody>()),
+        Route::new(Get, "/api/v1/dead_letters/{id}", "Read dead letter")
+            .returns(200, Content::json::<db::dead_letter::Object>()),
+        Route::new(
+            Post,
+            "/api/v1/dead_letters/{id}/replay",
+            "Replay dead letter",
+        )
+        .returns(202, Content::Empty),
+        Route::new(Get, "/api/v1/audit_log", "List audit log")
+            .query::<audit_log::ListFilters>()
+            .returns(200, Content::json::<audit_log::ListResponseBody>()),
+    ]
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+enum Content {
+    Empty,
+    Text,
+    Any,
+    Json(&'static str),
+    List(&'static str),
+}
+
+impl Content {
+    fn json<T: ToSchema<'static>>() -> Self {
+        Self::Json(T::schema().0)
+    }
+
+    fn list<T: ToSchema<'static>>() -> Self {
+        Self::List(T::schema().0)
+    }
+
+    fn build(&self) -> Option<(&'static str, utoipa::openapi::Content)> {
+        let (content_type, schema): (_, RefOr<Schema>) = match self {
+            Self::Empty => return None,
+            Self::Text => (
+                "text/plain",
+                ObjectBuilder::new().schema_type(SchemaType::String).into(),
+            ),
+            Self::Any => ("application/json", ObjectBuilder::new().into()),
+            Self::Json(name) => ("application/json", Ref::from_schema_name(*name).into()),
+            Self::List(name) => (
+                "application/json",
+                ArrayBuilder::new()
+                    .items(Ref::from_schema_name(*name))
+                    .into(),
+            ),
+        };
+
+        Some((content_type, ContentBuilder::new().schema(schema).build()))
+    }
+}
+
+struct Route {
+    method: PathItemType,
+    path: String,
+    summary: &'static str,
+    body: Option<(Content, Required)>,
+    query: Vec<Parameter>,
+    responses: Vec<(u16, Content)>,
+    text_errors: bool,
+}
+
+impl Route {
+    fn new(method: PathItemType, path: impl Into<String>, summary: &'static str) -> Self {
+        Self {
+            method,
+            path: path.into(),
+            summary,
+            body: None,
+            query: vec![],
+            responses: vec![],
+            text_errors: false,
+        }
+    }
+
+    fn body(mut self, content: Content) -> Self {
+        self.body = Some((content, Required::True));
+        self
+    }
+
+    fn optional_body(mut self, content: Content) -> Self {
+        self.body = Some((content, Required::False));
+        self
+    }
+
+    fn query<P: IntoParams>(mut self) -> Self {
+        self.query = P::into_params(|| Some(ParameterIn::Query));
+        self
+    }
+
+    fn returns(mut self, status: u16, content: Content) -> Self {
+        self.responses.push((status, content));
+        self
+    }
+
+    // The handler doesn't go through `app::error::Error` and answers with plain text on failure.
+    fn text_errors(mut self) -> Self {
+        self.text_errors = true;
+        self
+    }
+
+    fn into_operation(self, tag: &str) -> (PathItemType, String, Operation) {
+        let mut operation = OperationBuilder::new()
+            .tag(tag)
+            .summary(Some(self.summary))
+            .parameters(Some(path_params(&self.path).into_iter().chain(self.query)));
+
+        if let Some((content, required)) = self.body {
+            let mut body = RequestBodyBuilder::new().required(Some(required));
+
+            if let Some((content_type, content)) = content.build() {
+                body = body.content(content_type, content);
+            }
+
+            operation = operation.request_body(Some(body.build()));
+        }
+
+        // Contents of the same status are the ones the client picks from with `Accept`.
+        let mut responses = BTreeMap::<u16, ResponseBuilder>::new();
+
+        for (status, content) in self.responses {
+            let response = responses.entry(status).or_insert_with(|| {
+                let description = http::StatusCode::from_u16(status)
+                    .ok()
+                    .and_then(|status| status.canonical_reason())
+                    .unwrap_or_default();
+
+                ResponseBuilder::new().description(description)
+            });
+
+            if let Some((content_type, content)) = content.build() {
+                *response = std::mem::take(response).content(content_type, content);
+            }
+        }
+
+        for (status, response) in responses {
+            operation = operation.response(status.to_string(), response.build());
+        }
+
+        let error = if self.text_errors {
+            Content::Text
+        } else {
+            Content::Json("Error")
+        };
+
+        let mut response = ResponseBuilder::new().description("Error");
+        if let Some((content_type, content)) = error.build() {
+            response = response.content(content_type, content);
+        }
+
+        operation = operation.response("default", response.build());
+
+        (self.method, self.path, operation.build())
+    }
+}
+
+// Class, edition and dead letter ids are uuids, frontend ids are integers,
+// the rest of the path segments are strings.
+fn path_params(path: &str) -> Vec<Parameter> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| {
+            let schema = match name {
+                "id" => ObjectBuilder::new()
+                    .schema_type(SchemaType::String)
+                    .format(Some(SchemaFormat::KnownFormat(KnownFormat::Uuid))),
+                "frontend_id" => ObjectBuilder::new()
+                    .schema_type(SchemaType::Integer)
+                    .format(Some(SchemaFormat::KnownFormat(KnownFormat::Int64))),
+                _ => ObjectBuilder::new().schema_type(SchemaType::String),
+            };
+
+            ParameterBuilder::new()
+                .name(name)
+                .parameter_in(ParameterIn::Path)
+                .required(Required::True)
+                .schema(Some(schema))
+                .build()
+        })
+        .collect()
+}
+
+// Body of `app::error::Error` responses, the status code goes with the kind.
+fn error_schema() -> Schema {
+    let string = || ObjectBuilder::new().schema_type(SchemaType::String);
+
+    let kinds = ErrorKind::ALL
+        .iter()
+        .map(|kind| format!("- `{}` ({}): {}", kind.kind(), kind.status().as_u16(), kind))
+        .collect::<Vec<_>>();
+
+    let kind = string()
+        .enum_values(Some(ErrorKind::ALL.iter().map(|kind| kind.kind())))
+        .description(Some(kinds.join("\n")));
+
+    ObjectBuilder::new()
+        .property("type", kind)
+        .required("type")
+        .property("title", string())
+        .required("title")
+        .property("detail", string())
+        .into()
+}
+
+fn download_schema() -> Schema {
+    ObjectBuilder::new()
+        .property("url", ObjectBuilder::new().schema_type(SchemaType::String))
+        .required("url")
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+    use std::sync::Arc;
+
+    use hyper::Request;
+    use serde_json::Value as JsonValue;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::app::error::Error as AppError;
+    use crate::app::readiness::Readiness;
+    use crate::app::{http, AppContext, EventSender};
+    use crate::test_helpers::prelude::*;
+
+    const METHODS: [&str; 5] = ["GET", "POST", "PUT", "DELETE", "PATCH"];
+
+    // Paths as axum has them, e.g. `/api/v1/webinars/:id`. The router doesn't expose its routes
+    // but prints them in its `Debug`.
+    fn router_paths(router: &axum::Router) -> BTreeSet<String> {
+        format!("{:?}", router)
+            .split('"')
+            .filter(|s| s.starts_with('/') && !s.contains("__private__"))
+            .map(ToOwned::to_owned)
+            .collect()
+    }
+
+    fn to_openapi_path(path: &str) -> String {
+        path.split('/')
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(name) => format!("{{{}}}", name),
+                None => segment.to_owned(),
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn to_uri(path: &str) -> String {
+        path.split('/')
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(_) => Uuid::new_v4().to_string(),
+                None => segment.to_owned(),
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    #[tokio::test]
+    async fn documents_every_route() {
+        let state = TestState::new(TestAuthz::new()).await;
+        let state = Arc::new(state) as Arc<dyn AppContext>;
+        let (events, _) = EventSender::channel(Default::default(), 1);
+        let readiness = Readiness::new(None, &state.config().readiness);
+        let router = http::router(state, events, readiness, HashMap::new());
+
+        let paths = router_paths(&router);
+        assert!(paths.contains("/api/v1/webinars/:id"), "{:?}", paths);
+
+        let document = document();
+        let mut routed = BTreeSet::new();
+
+        // The route exists for the method unless the router answers with 405 or 404. Requests
+        // go without a token so the handlers behind authn don't get to do anything.
+        for path in &paths {
+            for method in METHODS {
+                let request = Request::builder()
+                    .method(method)
+                    .uri(to_uri(path))
+                    .body(Body::empty())
+                    .unwrap();
+
+                let response = router.clone().oneshot(request).await.unwrap();
+
+                let status = response.status();
+                if status != hyper::StatusCode::METHOD_NOT_ALLOWED
+                    && status != hyper::StatusCode::NOT_FOUND
+                {
+                    routed.insert((to_openapi_path(path), method.to_lowercase()));
+                }
+            }
+        }
+
+        let documented = document
+            .paths
+            .paths
+            .iter()
+            .flat_map(|(path, item)| {
+                item.operations.keys().map(move |item_type| {
+                    let method = serde_json::to_value(item_type).unwrap();
+                    (path.to_owned(), method.as_str().unwrap().to_owned())
+                })
+            })
+            .collect::<BTreeSet<_>>();
+
+        let undocumented = routed.difference(&documented).collect::<Vec<_>>();
+        assert!(undocumented.is_empty(), "Undocumented: {:?}", undocumented);
+
+        let unrouted = documented.difference(&routed).collect::<Vec<_>>();
+        assert!(unrouted.is_empty(), "Not routed: {:?}", unrouted);
+    }
+
+    #[test]
+    fn resolves_every_ref() {
+        fn refs(value: &JsonValue, found: &mut Vec<String>) {
+            match value {
+                JsonValue::Object(map) => {
+                    if let Some(JsonValue::String(r)) = map.get("$ref") {
+                        found.push(r.to_owned());
+                    }
+
+                    map.values().for_each(|v| refs(v, found));
+                }
+                JsonValue::Array(values) => values.iter().for_each(|v| refs(v, found)),
+                _ => (),
+            }
+        }
+
+        let document = serde_json::to_value(document()).expect("Failed to serialize document");
+        let mut found = vec![];
+        refs(&document, &mut found);
+
+        assert!(!found.is_empty());
+
+        for r in found {
+            let name = r
+                .strip_prefix("#/components/schemas/")
+                .unwrap_or_else(|| panic!("Unexpected ref: {}", r));
+
+            assert!(
+                document["components"]["schemas"].get(name).is_some(),
+                "Missing schema: {}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn describes_error_body() {
+        let document = serde_json::to_value(document()).expect("Failed to serialize document");
+        let schema = &document["components"]["schemas"]["Error"];
+
+        for kind in ErrorKind::ALL {
+            let error = AppError::new(*kind, anyhow!("Oops"));
+            let body = serde_json::to_value(error.to_svc_error()).unwrap();
+
+            for key in body.as_object().unwrap().keys() {
+                assert!(
+                    schema["properties"].get(key).is_some(),
+                    "Undocumented error field: {}",
+                    key
+                );
+            }
+
+            let kinds = schema["properties"]["type"]["enum"].as_array().unwrap();
+            assert!(kinds.contains(&body["type"]));
+        }
+    }
+}
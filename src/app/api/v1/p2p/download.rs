@@ -0,0 +1,155 @@
+use axum::extract::{Extension, Path};
+use hyper::{Body, Response};
+use uuid::Uuid;
+
+use super::*;
+
+use crate::app::api::v1::find;
+
+use crate::db::class::{Object as Class, P2PType};
+use crate::{app::metrics::AuthorizeMetrics, config::StorageConfig};
+
+pub async fn download(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    Path(id): Path<Uuid>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+) -> AppResult {
+    let p2p = find::<P2PType>(ctx.as_ref(), id)
+        .await
+        .error(AppErrorKind::ClassNotFound)?;
+
+    let object = AuthzObject::new(&["classrooms", &p2p.id().to_string()]).into();
+    ctx.authz()
+        .authorize(
+            p2p.audience().to_owned(),
+            account_id.clone(),
+            object,
+            "download".into(),
+        )
+        .await
+        .measure()?;
+
+    let mut conn = ctx
+        .get_conn()
+        .await
+        .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+    let recordings = crate::db::recording::RecordingListQuery::new(p2p.id())
+        .execute(&mut conn)
+        .await
+        .context("Failed to query p2p recordings")
+        .error(AppErrorKind::DbQueryFailed)?;
+
+    (!recordings.is_empty())
+        .then_some(())
+        .ok_or_else(|| anyhow!("Zero p2p recordings"))
+        .error(AppErrorKind::RecordingNotFound)?;
+
+    recordings
+        .iter()
+        .all(|recording| recording.transcoded_at().is_some())
+        .then_some(())
+        .ok_or_else(|| anyhow!("P2P recordings were not transcoded"))
+        .error(AppErrorKind::RecordingNotFound)?;
+
+    let body = serde_json::json!({ "url": format_url(ctx.storage_config(), &p2p) });
+
+    let body = serde_json::to_string(&body).expect("Never fails");
+    let response = Response::builder().body(Body::from(body)).unwrap();
+    Ok(response)
+}
+
+// Participants' streams are composed into a single one stored by the class scope.
+fn format_url(config: &StorageConfig, p2p: &Class) -> String {
+    let mut url = config.base_url.clone();
+    let recording_id = format!("ms.webinar.{}::{}", p2p.audience(), p2p.scope());
+    url.path_segments_mut()
+        .expect("cannot-be-a-base URL")
+        .extend(&[
+            "api",
+            "v2",
+            "backends",
+            "yandex",
+            "sets",
+            &recording_id,
+            "objects",
+            "mp4",
+        ]);
+
+    url.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::prelude::*;
+
+    use chrono::Utc;
+    use hyper::body::to_bytes;
+
+    async fn insert_p2p(db_pool: &TestDb, agent: &TestAgent, transcoded: bool) -> Class {
+        let mut conn = db_pool.get_conn().await;
+
+        let p2p = factory::P2P::new(
+            random_string(),
+            USR_AUDIENCE.to_string(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        )
+        .insert(&mut conn)
+        .await;
+
+        let recording =
+            factory::Recording::new(p2p.id(), Uuid::new_v4(), agent.agent_id().to_owned());
+        let recording = if transcoded {
+            recording.transcoded_at(Utc::now())
+        } else {
+            recording
+        };
+        recording.insert(&mut conn).await;
+
+        p2p
+    }
+
+    #[tokio::test]
+    async fn download_p2p() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let db_pool = TestDb::new().await;
+
+        let p2p = insert_p2p(&db_pool, &agent, true).await;
+        let not_transcoded = insert_p2p(&db_pool, &agent, false).await;
+
+        let mut authz = TestAuthz::new();
+        for id in [p2p.id(), not_transcoded.id()] {
+            authz.allow(
+                agent.account_id(),
+                vec!["classrooms", &id.to_string()],
+                "download",
+            );
+        }
+
+        let state = Arc::new(TestState::new_with_pool(db_pool, authz));
+
+        let r = download(
+            Extension(state.clone()),
+            Path(p2p.id()),
+            AccountIdExtractor(agent.account_id().to_owned()),
+        )
+        .await
+        .expect("Failed to get download url");
+
+        let body = to_bytes(r.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let url = body["url"].as_str().unwrap();
+        assert!(url.starts_with(state.config().storage.base_url.as_str()));
+        assert!(url.contains(p2p.scope()));
+
+        download(
+            Extension(state.clone()),
+            Path(not_transcoded.id()),
+            AccountIdExtractor(agent.account_id().to_owned()),
+        )
+        .await
+        .expect_err("Unexpectedly succeeded");
+    }
+}
@@ -8,6 +8,7 @@ use serde_derive::Deserialize;
 use svc_agent::AccountId;
 use svc_utils::extractors::AccountIdExtractor;
 use tracing::{error, info, instrument};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::app::authz::AuthzObject;
@@ -16,6 +17,7 @@ use crate::app::error::ErrorKind as AppErrorKind;
 use crate::app::http::Json;
 use crate::app::metrics::AuthorizeMetrics;
 use crate::app::services;
+use crate::app::webhooks::{self, WebhookEvent};
 use crate::app::AppContext;
 use crate::db::class;
 use crate::db::class::ClassType;
@@ -24,7 +26,11 @@ use crate::db::class::KeyValueProperties;
 use super::AppError;
 use super::AppResult;
 
-#[derive(Deserialize)]
+pub use download::download;
+
+mod download;
+
+#[derive(Deserialize, ToSchema)]
 pub struct P2PCreatePayload {
     scope: String,
     audience: String,
@@ -87,11 +93,13 @@ async fn do_create(
         Ok((event_id, conference_id)) => {
             info!(?event_id, ?conference_id, "Created rooms",);
 
-            class::EstablishQuery::new(dummy.id(), event_id, conference_id)
+            let class = class::EstablishQuery::new(dummy.id(), event_id, conference_id)
                 .execute(&mut conn)
                 .await
                 .context("Failed to establish webinar dummy")
                 .error(AppErrorKind::DbQueryFailed)?;
+
+            webhooks::enqueue(state, &class, WebhookEvent::Created).await;
             event_id
         }
         Err(e) => {
@@ -158,7 +166,7 @@ async fn insert_p2p_dummy(
         .ok_or_else(|| AppError::from(AppErrorKind::ClassAlreadyEstablished))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct P2PConvertObject {
     scope: String,
     audience: String,
@@ -223,6 +231,8 @@ pub async fn convert(
     .await
     .error(AppErrorKind::MqttRequestFailed)?;
 
+    webhooks::enqueue(ctx.as_ref(), &p2p, WebhookEvent::Created).await;
+
     let body = serde_json::to_string(&p2p)
         .context("Failed to serialize p2p")
         .error(AppErrorKind::SerializationFailed)?;
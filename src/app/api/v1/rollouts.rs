@@ -0,0 +1,219 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::{Extension, Path};
+use hyper::{Body, Response};
+use serde_derive::{Deserialize, Serialize};
+use svc_authn::AccountId;
+use svc_utils::extractors::AccountIdExtractor;
+use utoipa::ToSchema;
+
+use super::AppResult;
+use crate::app::api::IntoJsonResponse;
+use crate::app::authz::authorize_service;
+use crate::app::error::ErrorExt;
+use crate::app::error::ErrorKind as AppErrorKind;
+use crate::app::http::Json;
+use crate::app::AppContext;
+use crate::db::frontend_rollout::{DeleteQuery, ListQuery, Object as FrontendRollout, UpsertQuery};
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RolloutPayload {
+    frontend_id: i64,
+    /// Percent of the scopes to send to the frontend, from 0 to 100
+    percent: i16,
+}
+
+#[derive(Serialize, ToSchema)]
+#[schema(as = FrontendRolloutList)]
+pub struct ListResponseBody {
+    rollouts: Vec<FrontendRollout>,
+}
+
+pub async fn list(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+) -> AppResult {
+    do_list(ctx.as_ref(), &account_id).await
+}
+
+async fn do_list(state: &dyn AppContext, account_id: &AccountId) -> AppResult {
+    authorize_service(state, account_id, &["rollouts"], "list").await?;
+
+    let mut conn = state
+        .get_conn()
+        .await
+        .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+    let rollouts = ListQuery::new()
+        .execute(&mut conn)
+        .await
+        .context("Failed to list rollouts")
+        .error(AppErrorKind::DbQueryFailed)?;
+
+    ListResponseBody { rollouts }
+        .into_json_response("Failed to serialize rollouts", http::StatusCode::OK)
+}
+
+pub async fn update(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    Path((tenant, app)): Path<(String, String)>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+    Json(payload): Json<RolloutPayload>,
+) -> AppResult {
+    do_update(ctx.as_ref(), &account_id, tenant, app, payload).await
+}
+
+async fn do_update(
+    state: &dyn AppContext,
+    account_id: &AccountId,
+    tenant: String,
+    app: String,
+    payload: RolloutPayload,
+) -> AppResult {
+    authorize_service(state, account_id, &["rollouts"], "update").await?;
+
+    if !(0..=100).contains(&payload.percent) {
+        return Err(anyhow!("Invalid rollout percent = {}", payload.percent))
+            .error(AppErrorKind::InvalidPayload);
+    }
+
+    let mut conn = state
+        .get_conn()
+        .await
+        .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+    let rollout = UpsertQuery::new(tenant, app, payload.frontend_id, payload.percent)
+        .execute(&mut conn)
+        .await
+        .context("Failed to update rollout")
+        .error(AppErrorKind::DbQueryFailed)?
+        .ok_or_else(|| anyhow!("Frontend not found, id = {}", payload.frontend_id))
+        .error(AppErrorKind::FrontendNotFound)?;
+
+    rollout.into_json_response("Failed to serialize rollout", http::StatusCode::OK)
+}
+
+pub async fn delete(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    Path((tenant, app)): Path<(String, String)>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+) -> AppResult {
+    do_delete(ctx.as_ref(), &account_id, tenant, app).await
+}
+
+async fn do_delete(
+    state: &dyn AppContext,
+    account_id: &AccountId,
+    tenant: String,
+    app: String,
+) -> AppResult {
+    authorize_service(state, account_id, &["rollouts"], "delete").await?;
+
+    let mut conn = state
+        .get_conn()
+        .await
+        .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+    DeleteQuery::new(tenant.clone(), app.clone())
+        .execute(&mut conn)
+        .await
+        .context("Failed to delete rollout")
+        .error(AppErrorKind::DbQueryFailed)?
+        .ok_or_else(|| anyhow!("Rollout not found, tenant = {}, app = {}", tenant, app))
+        .error(AppErrorKind::RolloutNotFound)?;
+
+    let response = Response::builder()
+        .status(http::StatusCode::NO_CONTENT)
+        .body(Body::empty())
+        .unwrap();
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value as JsonValue;
+
+    use super::*;
+    use crate::test_helpers::prelude::*;
+
+    fn allow(agent: &TestAgent, actions: &[&str]) -> TestAuthz {
+        let mut authz = TestAuthz::new();
+        authz.set_audience(SVC_AUDIENCE);
+
+        for action in actions {
+            authz.allow(agent.account_id(), vec!["rollouts"], action);
+        }
+
+        authz
+    }
+
+    #[tokio::test]
+    async fn update_rollout_with_invalid_percent() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let state = TestState::new(allow(&agent, &["update"])).await;
+
+        let payload = RolloutPayload {
+            frontend_id: 1,
+            percent: 101,
+        };
+
+        do_update(
+            &state,
+            agent.account_id(),
+            random_string(),
+            "webinar".into(),
+            payload,
+        )
+        .await
+        .expect_err("Unexpectedly succeeded");
+    }
+
+    #[tokio::test]
+    async fn update_and_delete_rollout() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let state = TestState::new(allow(&agent, &["update", "delete"])).await;
+        let tenant = random_string();
+
+        let frontend = {
+            let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+
+            factory::Frontend::new("https://canary.example.org".into())
+                .execute(&mut conn)
+                .await
+                .expect("Failed to seed frontend")
+        };
+
+        for percent in [10, 50] {
+            let payload = RolloutPayload {
+                frontend_id: frontend.id,
+                percent,
+            };
+
+            let r = do_update(
+                &state,
+                agent.account_id(),
+                tenant.clone(),
+                "webinar".into(),
+                payload,
+            )
+            .await
+            .expect("Failed to update rollout");
+
+            let r = hyper::body::to_bytes(r.into_body()).await.unwrap();
+            let v = serde_json::from_slice::<JsonValue>(&r[..]).expect("Failed to parse json");
+            assert_eq!(v["percent"], percent);
+            assert_eq!(v["frontend_id"], frontend.id);
+        }
+
+        let r = do_delete(&state, agent.account_id(), tenant.clone(), "webinar".into())
+            .await
+            .expect("Failed to delete rollout");
+        assert_eq!(r.status(), http::StatusCode::NO_CONTENT);
+
+        do_delete(&state, agent.account_id(), tenant, "webinar".into())
+            .await
+            .expect_err("Deleted rollout twice");
+    }
+}
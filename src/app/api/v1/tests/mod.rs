@@ -5,14 +5,17 @@ use svc_authn::jose::ConfigMap;
 use tower::ServiceExt;
 
 use super::*;
-use crate::app::http;
+use crate::app::readiness::Readiness;
+use crate::app::{http, EventSender};
 use crate::test_helpers::prelude::*;
 
 #[tokio::test]
 async fn test_healthz() {
     let state = TestState::new(TestAuthz::new()).await;
     let state = Arc::new(state) as Arc<dyn AppContext>;
-    let app = http::router(state, HashMap::new());
+    let (events, _) = EventSender::channel(Default::default(), 1);
+    let readiness = Readiness::new(None, &state.config().readiness);
+    let app = http::router(state, events, readiness, HashMap::new());
 
     let resp = app
         .oneshot(
@@ -39,7 +42,9 @@ async fn test_api_rollback() {
 
     let state = TestState::new(authz).await;
     let state = Arc::new(state) as Arc<dyn AppContext>;
-    let app = crate::app::http::router(state.clone(), make_authn());
+    let (events, _) = EventSender::channel(Default::default(), 1);
+    let readiness = Readiness::new(None, &state.config().readiness);
+    let app = crate::app::http::router(state.clone(), events, readiness, make_authn());
 
     let scope = shared_helpers::random_string();
 
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::{Extension, Path, Query};
+use hyper::{Body, Response};
+use serde_derive::{Deserialize, Serialize};
+use svc_authn::AccountId;
+use svc_utils::extractors::AccountIdExtractor;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use super::AppResult;
+use crate::app::error::ErrorExt;
+use crate::app::error::ErrorKind as AppErrorKind;
+use crate::app::AppContext;
+use crate::app::{authz::AuthzObject, metrics::AuthorizeMetrics};
+use crate::db::webhook_delivery::{
+    Attempt, AttemptListQuery, ListQuery, Object as Delivery, Status,
+};
+
+const DEFAULT_LIMIT: i64 = 25;
+const MAX_LIMIT: i64 = 100;
+
+#[derive(Default, Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListFilters {
+    class_id: Option<Uuid>,
+    #[param(value_type = Option<WebhookDeliveryStatus>)]
+    status: Option<Status>,
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[schema(as = WebhookDeliveryList)]
+pub struct ListResponseBody {
+    #[schema(inline)]
+    deliveries: Vec<DeliveryWithHistory>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct DeliveryWithHistory {
+    #[serde(flatten)]
+    #[schema(value_type = WebhookDelivery)]
+    delivery: Delivery,
+    #[schema(value_type = Vec<WebhookDeliveryAttempt>)]
+    history: Vec<Attempt>,
+}
+
+pub async fn list(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    Path(audience): Path<String>,
+    Query(filters): Query<ListFilters>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+) -> AppResult {
+    do_list(ctx.as_ref(), &account_id, audience, filters).await
+}
+
+async fn do_list(
+    state: &dyn AppContext,
+    account_id: &AccountId,
+    audience: String,
+    filters: ListFilters,
+) -> AppResult {
+    let object = AuthzObject::new(&["classrooms"]).into();
+    state
+        .authz()
+        .authorize(audience.clone(), account_id.clone(), object, "list".into())
+        .await
+        .measure()?;
+
+    let limit = filters.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let mut query = ListQuery::new(audience, limit);
+
+    if let Some(class_id) = filters.class_id {
+        query = query.class_id(class_id);
+    }
+
+    if let Some(status) = filters.status {
+        query = query.status(status);
+    }
+
+    let mut conn = state
+        .get_conn()
+        .await
+        .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+    let deliveries = query
+        .execute(&mut conn)
+        .await
+        .context("Failed to list webhook deliveries")
+        .error(AppErrorKind::DbQueryFailed)?;
+
+    let mut history = HashMap::<Uuid, Vec<Attempt>>::new();
+    AttemptListQuery::new(deliveries.iter().map(|d| d.id()).collect())
+        .execute(&mut conn)
+        .await
+        .context("Failed to list webhook delivery attempts")
+        .error(AppErrorKind::DbQueryFailed)?
+        .into_iter()
+        .for_each(|attempt| {
+            history
+                .entry(attempt.delivery_id())
+                .or_default()
+                .push(attempt)
+        });
+
+    let deliveries = deliveries
+        .into_iter()
+        .map(|delivery| DeliveryWithHistory {
+            history: history.remove(&delivery.id()).unwrap_or_default(),
+            delivery,
+        })
+        .collect();
+
+    let body = serde_json::to_string(&ListResponseBody { deliveries })
+        .context("Failed to serialize webhook deliveries")
+        .error(AppErrorKind::SerializationFailed)?;
+
+    let response = Response::builder().body(Body::from(body)).unwrap();
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value as JsonValue;
+
+    use super::*;
+    use crate::db::webhook_delivery::{AttemptInsertQuery, InsertQuery, UpdateQuery};
+    use crate::test_helpers::prelude::*;
+
+    #[tokio::test]
+    async fn list_webhooks_unauthorized() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let state = TestState::new(TestAuthz::new()).await;
+
+        do_list(
+            &state,
+            agent.account_id(),
+            USR_AUDIENCE.to_owned(),
+            ListFilters::default(),
+        )
+        .await
+        .expect_err("Unexpectedly succeeded");
+    }
+
+    #[tokio::test]
+    async fn list_webhooks_with_history() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let db_pool = TestDb::new().await;
+        let audience = random_string();
+        let class_id = Uuid::new_v4();
+
+        {
+            let mut conn = db_pool.get_conn().await;
+
+            for event in &["class.created", "class.stopped"] {
+                InsertQuery::new(
+                    class_id,
+                    audience.clone(),
+                    (*event).to_owned(),
+                    serde_json::json!({ "event": event }),
+                )
+                .execute(&mut conn)
+                .await
+                .expect("Failed to insert webhook delivery");
+            }
+
+            let other = InsertQuery::new(
+                Uuid::new_v4(),
+                audience.clone(),
+                "class.created".to_owned(),
+                serde_json::json!({}),
+            )
+            .execute(&mut conn)
+            .await
+            .expect("Failed to insert webhook delivery");
+
+            AttemptInsertQuery::new(other.id(), 1)
+                .error("Timeout".to_owned())
+                .execute(&mut conn)
+                .await
+                .expect("Failed to insert attempt");
+
+            AttemptInsertQuery::new(other.id(), 2)
+                .status_code(200)
+                .execute(&mut conn)
+                .await
+                .expect("Failed to insert attempt");
+
+            UpdateQuery::delivered(other.id())
+                .execute(&mut conn)
+                .await
+                .expect("Failed to update webhook delivery");
+        }
+
+        let mut authz = TestAuthz::new();
+        authz.set_audience(&audience);
+        authz.allow(agent.account_id(), vec!["classrooms"], "list");
+
+        let state = TestState::new_with_pool(db_pool, authz);
+
+        let r = do_list(
+            &state,
+            agent.account_id(),
+            audience.clone(),
+            ListFilters {
+                status: Some(Status::Delivered),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Failed to list webhooks");
+
+        let r = hyper::body::to_bytes(r.into_body()).await.unwrap();
+        let v = serde_json::from_slice::<JsonValue>(&r[..]).expect("Failed to parse json");
+        let deliveries = v["deliveries"].as_array().expect("Expected an array");
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0]["status"], "delivered");
+        assert!(deliveries[0]["delivered_at"].is_i64());
+
+        let history = deliveries[0]["history"]
+            .as_array()
+            .expect("Expected an array");
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0]["attempt"], 1);
+        assert_eq!(history[0]["error"], "Timeout");
+        assert_eq!(history[1]["attempt"], 2);
+        assert_eq!(history[1]["status_code"], 200);
+
+        let r = do_list(
+            &state,
+            agent.account_id(),
+            audience,
+            ListFilters {
+                class_id: Some(class_id),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Failed to list webhooks");
+
+        let r = hyper::body::to_bytes(r.into_body()).await.unwrap();
+        let v = serde_json::from_slice::<JsonValue>(&r[..]).expect("Failed to parse json");
+        let deliveries = v["deliveries"].as_array().expect("Expected an array");
+        assert_eq!(deliveries.len(), 2);
+        assert_eq!(deliveries[0]["event"], "class.stopped");
+        assert_eq!(deliveries[1]["event"], "class.created");
+        assert_eq!(deliveries[0]["status"], "pending");
+        assert!(deliveries[0]["history"].as_array().unwrap().is_empty());
+    }
+}
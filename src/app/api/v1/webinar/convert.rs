@@ -9,42 +9,48 @@ use serde_derive::Deserialize;
 use sqlx::Acquire;
 use svc_agent::AccountId;
 use svc_utils::extractors::AccountIdExtractor;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::app::error::ErrorExt;
 use crate::app::error::ErrorKind as AppErrorKind;
 use crate::app::http::Json;
+use crate::app::webhooks::{self, WebhookEvent};
 use crate::app::AppContext;
 use crate::app::{authz::AuthzObject, metrics::AuthorizeMetrics};
 use crate::clients::{conference::ConferenceRoomResponse, event::EventRoomResponse};
-use crate::db::class::BoundedDateTimeTuple;
 use crate::db::class::KeyValueProperties;
+use crate::db::class::{BoundedDateTimeTuple, ClassStatus};
 use crate::db::recording::Segments;
 
 use super::AppResult;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct WebinarConvertObject {
     scope: String,
     audience: String,
     event_room_id: Uuid,
     conference_room_id: Uuid,
     #[serde(default, with = "crate::serde::ts_seconds_option_bound_tuple")]
+    #[schema(value_type = Option<BoundedTime>)]
     time: Option<BoundedDateTimeTuple>,
     tags: Option<serde_json::Value>,
     #[serde(default)]
     properties: KeyValueProperties,
     original_event_room_id: Option<Uuid>,
     modified_event_room_id: Option<Uuid>,
+    #[schema(inline)]
     recording: Option<RecordingConvertObject>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct RecordingConvertObject {
     stream_id: Uuid,
     #[serde(deserialize_with = "crate::db::recording::serde::segments::deserialize")]
+    #[schema(value_type = Vec<Vec<i64>>)]
     segments: Segments,
     #[serde(deserialize_with = "crate::db::recording::serde::segments::deserialize")]
+    #[schema(value_type = Vec<Vec<i64>>)]
     modified_segments: Segments,
     uri: String,
 }
@@ -116,6 +122,13 @@ async fn do_convert(
 
     let query = query.properties(body.properties);
 
+    // Converted webinars come with an already transcoded recording.
+    let query = if body.recording.is_some() {
+        query.status(ClassStatus::Transcoded)
+    } else {
+        query
+    };
+
     let query = if let Some(id) = body.original_event_room_id {
         query.original_event_room_id(id)
     } else {
@@ -181,6 +194,8 @@ async fn do_convert(
         webinar
     };
 
+    webhooks::enqueue(state, &webinar, WebhookEvent::Created).await;
+
     let body = serde_json::to_string(&webinar)
         .context("Failed to serialize webinar")
         .error(AppErrorKind::SerializationFailed)?;
@@ -313,6 +328,49 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn convert_webinar_enqueues_created_webhook() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+
+        let mut authz = TestAuthz::new();
+        authz.allow(agent.account_id(), vec!["classrooms"], "convert");
+
+        let mut state = TestState::new(authz).await;
+        let event_room_id = Uuid::new_v4();
+        let conference_room_id = Uuid::new_v4();
+        convert_webinar_mocks(&mut state, event_room_id, conference_room_id);
+
+        // A random audience keeps other tests' deliveries out of the way.
+        let audience = random_string();
+        state.set_webhook_endpoint(&audience, "https://hooks.example.com/dispatcher", "secret");
+
+        let state = Arc::new(state);
+        let body = WebinarConvertObject {
+            scope: random_string(),
+            audience: audience.clone(),
+            time: Some((Bound::Unbounded, Bound::Unbounded)),
+            tags: Some(json!({"scope": "whatever"})),
+            properties: KeyValueProperties::default(),
+            event_room_id,
+            conference_room_id,
+            original_event_room_id: None,
+            modified_event_room_id: None,
+            recording: None,
+        };
+
+        do_convert(state.as_ref(), agent.account_id(), body)
+            .await
+            .expect("Failed to convert webinar");
+
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+        let deliveries = crate::db::webhook_delivery::ListQuery::new(audience, 100)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to list webhook deliveries");
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].event(), "class.created");
+    }
+
     #[tokio::test]
     async fn convert_webinar_with_recording() {
         let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
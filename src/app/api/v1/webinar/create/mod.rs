@@ -1,5 +1,5 @@
 mod replica;
 mod webinar;
 
-pub use replica::create as create_webinar_replica;
-pub use webinar::create as create_webinar;
+pub use replica::{create as create_webinar_replica, ReplicaCreatePayload};
+pub use webinar::{create as create_webinar, WebinarCreatePayload};
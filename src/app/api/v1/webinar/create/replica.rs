@@ -5,7 +5,9 @@ use crate::app::{
     error::ErrorKind as AppErrorKind,
     http::Json,
     metrics::AuthorizeMetrics,
-    services, AppContext,
+    services,
+    webhooks::{self, WebhookEvent},
+    AppContext,
 };
 use crate::db::class::{self, BoundedDateTimeTuple, ClassType};
 
@@ -21,9 +23,10 @@ use serde_derive::Deserialize;
 use svc_agent::AccountId;
 use svc_utils::extractors::AccountIdExtractor;
 use tracing::{error, info, instrument};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct ReplicaCreatePayload {
     scope: String,
     audience: String,
@@ -94,7 +97,7 @@ async fn do_create(
         Ok(conference_room_id) => {
             info!(?conference_room_id, "Conference room created");
 
-            class::EstablishQuery::new(
+            let class = class::EstablishQuery::new(
                 replica_class.id(),
                 original_class.event_room_id(),
                 conference_room_id,
@@ -103,6 +106,8 @@ async fn do_create(
             .await
             .context("Failed to establish webinar dummy")
             .error(AppErrorKind::DbQueryFailed)?;
+
+            webhooks::enqueue(state, &class, WebhookEvent::Created).await;
         }
         Err(e) => {
             info!("Failed to create conference room");
@@ -7,23 +7,26 @@ use serde_derive::Deserialize;
 use svc_agent::AccountId;
 use svc_utils::extractors::AccountIdExtractor;
 use tracing::{error, info, instrument};
+use utoipa::ToSchema;
 
 use crate::app::api::v1::{AppError, AppResult};
 use crate::app::error::ErrorExt;
 use crate::app::error::ErrorKind as AppErrorKind;
 use crate::app::http::Json;
 use crate::app::services::{self, lock_interaction};
+use crate::app::webhooks::{self, WebhookEvent};
 use crate::app::AppContext;
 use crate::app::{authz::AuthzObject, metrics::AuthorizeMetrics};
 use crate::clients::event::LockedTypes;
 use crate::db::class::KeyValueProperties;
 use crate::db::class::{self, BoundedDateTimeTuple, ClassType};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct WebinarCreatePayload {
     scope: String,
     audience: String,
     #[serde(default, with = "crate::serde::ts_seconds_option_bound_tuple")]
+    #[schema(value_type = Option<BoundedTime>)]
     time: Option<BoundedDateTimeTuple>,
     tags: Option<serde_json::Value>,
     #[serde(default)]
@@ -98,11 +101,13 @@ async fn do_create(
         Ok((event_id, conference_id)) => {
             info!(?event_id, ?conference_id, "Created rooms");
 
-            class::EstablishQuery::new(dummy.id(), event_id, conference_id)
+            let class = class::EstablishQuery::new(dummy.id(), event_id, conference_id)
                 .execute(&mut conn)
                 .await
                 .context("Failed to establish webinar dummy")
                 .error(AppErrorKind::DbQueryFailed)?;
+
+            webhooks::enqueue(state, &class, WebhookEvent::Created).await;
             event_id
         }
         Err(e) => {
@@ -40,8 +40,9 @@ pub async fn download(
         .context("Failed to query webinar recordings")
         .error(AppErrorKind::DbQueryFailed)?;
 
-    let recording = recordings
-        .first()
+    // Several publishers' recordings get composed into the set of the main one,
+    // the same stream the webinar read responds with.
+    let recording = crate::db::recording::main_recording(&recordings)
         .ok_or_else(|| anyhow!("Zero webinar recordings"))
         .error(AppErrorKind::RecordingNotFound)?;
 
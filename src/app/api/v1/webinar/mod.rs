@@ -9,10 +9,10 @@ use crate::db::class::WebinarType;
 
 use super::{find, AppResult};
 
-pub use convert::convert as convert_webinar;
+pub use convert::{convert as convert_webinar, WebinarConvertObject};
 pub use create::*;
 pub use download::download as download_webinar;
-pub use restart_transcoding::restart_transcoding;
+pub use restart_transcoding::{restart_transcoding, RestartTranscodingPayload};
 
 mod convert;
 mod create;
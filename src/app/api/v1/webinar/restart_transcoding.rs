@@ -5,6 +5,7 @@ use http::Response;
 use hyper::Body;
 use serde::Deserialize;
 use svc_utils::extractors::AccountIdExtractor;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::{
@@ -17,7 +18,7 @@ use crate::{
     clients::tq::Priority,
 };
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct RestartTranscodingPayload {
     priority: Priority,
 }
@@ -1,5 +1,11 @@
+use svc_agent::Authenticable;
+use svc_authn::AccountId;
 use svc_authz::IntentObject;
 
+use crate::app::error::Error as AppError;
+use crate::app::metrics::AuthorizeMetrics;
+use crate::app::AppContext;
+
 #[derive(Clone)]
 pub struct AuthzObject {
     object: Vec<String>,
@@ -41,3 +47,27 @@ impl From<AuthzObject> for Box<dyn IntentObject> {
         Box::new(o)
     }
 }
+
+/// Authorizes the action on the service's own objects, e.g. frontends or dead letters,
+/// which belong to no class so they're checked against the service's audience.
+pub async fn authorize_service(
+    state: &dyn AppContext,
+    account_id: &AccountId,
+    object: &[&str],
+    action: &str,
+) -> Result<(), AppError> {
+    let object = AuthzObject::new(object).into();
+
+    state
+        .authz()
+        .authorize(
+            state.agent_id().as_account_id().audience().to_owned(),
+            account_id.clone(),
+            object,
+            action.into(),
+        )
+        .await
+        .measure()?;
+
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use sqlx::Acquire;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::app::webhooks::WebhookEvent;
+use crate::app::AppContext;
+use crate::config::ClassSweeperConfig;
+use crate::db::class::EndedListQuery;
+
+// Arbitrary key shared by all the replicas, only one of them sweeps at a time.
+const LOCK_KEY: i64 = 0x6469_7370_7377_6570;
+
+/// Periodically closes classes whose time has ended but `room.close` never arrived.
+pub fn spawn(ctx: Arc<dyn AppContext>, config: ClassSweeperConfig) -> JoinHandle<()> {
+    tokio::task::spawn(async move {
+        let mut interval = tokio::time::interval(config.interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            match sweep(ctx.as_ref(), EndedListQuery::new(config.batch_size)).await {
+                Ok(0) => {}
+                Ok(closed) => info!(closed, "Closed ended classes"),
+                Err(e) => error!("Class sweep failed, err = {:?}", e),
+            }
+        }
+    })
+}
+
+async fn sweep(ctx: &dyn AppContext, query: EndedListQuery) -> Result<usize> {
+    let (closed, events) = {
+        let mut conn = ctx.get_conn().await?;
+        let mut txn = conn
+            .begin()
+            .await
+            .context("Failed to begin sqlx db transaction")?;
+
+        let locked = crate::db::advisory_lock::TryXactLockQuery::new(LOCK_KEY)
+            .execute(&mut txn)
+            .await
+            .context("Failed to take advisory lock")?;
+
+        if !locked {
+            return Ok(0);
+        }
+
+        let classes = query
+            .execute(&mut txn)
+            .await
+            .context("Failed to find ended classes")?;
+
+        let mut closed = Vec::with_capacity(classes.len());
+        let mut events = Vec::with_capacity(classes.len());
+        for class in classes {
+            let class = crate::app::services::close_class(&mut txn, &class, true).await?;
+            events.push(crate::app::services::push_class_stop(&mut txn, &class).await?);
+            closed.push(class);
+        }
+
+        txn.commit()
+            .await
+            .context("Failed to commit sqlx db transaction")?;
+
+        (closed, events)
+    };
+
+    crate::app::outbox::publish(ctx, events).await;
+
+    for class in &closed {
+        crate::app::webhooks::enqueue(ctx, class, WebhookEvent::Stopped).await;
+    }
+
+    Ok(closed.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use serde_json::Value as JsonValue;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::db::class::{ClassStatus, ReadQuery};
+    use crate::test_helpers::prelude::*;
+
+    #[tokio::test]
+    async fn sweep_closes_ended_classes() {
+        let db_pool = TestDb::new().await;
+        let now = Utc::now();
+        let audience = random_string();
+
+        let (ended, ongoing) = {
+            let mut conn = db_pool.get_conn().await;
+
+            let ended = factory::Webinar::new(
+                random_string(),
+                audience.clone(),
+                (
+                    Bound::Included(now - chrono::Duration::hours(2)),
+                    Bound::Excluded(now - chrono::Duration::hours(1)),
+                )
+                    .into(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+            )
+            .insert(&mut conn)
+            .await;
+
+            let ongoing = factory::Webinar::new(
+                random_string(),
+                audience.clone(),
+                (
+                    Bound::Included(now - chrono::Duration::hours(1)),
+                    Bound::Excluded(now + chrono::Duration::hours(1)),
+                )
+                    .into(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+            )
+            .insert(&mut conn)
+            .await;
+
+            (ended, ongoing)
+        };
+
+        let state = TestState::new_with_pool(db_pool, TestAuthz::new());
+
+        let closed = sweep(&state, EndedListQuery::new(1000).audience(audience))
+            .await
+            .expect("Failed to sweep");
+        assert_eq!(closed, 1);
+
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+
+        let class = ReadQuery::by_id(ended.id())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to read class")
+            .expect("Class not found");
+        assert_eq!(class.status(), ClassStatus::Closed);
+        assert!(class.timed_out());
+
+        let class = ReadQuery::by_id(ongoing.id())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to read class")
+            .expect("Class not found");
+        assert_eq!(class.status(), ClassStatus::RealTime);
+
+        let stopped = state.test_publisher().flush();
+        assert_eq!(stopped.len(), 1);
+        assert_eq!(
+            stopped[0].payload::<JsonValue>()["id"],
+            ended.id().to_string()
+        );
+
+        match stopped[0].properties() {
+            OutgoingEnvelopeProperties::Event(props) => {
+                assert_eq!(props.label(), "webinar.stop")
+            }
+            _ => panic!("Expected an event"),
+        }
+    }
+}
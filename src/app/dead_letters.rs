@@ -0,0 +1,105 @@
+use anyhow::{Context, Result};
+use svc_agent::mqtt::{IncomingEvent, IncomingEventProperties};
+
+use crate::app::error::Error as AppError;
+use crate::app::AppContext;
+use crate::db::dead_letter::{Object as DeadLetter, ResolveQuery, UpsertQuery};
+
+/// Stores the event whose handler failed with `error` so that it could be replayed later.
+pub async fn record(
+    ctx: &dyn AppContext,
+    key: &str,
+    event: &IncomingEvent<String>,
+    topic: &str,
+    error: &AppError,
+) -> Result<DeadLetter> {
+    let label = event.properties().label().unwrap_or_default().to_owned();
+    let properties =
+        serde_json::to_value(event.properties()).context("Failed to serialize event properties")?;
+
+    let mut conn = ctx.get_conn().await?;
+    UpsertQuery::new(
+        key.to_owned(),
+        label,
+        topic.to_owned(),
+        event.payload().to_owned(),
+        properties,
+        error.to_string(),
+    )
+    .execute(&mut conn)
+    .await
+    .context("Failed to store dead letter")
+}
+
+/// Marks the dead letter of the event resolved once the event got handled.
+pub async fn resolve(ctx: &dyn AppContext, key: &str) -> Result<()> {
+    let mut conn = ctx.get_conn().await?;
+    ResolveQuery::new(key.to_owned())
+        .execute(&mut conn)
+        .await
+        .context("Failed to resolve dead letter")
+}
+
+/// Rebuilds the event and its topic as they came from the broker.
+pub fn to_event(dead_letter: &DeadLetter) -> Result<(IncomingEvent<String>, String)> {
+    let properties =
+        serde_json::from_value::<IncomingEventProperties>(dead_letter.properties().to_owned())
+            .context("Failed to deserialize event properties")?;
+
+    let event = IncomingEvent::new(dead_letter.payload().to_owned(), properties);
+    Ok((event, dead_letter.topic().to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::error::ErrorKind as AppErrorKind;
+    use crate::db::dead_letter::ReadQuery;
+    use crate::test_helpers::prelude::*;
+
+    #[tokio::test]
+    async fn failed_event_is_stored_and_rebuilt() {
+        let db_pool = TestDb::new().await;
+        let state = TestState::new_with_pool(db_pool, TestAuthz::new());
+
+        let key = random_string();
+        let topic = format!("apps/conference.{}/api/v1/events", SVC_AUDIENCE);
+        let event = incoming_event("room.upload", &serde_json::json!({ "id": Uuid::new_v4() }));
+        let error = AppError::new(
+            AppErrorKind::TranscodingFlowFailed,
+            anyhow!("Class not found"),
+        );
+
+        let dead_letter = record(&state, &key, &event, &topic, &error)
+            .await
+            .expect("Failed to record dead letter");
+        assert_eq!(dead_letter.attempts(), 1);
+        assert_eq!(
+            dead_letter.error(),
+            "Transcoding flow failed: Class not found"
+        );
+
+        // The same event failing again is the same dead letter.
+        let dead_letter = record(&state, &key, &event, &topic, &error)
+            .await
+            .expect("Failed to record dead letter");
+        assert_eq!(dead_letter.attempts(), 2);
+
+        let (rebuilt, rebuilt_topic) = to_event(&dead_letter).expect("Failed to rebuild event");
+        assert_eq!(rebuilt.payload(), event.payload());
+        assert_eq!(rebuilt.properties().label(), Some("room.upload"));
+        assert_eq!(rebuilt_topic, topic);
+
+        resolve(&state, &key)
+            .await
+            .expect("Failed to resolve dead letter");
+
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+        let dead_letter = ReadQuery::by_id(dead_letter.id())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to read dead letter")
+            .expect("Dead letter not found");
+        assert!(dead_letter.is_resolved());
+    }
+}
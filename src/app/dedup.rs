@@ -0,0 +1,232 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::app::AppContext;
+use crate::config::DedupConfig;
+use crate::db::processed_event::{ClaimQuery, DeleteExpiredQuery, DeleteQuery, ProlongQuery};
+
+/// Incoming events carry no correlation data so a redelivered one is recognized
+/// by the hash of its label, topic and payload.
+///
+/// Attempts of a tq task often complete with the same payload so a task completion is
+/// recognized by the job attempt tq sends back in the tags instead.
+pub fn event_key(label: &str, topic: &str, payload: &str) -> String {
+    let mut hasher = Sha256::new();
+
+    hasher.update(label.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(topic.as_bytes());
+    hasher.update(b"\n");
+
+    match task_attempt(label, payload) {
+        Some((job_id, attempt)) => {
+            hasher.update(job_id.as_bytes());
+            hasher.update(b"\n");
+            hasher.update(attempt.to_string().as_bytes());
+        }
+        None => hasher.update(payload.as_bytes()),
+    }
+
+    hex::encode(hasher.finalize())
+}
+
+fn task_attempt(label: &str, payload: &str) -> Option<(String, i64)> {
+    if label != "task.complete" {
+        return None;
+    }
+
+    let payload = serde_json::from_str::<JsonValue>(payload).ok()?;
+    let tags = payload.get("tags")?;
+    let job_id = tags.get("job_id")?.as_str()?.to_owned();
+    let attempt = tags.get("attempt")?.as_i64()?;
+
+    Some((job_id, attempt))
+}
+
+/// Marks the event as being processed for the `dedup.lease` so that the event gets processed
+/// again if its handler never finishes. Returns `false` for a duplicate.
+pub async fn claim(ctx: &dyn AppContext, key: &str, label: &str) -> Result<bool> {
+    let lease =
+        chrono::Duration::from_std(ctx.config().dedup.lease).context("Invalid dedup lease")?;
+
+    let mut conn = ctx.get_conn().await?;
+    ClaimQuery::new(key.to_owned(), label.to_owned(), Utc::now() + lease)
+        .execute(&mut conn)
+        .await
+        .context("Failed to claim event")
+}
+
+/// Remembers the handled event for the `dedup.ttl` to skip its redeliveries.
+pub async fn complete(ctx: &dyn AppContext, key: &str) -> Result<()> {
+    let ttl = chrono::Duration::from_std(ctx.config().dedup.ttl).context("Invalid dedup ttl")?;
+
+    let mut conn = ctx.get_conn().await?;
+    ProlongQuery::new(key.to_owned(), Utc::now() + ttl)
+        .execute(&mut conn)
+        .await
+        .context("Failed to complete event")
+}
+
+/// Forgets the event so its redelivery gets processed again, e.g. after a handler failure.
+pub async fn release(ctx: &dyn AppContext, key: &str) -> Result<()> {
+    let mut conn = ctx.get_conn().await?;
+    DeleteQuery::new(key.to_owned())
+        .execute(&mut conn)
+        .await
+        .context("Failed to release event")
+}
+
+/// Periodically deletes expired processed events.
+pub fn spawn(ctx: Arc<dyn AppContext>, config: DedupConfig) -> JoinHandle<()> {
+    tokio::task::spawn(async move {
+        let mut interval = tokio::time::interval(config.cleanup_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            match cleanup(ctx.as_ref()).await {
+                Ok(0) => {}
+                Ok(deleted) => info!(deleted, "Deleted expired processed events"),
+                Err(e) => error!("Failed to delete expired processed events, err = {:?}", e),
+            }
+        }
+    })
+}
+
+async fn cleanup(ctx: &dyn AppContext) -> Result<u64> {
+    let mut conn = ctx.get_conn().await?;
+    DeleteExpiredQuery::new(Utc::now())
+        .execute(&mut conn)
+        .await
+        .context("Failed to delete expired processed events")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::prelude::*;
+
+    async fn expires_at(state: &TestState, key: &str) -> chrono::DateTime<Utc> {
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+
+        sqlx::query_scalar("SELECT expires_at FROM processed_event WHERE key = $1")
+            .bind(key)
+            .fetch_one(&mut conn)
+            .await
+            .expect("Failed to read processed event")
+    }
+
+    #[test]
+    fn event_key_depends_on_label_topic_and_payload() {
+        let key = event_key("room.upload", "apps/conference/events", "{}");
+
+        assert_eq!(
+            key,
+            event_key("room.upload", "apps/conference/events", "{}")
+        );
+        assert_ne!(
+            key,
+            event_key("room.adjust", "apps/conference/events", "{}")
+        );
+        assert_ne!(key, event_key("room.upload", "apps/event/events", "{}"));
+        assert_ne!(
+            key,
+            event_key("room.upload", "apps/conference/events", "{ }")
+        );
+    }
+
+    #[tokio::test]
+    async fn task_attempts_with_same_failure_are_not_duplicates() {
+        let db_pool = TestDb::new().await;
+        let state = TestState::new_with_pool(db_pool, TestAuthz::new());
+        let job_id = uuid::Uuid::new_v4();
+
+        let failure = |attempt: i32| {
+            serde_json::json!({
+                "status": "failure",
+                "error": {"kind": "oops"},
+                "tags": {
+                    "scope": "scope",
+                    "conference_room_id": "d2e0d0a2-5a04-4a4b-8b52-7cd2d1b1d7bb",
+                    "template": "convert-mjr-dumps-to-stream",
+                    "job_id": job_id,
+                    "attempt": attempt,
+                },
+            })
+            .to_string()
+        };
+
+        let first = event_key("task.complete", "apps/tq/events", &failure(1));
+        assert!(claim(&state, &first, "task.complete").await.unwrap());
+        complete(&state, &first).await.unwrap();
+
+        // Same payload redelivered.
+        let redelivered = event_key("task.complete", "apps/tq/events", &failure(1));
+        assert!(!claim(&state, &redelivered, "task.complete").await.unwrap());
+
+        let second = event_key("task.complete", "apps/tq/events", &failure(2));
+        assert_ne!(first, second);
+        assert!(claim(&state, &second, "task.complete").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn duplicate_is_not_claimed_until_released() {
+        let db_pool = TestDb::new().await;
+        let state = TestState::new_with_pool(db_pool, TestAuthz::new());
+        let key = event_key("room.upload", "apps/conference/events", &random_string());
+
+        assert!(claim(&state, &key, "room.upload").await.unwrap());
+        assert!(!claim(&state, &key, "room.upload").await.unwrap());
+
+        release(&state, &key).await.unwrap();
+
+        assert!(claim(&state, &key, "room.upload").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn claim_is_leased_until_completed() {
+        let db_pool = TestDb::new().await;
+        let state = TestState::new_with_pool(db_pool, TestAuthz::new());
+        let key = event_key("room.adjust", "apps/event/events", &random_string());
+
+        let lease = chrono::Duration::from_std(state.config().dedup.lease).unwrap();
+
+        assert!(claim(&state, &key, "room.adjust").await.unwrap());
+        let leased_until = expires_at(&state, &key).await;
+        assert!(leased_until <= Utc::now() + lease);
+
+        complete(&state, &key).await.unwrap();
+        let expires_at = expires_at(&state, &key).await;
+        assert!(expires_at > leased_until);
+        assert!(expires_at > Utc::now() + lease);
+
+        assert!(!claim(&state, &key, "room.adjust").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn expired_claim_is_taken_over() {
+        let db_pool = TestDb::new().await;
+        let state = TestState::new_with_pool(db_pool, TestAuthz::new());
+        let key = event_key("task.complete", "apps/tq/events", &random_string());
+
+        {
+            let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+            let expired = Utc::now() - chrono::Duration::seconds(1);
+
+            let claimed = ClaimQuery::new(key.clone(), "task.complete".to_owned(), expired)
+                .execute(&mut conn)
+                .await
+                .expect("Failed to claim event");
+            assert!(claimed);
+        }
+
+        assert!(claim(&state, &key, "task.complete").await.unwrap());
+    }
+}
@@ -41,13 +41,60 @@ pub enum ErrorKind {
     CreationWhiteboardFailed,
     ClassAlreadyEstablished,
     MissingTenant,
+    DeadLetterNotFound,
+    FrontendNotFound,
+    ScopeNotFound,
+    RolloutNotFound,
+    PayloadTooLarge,
 }
 
 impl ErrorKind {
+    /// Every kind there is, listed in the OpenAPI document. Keep in sync with the enum.
+    pub const ALL: &'static [ErrorKind] = &[
+        ErrorKind::AccessDenied,
+        ErrorKind::AuthorizationFailed,
+        ErrorKind::DbConnAcquisitionFailed,
+        ErrorKind::DbQueryFailed,
+        ErrorKind::InvalidParameter,
+        ErrorKind::InvalidPayload,
+        ErrorKind::MqttRequestFailed,
+        ErrorKind::SerializationFailed,
+        ErrorKind::Unauthorized,
+        ErrorKind::ClassNotFound,
+        ErrorKind::RecordingNotFound,
+        ErrorKind::ClassClosingFailed,
+        ErrorKind::TranscodingFlowFailed,
+        ErrorKind::EditionFailed,
+        ErrorKind::ClassPropertyNotFound,
+        ErrorKind::AudienceDoesNotMatch,
+        ErrorKind::AccountNotFound,
+        ErrorKind::AccountPropertyNotFound,
+        ErrorKind::InvalidQueryString,
+        ErrorKind::InternalFailure,
+        ErrorKind::CreationWhiteboardFailed,
+        ErrorKind::ClassAlreadyEstablished,
+        ErrorKind::MissingTenant,
+        ErrorKind::DeadLetterNotFound,
+        ErrorKind::FrontendNotFound,
+        ErrorKind::ScopeNotFound,
+        ErrorKind::RolloutNotFound,
+        ErrorKind::PayloadTooLarge,
+    ];
+
     pub fn is_notify_sentry(self) -> bool {
         let properties: ErrorKindProperties = self.into();
         properties.is_notify_sentry
     }
+
+    pub fn status(self) -> ResponseStatus {
+        let properties: ErrorKindProperties = self.into();
+        properties.status
+    }
+
+    pub fn kind(self) -> &'static str {
+        let properties: ErrorKindProperties = self.into();
+        properties.kind
+    }
 }
 
 impl fmt::Display for ErrorKind {
@@ -198,6 +245,36 @@ impl From<ErrorKind> for ErrorKindProperties {
                 title: "Tenant not found in config",
                 is_notify_sentry: false,
             },
+            ErrorKind::DeadLetterNotFound => ErrorKindProperties {
+                status: ResponseStatus::NOT_FOUND,
+                kind: "dead_letter_not_found",
+                title: "Dead letter not found",
+                is_notify_sentry: false,
+            },
+            ErrorKind::FrontendNotFound => ErrorKindProperties {
+                status: ResponseStatus::NOT_FOUND,
+                kind: "frontend_not_found",
+                title: "Frontend not found",
+                is_notify_sentry: false,
+            },
+            ErrorKind::ScopeNotFound => ErrorKindProperties {
+                status: ResponseStatus::NOT_FOUND,
+                kind: "scope_not_found",
+                title: "Scope not found",
+                is_notify_sentry: false,
+            },
+            ErrorKind::RolloutNotFound => ErrorKindProperties {
+                status: ResponseStatus::NOT_FOUND,
+                kind: "rollout_not_found",
+                title: "Rollout not found",
+                is_notify_sentry: false,
+            },
+            ErrorKind::PayloadTooLarge => ErrorKindProperties {
+                status: ResponseStatus::PAYLOAD_TOO_LARGE,
+                kind: "payload_too_large",
+                title: "Payload too large",
+                is_notify_sentry: false,
+            },
         }
     }
 }
@@ -311,3 +388,67 @@ where
         self.map_err(|err| Error::new(kind, err.into()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The match is exhaustive so a new kind doesn't compile until it gets its index here,
+    // then the test fails until the kind takes the same place in `ErrorKind::ALL`.
+    macro_rules! indices {
+        ($($kind:ident => $index:expr,)*) => {
+            fn index(kind: ErrorKind) -> usize {
+                match kind {
+                    $(ErrorKind::$kind => $index,)*
+                }
+            }
+
+            const COUNT: usize = [$($index),*].len();
+        };
+    }
+
+    #[test]
+    fn all_lists_every_kind() {
+        indices! {
+            AccessDenied => 0,
+            AuthorizationFailed => 1,
+            DbConnAcquisitionFailed => 2,
+            DbQueryFailed => 3,
+            InvalidParameter => 4,
+            InvalidPayload => 5,
+            MqttRequestFailed => 6,
+            SerializationFailed => 7,
+            Unauthorized => 8,
+            ClassNotFound => 9,
+            RecordingNotFound => 10,
+            ClassClosingFailed => 11,
+            TranscodingFlowFailed => 12,
+            EditionFailed => 13,
+            ClassPropertyNotFound => 14,
+            AudienceDoesNotMatch => 15,
+            AccountNotFound => 16,
+            AccountPropertyNotFound => 17,
+            InvalidQueryString => 18,
+            InternalFailure => 19,
+            CreationWhiteboardFailed => 20,
+            ClassAlreadyEstablished => 21,
+            MissingTenant => 22,
+            DeadLetterNotFound => 23,
+            FrontendNotFound => 24,
+            ScopeNotFound => 25,
+            RolloutNotFound => 26,
+            PayloadTooLarge => 27,
+        }
+
+        assert_eq!(ErrorKind::ALL.len(), COUNT);
+
+        for (i, kind) in ErrorKind::ALL.iter().enumerate() {
+            assert_eq!(
+                index(*kind),
+                i,
+                "{:?} is out of place in ErrorKind::ALL",
+                kind
+            );
+        }
+    }
+}
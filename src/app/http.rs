@@ -3,34 +3,44 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use axum::{
     extract::{rejection::JsonRejection, Extension, FromRequest},
-    routing::{get, post, Router},
+    middleware::from_fn,
+    routing::{get, post, put, Router},
 };
 use http::Request;
 use svc_utils::middleware::{CorsLayer, LogLayer, MeteredRoute};
 
 use super::api::v1::class::{
-    commit_edition, create_timestamp, read, read_by_scope, read_property, recreate, update,
-    update_by_scope, update_property,
+    commit_edition, create_timestamp, delete, list as list_classes, list_postprocessing_jobs, read,
+    read_by_scope, read_property, recreate, update, update_by_scope, update_property,
 };
 use super::api::v1::minigroup::{
     create as create_minigroup, create_whiteboard, download as download_minigroup,
 };
-use super::api::v1::p2p::{convert as convert_p2p, create as create_p2p};
+use super::api::v1::p2p::{convert as convert_p2p, create as create_p2p, download as download_p2p};
 use super::api::v1::webinar::{
     convert_webinar, create_webinar, create_webinar_replica, download_webinar,
 };
 use super::api::v1::{
-    account, minigroup::restart_transcoding as restart_transcoding_minigroup,
-    webinar::restart_transcoding as restart_transcoding_webinar,
+    account, audit_log, dead_letters, frontends,
+    minigroup::restart_transcoding as restart_transcoding_minigroup, openapi::openapi, rollouts,
+    webhooks::list as list_webhooks, webinar::restart_transcoding as restart_transcoding_webinar,
+};
+use super::api::{
+    audit, rollback, v1::create_event, v1::healthz, v1::readyz, v1::redirect_to_frontend,
 };
-use super::api::{rollback, v1::create_event, v1::healthz, v1::redirect_to_frontend};
 use super::info::{list_frontends, list_scopes};
 use super::{api::v1::authz::proxy as proxy_authz, error::ErrorExt};
 
-use crate::app::AppContext;
+use crate::app::readiness::Readiness;
+use crate::app::{AppContext, EventSender};
 use crate::db::class::{MinigroupType, P2PType, WebinarType};
 
-pub fn router(ctx: Arc<dyn AppContext>, authn: svc_authn::jose::ConfigMap) -> Router {
+pub fn router(
+    ctx: Arc<dyn AppContext>,
+    events: EventSender,
+    readiness: Readiness,
+    authn: svc_authn::jose::ConfigMap,
+) -> Router {
     let router = redirects_router()
         .merge(webinars_router())
         .merge(p2p_router())
@@ -38,9 +48,13 @@ pub fn router(ctx: Arc<dyn AppContext>, authn: svc_authn::jose::ConfigMap) -> Ro
         .merge(authz_router())
         .merge(utils_router());
 
+    // Goes inside the extensions since it relies on them.
     router
+        .layer(from_fn(audit::middleware))
         .layer(Extension(Arc::new(authn)))
         .layer(Extension(ctx))
+        .layer(Extension(events))
+        .layer(Extension(readiness))
         .layer(LogLayer::new())
 }
 
@@ -49,7 +63,23 @@ fn redirects_router() -> Router {
         .metered_route("/info/scopes", get(list_scopes))
         .metered_route("/info/frontends", get(list_frontends))
         .metered_route("/healthz", get(healthz))
+        .metered_route("/readyz", get(readyz))
+        .metered_route("/api/v1/openapi.json", get(openapi))
         .metered_route("/api/v1/scopes/:scope/rollback", post(rollback))
+        .metered_route(
+            "/api/v1/scopes/:scope/apps/:app",
+            put(frontends::bind).delete(frontends::unbind),
+        )
+        .metered_route("/api/v1/frontends", post(frontends::create))
+        .metered_route(
+            "/api/v1/frontends/:frontend_id",
+            put(frontends::update).delete(frontends::delete),
+        )
+        .metered_route("/api/v1/rollouts", get(rollouts::list))
+        .metered_route(
+            "/api/v1/rollouts/tenants/:tenant/apps/:app",
+            put(rollouts::update).delete(rollouts::delete),
+        )
         .metered_route("/api/v1/redirs", get(redirect_to_frontend))
         .metered_route(
             "/api/v1/redirs/tenants/:tenant/apps/:app",
@@ -61,7 +91,9 @@ fn webinars_router() -> Router {
     Router::new()
         .metered_route(
             "/api/v1/webinars/:id",
-            get(read::<WebinarType>).put(update::<WebinarType>),
+            get(read::<WebinarType>)
+                .put(update::<WebinarType>)
+                .delete(delete::<WebinarType>),
         )
         .metered_route(
             "/api/v1/audiences/:audience/webinars/:scope",
@@ -92,7 +124,10 @@ fn webinars_router() -> Router {
 
 fn p2p_router() -> Router {
     Router::new()
-        .metered_route("/api/v1/p2p/:id", get(read::<P2PType>))
+        .metered_route(
+            "/api/v1/p2p/:id",
+            get(read::<P2PType>).delete(delete::<P2PType>),
+        )
         .metered_route(
             "/api/v1/audiences/:audience/p2p/:scope",
             get(read_by_scope::<P2PType>),
@@ -104,6 +139,7 @@ fn p2p_router() -> Router {
         .layer(CorsLayer)
         .metered_route("/api/v1/p2p", post(create_p2p))
         .metered_route("/api/v1/p2p/convert", post(convert_p2p))
+        .metered_route("/api/v1/p2p/:id/download", get(download_p2p))
         .metered_route("/api/v1/p2p/:id/events", post(create_event))
 }
 
@@ -111,7 +147,9 @@ fn minigroups_router() -> Router {
     Router::new()
         .metered_route(
             "/api/v1/minigroups/:id",
-            get(read::<MinigroupType>).put(update::<MinigroupType>),
+            get(read::<MinigroupType>)
+                .put(update::<MinigroupType>)
+                .delete(delete::<MinigroupType>),
         )
         .metered_route(
             "/api/v1/audiences/:audience/minigroups/:scope",
@@ -142,6 +180,8 @@ fn authz_router() -> Router {
 
 fn utils_router() -> Router {
     Router::new()
+        .metered_route("/api/v1/audiences/:audience/classes", get(list_classes))
+        .metered_route("/api/v1/audiences/:audience/webhooks", get(list_webhooks))
         .metered_route(
             "/api/v1/audiences/:audience/classes/:scope/editions/:id",
             post(commit_edition),
@@ -150,6 +190,10 @@ fn utils_router() -> Router {
             "/api/v1/account/properties/:property_id",
             get(account::read_property).put(account::update_property),
         )
+        .metered_route(
+            "/api/v1/classes/:id/postprocessing",
+            get(list_postprocessing_jobs),
+        )
         .metered_route(
             "/api/v1/transcoding/minigroup/:id/restart",
             post(restart_transcoding_minigroup),
@@ -158,6 +202,13 @@ fn utils_router() -> Router {
             "/api/v1/transcoding/webinar/:id/restart",
             post(restart_transcoding_webinar),
         )
+        .metered_route("/api/v1/dead_letters", get(dead_letters::list))
+        .metered_route("/api/v1/dead_letters/:id", get(dead_letters::read))
+        .metered_route(
+            "/api/v1/dead_letters/:id/replay",
+            post(dead_letters::replay),
+        )
+        .metered_route("/api/v1/audit_log", get(audit_log::list))
         .layer(CorsLayer)
 }
 
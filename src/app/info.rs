@@ -1,70 +1,338 @@
-use std::fmt::Write;
+//! Scopes and frontends listings for the people running dispatcher. The response is plain text
+//! unless the client accepts `application/json`.
+
 use std::sync::Arc;
 
-use axum::extract;
+use anyhow::Context;
+use axum::extract::{Extension, Query};
+use http::HeaderMap;
 use hyper::{Body, Response};
-use tracing::error;
+use serde_derive::{Deserialize, Serialize};
+use svc_authn::AccountId;
+use svc_utils::extractors::AccountIdExtractor;
+use utoipa::{IntoParams, ToSchema};
 
+use super::api::v1::AppResult;
+use super::authz::authorize_service;
+use super::error::ErrorExt;
+use super::error::ErrorKind as AppErrorKind;
 use super::AppContext;
+use crate::db::frontend::{self, Object as Frontend};
+use crate::db::scope::{self, Object as Scope};
 
-pub async fn list_scopes(ctx: extract::Extension<Arc<dyn AppContext>>) -> Response<Body> {
-    match ctx.get_conn().await {
-        Err(e) => Response::builder()
-            .status(500)
-            .body(Body::from(format!("Failed to acquire conn: {}", e)))
-            .unwrap(),
-        Ok(mut conn) => {
-            let scopes = crate::db::scope::ListQuery::new().execute(&mut conn).await;
-            match scopes {
-                Err(e) => Response::builder()
-                    .body(Body::from(format!("Failed query: {}", e)))
-                    .unwrap(),
-                Ok(scopes) => {
-                    let mut s = String::new();
-                    writeln!(&mut s, "Frontends list:").unwrap();
-                    for scope in scopes {
-                        if let Err(e) = writeln!(
-                            &mut s,
-                            "{}\t{}\t{}\t{}",
-                            scope.id, scope.scope, scope.frontend_id, scope.created_at
-                        ) {
-                            error!("Failed to write response to buf string, reason = {:?}", e);
-                        }
-                    }
-                    Response::builder().body(Body::from(s)).unwrap()
-                }
-            }
-        }
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+#[derive(Default, Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ScopeFilters {
+    app: Option<String>,
+    frontend_id: Option<i64>,
+    scope_prefix: Option<String>,
+    after: Option<i64>,
+    limit: Option<i64>,
+}
+
+#[derive(Default, Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FrontendFilters {
+    after: Option<i64>,
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ScopeList {
+    scopes: Vec<Scope>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FrontendList {
+    frontends: Vec<Frontend>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<i64>,
+}
+
+pub async fn list_scopes(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    Query(filters): Query<ScopeFilters>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+    headers: HeaderMap,
+) -> AppResult {
+    do_list_scopes(ctx.as_ref(), &account_id, filters, accepts_json(&headers)).await
+}
+
+async fn do_list_scopes(
+    state: &dyn AppContext,
+    account_id: &AccountId,
+    filters: ScopeFilters,
+    json: bool,
+) -> AppResult {
+    authorize_service(state, account_id, &["scopes"], "list").await?;
+
+    let limit = filters.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    // Fetch one extra row to find out whether there is a next page.
+    let mut query = scope::ListQuery::new().limit(limit + 1);
+
+    if let Some(app) = filters.app {
+        query = query.app(app);
+    }
+
+    if let Some(frontend_id) = filters.frontend_id {
+        query = query.frontend_id(frontend_id);
+    }
+
+    if let Some(scope_prefix) = filters.scope_prefix {
+        query = query.scope_prefix(scope_prefix);
     }
+
+    if let Some(after) = filters.after {
+        query = query.after(after);
+    }
+
+    let mut scopes = {
+        let mut conn = state
+            .get_conn()
+            .await
+            .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+        query
+            .execute(&mut conn)
+            .await
+            .context("Failed to list scopes")
+            .error(AppErrorKind::DbQueryFailed)?
+    };
+
+    let next_cursor = if scopes.len() as i64 > limit {
+        scopes.truncate(limit as usize);
+        scopes.last().map(|scope| scope.id)
+    } else {
+        None
+    };
+
+    if json {
+        return json_response(&ScopeList {
+            scopes,
+            next_cursor,
+        });
+    }
+
+    let lines = scopes.iter().map(|scope| {
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            scope.id, scope.scope, scope.app, scope.frontend_id, scope.created_at
+        )
+    });
+
+    Ok(text_response("Scopes list:", lines, next_cursor))
 }
 
-pub async fn list_frontends(ctx: extract::Extension<Arc<dyn AppContext>>) -> Response<Body> {
-    match ctx.get_conn().await {
-        Err(e) => Response::builder()
-            .status(500)
-            .body(Body::from(format!("Failed to acquire conn: {}", e)))
-            .unwrap(),
-        Ok(mut conn) => {
-            let frontends = crate::db::frontend::ListQuery::new()
+pub async fn list_frontends(
+    Extension(ctx): Extension<Arc<dyn AppContext>>,
+    Query(filters): Query<FrontendFilters>,
+    AccountIdExtractor(account_id): AccountIdExtractor,
+    headers: HeaderMap,
+) -> AppResult {
+    do_list_frontends(ctx.as_ref(), &account_id, filters, accepts_json(&headers)).await
+}
+
+async fn do_list_frontends(
+    state: &dyn AppContext,
+    account_id: &AccountId,
+    filters: FrontendFilters,
+    json: bool,
+) -> AppResult {
+    authorize_service(state, account_id, &["frontends"], "list").await?;
+
+    let limit = filters.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let mut query = frontend::ListQuery::new().limit(limit + 1);
+
+    if let Some(after) = filters.after {
+        query = query.after(after);
+    }
+
+    let mut frontends = {
+        let mut conn = state
+            .get_conn()
+            .await
+            .error(AppErrorKind::DbConnAcquisitionFailed)?;
+
+        query
+            .execute(&mut conn)
+            .await
+            .context("Failed to list frontends")
+            .error(AppErrorKind::DbQueryFailed)?
+    };
+
+    let next_cursor = if frontends.len() as i64 > limit {
+        frontends.truncate(limit as usize);
+        frontends.last().map(|frontend| frontend.id)
+    } else {
+        None
+    };
+
+    if json {
+        return json_response(&FrontendList {
+            frontends,
+            next_cursor,
+        });
+    }
+
+    let lines = frontends
+        .iter()
+        .map(|fe| format!("{}\t{}\t{}", fe.id, fe.url, fe.created_at));
+
+    Ok(text_response("Frontends list:", lines, next_cursor))
+}
+
+fn accepts_json(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(http::header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media_type| media_type.trim().starts_with("application/json"))
+}
+
+fn json_response<T: serde::Serialize>(body: &T) -> AppResult {
+    let body = serde_json::to_string(body)
+        .context("Failed to serialize listing")
+        .error(AppErrorKind::SerializationFailed)?;
+
+    let response = Response::builder()
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap();
+
+    Ok(response)
+}
+
+fn text_response(
+    header: &str,
+    lines: impl Iterator<Item = String>,
+    next_cursor: Option<i64>,
+) -> Response<Body> {
+    let mut body = vec![header.to_owned()];
+    body.extend(lines);
+
+    if let Some(cursor) = next_cursor {
+        body.push(format!("Next page: after={}", cursor));
+    }
+
+    let mut body = body.join("\n");
+    body.push('\n');
+
+    Response::builder()
+        .header(http::header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value as JsonValue;
+
+    use super::*;
+    use crate::test_helpers::prelude::*;
+
+    fn allow(agent: &TestAgent, object: &str) -> TestAuthz {
+        let mut authz = TestAuthz::new();
+        authz.set_audience(SVC_AUDIENCE);
+        authz.allow(agent.account_id(), vec![object], "list");
+        authz
+    }
+
+    async fn body(response: Response<Body>) -> String {
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        String::from_utf8(body.to_vec()).expect("Invalid utf-8")
+    }
+
+    #[tokio::test]
+    async fn list_scopes_unauthorized() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let state = TestState::new(TestAuthz::new()).await;
+
+        do_list_scopes(&state, agent.account_id(), Default::default(), true)
+            .await
+            .expect_err("Unexpectedly succeeded");
+    }
+
+    #[tokio::test]
+    async fn list_scopes_by_prefix_and_app() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let state = TestState::new(allow(&agent, "scopes")).await;
+        let prefix = random_string();
+
+        {
+            let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+
+            let frontend = factory::Frontend::new("https://v2.example.org".into())
                 .execute(&mut conn)
-                .await;
-            match frontends {
-                Err(e) => Response::builder()
-                    .status(500)
-                    .body(Body::from(format!("Failed query: {}", e)))
-                    .unwrap(),
-                Ok(frontends) => {
-                    let mut s = String::new();
-                    writeln!(&mut s, "Frontends list:").unwrap();
-                    for fe in frontends {
-                        if let Err(e) = writeln!(&mut s, "{}\t{}\t{}", fe.id, fe.url, fe.created_at)
-                        {
-                            error!("Failed to write response to buf string, reason = {:?}", e);
-                        }
-                    }
-                    Response::builder().body(Body::from(s)).unwrap()
-                }
+                .await
+                .expect("Failed to seed frontend");
+
+            for (scope, app) in [("a", "webinar"), ("b", "webinar"), ("c", "minigroup")] {
+                factory::Scope::new(format!("{}-{}", prefix, scope), frontend.id, app.into())
+                    .execute(&mut conn)
+                    .await
+                    .expect("Failed to seed scope");
             }
         }
+
+        let filters = |after| ScopeFilters {
+            app: Some("webinar".into()),
+            scope_prefix: Some(prefix.clone()),
+            after,
+            limit: Some(1),
+            ..Default::default()
+        };
+
+        let r = do_list_scopes(&state, agent.account_id(), filters(None), true)
+            .await
+            .expect("Failed to list scopes");
+        let v = serde_json::from_str::<JsonValue>(&body(r).await).expect("Failed to parse json");
+        assert_eq!(v["scopes"].as_array().map(Vec::len), Some(1));
+        assert_eq!(v["scopes"][0]["scope"], format!("{}-a", prefix));
+
+        let r = do_list_scopes(
+            &state,
+            agent.account_id(),
+            filters(v["next_cursor"].as_i64()),
+            true,
+        )
+        .await
+        .expect("Failed to list scopes");
+        let v = serde_json::from_str::<JsonValue>(&body(r).await).expect("Failed to parse json");
+        assert_eq!(v["scopes"][0]["scope"], format!("{}-b", prefix));
+        assert!(v.get("next_cursor").is_none());
+    }
+
+    #[tokio::test]
+    async fn list_scopes_as_text() {
+        let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let state = TestState::new(allow(&agent, "scopes")).await;
+
+        let filters = ScopeFilters {
+            scope_prefix: Some(random_string()),
+            ..Default::default()
+        };
+
+        let r = do_list_scopes(&state, agent.account_id(), filters, false)
+            .await
+            .expect("Failed to list scopes");
+        assert_eq!(body(r).await, "Scopes list:\n");
+    }
+
+    #[test]
+    fn negotiates_json() {
+        let mut headers = HeaderMap::new();
+        assert!(!accepts_json(&headers));
+
+        headers.insert(
+            http::header::ACCEPT,
+            "text/html, application/json;q=0.9".parse().unwrap(),
+        );
+        assert!(accepts_json(&headers));
     }
 }
@@ -0,0 +1,265 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::pin::Pin;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tracing::error;
+
+use crate::app::metrics::MqttMetrics;
+
+pub type Job = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A fixed set of queues each handling its jobs one by one in the order they were pushed.
+/// Jobs with the same key always land in the same lane, different keys may run in parallel.
+pub struct Lanes {
+    lanes: Vec<Lane>,
+}
+
+/// A lane queues up to `capacity` jobs in its channel, the following ones wait in the overflow
+/// until the lane catches up so that pushing never waits for a busy lane.
+#[derive(Clone)]
+struct Lane {
+    tx: mpsc::Sender<Job>,
+    overflow: Arc<Mutex<VecDeque<Job>>>,
+}
+
+impl Lane {
+    // Moves the overflow into the channel as long as there's room keeping the order.
+    fn refill(&self) {
+        let mut overflow = self.overflow.lock();
+
+        while let Some(job) = overflow.pop_front() {
+            match self.tx.try_send(job) {
+                Ok(()) => (),
+                Err(TrySendError::Full(job)) | Err(TrySendError::Closed(job)) => {
+                    overflow.push_front(job);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl Lanes {
+    pub fn new(count: usize, capacity: usize) -> Self {
+        let lanes = (0..count.max(1))
+            .map(|lane| {
+                let (tx, mut rx) = mpsc::channel::<Job>(capacity.max(1));
+                let lane_label = lane.to_string();
+                let lane = Lane {
+                    tx,
+                    overflow: Default::default(),
+                };
+                let lane_ = lane.clone();
+
+                tokio::task::spawn(async move {
+                    while let Some(job) = rx.recv().await {
+                        // A panicking job must not take the whole lane down.
+                        if let Err(e) = tokio::task::spawn(job).await {
+                            error!(lane = %lane_label, "Lane job failed, err = {:?}", e);
+                        }
+
+                        MqttMetrics::observe_lane_pop(&lane_label);
+                        lane_.refill();
+                    }
+                });
+
+                lane
+            })
+            .collect();
+
+        Self { lanes }
+    }
+
+    /// Never waits, a full lane keeps the job in its overflow so only its own keys are held up.
+    pub fn push<K: Hash>(&self, key: &K, job: Job) {
+        let lane = self.lane(key);
+        let lane_label = lane.to_string();
+        let lane = &self.lanes[lane];
+
+        MqttMetrics::observe_lane_push(&lane_label);
+
+        let mut overflow = lane.overflow.lock();
+
+        // Jobs already waiting in the overflow go first.
+        if !overflow.is_empty() {
+            overflow.push_back(job);
+            return;
+        }
+
+        match lane.tx.try_send(job) {
+            Ok(()) => (),
+            Err(TrySendError::Full(job)) => overflow.push_back(job),
+            Err(TrySendError::Closed(_)) => {
+                MqttMetrics::observe_lane_pop(&lane_label);
+                error!(lane = %lane_label, "Lane is closed, job dropped");
+            }
+        }
+    }
+
+    fn lane<K: Hash>(&self, key: &K) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.lanes.len() as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use tokio::sync::oneshot;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn jobs_with_the_same_key_run_in_order() {
+        let lanes = Lanes::new(4, 10);
+        let done = Arc::new(Mutex::new(vec![]));
+        let (tx, rx) = oneshot::channel();
+
+        for i in 0..5u64 {
+            let done = done.clone();
+
+            lanes.push(
+                &"class",
+                Box::pin(async move {
+                    // Earlier jobs take longer so they'd finish last if run in parallel.
+                    tokio::time::sleep(Duration::from_millis(50 - i * 10)).await;
+                    done.lock().unwrap().push(i);
+                }),
+            );
+        }
+
+        lanes.push(
+            &"class",
+            Box::pin(async move {
+                tx.send(()).unwrap();
+            }),
+        );
+
+        rx.await.unwrap();
+        assert_eq!(*done.lock().unwrap(), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn jobs_in_different_lanes_run_in_parallel() {
+        let lanes = Lanes::new(2, 10);
+        let first = "class-0";
+        let second = (1..)
+            .map(|i| format!("class-{}", i))
+            .find(|key| lanes.lane(key) != lanes.lane(&first))
+            .unwrap();
+
+        let (unblock_tx, unblock_rx) = oneshot::channel();
+        let (done_tx, done_rx) = oneshot::channel();
+
+        // The first job waits for the second one so they'd deadlock if run sequentially.
+        lanes.push(
+            &first,
+            Box::pin(async move {
+                unblock_rx.await.unwrap();
+                done_tx.send(()).unwrap();
+            }),
+        );
+
+        lanes.push(
+            &second,
+            Box::pin(async move {
+                unblock_tx.send(()).unwrap();
+            }),
+        );
+
+        tokio::time::timeout(Duration::from_secs(5), done_rx)
+            .await
+            .expect("Jobs in different lanes didn't run in parallel")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn full_lane_does_not_hold_other_lanes() {
+        let lanes = Lanes::new(2, 1);
+        let first = "class-0";
+        let second = (1..)
+            .map(|i| format!("class-{}", i))
+            .find(|key| lanes.lane(key) != lanes.lane(&first))
+            .unwrap();
+
+        let done = Arc::new(Mutex::new(vec![]));
+        let (unblock_tx, unblock_rx) = oneshot::channel();
+        let (first_tx, first_rx) = oneshot::channel();
+        let (second_tx, second_rx) = oneshot::channel();
+
+        lanes.push(
+            &first,
+            Box::pin(async move {
+                unblock_rx.await.unwrap();
+            }),
+        );
+
+        // The lane of the first key is full past the first of these so the rest overflow.
+        for i in 0..3u64 {
+            let done = done.clone();
+
+            lanes.push(
+                &first,
+                Box::pin(async move {
+                    done.lock().unwrap().push(i);
+                }),
+            );
+        }
+
+        lanes.push(
+            &first,
+            Box::pin(async move {
+                first_tx.send(()).unwrap();
+            }),
+        );
+
+        lanes.push(
+            &second,
+            Box::pin(async move {
+                second_tx.send(()).unwrap();
+            }),
+        );
+
+        tokio::time::timeout(Duration::from_secs(5), second_rx)
+            .await
+            .expect("Full lane held another one")
+            .unwrap();
+
+        unblock_tx.send(()).unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), first_rx)
+            .await
+            .expect("Overflowed jobs didn't run")
+            .unwrap();
+
+        assert_eq!(*done.lock().unwrap(), vec![0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn panicking_job_does_not_stop_the_lane() {
+        let lanes = Lanes::new(1, 10);
+        let (tx, rx) = oneshot::channel();
+
+        lanes.push(&"class", Box::pin(async { panic!("Job failed") }));
+
+        lanes.push(
+            &"class",
+            Box::pin(async move {
+                tx.send(()).unwrap();
+            }),
+        );
+
+        tokio::time::timeout(Duration::from_secs(5), rx)
+            .await
+            .expect("Lane stopped after a panic")
+            .unwrap();
+    }
+}
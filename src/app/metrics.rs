@@ -1,7 +1,8 @@
 use chrono::Duration;
 use once_cell::sync::Lazy;
 use prometheus::{
-    register_histogram, register_int_counter_vec, Histogram, HistogramTimer, IntCounter,
+    register_histogram, register_int_counter_vec, register_int_gauge_vec, Histogram,
+    HistogramTimer, IntCounter, IntCounterVec, IntGaugeVec,
 };
 use prometheus_static_metric::make_static_metric;
 
@@ -47,6 +48,18 @@ impl MqttMetrics {
         METRICS.connection_error.inc()
     }
 
+    pub fn observe_duplicate_event(label: &str) {
+        METRICS.duplicate_events.with_label_values(&[label]).inc()
+    }
+
+    pub fn observe_lane_push(lane: &str) {
+        METRICS.lane_queue_depth.with_label_values(&[lane]).inc()
+    }
+
+    pub fn observe_lane_pop(lane: &str) {
+        METRICS.lane_queue_depth.with_label_values(&[lane]).dec()
+    }
+
     pub fn observe_event_result(result: &Result<(), Error>, label: Option<&str>) {
         match label {
             Some("room.close") => {
@@ -96,6 +109,14 @@ impl MqttMetrics {
     }
 }
 
+pub struct RedirectMetrics;
+
+impl RedirectMetrics {
+    pub fn observe(source: &str) {
+        METRICS.redirects.with_label_values(&[source]).inc()
+    }
+}
+
 pub trait AuthorizeMetrics {
     fn measure(self) -> Self;
 }
@@ -117,6 +138,9 @@ struct Metrics {
     connection_error: IntCounter,
     disconnect: IntCounter,
     reconnection: IntCounter,
+    duplicate_events: IntCounterVec,
+    lane_queue_depth: IntGaugeVec,
+    redirects: IntCounterVec,
     authz_time: Histogram,
 }
 
@@ -133,6 +157,24 @@ impl Metrics {
             connection_error: mqtt_errors.with_label_values(&["connection_error"]),
             disconnect: mqtt_errors.with_label_values(&["disconnect"]),
             reconnection: mqtt_errors.with_label_values(&["reconnect"]),
+            duplicate_events: register_int_counter_vec!(
+                "mqtt_duplicate_events",
+                "Redelivered mqtt events skipped",
+                &["method"]
+            )
+            .expect("Bad duplicate events metric"),
+            lane_queue_depth: register_int_gauge_vec!(
+                "mqtt_lane_queue_depth",
+                "Mqtt events queued or being handled per lane",
+                &["lane"]
+            )
+            .expect("Bad lane queue depth metric"),
+            redirects: register_int_counter_vec!(
+                "frontend_redirects",
+                "Redirects to frontends by where the frontend came from",
+                &["source"]
+            )
+            .expect("Bad frontend redirects metric"),
             authz_time: register_histogram!("auth_time", "Authorization time")
                 .expect("Bad authz hist"),
         }
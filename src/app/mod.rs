@@ -1,47 +1,76 @@
 use std::sync::Arc;
-use std::time::Duration;
 
 use anyhow::{Context, Result};
+use futures::future::Either;
 use futures::StreamExt;
 use signal_hook::consts::TERM_SIGNALS;
 use sqlx::postgres::PgPool;
 use svc_agent::{
-    mqtt::{Agent, AgentBuilder, AgentNotification, ConnectionMode, IncomingMessage, QoS},
+    mqtt::{
+        Agent, AgentBuilder, AgentNotification, ConnectionMode, IncomingEvent, IncomingMessage, QoS,
+    },
     request::Dispatcher,
     AgentId, Authenticable, SharedGroup, Subscription,
 };
 use svc_authn::token::jws_compact;
-use svc_authz::cache::AuthzCache;
+use svc_authn::AccountId;
+use svc_authz::cache::{AuthzCache, ConnectionPool};
 use svc_authz::ClientMap as Authz;
 use svc_error::extension::sentry as svc_sentry;
+use tokio::sync::mpsc;
 use tracing::{error, info};
 
 use crate::clients::event::{EventClient, TowerClient};
 use crate::clients::tq::{HttpTqClient, TqClient};
+use crate::clients::webhook::HttpWebhookClient;
 use crate::config::{self, Config};
-use crate::{
-    app::metrics::MqttMetrics,
-    clients::conference::{ConferenceClient, MqttConferenceClient},
-};
+use crate::{app::metrics::MqttMetrics, clients::conference::ConferenceClient};
 pub use authz::AuthzObject;
+use lanes::Lanes;
+use readiness::Readiness;
+use task_tracker::{TaskGuard, TaskTracker};
 use tide_state::message_handler::MessageHandler;
 pub use tide_state::{AppContext, Publisher, TideState};
 
 pub const API_VERSION: &str = "v1";
 
-pub async fn run(db: PgPool, authz_cache: Option<Box<dyn AuthzCache>>) -> Result<()> {
+pub type QueuedEvent = (IncomingEvent<String>, String, TaskGuard);
+
+/// Sends incoming events with their topics to the lanes. An event is tracked from the moment
+/// it's sent until it's handled so that shutdown could wait for it.
+#[derive(Clone)]
+pub struct EventSender {
+    tx: mpsc::Sender<QueuedEvent>,
+    tracker: TaskTracker,
+}
+
+impl EventSender {
+    pub fn channel(tracker: TaskTracker, capacity: usize) -> (Self, mpsc::Receiver<QueuedEvent>) {
+        let (tx, rx) = mpsc::channel(capacity.max(1));
+        (Self { tx, tracker }, rx)
+    }
+
+    /// Waits for room when the router is behind.
+    pub async fn send(&self, event: IncomingEvent<String>, topic: String) -> Result<()> {
+        self.tx
+            .send((event, topic, self.tracker.guard()))
+            .await
+            .map_err(|_| anyhow!("Event router is gone"))
+    }
+}
+
+pub async fn run(
+    db: PgPool,
+    authz_cache: Option<Box<dyn AuthzCache>>,
+    authz_cache_pool: Option<ConnectionPool>,
+) -> Result<()> {
     let config = config::load().context("Failed to load config")?;
     info!("App config: {:?}", config);
 
     let agent_id = AgentId::new(&config.agent_label, config.id.clone());
     info!("Agent id: {:?}", &agent_id);
 
-    let token = jws_compact::TokenBuilder::new()
-        .issuer(agent_id.as_account_id().audience())
-        .subject(&agent_id)
-        .key(config.id_token.algorithm, config.id_token.key.as_slice())
-        .build()
-        .context("Error creating an id token")?;
+    let token = id_token(&config, &agent_id)?;
 
     let mut agent_config = config.mqtt.clone();
     agent_config.set_password(&token);
@@ -52,9 +81,12 @@ pub async fn run(db: PgPool, authz_cache: Option<Box<dyn AuthzCache>>) -> Result
         .context("Failed to create an agent")?;
 
     let dispatcher = Arc::new(Dispatcher::new(&agent));
-    let event_client = build_event_client(&config, dispatcher.clone());
-    let conference_client = build_conference_client(&config, dispatcher.clone());
-    let tq_client = build_tq_client(&config, &token);
+    let tracker = TaskTracker::default();
+    let (events_tx, events_rx) =
+        EventSender::channel(tracker.clone(), config.event_lanes.intake_capacity);
+    let (event_client, conference_client, tq_client) =
+        build_clients(&config, dispatcher.clone(), &token, &events_tx);
+    let webhook_client = Arc::new(HttpWebhookClient::new(config.webhooks.timeout));
     let authz = Authz::new(&config.id, authz_cache, config.authz.clone(), None)
         .context("Error converting authz config to clients")?;
 
@@ -64,33 +96,58 @@ pub async fn run(db: PgPool, authz_cache: Option<Box<dyn AuthzCache>>) -> Result
         event_client,
         conference_client,
         tq_client,
+        webhook_client,
         agent.clone(),
         authz,
     );
     let state = Arc::new(state) as Arc<dyn AppContext>;
     let state_ = state.clone();
 
+    class_sweeper::spawn(state.clone(), config.class_sweeper.clone());
+    tq_retry::spawn(state.clone(), config.tq_client.retry.clone());
+    outbox::spawn(state.clone(), config.outbox.clone());
+    dedup::spawn(state.clone(), config.dedup.clone());
+    webhooks::spawn(state.clone(), config.webhooks.clone());
+
+    let tracker_ = tracker.clone();
+    let readiness = Readiness::new(authz_cache_pool, &config.readiness);
+    let readiness_ = readiness.clone();
+    let events_tx_ = events_tx.clone();
+
     let message_handler = Arc::new(MessageHandler::new(state_, dispatcher));
+    spawn_event_router(message_handler.clone(), &config, events_rx);
     tokio::task::spawn(async move {
         while let Some(message) = rx.recv().await {
             let message_handler_ = message_handler.clone();
+            let events = events_tx_.clone();
+            let tracker = tracker_.clone();
+            let readiness = readiness_.clone();
             tokio::task::spawn(async move {
                 match message {
                     AgentNotification::Message(Ok(IncomingMessage::Response(data)), _) => {
                         message_handler_.handle_response(data).await;
                     }
                     AgentNotification::Message(Ok(IncomingMessage::Event(data)), message_data) => {
-                        let topic = message_data.topic;
-                        message_handler_.handle_event(data, topic).await;
+                        if let Err(e) = events.send(data, message_data.topic).await {
+                            error!("Failed to queue event, err = {:?}", e);
+                        }
                     }
                     AgentNotification::Message(_, _) => (),
                     AgentNotification::ConnectionError => {
+                        readiness.set_mqtt_connected(false);
                         MqttMetrics::observe_connection_error();
                         error!("Connection to broker errored")
                     }
                     AgentNotification::Reconnection => {
                         error!("Reconnected to broker");
+                        readiness.set_mqtt_connected(true);
                         MqttMetrics::observe_reconnect();
+
+                        // Events are left to other replicas once the shutdown has begun.
+                        if tracker.is_closed() {
+                            return;
+                        }
+
                         resubscribe(
                             &mut message_handler_
                                 .ctx()
@@ -107,13 +164,14 @@ pub async fn run(db: PgPool, authz_cache: Option<Box<dyn AuthzCache>>) -> Result
                     AgentNotification::Suback(_) => (),
                     AgentNotification::Unsuback(_) => (),
                     AgentNotification::Connect(_) => (),
-                    AgentNotification::Connack(_) => (),
+                    AgentNotification::Connack(_) => readiness.set_mqtt_connected(true),
                     AgentNotification::Pubrel(_) => (),
                     AgentNotification::Subscribe(_) => (),
                     AgentNotification::Unsubscribe(_) => (),
                     AgentNotification::PingReq => (),
                     AgentNotification::PingResp => (),
                     AgentNotification::Disconnect => {
+                        readiness.set_mqtt_connected(false);
                         MqttMetrics::observe_disconnect();
                     }
                 }
@@ -125,25 +183,157 @@ pub async fn run(db: PgPool, authz_cache: Option<Box<dyn AuthzCache>>) -> Result
     let metrics_server =
         svc_utils::metrics::MetricsServer::new(config.http.metrics_listener_address);
 
-    let router = http::router(state, config.authn.clone());
+    let router = http::router(state, events_tx, readiness, config.authn.clone());
 
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
     let app_future = axum::Server::bind(&config.http.listener_address.parse().unwrap())
-        .serve(router.into_make_service());
-    pin_utils::pin_mut!(app_future);
+        .serve(router.into_make_service())
+        .with_graceful_shutdown(async {
+            shutdown_rx.await.ok();
+        });
+    let mut app_handle = tokio::task::spawn(app_future);
     let mut signals_stream = signal_hook_tokio::Signals::new(TERM_SIGNALS)?.fuse();
     let signals = signals_stream.next();
 
-    futures::future::select(app_future, signals).await;
+    if let Either::Left((r, _)) = futures::future::select(&mut app_handle, signals).await {
+        error!("Http server stopped unexpectedly, result = {:?}", r);
+    }
 
-    metrics_server.shutdown().await;
+    info!("Shutting down");
+
+    if let Err(e) = unsubscribe(&mut agent, &agent_id, &config) {
+        error!("Failed to unsubscribe from events, err = {:?}", e);
+    }
 
-    // sleep for 2 secs to finish requests
-    // this is very primitive way of waiting for them but
-    // neither tide nor svc-agent (rumqtt) support graceful shutdowns
-    tokio::time::sleep(Duration::from_secs(2)).await;
+    tracker.close();
+    shutdown_tx.send(()).ok();
+
+    // Http requests finish by themselves, events in flight are waited for.
+    let drain = async {
+        app_handle.await.ok();
+        tracker.wait().await;
+    };
+
+    if tokio::time::timeout(config.shutdown.timeout, drain)
+        .await
+        .is_err()
+    {
+        error!(
+            in_flight = tracker.in_flight(),
+            "Shutdown timed out with events still being handled"
+        );
+    }
+
+    metrics_server.shutdown().await;
     Ok(())
 }
 
+/// Builds the app context for one-off operations next to the running service. Its agent shares
+/// the service account under its own label not to take over the service connection and only
+/// receives responses to its requests, events and background jobs are left to the service.
+pub(crate) fn standalone_context(db: PgPool, agent_label: &str) -> Result<Arc<dyn AppContext>> {
+    let config = config::load().context("Failed to load config")?;
+    let agent_id = AgentId::new(agent_label, config.id.clone());
+    let token = id_token(&config, &agent_id)?;
+
+    let mut agent_config = config.mqtt.clone();
+    agent_config.set_password(&token);
+
+    let (mut agent, mut rx) = AgentBuilder::new(agent_id, API_VERSION)
+        .connection_mode(ConnectionMode::Service)
+        .start(&agent_config)
+        .context("Failed to create an agent")?;
+
+    agent
+        .subscribe(&Subscription::unicast_requests(), QoS::AtMostOnce, None)
+        .context("Error subscribing to unicast requests")?;
+
+    let dispatcher = Arc::new(Dispatcher::new(&agent));
+    let event_client = build_event_client(&config, dispatcher.clone());
+    let conference_client = build_conference_client(&config, dispatcher.clone());
+    let tq_client = build_tq_client(&config, &token);
+    let webhook_client = Arc::new(HttpWebhookClient::new(config.webhooks.timeout));
+    let authz = Authz::new(&config.id, None, config.authz.clone(), None)
+        .context("Error converting authz config to clients")?;
+
+    let state = TideState::new(
+        db,
+        config,
+        event_client,
+        conference_client,
+        tq_client,
+        webhook_client,
+        agent,
+        authz,
+    );
+    let state = Arc::new(state) as Arc<dyn AppContext>;
+
+    let message_handler = MessageHandler::new(state.clone(), dispatcher);
+    tokio::task::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            if let AgentNotification::Message(Ok(IncomingMessage::Response(data)), _) = message {
+                message_handler.handle_response(data).await;
+            }
+        }
+    });
+
+    Ok(state)
+}
+
+fn id_token(config: &Config, agent_id: &AgentId) -> Result<String> {
+    jws_compact::TokenBuilder::new()
+        .issuer(agent_id.as_account_id().audience())
+        .subject(agent_id)
+        .key(config.id_token.algorithm, config.id_token.key.as_slice())
+        .build()
+        .context("Error creating an id token")
+}
+
+// Events of a class get handled one by one in its lane while different classes go in parallel.
+// Each event is sent from its own task so a full router channel holds that event only and never
+// the notifications loop which brings the responses the handlers await. Classes are looked up
+// concurrently while keeping the order the events came in, and a busy lane never holds the router.
+fn spawn_event_router(
+    message_handler: Arc<MessageHandler>,
+    config: &Config,
+    rx: mpsc::Receiver<QueuedEvent>,
+) {
+    let lanes = Lanes::new(config.event_lanes.count, config.event_lanes.capacity);
+    let lookups = config.event_lanes.count.max(1);
+
+    let events = futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|event| (event, rx))
+    });
+
+    let mut events = events
+        .map(move |(data, topic, guard)| {
+            let message_handler = message_handler.clone();
+
+            async move {
+                let key = message_handler.lane_key(&data).await;
+                let job = Box::pin(async move {
+                    message_handler.handle_event(data, topic).await;
+                    drop(guard);
+                });
+
+                (key, job)
+            }
+        })
+        .buffered(lookups)
+        .boxed();
+
+    tokio::task::spawn(async move {
+        while let Some((key, job)) = events.next().await {
+            match key {
+                Some(key) => lanes.push(&key, job),
+                None => {
+                    tokio::task::spawn(job);
+                }
+            }
+        }
+    });
+}
+
 fn subscribe(agent: &mut Agent, agent_id: &AgentId, config: &Config) -> Result<()> {
     agent
         .subscribe(&Subscription::unicast_requests(), QoS::AtMostOnce, None)
@@ -152,46 +342,66 @@ fn subscribe(agent: &mut Agent, agent_id: &AgentId, config: &Config) -> Result<(
     let group = SharedGroup::new("loadbalancer", agent_id.as_account_id().clone());
     // Audience level events for each tenant
     for tenant_audience in &config.tenants {
-        agent
-            .subscribe(
-                &Subscription::broadcast_events(
-                    &config.conference_client.account_id,
-                    &config.conference_client.api_version,
-                    &format!("audiences/{}/events", tenant_audience),
-                ),
-                QoS::AtLeastOnce,
-                Some(&group),
-            )
-            .context("Error subscribing to app's conference topic")?;
-
-        agent
-            .subscribe(
-                &Subscription::broadcast_events(
-                    &config.event_client.account_id,
-                    &config.event_client.api_version,
-                    &format!("audiences/{}/events", tenant_audience),
-                ),
-                QoS::AtLeastOnce,
-                Some(&group),
-            )
-            .context("Error subscribing to app's events topic")?;
-
-        agent
-            .subscribe(
-                &Subscription::broadcast_events(
-                    &config.tq_client.account_id,
-                    &config.tq_client.api_version,
-                    &format!("audiences/{}/events", tenant_audience),
-                ),
-                QoS::AtLeastOnce,
-                Some(&group),
-            )
-            .context("Error subscribing to app's tq topic")?;
+        for (account_id, api_version, app) in event_sources(config).iter() {
+            agent
+                .subscribe(
+                    &Subscription::broadcast_events(
+                        *account_id,
+                        api_version,
+                        &format!("audiences/{}/events", tenant_audience),
+                    ),
+                    QoS::AtLeastOnce,
+                    Some(&group),
+                )
+                .with_context(|| format!("Error subscribing to app's {} topic", app))?;
+        }
     }
 
     Ok(())
 }
 
+// Lets the broker send the shared group events to other replicas.
+fn unsubscribe(agent: &mut Agent, agent_id: &AgentId, config: &Config) -> Result<()> {
+    let group = SharedGroup::new("loadbalancer", agent_id.as_account_id().clone());
+
+    for tenant_audience in &config.tenants {
+        for (account_id, api_version, app) in event_sources(config).iter() {
+            agent
+                .unsubscribe(
+                    &Subscription::broadcast_events(
+                        *account_id,
+                        api_version,
+                        &format!("audiences/{}/events", tenant_audience),
+                    ),
+                    Some(&group),
+                )
+                .with_context(|| format!("Error unsubscribing from app's {} topic", app))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn event_sources(config: &Config) -> [(&AccountId, &str, &str); 3] {
+    [
+        (
+            &config.conference_client.account_id,
+            &config.conference_client.api_version,
+            "conference",
+        ),
+        (
+            &config.event_client.account_id,
+            &config.event_client.api_version,
+            "events",
+        ),
+        (
+            &config.tq_client.account_id,
+            &config.tq_client.api_version,
+            "tq",
+        ),
+    ]
+}
+
 fn resubscribe(agent: &mut Agent, agent_id: &AgentId, config: &Config) {
     if let Err(err) = subscribe(agent, agent_id, config) {
         let err = err.context("Failed to resubscribe after reconnection");
@@ -202,16 +412,34 @@ fn resubscribe(agent: &mut Agent, agent_id: &AgentId, config: &Config) {
     }
 }
 
+#[cfg_attr(not(feature = "dev-stubs"), allow(unused_variables))]
+fn build_clients(
+    config: &Config,
+    dispatcher: Arc<Dispatcher>,
+    token: &str,
+    events: &EventSender,
+) -> (
+    Arc<dyn EventClient>,
+    Arc<dyn ConferenceClient>,
+    Arc<dyn TqClient>,
+) {
+    // Events of the stubbed services go right to the router.
+    #[cfg(feature = "dev-stubs")]
+    if let Some(stubs_config) = &config.dev_stubs {
+        return crate::clients::stub::start(config, stubs_config, events.clone());
+    }
+
+    (
+        build_event_client(config, dispatcher.clone()),
+        build_conference_client(config, dispatcher),
+        build_tq_client(config, token),
+    )
+}
+
 fn build_event_client(config: &Config, dispatcher: Arc<Dispatcher>) -> Arc<dyn EventClient> {
     let agent_id = AgentId::new(&config.agent_label, config.id.clone());
 
-    Arc::new(TowerClient::new(
-        agent_id,
-        config.event_client.account_id.clone(),
-        dispatcher,
-        Some(Duration::from_secs(config.event_client.timeout)),
-        &config.event_client.api_version,
-    ))
+    Arc::new(TowerClient::new(agent_id, dispatcher, &config.event_client))
 }
 
 fn build_conference_client(
@@ -220,12 +448,10 @@ fn build_conference_client(
 ) -> Arc<dyn ConferenceClient> {
     let agent_id = AgentId::new(&config.agent_label, config.id.clone());
 
-    Arc::new(MqttConferenceClient::new(
+    Arc::new(crate::clients::conference::TowerClient::new(
         agent_id,
-        config.conference_client.account_id.clone(),
         dispatcher,
-        Some(Duration::from_secs(config.conference_client.timeout)),
-        &config.conference_client.api_version,
+        &config.conference_client,
     ))
 }
 
@@ -238,13 +464,24 @@ fn build_tq_client(config: &Config, token: &str) -> Arc<dyn TqClient> {
     ))
 }
 
+pub mod admin;
 mod api;
 mod authz;
+mod class_sweeper;
+mod dead_letters;
+mod dedup;
 mod error;
 mod http;
 mod info;
+mod lanes;
 mod metrics;
+mod outbox;
 mod postprocessing_strategy;
+mod readiness;
+mod rollout;
 pub mod services;
+mod task_tracker;
 mod tide_state;
+mod tq_retry;
 pub mod turn_host;
+mod webhooks;
@@ -0,0 +1,307 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::{Acquire, PgConnection};
+use svc_agent::mqtt::{
+    IntoPublishableMessage, OutgoingEvent, OutgoingEventProperties, ShortTermTimingProperties,
+};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::app::AppContext;
+use crate::config::OutboxConfig;
+use crate::db::outbox::{DeleteSentQuery, InsertQuery, MarkSentQuery, Object, TakeUnsentQuery};
+
+const BATCH_SIZE: i64 = 100;
+
+// Outgoing event labels have to be static so the stored ones get resolved against this list.
+const LABELS: &[&str] = &[
+    "minigroup.deleted",
+    "minigroup.ready",
+    "minigroup.stop",
+    "p2p.deleted",
+    "p2p.ready",
+    "p2p.stop",
+    "scope.frontend.rollback",
+    "scope.frontend.update",
+    "transcoding.failed",
+    "webinar.deleted",
+    "webinar.ready",
+    "webinar.stop",
+];
+
+pub type Event = Object;
+
+/// Writes the event to the outbox. Call it within the transaction of the state change
+/// the event is about and [`publish`] the event once the transaction is committed.
+pub async fn push<T: Serialize>(
+    conn: &mut PgConnection,
+    label: &'static str,
+    path: &str,
+    payload: &T,
+) -> Result<Event> {
+    if !LABELS.contains(&label) {
+        bail!("Unknown outbox event label = {}", label);
+    }
+
+    let payload = serde_json::to_value(payload)
+        .with_context(|| format!("Failed to serialize {} event", label))?;
+
+    InsertQuery::new(label.to_owned(), path.to_owned(), payload)
+        .execute(conn)
+        .await
+        .with_context(|| format!("Failed to write {} event to outbox", label))
+}
+
+/// Publishes committed events right away. Whatever fails is left to the relay.
+pub async fn publish(ctx: &dyn AppContext, events: Vec<Event>) {
+    let mut sent = Vec::with_capacity(events.len());
+
+    for event in &events {
+        match publish_event(ctx, event) {
+            Ok(()) => sent.push(event.id()),
+            Err(e) => error!(
+                outbox_id = event.id(),
+                "Failed to publish {} event, err = {:?}",
+                event.label(),
+                e
+            ),
+        }
+    }
+
+    if sent.is_empty() {
+        return;
+    }
+
+    let r = match ctx.get_conn().await {
+        Ok(mut conn) => MarkSentQuery::new(sent)
+            .execute(&mut conn)
+            .await
+            .map_err(Into::into),
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = r {
+        error!("Failed to mark outbox events sent, err = {:?}", e);
+    }
+}
+
+/// Periodically republishes events that didn't get published after their transaction
+/// and cleans up the old sent ones.
+pub fn spawn(ctx: Arc<dyn AppContext>, config: OutboxConfig) -> JoinHandle<()> {
+    tokio::task::spawn(async move {
+        let mut interval = tokio::time::interval(config.poll_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            match relay(ctx.as_ref(), &config).await {
+                Ok(0) => {}
+                Ok(relayed) => info!(relayed, "Relayed outbox events"),
+                Err(e) => error!("Failed to relay outbox events, err = {:?}", e),
+            }
+
+            if let Err(e) = cleanup(ctx.as_ref(), &config).await {
+                error!("Failed to clean up outbox, err = {:?}", e);
+            }
+        }
+    })
+}
+
+async fn relay(ctx: &dyn AppContext, config: &OutboxConfig) -> Result<usize> {
+    // Fresh events are most likely being published by whoever wrote them.
+    let relay_after =
+        chrono::Duration::from_std(config.relay_after).context("Invalid relay_after")?;
+
+    let mut conn = ctx.get_conn().await?;
+    let mut txn = conn
+        .begin()
+        .await
+        .context("Failed to begin sqlx db transaction")?;
+
+    let events = TakeUnsentQuery::new(Utc::now() - relay_after, BATCH_SIZE)
+        .execute(&mut txn)
+        .await
+        .context("Failed to take unsent outbox events")?;
+
+    let mut sent = Vec::with_capacity(events.len());
+
+    for event in &events {
+        match publish_event(ctx, event) {
+            Ok(()) => sent.push(event.id()),
+            Err(e) => error!(
+                outbox_id = event.id(),
+                "Failed to relay {} event, err = {:?}",
+                event.label(),
+                e
+            ),
+        }
+    }
+
+    let relayed = sent.len();
+
+    MarkSentQuery::new(sent)
+        .execute(&mut txn)
+        .await
+        .context("Failed to mark outbox events sent")?;
+
+    txn.commit()
+        .await
+        .context("Failed to commit sqlx db transaction")?;
+
+    Ok(relayed)
+}
+
+async fn cleanup(ctx: &dyn AppContext, config: &OutboxConfig) -> Result<()> {
+    let retention = chrono::Duration::from_std(config.retention).context("Invalid retention")?;
+
+    let mut conn = ctx.get_conn().await?;
+    DeleteSentQuery::new(Utc::now() - retention)
+        .execute(&mut conn)
+        .await
+        .context("Failed to delete sent outbox events")?;
+
+    Ok(())
+}
+
+fn publish_event(ctx: &dyn AppContext, event: &Event) -> Result<()> {
+    let label = LABELS
+        .iter()
+        .find(|label| **label == event.label())
+        .ok_or_else(|| anyhow!("Unknown outbox event label"))?;
+
+    let timing = ShortTermTimingProperties::new(Utc::now());
+    let props = OutgoingEventProperties::new(label, timing);
+
+    let event = OutgoingEvent::broadcast(event.payload().to_owned(), props, event.path());
+    let boxed_event = Box::new(event) as Box<dyn IntoPublishableMessage + Send>;
+
+    ctx.publisher()
+        .publish(boxed_event)
+        .context("Failed to publish event")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value as JsonValue;
+
+    use super::*;
+    use crate::test_helpers::prelude::*;
+
+    async fn is_unsent(state: &TestState, id: i64) -> bool {
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+        let mut txn = conn.begin().await.expect("Failed to begin transaction");
+
+        TakeUnsentQuery::new(Utc::now() + chrono::Duration::seconds(1), i64::MAX)
+            .execute(&mut txn)
+            .await
+            .expect("Failed to take unsent events")
+            .iter()
+            .any(|event| event.id() == id)
+    }
+
+    fn published_with_id(state: &TestState, id: &str) -> Vec<String> {
+        state
+            .test_publisher()
+            .flush()
+            .into_iter()
+            .filter(|message| message.payload::<JsonValue>()["id"] == id)
+            .map(|message| message.topic().to_owned())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn committed_event_is_published_right_away() {
+        let db_pool = TestDb::new().await;
+        let state = TestState::new_with_pool(db_pool, TestAuthz::new());
+        let id = random_string();
+
+        let event = {
+            let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+            let path = format!("audiences/{}/events", USR_AUDIENCE);
+            push(
+                &mut conn,
+                "webinar.stop",
+                &path,
+                &serde_json::json!({ "id": id }),
+            )
+            .await
+            .expect("Failed to push event")
+        };
+
+        let event_id = event.id();
+        assert!(is_unsent(&state, event_id).await);
+
+        publish(&state, vec![event]).await;
+
+        let topics = published_with_id(&state, &id);
+        assert_eq!(topics.len(), 1);
+        assert_eq!(
+            topics[0],
+            format!(
+                "apps/{}/api/{}/audiences/{}/events",
+                state.config().id,
+                crate::app::API_VERSION,
+                USR_AUDIENCE
+            )
+        );
+
+        assert!(!is_unsent(&state, event_id).await);
+    }
+
+    #[tokio::test]
+    async fn unknown_label_is_not_pushed() {
+        let db_pool = TestDb::new().await;
+        let state = TestState::new_with_pool(db_pool, TestAuthz::new());
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+        let path = format!("audiences/{}/events", USR_AUDIENCE);
+
+        push(&mut conn, "webinar.unknown", &path, &serde_json::json!({}))
+            .await
+            .expect_err("Unexpectedly pushed event");
+
+        let pushed = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM outbox WHERE label = 'webinar.unknown'",
+        )
+        .fetch_one(&mut conn)
+        .await
+        .expect("Failed to count outbox events");
+        assert_eq!(pushed, 0);
+    }
+
+    #[tokio::test]
+    async fn relay_publishes_events_left_unpublished() {
+        let db_pool = TestDb::new().await;
+        let state = TestState::new_with_pool(db_pool, TestAuthz::new());
+        let id = random_string();
+
+        // The event is committed but whoever wrote it failed to publish it.
+        let event_id = {
+            let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+            let path = format!("audiences/{}/events", USR_AUDIENCE);
+            push(
+                &mut conn,
+                "transcoding.failed",
+                &path,
+                &serde_json::json!({ "id": id }),
+            )
+            .await
+            .expect("Failed to push event")
+            .id()
+        };
+
+        let config = OutboxConfig {
+            relay_after: std::time::Duration::ZERO,
+            ..Default::default()
+        };
+
+        relay(&state, &config).await.expect("Failed to relay");
+
+        // Other tests share the database so their events may get relayed too.
+        assert_eq!(published_with_id(&state, &id).len(), 1);
+        assert!(!is_unsent(&state, event_id).await);
+    }
+}
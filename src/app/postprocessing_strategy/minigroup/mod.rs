@@ -7,20 +7,17 @@ use chrono::{DateTime, Duration, Utc};
 use serde_derive::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use sqlx::{postgres::PgConnection, Acquire};
-use svc_agent::{
-    mqtt::{
-        IntoPublishableMessage, OutgoingEvent, OutgoingEventProperties, ShortTermTimingProperties,
-    },
-    AgentId,
-};
-use tracing::{error, warn};
+use svc_agent::AgentId;
 use uuid::Uuid;
 
+use crate::app::outbox;
 use crate::clients::tq::{
     Priority, Task as TqTask, TranscodeMinigroupToHlsStream, TranscodeMinigroupToHlsSuccess,
 };
 use crate::db::class::Object as Class;
-use crate::db::recording::Segments;
+use crate::db::recording::{
+    BoundedOffsetTuples, HostSelection, ModifiedSegmentsUpdateQuery, Object as Recording, Segments,
+};
 use crate::{app::AppContext, clients::conference::ConfigSnapshot};
 use crate::{
     clients::event::{Event, EventData, RoomAdjustResult},
@@ -28,12 +25,15 @@ use crate::{
 };
 
 use super::{
-    shared_helpers, MjrDumpsUploadReadyData, MjrDumpsUploadResult, TranscodeSuccess, UploadedStream,
+    shared_helpers, MjrDumpsUploadReadyData, MjrDumpsUploadResult, Progress, TranscodeSuccess,
+    UploadedStream,
 };
 
+#[cfg(test)]
+use super::shared_helpers::HOST_EVENT_TYPE;
+
 const NS_IN_MS: i64 = 1_000_000;
 const PIN_EVENT_TYPE: &str = "pin";
-const HOST_EVENT_TYPE: &str = "host";
 
 pub(super) struct MinigroupPostprocessingStrategy {
     ctx: Arc<dyn AppContext>,
@@ -48,30 +48,14 @@ impl MinigroupPostprocessingStrategy {
 
 #[async_trait]
 impl super::PostprocessingStrategy for MinigroupPostprocessingStrategy {
-    async fn handle_stream_upload(&self, stream: UploadedStream) -> Result<()> {
-        match stream.parsed_data {
-            Ok(stream_data) => {
-                let mut conn = self.ctx.get_conn().await?;
-                crate::db::recording::StreamUploadUpdateQuery::new(
-                    self.minigroup.id(),
-                    stream.id,
-                    stream_data.segments,
-                    stream_data.uri,
-                    stream_data.started_at,
-                )
-                .execute(&mut conn)
-                .await?;
-            }
-            Err(err) => {
-                warn!(
-                    stream_id = ?stream.id,
-                    "Failed to transcode recording with stream_id: {}, err: {:?}", stream.id, err
-                );
-                let mut conn = self.ctx.get_conn().await?;
-                crate::db::recording::remove_recording(self.minigroup.id(), stream.id, &mut conn)
-                    .await?;
-            }
-        }
+    async fn handle_stream_upload(
+        &self,
+        stream: UploadedStream,
+        progress: &Progress,
+    ) -> Result<()> {
+        shared_helpers::save_stream_upload(self.ctx.as_ref(), &self.minigroup, stream, progress)
+            .await?;
+
         let recordings = {
             let mut conn = self.ctx.get_conn().await?;
             crate::db::recording::RecordingListQuery::new(self.minigroup.id())
@@ -87,36 +71,17 @@ impl super::PostprocessingStrategy for MinigroupPostprocessingStrategy {
             return Ok(());
         }
 
-        let host_recording = {
-            // Find host stream id.
-            let host = match self.find_host(self.minigroup.event_room_id()).await? {
-                None => {
-                    error!(
-                        class_id = ?self.minigroup.id(),
-                        event_room_id = ?self.minigroup.event_room_id(),
-                        "No host in room",
-                    );
-                    return Ok(());
-                }
-                Some(agent_id) => agent_id,
-            };
-
-            let maybe_recording = ready_recordings
-                .into_iter()
-                .find(|recording| recording.created_by == host);
-
-            match maybe_recording {
-                None => {
-                    error!(
-                        class_id = ?self.minigroup.id(),
-                        "No host recording in room",
-                    );
-                    return Ok(());
-                }
-                Some(recording) => recording,
-            }
+        let host = match self
+            .select_host(self.minigroup.event_room_id(), &recordings)
+            .await?
+        {
+            None => bail!("No recordings left, minigroup id = {}", self.minigroup.id()),
+            Some((host, _selection)) => host,
         };
 
+        let host_recording = host_timeline(ready_recordings, &host)
+            .ok_or_else(|| anyhow!("No host recording, minigroup id = {}", self.minigroup.id()))?;
+
         call_adjust(
             self.ctx.clone(),
             self.minigroup.event_room_id(),
@@ -127,7 +92,11 @@ impl super::PostprocessingStrategy for MinigroupPostprocessingStrategy {
         Ok(())
     }
 
-    async fn handle_adjust(&self, room_adjust_result: RoomAdjustResult) -> Result<()> {
+    async fn handle_adjust(
+        &self,
+        room_adjust_result: RoomAdjustResult,
+        progress: &Progress,
+    ) -> Result<()> {
         match room_adjust_result {
             RoomAdjustResult::Success {
                 original_room_id,
@@ -135,18 +104,19 @@ impl super::PostprocessingStrategy for MinigroupPostprocessingStrategy {
                 cut_original_segments,
                 ..
             } => {
-                // Find host stream id.
-                let host = match self.find_host(modified_room_id).await? {
-                    None => {
-                        error!(
-                            class_id = ?self.minigroup.id(),
-                            "No host in room",
-                        );
-                        return Ok(());
-                    }
-                    Some(agent_id) => agent_id,
+                let recordings = {
+                    let mut conn = self.ctx.get_conn().await?;
+                    crate::db::recording::RecordingListQuery::new(self.minigroup.id())
+                        .execute(&mut conn)
+                        .await?
                 };
 
+                let (host, host_selection) =
+                    match self.select_host(modified_room_id, &recordings).await? {
+                        None => bail!("No recordings, minigroup id = {}", self.minigroup.id()),
+                        Some(host) => host,
+                    };
+
                 // Save adjust results to the DB and fetch recordings.
                 let recordings = {
                     let mut conn = self.ctx.get_conn().await?;
@@ -166,21 +136,28 @@ impl super::PostprocessingStrategy for MinigroupPostprocessingStrategy {
 
                     let recordings = crate::db::recording::AdjustMinigroupUpdateQuery::new(
                         self.minigroup.id(),
-                        cut_original_segments,
+                        cut_original_segments.clone(),
                         host.clone(),
                     )
+                    .host_selection(host_selection)
                     .execute(&mut txn)
                     .await?;
 
+                    let recordings =
+                        split_host_segments(&mut txn, recordings, &host, &cut_original_segments)
+                            .await?;
+
+                    progress.save(&mut txn, &self.minigroup).await?;
                     txn.commit().await?;
 
                     recordings
                 };
 
-                send_transcoding_task(
+                send_composite_transcoding_task(
                     &self.ctx,
                     &self.minigroup,
                     recordings,
+                    &host,
                     modified_room_id,
                     Priority::Normal,
                 )
@@ -195,6 +172,7 @@ impl super::PostprocessingStrategy for MinigroupPostprocessingStrategy {
     async fn handle_transcoding_completion(
         &self,
         completion_result: TranscodeSuccess,
+        progress: &Progress,
     ) -> Result<()> {
         match completion_result {
             TranscodeSuccess::TranscodeMinigroupToHls(TranscodeMinigroupToHlsSuccess {
@@ -203,16 +181,6 @@ impl super::PostprocessingStrategy for MinigroupPostprocessingStrategy {
             }) => {
                 let stream_duration = recording_duration.parse::<f64>()?.round() as u64;
 
-                {
-                    let mut conn = self.ctx.get_conn().await?;
-
-                    crate::db::recording::TranscodingUpdateQuery::new(self.minigroup.id())
-                        .execute(&mut conn)
-                        .await?;
-                }
-
-                let timing = ShortTermTimingProperties::new(Utc::now());
-                let props = OutgoingEventProperties::new("minigroup.ready", timing);
                 let path = format!("audiences/{}/events", self.minigroup.audience());
 
                 let payload = MinigroupReady {
@@ -223,13 +191,22 @@ impl super::PostprocessingStrategy for MinigroupPostprocessingStrategy {
                     stream_duration,
                 };
 
-                let event = OutgoingEvent::broadcast(payload, props, &path);
-                let boxed_event = Box::new(event) as Box<dyn IntoPublishableMessage + Send>;
+                let event = {
+                    let mut conn = self.ctx.get_conn().await?;
+                    let mut txn = conn.begin().await?;
 
-                self.ctx
-                    .publisher()
-                    .publish(boxed_event)
-                    .context("Failed to publish minigroup.ready event")
+                    crate::db::recording::TranscodingUpdateQuery::new(self.minigroup.id())
+                        .execute(&mut txn)
+                        .await?;
+
+                    let event = outbox::push(&mut txn, "minigroup.ready", &path, &payload).await?;
+                    progress.save(&mut txn, &self.minigroup).await?;
+                    txn.commit().await?;
+                    event
+                };
+
+                outbox::publish(self.ctx.as_ref(), vec![event]).await;
+                Ok(())
             }
             TranscodeSuccess::TranscodeStreamToHls(success_result) => {
                 bail!(
@@ -241,55 +218,62 @@ impl super::PostprocessingStrategy for MinigroupPostprocessingStrategy {
         }
     }
 
-    async fn handle_mjr_dumps_upload(&self, dumps: Vec<MjrDumpsUploadResult>) -> Result<()> {
+    async fn handle_mjr_dumps_upload(
+        &self,
+        dumps: Vec<MjrDumpsUploadResult>,
+        progress: &Progress,
+    ) -> Result<()> {
         if dumps.is_empty() {
             bail!("Expected at least 1 RTC");
         }
 
         let ready_dumps = shared_helpers::extract_ready_dumps(dumps)?;
-        {
-            let mut conn = self.ctx.get_conn().await?;
-            insert_recordings(&mut conn, self.minigroup.id(), &ready_dumps).await?;
-        }
-        let tq_client = self.ctx.tq_client();
+        shared_helpers::save_dumps_upload(
+            self.ctx.as_ref(),
+            &self.minigroup,
+            &ready_dumps,
+            progress,
+        )
+        .await?;
+
         for dump in ready_dumps {
-            tq_client
-                .create_task(
-                    &self.minigroup,
-                    TqTask::ConvertMjrDumpsToStream {
-                        mjr_dumps_uris: dump.mjr_dumps_uris,
-                        stream_uri: dump.uri,
-                        stream_id: dump.id,
-                    },
-                    Priority::Normal,
-                )
-                .await?
+            shared_helpers::create_tq_task(
+                self.ctx.as_ref(),
+                &self.minigroup,
+                TqTask::ConvertMjrDumpsToStream {
+                    mjr_dumps_uris: dump.mjr_dumps_uris,
+                    stream_uri: dump.uri,
+                    stream_id: dump.id,
+                },
+                Priority::Normal,
+            )
+            .await?
         }
         Ok(())
     }
 }
 
 impl MinigroupPostprocessingStrategy {
-    async fn find_host(&self, event_room_id: Uuid) -> Result<Option<AgentId>> {
-        find_host(self.ctx.clone(), event_room_id).await
+    async fn select_host(
+        &self,
+        event_room_id: Uuid,
+        recordings: &[Recording],
+    ) -> Result<Option<(AgentId, HostSelection)>> {
+        select_host(&self.ctx, &self.minigroup, event_room_id, recordings).await
     }
 }
 
-async fn find_host(ctx: Arc<dyn AppContext>, event_room_id: Uuid) -> Result<Option<AgentId>> {
-    let host_events = ctx
-        .event_client()
-        .list_events(event_room_id, HOST_EVENT_TYPE)
-        .await
-        .context("Failed to get host events for room")?;
-
-    match host_events.first().map(|event| event.data()) {
-        None => Ok(None),
-        Some(EventData::Host(data)) => Ok(Some(data.agent_id().to_owned())),
-        Some(other) => bail!("Got unexpected host event data: {:?}", other),
-    }
+async fn select_host(
+    ctx: &Arc<dyn AppContext>,
+    minigroup: &Class,
+    event_room_id: Uuid,
+    recordings: &[Recording],
+) -> Result<Option<(AgentId, HostSelection)>> {
+    let fallback = &ctx.config().minigroup_postprocessing.host_fallback;
+    shared_helpers::select_host(ctx, minigroup, event_room_id, recordings, fallback).await
 }
 
-async fn insert_recordings(
+pub(super) async fn insert_recordings(
     conn: &mut PgConnection,
     class_id: Uuid,
     dumps: &[MjrDumpsUploadReadyData],
@@ -313,7 +297,87 @@ async fn insert_recordings(
     Ok(())
 }
 
-async fn call_adjust(
+/// Glues the host's sequential recordings into a single one starting along with the first
+/// of them, so the room gets adjusted along the whole host's presence.
+pub(super) fn host_timeline(
+    recordings: Vec<ReadyRecording>,
+    host: &AgentId,
+) -> Option<ReadyRecording> {
+    let mut sessions = recordings
+        .into_iter()
+        .filter(|recording| recording.created_by == *host)
+        .collect::<Vec<_>>();
+    sessions.sort_by_key(|recording| recording.started_at);
+
+    let mut sessions = sessions.into_iter();
+    let mut timeline = sessions.next()?;
+
+    for session in sessions {
+        let offset = (session.started_at - timeline.started_at).num_milliseconds();
+
+        let mut segments = BoundedOffsetTuples::from(timeline.segments);
+        segments.extend(BoundedOffsetTuples::from(session.segments.shift(offset)));
+        timeline.segments = segments.into();
+
+        let mut modified_segments = BoundedOffsetTuples::from(timeline.modified_segments);
+        modified_segments.extend(BoundedOffsetTuples::from(
+            session.modified_segments.shift(offset),
+        ));
+        timeline.modified_segments = modified_segments.into();
+    }
+
+    Some(timeline)
+}
+
+/// Hands each of the host's sequential recordings its own part of the segments
+/// the glued host timeline got cut to.
+pub(super) async fn split_host_segments(
+    conn: &mut PgConnection,
+    recordings: Vec<Recording>,
+    host: &AgentId,
+    cut_segments: &Segments,
+) -> Result<Vec<Recording>> {
+    let timeline_start = recordings
+        .iter()
+        .filter(|recording| recording.created_by() == host)
+        .filter_map(|recording| recording.started_at())
+        .min();
+
+    let sessions_count = recordings
+        .iter()
+        .filter(|recording| recording.created_by() == host)
+        .count();
+
+    let timeline_start = match timeline_start {
+        Some(timeline_start) if sessions_count > 1 => timeline_start,
+        _ => return Ok(recordings),
+    };
+
+    let mut split = Vec::with_capacity(recordings.len());
+
+    for recording in recordings {
+        match (recording.started_at(), recording.segments()) {
+            (Some(started_at), Some(segments)) if recording.created_by() == host => {
+                let offset = (started_at - timeline_start).num_milliseconds();
+                let end = segments_end(segments)?;
+
+                let recording = ModifiedSegmentsUpdateQuery::new(
+                    recording.id(),
+                    cut_segments.slice(offset, offset + end),
+                )
+                .execute(conn)
+                .await?;
+
+                split.push(recording);
+            }
+            _ => split.push(recording),
+        }
+    }
+
+    Ok(split)
+}
+
+pub(super) async fn call_adjust(
     ctx: Arc<dyn AppContext>,
     room_id: Uuid,
     host_recording: ReadyRecording,
@@ -368,24 +432,45 @@ async fn send_transcoding_task(
     modified_event_room_id: Uuid,
     priority: Priority,
 ) -> Result<()> {
-    // Find host stream id.
-    let host = match find_host(ctx.clone(), modified_event_room_id).await? {
-        None => {
-            error!(class_id = ?minigroup.id(), "No host in room");
-            return Ok(());
-        }
-        Some(agent_id) => agent_id,
+    let host = match select_host(ctx, minigroup, modified_event_room_id, &recordings).await? {
+        None => bail!("No recordings, minigroup id = {}", minigroup.id()),
+        Some((host, _selection)) => host,
     };
 
-    let recordings = recordings
+    send_composite_transcoding_task(
+        ctx,
+        minigroup,
+        recordings,
+        &host,
+        modified_event_room_id,
+        priority,
+    )
+    .await
+}
+
+/// Sends a task composing all the class recordings into a single HLS stream
+/// with the host's one being the main.
+pub(super) async fn send_composite_transcoding_task(
+    ctx: &Arc<dyn AppContext>,
+    class: &Class,
+    recordings: Vec<crate::db::recording::Object>,
+    host: &AgentId,
+    modified_event_room_id: Uuid,
+    priority: Priority,
+) -> Result<()> {
+    let mut recordings = recordings
         .into_iter()
         .map(|recording| ReadyRecording::from_db_object(&recording))
         .collect::<Option<Vec<_>>>()
         .ok_or_else(|| anyhow!("Not all recordings are ready"))?;
 
+    recordings.sort_by_key(|recording| recording.started_at);
+
+    // The host may have several sequential recordings, the first one leads.
     let maybe_host_recording = recordings
         .iter()
-        .find(|recording| recording.created_by == host);
+        .filter(|recording| recording.created_by == *host)
+        .min_by_key(|recording| recording.started_at);
 
     let host_stream = match maybe_host_recording {
         // Host has been set but there's no recording, skip transcoding.
@@ -426,7 +511,7 @@ async fn send_transcoding_task(
     // Fetch writer config snapshots for building muted segments.
     let mute_events = ctx
         .conference_client()
-        .read_config_snapshots(minigroup.conference_room_id())
+        .read_config_snapshots(class.conference_room_id())
         .await
         .context("Failed to get writer config snapshots for room")?;
 
@@ -436,7 +521,7 @@ async fn send_transcoding_task(
         .map(|recording| {
             let event_room_offset = recording.started_at
                 - (host_stream.started_at
-                    - Duration::milliseconds(ctx.get_preroll_offset(minigroup.audience())));
+                    - Duration::milliseconds(ctx.get_preroll_offset(class.audience())));
 
             let recording_offset = recording.started_at - earliest_recording.started_at;
 
@@ -458,10 +543,7 @@ async fn send_transcoding_task(
         host_stream_id,
     };
 
-    ctx.tq_client()
-        .create_task(minigroup, task, priority)
-        .await
-        .context("TqClient create task failed")
+    shared_helpers::create_tq_task(ctx.as_ref(), class, task, priority).await
 }
 
 fn build_stream(
@@ -471,15 +553,7 @@ fn build_stream(
     recording_offset: Duration,
     configs_changes: &[ConfigSnapshot],
 ) -> anyhow::Result<TranscodeMinigroupToHlsStream> {
-    let recording_end = match recording
-        .segments
-        .last()
-        .map(|range| range.end)
-        .ok_or_else(|| anyhow!("Recording segments have no end?"))?
-    {
-        Bound::Included(t) | Bound::Excluded(t) => t,
-        Bound::Unbounded => bail!("Unbounded recording end"),
-    };
+    let recording_end = segments_end(&recording.segments)?;
 
     let pin_segments = collect_pin_segments(
         pin_events,
@@ -553,6 +627,17 @@ fn build_stream(
     Ok(v)
 }
 
+fn segments_end(segments: &Segments) -> Result<i64> {
+    match segments
+        .last()
+        .map(|range| range.end)
+        .ok_or_else(|| anyhow!("Recording segments have no end?"))?
+    {
+        Bound::Included(t) | Bound::Excluded(t) => Ok(t),
+        Bound::Unbounded => bail!("Unbounded recording end"),
+    }
+}
+
 fn collect_pin_segments(
     pin_events: &[Event],
     event_room_offset: Duration,
@@ -607,17 +692,17 @@ fn collect_pin_segments(
 }
 
 #[derive(Debug)]
-struct ReadyRecording {
+pub(super) struct ReadyRecording {
     rtc_id: Uuid,
     stream_uri: String,
     segments: Segments,
     modified_segments: Segments,
-    started_at: DateTime<Utc>,
-    created_by: AgentId,
+    pub(super) started_at: DateTime<Utc>,
+    pub(super) created_by: AgentId,
 }
 
 impl ReadyRecording {
-    fn from_db_object(recording: &crate::db::recording::Object) -> Option<Self> {
+    pub(super) fn from_db_object(recording: &crate::db::recording::Object) -> Option<Self> {
         Some(Self {
             rtc_id: recording.rtc_id(),
             stream_uri: recording.stream_uri().cloned()?,
@@ -11,8 +11,9 @@ mod handle_upload {
     use crate::db::recording::{RecordingListQuery, Segments};
     use crate::test_helpers::{prelude::*, shared_helpers::random_string};
 
-    use super::super::super::PostprocessingStrategy;
+    use super::super::super::{PostprocessingStrategy, Progress};
     use super::super::*;
+    use crate::db::class::ClassStatus;
 
     #[tokio::test]
     async fn handle_upload_stream() {
@@ -155,7 +156,7 @@ mod handle_upload {
         let state = Arc::new(state);
 
         MinigroupPostprocessingStrategy::new(state.clone(), minigroup.clone())
-            .handle_stream_upload(stream1)
+            .handle_stream_upload(stream1, &Progress::new(ClassStatus::Finished))
             .await
             .expect("Failed to handle upload");
 
@@ -174,7 +175,7 @@ mod handle_upload {
         }
 
         MinigroupPostprocessingStrategy::new(state.clone(), minigroup.clone())
-            .handle_stream_upload(stream2)
+            .handle_stream_upload(stream2, &Progress::new(ClassStatus::Finished))
             .await
             .expect("Failed to handle upload");
 
@@ -191,7 +192,7 @@ mod handle_upload {
         };
 
         MinigroupPostprocessingStrategy::new(state.clone(), minigroup)
-            .handle_stream_upload(stream3)
+            .handle_stream_upload(stream3, &Progress::new(ClassStatus::Finished))
             .await
             .expect("Failed to handle upload");
 
@@ -261,7 +262,7 @@ mod handle_upload {
             .tq_client_mock()
             .expect_create_task()
             .times(2)
-            .returning(|_, _, _| Ok(()));
+            .returning(|_, _, _, _| Ok(()));
 
         // Handle uploading two RTCs.
         let rtc1_id = Uuid::new_v4();
@@ -289,7 +290,7 @@ mod handle_upload {
         let state = Arc::new(state);
 
         MinigroupPostprocessingStrategy::new(state.clone(), minigroup)
-            .handle_mjr_dumps_upload(vec![rtc1, rtc2])
+            .handle_mjr_dumps_upload(vec![rtc1, rtc2], &Progress::new(ClassStatus::Finished))
             .await
             .expect("Failed to handle upload");
 
@@ -339,8 +340,9 @@ mod handle_adjust {
     use crate::db::recording::{RecordingListQuery, Segments};
     use crate::test_helpers::{prelude::*, shared_helpers::random_string};
 
-    use super::super::super::PostprocessingStrategy;
+    use super::super::super::{PostprocessingStrategy, Progress};
     use super::super::*;
+    use crate::db::class::ClassStatus;
 
     #[tokio::test]
     async fn handle_adjust() {
@@ -519,23 +521,26 @@ mod handle_adjust {
         state
             .tq_client_mock()
             .expect_create_task()
-            .withf(move |class: &Class, task: &TqTask, _p: &Priority| {
+            .withf(move |class: &Class, task: &TqTask, _p: &Priority, _job| {
                 assert_eq!(class.id(), minigroup_id);
                 assert_eq!(task, &expected_task);
                 true
             })
-            .returning(|_, _, _| Ok(()));
+            .returning(|_, _, _, _| Ok(()));
 
         // Handle event room adjustment.
         let state = Arc::new(state);
 
         MinigroupPostprocessingStrategy::new(state.clone(), minigroup)
-            .handle_adjust(RoomAdjustResult::Success {
-                original_room_id: original_event_room_id,
-                modified_room_id: modified_event_room_id,
-                cut_original_segments: cut_original_segments.clone(),
-                modified_segments,
-            })
+            .handle_adjust(
+                RoomAdjustResult::Success {
+                    original_room_id: original_event_room_id,
+                    modified_room_id: modified_event_room_id,
+                    cut_original_segments: cut_original_segments.clone(),
+                    modified_segments,
+                },
+                &Progress::new(ClassStatus::Adjusted),
+            )
             .await
             .expect("Failed to handle event room adjustment");
 
@@ -579,6 +584,15 @@ mod handle_adjust {
                     recording.segments()
                 }
             );
+
+            assert_eq!(
+                updated_recording.host_selection(),
+                if recording.id() == recording1.id() {
+                    Some(HostSelection::HostEvent)
+                } else {
+                    None
+                }
+            );
         }
     }
 
@@ -743,23 +757,26 @@ mod handle_adjust {
         state
             .tq_client_mock()
             .expect_create_task()
-            .withf(move |class: &Class, task: &TqTask, _p: &Priority| {
+            .withf(move |class: &Class, task: &TqTask, _p: &Priority, _job| {
                 assert_eq!(class.id(), minigroup_id);
                 assert_eq!(task, &expected_task);
                 true
             })
-            .returning(|_, _, _| Ok(()));
+            .returning(|_, _, _, _| Ok(()));
 
         // Handle event room adjustment.
         let state = Arc::new(state);
 
         MinigroupPostprocessingStrategy::new(state.clone(), minigroup)
-            .handle_adjust(RoomAdjustResult::Success {
-                original_room_id: original_event_room_id,
-                modified_room_id: modified_event_room_id,
-                cut_original_segments: cut_original_segments.clone(),
-                modified_segments,
-            })
+            .handle_adjust(
+                RoomAdjustResult::Success {
+                    original_room_id: original_event_room_id,
+                    modified_room_id: modified_event_room_id,
+                    cut_original_segments: cut_original_segments.clone(),
+                    modified_segments,
+                },
+                &Progress::new(ClassStatus::Adjusted),
+            )
             .await
             .expect("Failed to handle event room adjustment");
 
@@ -819,8 +836,9 @@ mod handle_transcoding_completion {
     use crate::db::recording::{RecordingListQuery, Segments};
     use crate::test_helpers::{prelude::*, shared_helpers::random_string};
 
-    use super::super::super::PostprocessingStrategy;
+    use super::super::super::{PostprocessingStrategy, Progress};
     use super::super::*;
+    use crate::db::class::ClassStatus;
 
     #[tokio::test]
     async fn handle_transcoding_completion() {
@@ -888,11 +906,12 @@ mod handle_transcoding_completion {
         let state = Arc::new(state);
 
         MinigroupPostprocessingStrategy::new(state.clone(), minigroup.clone())
-            .handle_transcoding_completion(TranscodeSuccess::TranscodeMinigroupToHls(
-                TranscodeMinigroupToHlsSuccess {
+            .handle_transcoding_completion(
+                TranscodeSuccess::TranscodeMinigroupToHls(TranscodeMinigroupToHlsSuccess {
                     recording_duration: "3000.0".to_string(),
-                },
-            ))
+                }),
+                &Progress::new(ClassStatus::Transcoded),
+            )
             .await
             .expect("Failed to handle tq transcoding completion");
 
@@ -947,6 +966,383 @@ mod handle_transcoding_completion {
     }
 }
 
+mod select_host {
+    use std::ops::Bound;
+    use std::sync::Arc;
+
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    use crate::app::AppContext;
+    use crate::db::class::ClassUpdateQuery;
+    use crate::test_helpers::{prelude::*, shared_helpers::random_string};
+
+    use super::super::*;
+
+    #[tokio::test]
+    async fn falls_back_when_no_host_event() {
+        let agent1 = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let agent2 = TestAgent::new("web", "user2", USR_AUDIENCE);
+        let agent3 = TestAgent::new("web", "user3", USR_AUDIENCE);
+        let mut state = TestState::new(TestAuthz::new()).await;
+        let event_room_id = Uuid::new_v4();
+
+        let (minigroup, recordings) = {
+            let mut conn = state.get_conn().await.expect("Failed to get conn");
+
+            let minigroup = factory::Minigroup::new(
+                format!("minigroup-{}", random_string()),
+                USR_AUDIENCE.to_string(),
+                (Bound::Unbounded, Bound::Unbounded).into(),
+                Uuid::new_v4(),
+                event_room_id,
+            )
+            .insert(&mut conn)
+            .await;
+
+            let mut recordings = vec![];
+            for (agent, duration) in [(&agent1, 1_000), (&agent2, 3_000)] {
+                let recording = factory::Recording::new(
+                    minigroup.id(),
+                    Uuid::new_v4(),
+                    agent.agent_id().to_owned(),
+                )
+                .segments(vec![(Bound::Included(0), Bound::Excluded(duration))].into())
+                .started_at(Utc::now())
+                .insert(&mut conn)
+                .await;
+
+                recordings.push(recording);
+            }
+
+            (minigroup, recordings)
+        };
+
+        state
+            .event_client_mock()
+            .expect_list_events()
+            .returning(|_, _| Ok(vec![]));
+
+        let state = Arc::new(state) as Arc<dyn AppContext>;
+
+        // The class host has got a recording.
+        let minigroup_with_host = {
+            let mut conn = state.get_conn().await.expect("Failed to get conn");
+            ClassUpdateQuery::new(minigroup.id())
+                .host(agent1.agent_id().to_owned())
+                .execute(&mut conn)
+                .await
+                .expect("Failed to update minigroup host")
+        };
+
+        let host = select_host(&state, &minigroup_with_host, event_room_id, &recordings)
+            .await
+            .expect("Failed to select host");
+        assert_eq!(
+            host,
+            Some((agent1.agent_id().to_owned(), HostSelection::ClassHost))
+        );
+
+        // The class host hasn't got a recording.
+        let minigroup_with_host = {
+            let mut conn = state.get_conn().await.expect("Failed to get conn");
+            ClassUpdateQuery::new(minigroup.id())
+                .host(agent3.agent_id().to_owned())
+                .execute(&mut conn)
+                .await
+                .expect("Failed to update minigroup host")
+        };
+
+        let host = select_host(&state, &minigroup_with_host, event_room_id, &recordings)
+            .await
+            .expect("Failed to select host");
+        assert_eq!(
+            host,
+            Some((
+                agent2.agent_id().to_owned(),
+                HostSelection::LongestRecording
+            ))
+        );
+
+        let host = select_host(&state, &minigroup_with_host, event_room_id, &[])
+            .await
+            .expect("Failed to select host");
+        assert_eq!(host, None);
+    }
+}
+
+mod sequential_recordings {
+    use std::ops::Bound;
+    use std::sync::Arc;
+
+    use chrono::{DateTime, Duration, Utc};
+    use uuid::Uuid;
+
+    use crate::app::{postprocessing_strategy::StreamData, AppContext};
+    use crate::clients::event::test_helpers::EventBuilder;
+    use crate::clients::event::{EventData, EventRoomResponse, HostEventData};
+    use crate::db::recording::{RecordingListQuery, Segments};
+    use crate::test_helpers::{prelude::*, shared_helpers::random_string};
+
+    use super::super::super::{PostprocessingStrategy, Progress};
+    use super::super::*;
+    use crate::db::class::ClassStatus;
+
+    fn host_event(room_id: Uuid, host: &TestAgent) -> Vec<Event> {
+        vec![EventBuilder::new()
+            .room_id(room_id)
+            .set(HOST_EVENT_TYPE.to_string())
+            .data(EventData::Host(HostEventData::new(
+                host.agent_id().to_owned(),
+            )))
+            .occurred_at(0)
+            .build()]
+    }
+
+    async fn insert_minigroup(state: &TestState, event_room_id: Uuid) -> Class {
+        let mut conn = state.get_conn().await.expect("Failed to get conn");
+
+        factory::Minigroup::new(
+            format!("minigroup-{}", random_string()),
+            USR_AUDIENCE.to_string(),
+            (Bound::Unbounded, Bound::Unbounded).into(),
+            Uuid::new_v4(),
+            event_room_id,
+        )
+        .insert(&mut conn)
+        .await
+    }
+
+    #[tokio::test]
+    async fn handle_upload_stream_glues_host_sessions() {
+        let now = Utc::now();
+        let mut state = TestState::new(TestAuthz::new()).await;
+        let event_room_id = Uuid::new_v4();
+        let agent1 = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let agent2 = TestAgent::new("web", "user2", USR_AUDIENCE);
+        let started_at: DateTime<Utc> = now - Duration::hours(1);
+        let rejoin_rtc_id = Uuid::new_v4();
+
+        let minigroup = insert_minigroup(&state, event_room_id).await;
+
+        {
+            let mut conn = state.get_conn().await.expect("Failed to get conn");
+
+            // The host has dropped 10 minutes in.
+            factory::Recording::new(minigroup.id(), Uuid::new_v4(), agent1.agent_id().to_owned())
+                .stream_uri("s3://minigroup.origin.dev.example.com/rtc1.webm".to_string())
+                .segments(vec![(Bound::Included(0), Bound::Excluded(600_000))].into())
+                .started_at(started_at)
+                .insert(&mut conn)
+                .await;
+
+            // The host has rejoined with another rtc.
+            factory::Recording::new(minigroup.id(), rejoin_rtc_id, agent1.agent_id().to_owned())
+                .insert(&mut conn)
+                .await;
+
+            factory::Recording::new(minigroup.id(), Uuid::new_v4(), agent2.agent_id().to_owned())
+                .stream_uri("s3://minigroup.origin.dev.example.com/rtc3.webm".to_string())
+                .segments(vec![(Bound::Included(0), Bound::Excluded(2_700_000))].into())
+                .started_at(now - Duration::minutes(50))
+                .insert(&mut conn)
+                .await;
+        }
+
+        state
+            .event_client_mock()
+            .expect_list_events()
+            .returning(move |room_id, _kind| Ok(host_event(room_id, &agent1)));
+
+        let expected_segments: Segments = vec![
+            (Bound::Included(0), Bound::Excluded(600_000)),
+            (Bound::Included(900_000), Bound::Excluded(2_100_000)),
+        ]
+        .into();
+
+        state
+            .event_client_mock()
+            .expect_adjust_room()
+            .withf(move |room_id, adjust_started_at, segments, _offset| {
+                assert_eq!(*room_id, event_room_id);
+                assert_eq!(
+                    adjust_started_at.timestamp_millis(),
+                    started_at.timestamp_millis()
+                );
+                assert_eq!(*segments, expected_segments);
+                true
+            })
+            .returning(|_, _, _, _| Ok(()));
+
+        let state = Arc::new(state);
+
+        MinigroupPostprocessingStrategy::new(state.clone(), minigroup)
+            .handle_stream_upload(
+                UploadedStream {
+                    id: rejoin_rtc_id,
+                    parsed_data: Ok(StreamData {
+                        uri: "s3://minigroup.origin.dev.example.com/rtc2.webm".to_string(),
+                        started_at: started_at + Duration::minutes(15),
+                        segments: vec![(Bound::Included(0), Bound::Excluded(1_200_000))].into(),
+                    }),
+                },
+                &Progress::new(ClassStatus::Finished),
+            )
+            .await
+            .expect("Failed to handle upload");
+    }
+
+    #[tokio::test]
+    async fn handle_adjust_splits_host_sessions() {
+        let now = Utc::now();
+        let mut state = TestState::new(TestAuthz::new()).await;
+        let modified_event_room_id = Uuid::new_v4();
+        let agent1 = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let agent2 = TestAgent::new("web", "user2", USR_AUDIENCE);
+        let started_at: DateTime<Utc> = now - Duration::hours(1);
+
+        let minigroup = insert_minigroup(&state, Uuid::new_v4()).await;
+        let minigroup_id = minigroup.id();
+
+        let recordings = {
+            let mut conn = state.get_conn().await.expect("Failed to get conn");
+
+            let sessions = vec![
+                (&agent1, started_at, 600_000),
+                (&agent1, started_at + Duration::minutes(15), 1_200_000),
+                (&agent2, started_at + Duration::minutes(10), 2_700_000),
+            ];
+
+            let mut recordings = vec![];
+            for (idx, (agent, started_at, duration)) in sessions.into_iter().enumerate() {
+                let recording = factory::Recording::new(
+                    minigroup_id,
+                    Uuid::new_v4(),
+                    agent.agent_id().to_owned(),
+                )
+                .stream_uri(format!(
+                    "s3://minigroup.origin.dev.example.com/rtc{}.webm",
+                    idx
+                ))
+                .segments(vec![(Bound::Included(0), Bound::Excluded(duration))].into())
+                .started_at(started_at)
+                .insert(&mut conn)
+                .await;
+
+                recordings.push(recording);
+            }
+
+            recordings
+        };
+
+        // Assume there was a single cut-stop at the beginning of the glued host timeline.
+        let cut_original_segments: Segments = vec![
+            (Bound::Included(3_000), Bound::Excluded(600_000)),
+            (Bound::Included(900_000), Bound::Excluded(2_100_000)),
+        ]
+        .into();
+
+        let expected_modified_segments: Vec<Segments> = vec![
+            vec![(Bound::Included(3_000), Bound::Excluded(600_000))].into(),
+            vec![(Bound::Included(0), Bound::Excluded(1_200_000))].into(),
+            recordings[2].segments().unwrap().to_owned(),
+        ];
+
+        state
+            .event_client_mock()
+            .expect_list_events()
+            .returning(move |room_id, kind| match kind {
+                HOST_EVENT_TYPE => Ok(host_event(room_id, &agent1)),
+                _ => Ok(vec![]),
+            });
+
+        state
+            .event_client_mock()
+            .expect_read_room()
+            .returning(move |room_id| {
+                Ok(EventRoomResponse {
+                    id: room_id,
+                    time: (Bound::Included(started_at), Bound::Unbounded),
+                    tags: None,
+                })
+            });
+
+        state
+            .event_client_mock()
+            .expect_dump_room()
+            .returning(|_| Ok(()));
+
+        state
+            .conference_client_mock()
+            .expect_read_config_snapshots()
+            .returning(|_| Ok(vec![]));
+
+        // Streams go in the order of their start.
+        let expected_task = TqTask::TranscodeMinigroupToHls {
+            streams: [0, 2, 1]
+                .iter()
+                .map(|idx| (&recordings[*idx], &expected_modified_segments[*idx]))
+                .zip(vec![0, 600_000, 900_000])
+                .map(|((recording, modified_segments), offset)| {
+                    TranscodeMinigroupToHlsStream::new(
+                        recording.rtc_id(),
+                        recording.stream_uri().unwrap().to_owned(),
+                    )
+                    .offset(offset)
+                    .segments(recording.segments().unwrap().to_owned())
+                    .modified_segments(modified_segments.to_owned())
+                    .pin_segments(vec![].into())
+                })
+                .collect(),
+            host_stream_id: recordings[0].rtc_id(),
+        };
+
+        state
+            .tq_client_mock()
+            .expect_create_task()
+            .withf(move |_class: &Class, task: &TqTask, _p: &Priority, _job| {
+                assert_eq!(task, &expected_task);
+                true
+            })
+            .returning(|_, _, _, _| Ok(()));
+
+        let state = Arc::new(state);
+
+        MinigroupPostprocessingStrategy::new(state.clone(), minigroup)
+            .handle_adjust(
+                RoomAdjustResult::Success {
+                    original_room_id: Uuid::new_v4(),
+                    modified_room_id: modified_event_room_id,
+                    cut_original_segments,
+                    modified_segments: vec![(Bound::Included(0), Bound::Excluded(1_797_000))]
+                        .into(),
+                },
+                &Progress::new(ClassStatus::Adjusted),
+            )
+            .await
+            .expect("Failed to handle event room adjustment");
+
+        let mut conn = state.get_conn().await.expect("Failed to get conn");
+
+        let updated_recordings = RecordingListQuery::new(minigroup_id)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to fetch recordings");
+
+        for (recording, modified_segments) in recordings.iter().zip(&expected_modified_segments) {
+            let updated_recording = updated_recordings
+                .iter()
+                .find(|r| r.id() == recording.id())
+                .expect("Missing recording");
+
+            assert_eq!(
+                updated_recording.modified_segments(),
+                Some(modified_segments)
+            );
+        }
+    }
+}
+
 mod collect_pinned_events {
     use super::super::*;
     use crate::clients::event::test_helpers::EventBuilder;
@@ -1,16 +1,19 @@
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde_derive::Deserialize;
+use sqlx::postgres::PgConnection;
 use svc_agent::AgentId;
 use uuid::Uuid;
 
 use crate::{app::AppContext, clients::tq::TranscodeMinigroupToHlsSuccess};
 use crate::{
     clients::tq::TranscodeStreamToHlsSuccess,
-    db::class::{ClassType, Object as Class},
+    db::class::{ClassStatus, ClassType, Object as Class, UpdateStatusQuery},
+    db::postprocessing_job::CompleteQuery,
 };
 use crate::{
     clients::{event::RoomAdjustResult, tq::ConvertMjrDumpsToStreamSuccess},
@@ -18,9 +21,11 @@ use crate::{
 };
 
 use minigroup::MinigroupPostprocessingStrategy;
+use p2p::P2PPostprocessingStrategy;
 use webinar::WebinarPostprocessingStrategy;
 
 pub use minigroup::restart_transcoding as restart_minigroup_transcoding;
+pub use shared_helpers::resubmit_tq_task;
 pub use webinar::restart_transcoding as restart_webinar_transcoding;
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -30,24 +35,91 @@ pub(crate) fn get(
     class: Class,
 ) -> Result<Box<dyn PostprocessingStrategy + Send + Sync>> {
     match class.kind() {
-        ClassType::P2P => bail!("Postprocessing for a p2p is not available"),
+        ClassType::P2P => Ok(Box::new(P2PPostprocessingStrategy::new(ctx, class))),
         ClassType::Minigroup => Ok(Box::new(MinigroupPostprocessingStrategy::new(ctx, class))),
         ClassType::Webinar => Ok(Box::new(WebinarPostprocessingStrategy::new(ctx, class))),
     }
 }
 
+/// Handlers save the progress within the transaction that saves their results.
 #[async_trait]
 pub(crate) trait PostprocessingStrategy {
-    async fn handle_mjr_dumps_upload(&self, rtcs: Vec<MjrDumpsUploadResult>) -> Result<()>;
-    async fn handle_stream_upload(&self, stream: UploadedStream) -> Result<()>;
-    async fn handle_adjust(&self, room_adjust_result: RoomAdjustResult) -> Result<()>;
+    async fn handle_mjr_dumps_upload(
+        &self,
+        rtcs: Vec<MjrDumpsUploadResult>,
+        progress: &Progress,
+    ) -> Result<()>;
+
+    async fn handle_stream_upload(&self, stream: UploadedStream, progress: &Progress)
+        -> Result<()>;
+
+    async fn handle_adjust(
+        &self,
+        room_adjust_result: RoomAdjustResult,
+        progress: &Progress,
+    ) -> Result<()>;
 
     async fn handle_transcoding_completion(
         &self,
         completion_result: TranscodeSuccess,
+        progress: &Progress,
     ) -> Result<()>;
 }
 
+/// The status the class moves to once the event is handled and the tq job
+/// (template and stream id) the event completes if any.
+#[derive(Debug, Clone)]
+pub struct Progress {
+    status: ClassStatus,
+    job: Option<(String, Option<Uuid>)>,
+    transitioned: Arc<AtomicBool>,
+}
+
+impl Progress {
+    pub fn new(status: ClassStatus) -> Self {
+        Self {
+            status,
+            job: None,
+            transitioned: Default::default(),
+        }
+    }
+
+    pub fn completes_job(self, template: String, stream_id: Option<Uuid>) -> Self {
+        Self {
+            job: Some((template, stream_id)),
+            ..self
+        }
+    }
+
+    pub fn status(&self) -> ClassStatus {
+        self.status
+    }
+
+    /// Whether saving has actually moved the class to the status, a redelivered or
+    /// late event leaves it as is.
+    pub fn transitioned(&self) -> bool {
+        self.transitioned.load(Ordering::SeqCst)
+    }
+
+    pub(self) async fn save(&self, conn: &mut PgConnection, class: &Class) -> Result<()> {
+        let transitioned = UpdateStatusQuery::new(class.id(), self.status)
+            .execute(conn)
+            .await
+            .with_context(|| format!("Failed to update class status to {:?}", self.status))?;
+
+        self.transitioned.store(transitioned, Ordering::SeqCst);
+
+        if let Some((template, stream_id)) = &self.job {
+            CompleteQuery::succeeded(class.id(), template.to_owned(), *stream_id)
+                .execute(conn)
+                .await
+                .context("Failed to complete postprocessing job")?;
+        }
+
+        Ok(())
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 #[derive(Debug, Deserialize)]
@@ -110,5 +182,44 @@ impl UploadedStream {
 ////////////////////////////////////////////////////////////////////////////////
 
 mod minigroup;
+mod p2p;
 pub(self) mod shared_helpers;
 mod webinar;
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use super::*;
+    use crate::test_helpers::prelude::*;
+
+    #[tokio::test]
+    async fn progress_saved_twice_transitions_once() {
+        let db = TestDb::new().await;
+        let mut conn = db.get_conn().await;
+
+        let webinar = factory::Webinar::new(
+            random_string(),
+            USR_AUDIENCE.to_string(),
+            (Bound::Unbounded, Bound::Unbounded).into(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        )
+        .insert(&mut conn)
+        .await;
+
+        let progress = Progress::new(ClassStatus::Adjusted);
+        progress
+            .save(&mut conn, &webinar)
+            .await
+            .expect("Failed to save progress");
+        assert!(progress.transitioned());
+
+        let progress = Progress::new(ClassStatus::Adjusted);
+        progress
+            .save(&mut conn, &webinar)
+            .await
+            .expect("Failed to save progress");
+        assert!(!progress.transitioned());
+    }
+}
@@ -0,0 +1,592 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde_derive::Serialize;
+use serde_json::Value as JsonValue;
+use sqlx::Acquire;
+use svc_agent::AgentId;
+use uuid::Uuid;
+
+use crate::app::outbox;
+use crate::app::AppContext;
+use crate::clients::event::RoomAdjustResult;
+use crate::clients::tq::{Priority, Task as TqTask, TranscodeMinigroupToHlsSuccess};
+use crate::db::class::Object as Class;
+use crate::db::recording::Object as Recording;
+
+use super::minigroup::{
+    call_adjust, host_timeline, send_composite_transcoding_task, split_host_segments,
+    ReadyRecording,
+};
+use super::{shared_helpers, MjrDumpsUploadResult, Progress, TranscodeSuccess, UploadedStream};
+
+const MAX_PARTICIPANTS: usize = 2;
+
+pub(super) struct P2PPostprocessingStrategy {
+    ctx: Arc<dyn AppContext>,
+    p2p: Class,
+}
+
+impl P2PPostprocessingStrategy {
+    pub(super) fn new(ctx: Arc<dyn AppContext>, p2p: Class) -> Self {
+        Self { ctx, p2p }
+    }
+}
+
+#[async_trait]
+impl super::PostprocessingStrategy for P2PPostprocessingStrategy {
+    async fn handle_mjr_dumps_upload(
+        &self,
+        dumps: Vec<MjrDumpsUploadResult>,
+        progress: &Progress,
+    ) -> Result<()> {
+        if dumps.is_empty() {
+            bail!("Expected at least 1 RTC");
+        }
+
+        let ready_dumps = shared_helpers::extract_ready_dumps(dumps)?;
+
+        // A participant who has rejoined has several RTCs.
+        let participants = ready_dumps
+            .iter()
+            .map(|dump| &dump.created_by)
+            .collect::<HashSet<_>>()
+            .len();
+
+        if participants > MAX_PARTICIPANTS {
+            bail!(
+                "Expected 1 to {} participants, got {}",
+                MAX_PARTICIPANTS,
+                participants
+            );
+        }
+
+        shared_helpers::save_dumps_upload(self.ctx.as_ref(), &self.p2p, &ready_dumps, progress)
+            .await?;
+
+        for dump in ready_dumps {
+            shared_helpers::create_tq_task(
+                self.ctx.as_ref(),
+                &self.p2p,
+                TqTask::ConvertMjrDumpsToStream {
+                    mjr_dumps_uris: dump.mjr_dumps_uris,
+                    stream_uri: dump.uri,
+                    stream_id: dump.id,
+                },
+                Priority::Normal,
+            )
+            .await?
+        }
+        Ok(())
+    }
+
+    async fn handle_stream_upload(
+        &self,
+        stream: UploadedStream,
+        progress: &Progress,
+    ) -> Result<()> {
+        shared_helpers::save_stream_upload(self.ctx.as_ref(), &self.p2p, stream, progress).await?;
+
+        let recordings = self.list_recordings().await?;
+
+        let ready_recordings = recordings
+            .iter()
+            .filter_map(ReadyRecording::from_db_object)
+            .collect::<Vec<_>>();
+        if recordings.len() != ready_recordings.len() {
+            return Ok(());
+        }
+
+        let leading_recording = leading_participant(&ready_recordings)
+            .and_then(|leader| host_timeline(ready_recordings, &leader));
+
+        let leading_recording = match leading_recording {
+            None => bail!("No recordings left for p2p, id = {}", self.p2p.id()),
+            Some(recording) => recording,
+        };
+
+        call_adjust(
+            self.ctx.clone(),
+            self.p2p.event_room_id(),
+            leading_recording,
+            self.ctx.get_preroll_offset(self.p2p.audience()),
+        )
+        .await
+    }
+
+    async fn handle_adjust(
+        &self,
+        room_adjust_result: RoomAdjustResult,
+        progress: &Progress,
+    ) -> Result<()> {
+        match room_adjust_result {
+            RoomAdjustResult::Success {
+                original_room_id,
+                modified_room_id,
+                cut_original_segments,
+                ..
+            } => {
+                let recordings = self.list_recordings().await?;
+                let ready_recordings = recordings
+                    .iter()
+                    .filter_map(ReadyRecording::from_db_object)
+                    .collect::<Vec<_>>();
+                let leading = leading_participant(&ready_recordings)
+                    .ok_or_else(|| anyhow!("No ready recordings, p2p id = {}", self.p2p.id()))?;
+
+                // Save adjust results to the DB and fetch recordings.
+                let recordings = {
+                    let mut conn = self.ctx.get_conn().await?;
+
+                    let mut txn = conn
+                        .begin()
+                        .await
+                        .context("Failed to begin sqlx db transaction")?;
+
+                    crate::db::class::UpdateAdjustedRoomsQuery::new(
+                        self.p2p.id(),
+                        original_room_id,
+                        modified_room_id,
+                    )
+                    .execute(&mut txn)
+                    .await?;
+
+                    let recordings = crate::db::recording::AdjustMinigroupUpdateQuery::new(
+                        self.p2p.id(),
+                        cut_original_segments.clone(),
+                        leading.clone(),
+                    )
+                    .execute(&mut txn)
+                    .await?;
+
+                    let recordings =
+                        split_host_segments(&mut txn, recordings, &leading, &cut_original_segments)
+                            .await?;
+
+                    progress.save(&mut txn, &self.p2p).await?;
+                    txn.commit().await?;
+
+                    recordings
+                };
+
+                send_composite_transcoding_task(
+                    &self.ctx,
+                    &self.p2p,
+                    recordings,
+                    &leading,
+                    modified_room_id,
+                    Priority::Normal,
+                )
+                .await
+            }
+            RoomAdjustResult::Error { error } => {
+                bail!("Adjust failed, err = {:#?}", error);
+            }
+        }
+    }
+
+    async fn handle_transcoding_completion(
+        &self,
+        completion_result: TranscodeSuccess,
+        progress: &Progress,
+    ) -> Result<()> {
+        match completion_result {
+            TranscodeSuccess::TranscodeMinigroupToHls(TranscodeMinigroupToHlsSuccess {
+                recording_duration,
+                ..
+            }) => {
+                let stream_duration = recording_duration.parse::<f64>()?.round() as u64;
+
+                let path = format!("audiences/{}/events", self.p2p.audience());
+
+                let payload = P2PReady {
+                    id: self.p2p.id(),
+                    scope: self.p2p.scope().to_owned(),
+                    tags: self.p2p.tags().map(ToOwned::to_owned),
+                    status: "success",
+                    stream_duration,
+                };
+
+                let event = {
+                    let mut conn = self.ctx.get_conn().await?;
+                    let mut txn = conn.begin().await?;
+
+                    crate::db::recording::TranscodingUpdateQuery::new(self.p2p.id())
+                        .execute(&mut txn)
+                        .await?;
+
+                    let event = outbox::push(&mut txn, "p2p.ready", &path, &payload).await?;
+                    progress.save(&mut txn, &self.p2p).await?;
+                    txn.commit().await?;
+                    event
+                };
+
+                outbox::publish(self.ctx.as_ref(), vec![event]).await;
+                Ok(())
+            }
+            TranscodeSuccess::TranscodeStreamToHls(success_result) => {
+                bail!(
+                    "Got transcoding success for an unexpected tq template; expected transcode-minigroup-to-hls for a p2p, id = {}, result = {:#?}",
+                    self.p2p.id(),
+                    success_result,
+                );
+            }
+        }
+    }
+}
+
+impl P2PPostprocessingStrategy {
+    async fn list_recordings(&self) -> Result<Vec<Recording>> {
+        let mut conn = self.ctx.get_conn().await?;
+        crate::db::recording::RecordingListQuery::new(self.p2p.id())
+            .execute(&mut conn)
+            .await
+            .context("Failed to list p2p recordings")
+    }
+}
+
+/// P2P has no host so the participant who started publishing first leads the composition.
+/// Their recordings get glued like the minigroup host's ones when they have rejoined.
+fn leading_participant(recordings: &[ReadyRecording]) -> Option<AgentId> {
+    recordings
+        .iter()
+        .min_by(|a, b| a.started_at.cmp(&b.started_at))
+        .map(|recording| recording.created_by.clone())
+}
+
+#[derive(Serialize)]
+struct P2PReady {
+    id: Uuid,
+    scope: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<JsonValue>,
+    status: &'static str,
+    stream_duration: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use chrono::Duration;
+
+    use super::super::{MjrDumpsUploadReadyData, PostprocessingStrategy, StreamData};
+    use super::*;
+    use crate::clients::event::EventRoomResponse;
+    use crate::db::class::ClassStatus;
+    use crate::db::recording::{RecordingListQuery, Segments};
+    use crate::test_helpers::prelude::*;
+
+    #[tokio::test]
+    async fn handle_mjr_dumps_upload_rejects_extra_participants() {
+        let state = TestState::new(TestAuthz::new()).await;
+
+        let p2p = {
+            let mut conn = state.get_conn().await.expect("Failed to get conn");
+            factory::P2P::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+            )
+            .insert(&mut conn)
+            .await
+        };
+
+        let dumps = (1..=3)
+            .map(|i| {
+                let agent = TestAgent::new("web", &format!("user{}", i), USR_AUDIENCE);
+                MjrDumpsUploadResult::Ready(MjrDumpsUploadReadyData {
+                    id: Uuid::new_v4(),
+                    uri: format!("s3://p2p.origin.dev.example.com/rtc{}.webm", i),
+                    created_by: agent.agent_id().to_owned(),
+                    mjr_dumps_uris: vec![format!("s3://p2p.origin.dev.example.com/rtc{}.mjr", i)],
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let p2p_id = p2p.id();
+        let state = Arc::new(state);
+
+        P2PPostprocessingStrategy::new(state.clone(), p2p)
+            .handle_mjr_dumps_upload(dumps, &Progress::new(ClassStatus::Finished))
+            .await
+            .expect_err("Unexpectedly succeeded");
+
+        let mut conn = state.get_conn().await.expect("Failed to get conn");
+        let recordings = RecordingListQuery::new(p2p_id)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to list recordings");
+        assert!(recordings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn handle_mjr_dumps_upload_accepts_rejoined_participant() {
+        let mut state = TestState::new(TestAuthz::new()).await;
+
+        let p2p = {
+            let mut conn = state.get_conn().await.expect("Failed to get conn");
+            factory::P2P::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+            )
+            .insert(&mut conn)
+            .await
+        };
+
+        // The first participant has rejoined with another RTC.
+        let dumps = [1, 1, 2]
+            .iter()
+            .enumerate()
+            .map(|(idx, user)| {
+                let agent = TestAgent::new("web", &format!("user{}", user), USR_AUDIENCE);
+                MjrDumpsUploadResult::Ready(MjrDumpsUploadReadyData {
+                    id: Uuid::new_v4(),
+                    uri: format!("s3://p2p.origin.dev.example.com/rtc{}.webm", idx),
+                    created_by: agent.agent_id().to_owned(),
+                    mjr_dumps_uris: vec![format!("s3://p2p.origin.dev.example.com/rtc{}.mjr", idx)],
+                })
+            })
+            .collect::<Vec<_>>();
+
+        state
+            .tq_client_mock()
+            .expect_create_task()
+            .times(3)
+            .returning(|_, _, _, _| Ok(()));
+
+        let p2p_id = p2p.id();
+        let state = Arc::new(state);
+
+        P2PPostprocessingStrategy::new(state.clone(), p2p)
+            .handle_mjr_dumps_upload(dumps, &Progress::new(ClassStatus::Finished))
+            .await
+            .expect("Failed to handle dumps upload");
+
+        let mut conn = state.get_conn().await.expect("Failed to get conn");
+        let recordings = RecordingListQuery::new(p2p_id)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to list recordings");
+        assert_eq!(recordings.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn handle_stream_upload_glues_rejoined_leader_sessions() {
+        let now = Utc::now();
+        let mut state = TestState::new(TestAuthz::new()).await;
+        let event_room_id = Uuid::new_v4();
+        let agent1 = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let agent2 = TestAgent::new("web", "user2", USR_AUDIENCE);
+        let started_at = now - Duration::hours(1);
+        let rejoin_rtc_id = Uuid::new_v4();
+
+        let p2p = {
+            let mut conn = state.get_conn().await.expect("Failed to get conn");
+
+            let p2p = factory::P2P::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                Uuid::new_v4(),
+                event_room_id,
+            )
+            .insert(&mut conn)
+            .await;
+
+            // The leader has dropped 10 minutes in.
+            factory::Recording::new(p2p.id(), Uuid::new_v4(), agent1.agent_id().to_owned())
+                .stream_uri("s3://p2p.origin.dev.example.com/rtc1.webm".to_string())
+                .segments(vec![(Bound::Included(0), Bound::Excluded(600_000))].into())
+                .started_at(started_at)
+                .insert(&mut conn)
+                .await;
+
+            // The leader has rejoined with another RTC.
+            factory::Recording::new(p2p.id(), rejoin_rtc_id, agent1.agent_id().to_owned())
+                .insert(&mut conn)
+                .await;
+
+            factory::Recording::new(p2p.id(), Uuid::new_v4(), agent2.agent_id().to_owned())
+                .stream_uri("s3://p2p.origin.dev.example.com/rtc3.webm".to_string())
+                .segments(vec![(Bound::Included(0), Bound::Excluded(2_700_000))].into())
+                .started_at(now - Duration::minutes(50))
+                .insert(&mut conn)
+                .await;
+
+            p2p
+        };
+
+        let expected_segments: Segments = vec![
+            (Bound::Included(0), Bound::Excluded(600_000)),
+            (Bound::Included(900_000), Bound::Excluded(2_100_000)),
+        ]
+        .into();
+
+        state
+            .event_client_mock()
+            .expect_adjust_room()
+            .withf(move |room_id, adjust_started_at, segments, _offset| {
+                assert_eq!(*room_id, event_room_id);
+                assert_eq!(
+                    adjust_started_at.timestamp_millis(),
+                    started_at.timestamp_millis()
+                );
+                assert_eq!(*segments, expected_segments);
+                true
+            })
+            .returning(|_, _, _, _| Ok(()));
+
+        let state = Arc::new(state);
+
+        P2PPostprocessingStrategy::new(state.clone(), p2p)
+            .handle_stream_upload(
+                UploadedStream {
+                    id: rejoin_rtc_id,
+                    parsed_data: Ok(StreamData {
+                        uri: "s3://p2p.origin.dev.example.com/rtc2.webm".to_string(),
+                        started_at: started_at + Duration::minutes(15),
+                        segments: vec![(Bound::Included(0), Bound::Excluded(1_200_000))].into(),
+                    }),
+                },
+                &Progress::new(ClassStatus::Finished),
+            )
+            .await
+            .expect("Failed to handle upload");
+    }
+
+    #[tokio::test]
+    async fn handle_adjust() {
+        let now = Utc::now();
+        let agent1 = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let agent2 = TestAgent::new("web", "user2", USR_AUDIENCE);
+        let mut state = TestState::new(TestAuthz::new()).await;
+        let conference_room_id = Uuid::new_v4();
+        let modified_event_room_id = Uuid::new_v4();
+
+        let segments: Segments = vec![(Bound::Included(0), Bound::Excluded(2_700_000))].into();
+        let cut_original_segments: Segments =
+            vec![(Bound::Included(3_000), Bound::Excluded(2_700_000))].into();
+
+        let (p2p, early_recording, late_recording) = {
+            let mut conn = state.get_conn().await.expect("Failed to get conn");
+
+            let p2p = factory::P2P::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                conference_room_id,
+                Uuid::new_v4(),
+            )
+            .insert(&mut conn)
+            .await;
+
+            let late_recording =
+                factory::Recording::new(p2p.id(), Uuid::new_v4(), agent2.agent_id().to_owned())
+                    .stream_uri("s3://p2p.origin.dev.example.com/rtc2.webm".to_string())
+                    .segments(segments.clone())
+                    .started_at(now - Duration::minutes(50))
+                    .insert(&mut conn)
+                    .await;
+
+            let early_recording =
+                factory::Recording::new(p2p.id(), Uuid::new_v4(), agent1.agent_id().to_owned())
+                    .stream_uri("s3://p2p.origin.dev.example.com/rtc1.webm".to_string())
+                    .segments(segments.clone())
+                    .started_at(now - Duration::hours(1))
+                    .insert(&mut conn)
+                    .await;
+
+            (p2p, early_recording, late_recording)
+        };
+
+        let p2p_id = p2p.id();
+
+        state
+            .conference_client_mock()
+            .expect_read_config_snapshots()
+            .with(mockall::predicate::eq(conference_room_id))
+            .returning(|_room_id| Ok(vec![]));
+
+        state
+            .event_client_mock()
+            .expect_read_room()
+            .with(mockall::predicate::eq(modified_event_room_id))
+            .returning(move |room_id| {
+                Ok(EventRoomResponse {
+                    id: room_id,
+                    time: (
+                        Bound::Included(now - Duration::hours(1)),
+                        Bound::Excluded(now - Duration::minutes(10)),
+                    ),
+                    tags: None,
+                })
+            });
+
+        state
+            .event_client_mock()
+            .expect_dump_room()
+            .with(mockall::predicate::eq(modified_event_room_id))
+            .returning(|_room_id| Ok(()));
+
+        state
+            .event_client_mock()
+            .expect_list_events()
+            .returning(|_, _| Ok(vec![]));
+
+        let early_rtc_id = early_recording.rtc_id();
+
+        state
+            .tq_client_mock()
+            .expect_create_task()
+            .withf(move |class: &Class, task: &TqTask, _p: &Priority, _job| {
+                assert_eq!(class.id(), p2p_id);
+                match task {
+                    TqTask::TranscodeMinigroupToHls {
+                        streams,
+                        host_stream_id,
+                    } => {
+                        assert_eq!(streams.len(), 2);
+                        assert_eq!(*host_stream_id, early_rtc_id);
+                    }
+                    other => panic!("Unexpected tq task: {:?}", other),
+                }
+                true
+            })
+            .returning(|_, _, _, _| Ok(()));
+
+        let state = Arc::new(state);
+
+        P2PPostprocessingStrategy::new(state.clone(), p2p)
+            .handle_adjust(
+                RoomAdjustResult::Success {
+                    original_room_id: Uuid::new_v4(),
+                    modified_room_id: modified_event_room_id,
+                    cut_original_segments: cut_original_segments.clone(),
+                    modified_segments: segments.clone(),
+                },
+                &Progress::new(ClassStatus::Adjusted),
+            )
+            .await
+            .expect("Failed to handle event room adjustment");
+
+        let mut conn = state.get_conn().await.expect("Failed to get conn");
+        let recordings = RecordingListQuery::new(p2p_id)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to list recordings");
+
+        // Only the leading participant's recording gets cut.
+        for recording in recordings {
+            if recording.id() == early_recording.id() {
+                assert_eq!(recording.modified_segments(), Some(&cut_original_segments));
+            } else {
+                assert_eq!(recording.id(), late_recording.id());
+                assert_eq!(recording.modified_segments(), Some(&segments));
+            }
+        }
+    }
+}
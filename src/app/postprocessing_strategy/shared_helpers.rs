@@ -1,11 +1,24 @@
 use std::ops::Bound;
+use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, NaiveDateTime, Utc};
+use serde_json::json;
+use sqlx::Acquire;
+use svc_agent::AgentId;
+use tracing::{error, warn};
+use uuid::Uuid;
 
-use crate::db::recording::Segments;
+use crate::app::AppContext;
+use crate::clients::event::EventData;
+use crate::clients::tq::{JobAttempt, Priority, Task as TqTask};
+use crate::db::class::Object as Class;
+use crate::db::postprocessing_job::UpsertQuery;
+use crate::db::recording::{HostSelection, Object as Recording, Segments};
 
-use super::{MjrDumpsUploadReadyData, MjrDumpsUploadResult};
+use super::{MjrDumpsUploadReadyData, MjrDumpsUploadResult, Progress, UploadedStream};
+
+pub(super) const HOST_EVENT_TYPE: &str = "host";
 
 pub(super) fn extract_ready_dumps(
     rtcs: Vec<MjrDumpsUploadResult>,
@@ -24,6 +37,69 @@ pub(super) fn extract_ready_dumps(
     Ok(ready_rtcs)
 }
 
+/// Inserts recordings of the uploaded dumps and saves the progress.
+pub(super) async fn save_dumps_upload(
+    ctx: &dyn AppContext,
+    class: &Class,
+    dumps: &[MjrDumpsUploadReadyData],
+    progress: &Progress,
+) -> Result<()> {
+    let mut conn = ctx.get_conn().await?;
+
+    let mut txn = conn
+        .begin()
+        .await
+        .context("Failed to begin sqlx db transaction")?;
+
+    super::minigroup::insert_recordings(&mut txn, class.id(), dumps).await?;
+    progress.save(&mut txn, class).await?;
+
+    txn.commit().await?;
+    Ok(())
+}
+
+/// Saves the uploaded stream and the progress. A stream that failed to convert
+/// is removed along with its recording.
+pub(super) async fn save_stream_upload(
+    ctx: &dyn AppContext,
+    class: &Class,
+    stream: UploadedStream,
+    progress: &Progress,
+) -> Result<()> {
+    let mut conn = ctx.get_conn().await?;
+
+    let mut txn = conn
+        .begin()
+        .await
+        .context("Failed to begin sqlx db transaction")?;
+
+    match stream.parsed_data {
+        Ok(stream_data) => {
+            crate::db::recording::StreamUploadUpdateQuery::new(
+                class.id(),
+                stream.id,
+                stream_data.segments,
+                stream_data.uri,
+                stream_data.started_at,
+            )
+            .execute(&mut txn)
+            .await?;
+        }
+        Err(err) => {
+            warn!(
+                stream_id = ?stream.id,
+                "Failed to transcode recording with stream_id: {}, err: {:?}", stream.id, err
+            );
+            crate::db::recording::remove_recording(class.id(), stream.id, &mut txn).await?;
+        }
+    }
+
+    progress.save(&mut txn, class).await?;
+
+    txn.commit().await?;
+    Ok(())
+}
+
 pub fn parse_segments(segments: &str) -> Result<(DateTime<Utc>, Segments)> {
     let segments = segments
         .split('\n')
@@ -74,3 +150,157 @@ pub fn parse_segments(segments: &str) -> Result<(DateTime<Utc>, Segments)> {
     };
     Ok((absolute_started_at, relative_segments.into()))
 }
+
+/// Sends the task to tq keeping track of it in postprocessing jobs.
+pub async fn create_tq_task(
+    ctx: &dyn AppContext,
+    class: &Class,
+    task: TqTask,
+    priority: Priority,
+) -> Result<()> {
+    send_tq_task(ctx, class, task, priority, false).await
+}
+
+/// Sends the task of a failed job to tq once again as the next attempt of the job.
+pub async fn resubmit_tq_task(
+    ctx: &dyn AppContext,
+    class: &Class,
+    task: TqTask,
+    priority: Priority,
+) -> Result<()> {
+    send_tq_task(ctx, class, task, priority, true).await
+}
+
+async fn send_tq_task(
+    ctx: &dyn AppContext,
+    class: &Class,
+    task: TqTask,
+    priority: Priority,
+    retry: bool,
+) -> Result<()> {
+    let template = task.template().to_owned();
+    let stream_id = task.target_stream_id();
+    let serialized_task = serde_json::to_value(&task).context("Failed to serialize tq task")?;
+
+    let job = {
+        let mut conn = ctx.get_conn().await?;
+        let mut query = UpsertQuery::new(class.id(), template.clone(), stream_id, priority)
+            .task(serialized_task);
+        if retry {
+            query = query.retry();
+        }
+
+        query
+            .execute(&mut conn)
+            .await
+            .context("Failed to save postprocessing job")?
+    };
+
+    let job = JobAttempt {
+        job_id: job.id(),
+        attempt: job.attempts(),
+    };
+
+    // The failed job is up to the retry scheduler from now on, failing here would make
+    // a redelivery submit it once again and skip the rest of the tasks of the event.
+    if let Err(err) = ctx
+        .tq_client()
+        .create_task(class, task, priority, job)
+        .await
+    {
+        error!(
+            class_id = %class.id(),
+            template,
+            "TqClient create task failed, err = {:?}", err
+        );
+
+        let error = json!(err.to_string());
+        crate::app::tq_retry::handle_failed_job(ctx, class, template, stream_id, &error).await?;
+    }
+
+    Ok(())
+}
+
+/// Picks the host whose recording the others get composed around. The host event wins,
+/// otherwise configured fallbacks are tried in order. Only participants with a recording
+/// may become the host.
+pub(super) async fn select_host(
+    ctx: &Arc<dyn AppContext>,
+    class: &Class,
+    event_room_id: Uuid,
+    recordings: &[Recording],
+    fallback: &[HostSelection],
+) -> Result<Option<(AgentId, HostSelection)>> {
+    let has_recording = |agent_id: &AgentId| {
+        recordings
+            .iter()
+            .any(|recording| recording.created_by() == agent_id)
+    };
+
+    match find_host(ctx.clone(), event_room_id).await? {
+        Some(host) if has_recording(&host) => return Ok(Some((host, HostSelection::HostEvent))),
+        Some(host) => warn!(class_id = ?class.id(), %host, "No host recording in room"),
+        None => warn!(class_id = ?class.id(), %event_room_id, "No host in room"),
+    }
+
+    for selection in fallback {
+        let host = match selection {
+            HostSelection::HostEvent => None,
+            HostSelection::ClassHost => class.host().filter(|host| has_recording(host)).cloned(),
+            HostSelection::LongestRecording => recorded_durations(recordings)
+                .into_iter()
+                .max_by_key(|(_agent_id, duration)| *duration)
+                .map(|(agent_id, _duration)| agent_id.to_owned()),
+            HostSelection::FirstRecording => recordings
+                .iter()
+                .min_by_key(|recording| recording.created_at())
+                .map(|recording| recording.created_by().to_owned()),
+        };
+
+        if let Some(host) = host {
+            warn!(class_id = ?class.id(), %host, ?selection, "Fell back to another host");
+            return Ok(Some((host, *selection)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Sums up the durations of each participant's recordings as one may have several of them.
+fn recorded_durations(recordings: &[Recording]) -> Vec<(&AgentId, i64)> {
+    let mut durations: Vec<(&AgentId, i64)> = vec![];
+
+    for recording in recordings {
+        let duration = match recording.segments() {
+            Some(segments) => segments.duration(),
+            None => continue,
+        };
+
+        match durations
+            .iter_mut()
+            .find(|(agent_id, _)| *agent_id == recording.created_by())
+        {
+            Some((_, total)) => *total += duration,
+            None => durations.push((recording.created_by(), duration)),
+        }
+    }
+
+    durations
+}
+
+pub(super) async fn find_host(
+    ctx: Arc<dyn AppContext>,
+    event_room_id: Uuid,
+) -> Result<Option<AgentId>> {
+    let host_events = ctx
+        .event_client()
+        .list_events(event_room_id, HOST_EVENT_TYPE)
+        .await
+        .context("Failed to get host events for room")?;
+
+    match host_events.first().map(|event| event.data()) {
+        None => Ok(None),
+        Some(EventData::Host(data)) => Ok(Some(data.agent_id().to_owned())),
+        Some(other) => bail!("Got unexpected host event data: {:?}", other),
+    }
+}
@@ -2,25 +2,24 @@ use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use chrono::Utc;
 use serde_derive::Serialize;
 use serde_json::Value as JsonValue;
 use sqlx::Acquire;
-use svc_agent::mqtt::{
-    IntoPublishableMessage, OutgoingEvent, OutgoingEventProperties, ShortTermTimingProperties,
-};
+use svc_agent::AgentId;
 use uuid::Uuid;
 
+use crate::app::outbox;
 use crate::app::AppContext;
 use crate::clients::event::RoomAdjustResult;
-use crate::clients::tq::Priority;
-use crate::db::class::{ClassType, Object as Class};
-use crate::{
-    clients::tq::{Task as TqTask, TranscodeStreamToHlsSuccess},
-    db::recording::RecordingInsertQuery,
+use crate::clients::tq::{
+    Priority, Task as TqTask, TranscodeMinigroupToHlsSuccess, TranscodeStreamToHlsSuccess,
 };
+use crate::config::WebinarRecordingsMode;
+use crate::db::class::{ClassType, Object as Class};
+use crate::db::recording::{HostSelection, Object as Recording, Segments};
 
-use super::{MjrDumpsUploadResult, TranscodeSuccess, UploadedStream};
+use super::minigroup::{send_composite_transcoding_task, ReadyRecording};
+use super::{shared_helpers, MjrDumpsUploadResult, Progress, TranscodeSuccess, UploadedStream};
 
 pub(super) struct WebinarPostprocessingStrategy {
     ctx: Arc<dyn AppContext>,
@@ -35,21 +34,22 @@ impl WebinarPostprocessingStrategy {
 
 #[async_trait]
 impl super::PostprocessingStrategy for WebinarPostprocessingStrategy {
-    async fn handle_mjr_dumps_upload(&self, rtcs: Vec<MjrDumpsUploadResult>) -> Result<()> {
-        let mut ready_dumps = super::shared_helpers::extract_ready_dumps(rtcs)?;
-        if ready_dumps.len() != 1 {
-            return Err(anyhow!("Expected exactly 1 dump"));
-        }
-        let dump = ready_dumps.pop().unwrap();
-        {
-            let mut conn = self.ctx.get_conn().await?;
-            RecordingInsertQuery::new(self.webinar.id(), dump.id, dump.created_by)
-                .execute(&mut conn)
-                .await?;
+    async fn handle_mjr_dumps_upload(
+        &self,
+        rtcs: Vec<MjrDumpsUploadResult>,
+        progress: &Progress,
+    ) -> Result<()> {
+        let ready_dumps = shared_helpers::extract_ready_dumps(rtcs)?;
+        if ready_dumps.is_empty() {
+            bail!("Expected at least 1 dump");
         }
-        self.ctx
-            .tq_client()
-            .create_task(
+
+        shared_helpers::save_dumps_upload(self.ctx.as_ref(), &self.webinar, &ready_dumps, progress)
+            .await?;
+
+        for dump in ready_dumps {
+            shared_helpers::create_tq_task(
+                self.ctx.as_ref(),
                 &self.webinar,
                 TqTask::ConvertMjrDumpsToStream {
                     mjr_dumps_uris: dump.mjr_dumps_uris,
@@ -60,17 +60,45 @@ impl super::PostprocessingStrategy for WebinarPostprocessingStrategy {
             )
             .await
             .context("Failed to set mjr dumps convert task")?;
+        }
+
         Ok(())
     }
 
-    async fn handle_adjust(&self, room_adjust_result: RoomAdjustResult) -> Result<()> {
+    async fn handle_adjust(
+        &self,
+        room_adjust_result: RoomAdjustResult,
+        progress: &Progress,
+    ) -> Result<()> {
         match room_adjust_result {
             RoomAdjustResult::Success {
                 original_room_id,
                 modified_room_id,
                 modified_segments,
-                ..
+                cut_original_segments,
             } => {
+                if self.mode() == WebinarRecordingsMode::Composite {
+                    let recordings = self.list_recordings().await?;
+
+                    return self
+                        .handle_composite_adjust(
+                            recordings,
+                            original_room_id,
+                            modified_room_id,
+                            cut_original_segments,
+                            progress,
+                        )
+                        .await;
+                }
+
+                let mut recordings = self.list_recordings().await?;
+
+                let main_recording = match recordings.len() {
+                    0 => bail!("No recordings left, webinar id = {}", self.webinar.id()),
+                    1 => recordings.remove(0),
+                    _ => self.select_main_recording(recordings).await?,
+                };
+
                 let recording = {
                     let mut conn = self.ctx.get_conn().await?;
 
@@ -87,12 +115,19 @@ impl super::PostprocessingStrategy for WebinarPostprocessingStrategy {
 
                     q.execute(&mut txn).await?;
 
+                    // Only the main stream gets transcoded, the others are kept deleted.
+                    crate::db::recording::DeleteQuery::new(self.webinar.id())
+                        .except(main_recording.rtc_id())
+                        .execute(&mut txn)
+                        .await?;
+
                     let q = crate::db::recording::AdjustWebinarUpdateQuery::new(
                         self.webinar.id(),
                         modified_segments.clone(),
                     );
 
                     let recording = q.execute(&mut txn).await?;
+                    progress.save(&mut txn, &self.webinar).await?;
                     txn.commit().await?;
                     recording
                 };
@@ -116,6 +151,7 @@ impl super::PostprocessingStrategy for WebinarPostprocessingStrategy {
     async fn handle_transcoding_completion(
         &self,
         completion_result: TranscodeSuccess,
+        progress: &Progress,
     ) -> Result<()> {
         match completion_result {
             TranscodeSuccess::TranscodeStreamToHls(TranscodeStreamToHlsSuccess {
@@ -126,69 +162,80 @@ impl super::PostprocessingStrategy for WebinarPostprocessingStrategy {
             }) => {
                 let stream_duration = stream_duration.parse::<f64>()?.round() as u64;
 
-                {
-                    let mut conn = self.ctx.get_conn().await?;
+                self.complete_transcoding(
+                    WebinarReady {
+                        tags: self.webinar.tags().map(ToOwned::to_owned),
+                        stream_duration,
+                        stream_uri: Some(stream_uri),
+                        stream_id: Some(stream_id),
+                        status: "success",
+                        scope: self.webinar.scope().to_owned(),
+                        id: self.webinar.id(),
+                        event_room_id,
+                    },
+                    progress,
+                )
+                .await
+            }
+            TranscodeSuccess::TranscodeMinigroupToHls(TranscodeMinigroupToHlsSuccess {
+                recording_duration,
+            }) => {
+                let stream_duration = recording_duration.parse::<f64>()?.round() as u64;
+                let event_room_id = self.webinar.modified_event_room_id().ok_or_else(|| {
+                    anyhow!("Not adjusted yet, webinar id = {}", self.webinar.id())
+                })?;
 
-                    crate::db::recording::TranscodingUpdateQuery::new(self.webinar.id())
-                        .execute(&mut conn)
-                        .await?;
-                }
+                self.complete_transcoding(
+                    WebinarReady {
+                        tags: self.webinar.tags().map(ToOwned::to_owned),
+                        stream_duration,
+                        stream_uri: None,
+                        stream_id: None,
+                        status: "success",
+                        scope: self.webinar.scope().to_owned(),
+                        id: self.webinar.id(),
+                        event_room_id,
+                    },
+                    progress,
+                )
+                .await
+            }
+        }
+    }
 
-                let timing = ShortTermTimingProperties::new(Utc::now());
-                let props = OutgoingEventProperties::new("webinar.ready", timing);
-                let path = format!("audiences/{}/events", self.webinar.audience());
-
-                let payload = WebinarReady {
-                    tags: self.webinar.tags().map(ToOwned::to_owned),
-                    stream_duration,
-                    stream_uri,
-                    stream_id,
-                    status: "success",
-                    scope: self.webinar.scope().to_owned(),
-                    id: self.webinar.id(),
-                    event_room_id,
-                };
+    async fn handle_stream_upload(
+        &self,
+        stream: UploadedStream,
+        progress: &Progress,
+    ) -> Result<()> {
+        shared_helpers::save_stream_upload(self.ctx.as_ref(), &self.webinar, stream, progress)
+            .await?;
 
-                let event = OutgoingEvent::broadcast(payload, props, &path);
-                let boxed_event = Box::new(event) as Box<dyn IntoPublishableMessage + Send>;
+        let mut recordings = self.list_recordings().await?;
 
-                self.ctx
-                    .publisher()
-                    .publish(boxed_event)
-                    .context("Failed to publish webinar.ready event")
-            }
-            TranscodeSuccess::TranscodeMinigroupToHls(result) => {
-                bail!(
-                    "Got transcoding success for an unexpected tq template; expected transcode-stream-to-hls for a webinar, id = {}, result = {:?}",
-                    self.webinar.id(),
-                    result,
-                );
-            }
+        // Wait for the rest of the publishers' streams.
+        if recordings
+            .iter()
+            .any(|recording| ReadyRecording::from_db_object(recording).is_none())
+        {
+            return Ok(());
         }
-    }
 
-    async fn handle_stream_upload(&self, stream: UploadedStream) -> Result<()> {
-        let rtc = {
-            let mut conn = self.ctx.get_conn().await?;
-            let parsed_data = stream.parsed_data?;
-            crate::db::recording::StreamUploadUpdateQuery::new(
-                self.webinar.id(),
-                stream.id,
-                parsed_data.segments,
-                parsed_data.uri,
-                parsed_data.started_at,
-            )
-            .execute(&mut conn)
-            .await?
+        let main_recording = match recordings.len() {
+            0 => bail!("No recordings left, webinar id = {}", self.webinar.id()),
+            1 => recordings.remove(0),
+            _ => self.select_main_recording(recordings).await?,
         };
 
         self.ctx
             .event_client()
             .adjust_room(
                 self.webinar.event_room_id(),
-                rtc.started_at()
+                main_recording
+                    .started_at()
                     .ok_or_else(|| anyhow!("Missing started at after upload"))?,
-                rtc.segments()
+                main_recording
+                    .segments()
                     .ok_or_else(|| anyhow!("Missing segments after upload"))?
                     .clone(),
                 self.ctx.get_preroll_offset(self.webinar.audience()),
@@ -198,6 +245,128 @@ impl super::PostprocessingStrategy for WebinarPostprocessingStrategy {
     }
 }
 
+impl WebinarPostprocessingStrategy {
+    fn mode(&self) -> WebinarRecordingsMode {
+        self.ctx
+            .config()
+            .webinar_postprocessing
+            .mode(self.webinar.audience())
+    }
+
+    async fn list_recordings(&self) -> Result<Vec<Recording>> {
+        let mut conn = self.ctx.get_conn().await?;
+        crate::db::recording::RecordingListQuery::new(self.webinar.id())
+            .execute(&mut conn)
+            .await
+            .context("Failed to list webinar recordings")
+    }
+
+    /// Picks the host's recording to adjust the room against, the earliest one when the host
+    /// has several.
+    async fn select_main_recording(&self, recordings: Vec<Recording>) -> Result<Recording> {
+        let (host, _selection) = select_host(
+            &self.ctx,
+            &self.webinar,
+            self.webinar.event_room_id(),
+            &recordings,
+        )
+        .await?
+        .ok_or_else(|| anyhow!("No host recording, webinar id = {}", self.webinar.id()))?;
+
+        recordings
+            .into_iter()
+            .filter(|recording| *recording.created_by() == host)
+            .min_by_key(|recording| (recording.started_at(), recording.rtc_id()))
+            .ok_or_else(|| anyhow!("No host recording, webinar id = {}", self.webinar.id()))
+    }
+
+    async fn handle_composite_adjust(
+        &self,
+        recordings: Vec<Recording>,
+        original_room_id: Uuid,
+        modified_room_id: Uuid,
+        cut_original_segments: Segments,
+        progress: &Progress,
+    ) -> Result<()> {
+        let (host, host_selection) =
+            select_host(&self.ctx, &self.webinar, modified_room_id, &recordings)
+                .await?
+                .ok_or_else(|| anyhow!("No host recording, webinar id = {}", self.webinar.id()))?;
+
+        let recordings = {
+            let mut conn = self.ctx.get_conn().await?;
+
+            let mut txn = conn
+                .begin()
+                .await
+                .context("Failed to begin sqlx db transaction")?;
+
+            crate::db::class::UpdateAdjustedRoomsQuery::new(
+                self.webinar.id(),
+                original_room_id,
+                modified_room_id,
+            )
+            .execute(&mut txn)
+            .await?;
+
+            let recordings = crate::db::recording::AdjustMinigroupUpdateQuery::new(
+                self.webinar.id(),
+                cut_original_segments,
+                host.clone(),
+            )
+            .host_selection(host_selection)
+            .execute(&mut txn)
+            .await?;
+
+            progress.save(&mut txn, &self.webinar).await?;
+            txn.commit().await?;
+
+            recordings
+        };
+
+        send_composite_transcoding_task(
+            &self.ctx,
+            &self.webinar,
+            recordings,
+            &host,
+            modified_room_id,
+            Priority::Normal,
+        )
+        .await
+    }
+
+    async fn complete_transcoding(&self, payload: WebinarReady, progress: &Progress) -> Result<()> {
+        let path = format!("audiences/{}/events", self.webinar.audience());
+
+        let event = {
+            let mut conn = self.ctx.get_conn().await?;
+            let mut txn = conn.begin().await?;
+
+            crate::db::recording::TranscodingUpdateQuery::new(self.webinar.id())
+                .execute(&mut txn)
+                .await?;
+
+            let event = outbox::push(&mut txn, "webinar.ready", &path, &payload).await?;
+            progress.save(&mut txn, &self.webinar).await?;
+            txn.commit().await?;
+            event
+        };
+
+        outbox::publish(self.ctx.as_ref(), vec![event]).await;
+        Ok(())
+    }
+}
+
+async fn select_host(
+    ctx: &Arc<dyn AppContext>,
+    webinar: &Class,
+    event_room_id: Uuid,
+    recordings: &[Recording],
+) -> Result<Option<(AgentId, HostSelection)>> {
+    let fallback = &ctx.config().webinar_postprocessing.host_fallback;
+    shared_helpers::select_host(ctx, webinar, event_room_id, recordings, fallback).await
+}
+
 pub async fn restart_transcoding(
     ctx: Arc<dyn AppContext>,
     webinar: Class,
@@ -212,10 +381,30 @@ pub async fn restart_transcoding(
         None => bail!("Not adjusted yet"),
     };
 
-    let mut conn = ctx.get_conn().await?;
-    let recordings = crate::db::recording::RecordingListQuery::new(webinar.id())
-        .execute(&mut conn)
-        .await?;
+    let recordings = {
+        let mut conn = ctx.get_conn().await?;
+        crate::db::recording::RecordingListQuery::new(webinar.id())
+            .execute(&mut conn)
+            .await?
+    };
+
+    let mode = ctx.config().webinar_postprocessing.mode(webinar.audience());
+
+    if mode == WebinarRecordingsMode::Composite {
+        let (host, _selection) = select_host(&ctx, &webinar, modified_event_room_id, &recordings)
+            .await?
+            .ok_or_else(|| anyhow!("No host recording, webinar id = {}", webinar.id()))?;
+
+        return send_composite_transcoding_task(
+            &ctx,
+            &webinar,
+            recordings,
+            &host,
+            modified_event_room_id,
+            priority,
+        )
+        .await;
+    }
 
     for recording in recordings {
         send_transcoding_task(&ctx, &webinar, recording, modified_event_room_id, priority).await?;
@@ -236,24 +425,21 @@ async fn send_transcoding_task(
         .await
         .context("Dump room event failed")?;
 
-    ctx.tq_client()
-        .create_task(
-            webinar,
-            TqTask::TranscodeStreamToHls {
-                stream_id: recording.rtc_id(),
-                stream_uri: recording
-                    .stream_uri()
-                    .ok_or_else(|| {
-                        anyhow!("Missing stream_uri in adjust for {}", recording.rtc_id())
-                    })?
-                    .clone(),
-                event_room_id: Some(modified_event_room_id),
-                segments: recording.modified_segments().cloned(),
-            },
-            priority,
-        )
-        .await
-        .context("TqClient create task failed")
+    shared_helpers::create_tq_task(
+        ctx.as_ref(),
+        webinar,
+        TqTask::TranscodeStreamToHls {
+            stream_id: recording.rtc_id(),
+            stream_uri: recording
+                .stream_uri()
+                .ok_or_else(|| anyhow!("Missing stream_uri in adjust for {}", recording.rtc_id()))?
+                .clone(),
+            event_room_id: Some(modified_event_room_id),
+            segments: recording.modified_segments().cloned(),
+        },
+        priority,
+    )
+    .await
 }
 
 #[derive(Serialize)]
@@ -262,9 +448,474 @@ struct WebinarReady {
     tags: Option<JsonValue>,
     status: &'static str,
     stream_duration: u64,
-    stream_id: Uuid,
-    stream_uri: String,
+    // Composed streams of several publishers have neither.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_uri: Option<String>,
     scope: String,
     id: Uuid,
     event_room_id: Uuid,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use chrono::{DateTime, Duration};
+    use mockall::predicate as pred;
+
+    use super::super::shared_helpers::HOST_EVENT_TYPE;
+    use super::super::{MjrDumpsUploadReadyData, PostprocessingStrategy, StreamData};
+    use super::*;
+    use crate::clients::event::test_helpers::EventBuilder;
+    use crate::clients::event::{EventData, EventRoomResponse, HostEventData};
+    use crate::clients::ClientError;
+    use crate::db::class::ClassStatus;
+    use crate::db::postprocessing_job::UpsertQuery;
+    use crate::db::recording::RecordingListQuery;
+    use crate::test_helpers::prelude::*;
+
+    async fn insert_webinar(state: &TestState, event_room_id: Uuid) -> Class {
+        let mut conn = state.get_conn().await.expect("Failed to get conn");
+        factory::Webinar::new(
+            random_string(),
+            USR_AUDIENCE.to_string(),
+            (Bound::Unbounded, Bound::Unbounded).into(),
+            Uuid::new_v4(),
+            event_room_id,
+        )
+        .insert(&mut conn)
+        .await
+    }
+
+    fn host_events(event_room_id: Uuid, host: &TestAgent) -> Vec<crate::clients::event::Event> {
+        vec![EventBuilder::new()
+            .room_id(event_room_id)
+            .set(HOST_EVENT_TYPE.to_string())
+            .data(EventData::Host(HostEventData::new(
+                host.agent_id().to_owned(),
+            )))
+            .occurred_at(0)
+            .build()]
+    }
+
+    #[tokio::test]
+    async fn handle_mjr_dumps_upload_of_several_publishers() {
+        let mut state = TestState::new(TestAuthz::new()).await;
+        let webinar = insert_webinar(&state, Uuid::new_v4()).await;
+        let webinar_id = webinar.id();
+
+        state
+            .tq_client_mock()
+            .expect_create_task()
+            .times(2)
+            .returning(|_, _, _, _| Ok(()));
+
+        let dumps = ["user1", "user2"]
+            .iter()
+            .map(|user| {
+                MjrDumpsUploadResult::Ready(MjrDumpsUploadReadyData {
+                    id: Uuid::new_v4(),
+                    created_by: TestAgent::new("web", user, USR_AUDIENCE)
+                        .agent_id()
+                        .to_owned(),
+                    uri: format!("s3://webinar.origin.dev.example.com/{}.webm", user),
+                    mjr_dumps_uris: vec![format!(
+                        "s3://webinar.origin.dev.example.com/{}.mjr",
+                        user
+                    )],
+                })
+            })
+            .collect();
+
+        let state = Arc::new(state);
+
+        WebinarPostprocessingStrategy::new(state.clone(), webinar)
+            .handle_mjr_dumps_upload(dumps, &Progress::new(ClassStatus::Finished))
+            .await
+            .expect("Failed to handle upload");
+
+        let mut conn = state.get_conn().await.expect("Failed to get conn");
+        let recordings = RecordingListQuery::new(webinar_id)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to list recordings");
+        assert_eq!(recordings.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn handle_mjr_dumps_upload_leaves_failed_tasks_to_retries() {
+        let mut state = TestState::new(TestAuthz::new()).await;
+        let webinar = insert_webinar(&state, Uuid::new_v4()).await;
+        let webinar_id = webinar.id();
+
+        state
+            .tq_client_mock()
+            .expect_create_task()
+            .times(2)
+            .returning(|_, _, _, _| Err(ClientError::Http("Unavailable".to_owned())));
+
+        let dumps = ["user1", "user2"]
+            .iter()
+            .map(|user| {
+                MjrDumpsUploadResult::Ready(MjrDumpsUploadReadyData {
+                    id: Uuid::new_v4(),
+                    created_by: TestAgent::new("web", user, USR_AUDIENCE)
+                        .agent_id()
+                        .to_owned(),
+                    uri: format!("s3://webinar.origin.dev.example.com/{}.webm", user),
+                    mjr_dumps_uris: vec![format!(
+                        "s3://webinar.origin.dev.example.com/{}.mjr",
+                        user
+                    )],
+                })
+            })
+            .collect();
+
+        let state = Arc::new(state);
+
+        WebinarPostprocessingStrategy::new(state.clone(), webinar)
+            .handle_mjr_dumps_upload(dumps, &Progress::new(ClassStatus::Finished))
+            .await
+            .expect("Failed to handle upload");
+
+        // Every dump's job is scheduled for a retry rather than the first failure skipping the rest.
+        let mut conn = state.get_conn().await.expect("Failed to get conn");
+        let jobs = crate::db::postprocessing_job::ListQuery::new(webinar_id)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to list postprocessing jobs");
+        assert_eq!(jobs.len(), 2);
+        assert!(jobs.iter().all(|job| job.retry_at().is_some()));
+    }
+
+    #[tokio::test]
+    async fn handle_stream_upload_adjusts_against_main_stream() {
+        let now = Utc::now();
+        let agent1 = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let agent2 = TestAgent::new("web", "user2", USR_AUDIENCE);
+        let mut state = TestState::new(TestAuthz::new()).await;
+        let event_room_id = Uuid::new_v4();
+        let webinar = insert_webinar(&state, event_room_id).await;
+        let webinar_id = webinar.id();
+        let rtc2_id = Uuid::new_v4();
+        let started_at2: DateTime<Utc> = now - Duration::minutes(50);
+        let segments: Segments = vec![(Bound::Included(0), Bound::Excluded(2_700_000))].into();
+
+        {
+            let mut conn = state.get_conn().await.expect("Failed to get conn");
+
+            factory::Recording::new(webinar_id, Uuid::new_v4(), agent1.agent_id().to_owned())
+                .stream_uri("s3://webinar.origin.dev.example.com/rtc1.webm".to_string())
+                .segments(segments.clone())
+                .started_at(now - Duration::hours(1))
+                .insert(&mut conn)
+                .await;
+
+            factory::Recording::new(webinar_id, rtc2_id, agent2.agent_id().to_owned())
+                .insert(&mut conn)
+                .await;
+
+            for stream_id in [rtc2_id, Uuid::new_v4()] {
+                UpsertQuery::new(
+                    webinar_id,
+                    "convert-mjr-dumps-to-stream".to_owned(),
+                    Some(stream_id),
+                    Priority::Normal,
+                )
+                .execute(&mut conn)
+                .await
+                .expect("Failed to insert postprocessing job");
+            }
+        }
+
+        let host = agent2.clone();
+        state
+            .event_client_mock()
+            .expect_list_events()
+            .with(pred::eq(event_room_id), pred::eq(HOST_EVENT_TYPE))
+            .returning(move |room_id, _| Ok(host_events(room_id, &host)));
+
+        let expected_segments = segments.clone();
+        state
+            .event_client_mock()
+            .expect_adjust_room()
+            .withf(move |room_id, started_at, segments, _offset| {
+                *room_id == event_room_id
+                    && started_at.timestamp_millis() == started_at2.timestamp_millis()
+                    && *segments == expected_segments
+            })
+            .returning(|_, _, _, _| Ok(()));
+
+        let state = Arc::new(state);
+
+        WebinarPostprocessingStrategy::new(state.clone(), webinar)
+            .handle_stream_upload(
+                UploadedStream {
+                    id: rtc2_id,
+                    parsed_data: Ok(StreamData {
+                        uri: "s3://webinar.origin.dev.example.com/rtc2.webm".to_string(),
+                        started_at: started_at2,
+                        segments,
+                    }),
+                },
+                &Progress::new(ClassStatus::Finished)
+                    .completes_job("convert-mjr-dumps-to-stream".to_owned(), Some(rtc2_id)),
+            )
+            .await
+            .expect("Failed to handle upload");
+
+        // Co-presenters' streams are kept until the adjust succeeds.
+        let mut conn = state.get_conn().await.expect("Failed to get conn");
+        let recordings = RecordingListQuery::new(webinar_id)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to list recordings");
+        assert_eq!(recordings.len(), 2);
+
+        let statuses = sqlx::query_scalar::<_, String>(
+            "SELECT status::text FROM postprocessing_job WHERE class_id = $1 ORDER BY stream_id = $2",
+        )
+        .bind(webinar_id)
+        .bind(rtc2_id)
+        .fetch_all(&mut conn)
+        .await
+        .expect("Failed to list postprocessing jobs");
+        assert_eq!(statuses, vec!["pending", "succeeded"]);
+    }
+
+    #[tokio::test]
+    async fn main_stream_of_host_with_two_recordings_is_the_earliest_one() {
+        let now = Utc::now();
+        let host = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let mut state = TestState::new(TestAuthz::new()).await;
+        let event_room_id = Uuid::new_v4();
+        let modified_event_room_id = Uuid::new_v4();
+        let webinar = insert_webinar(&state, event_room_id).await;
+        let webinar_id = webinar.id();
+        let rtc1_id = Uuid::new_v4();
+        let rtc2_id = Uuid::new_v4();
+        let started_at1: DateTime<Utc> = now - Duration::hours(1);
+        let segments1: Segments = vec![(Bound::Included(0), Bound::Excluded(600_000))].into();
+        let segments2: Segments = vec![(Bound::Included(0), Bound::Excluded(1_800_000))].into();
+
+        {
+            let mut conn = state.get_conn().await.expect("Failed to get conn");
+
+            factory::Recording::new(webinar_id, rtc1_id, host.agent_id().to_owned())
+                .stream_uri("s3://webinar.origin.dev.example.com/rtc1.webm".to_string())
+                .segments(segments1.clone())
+                .started_at(started_at1)
+                .insert(&mut conn)
+                .await;
+
+            factory::Recording::new(webinar_id, rtc2_id, host.agent_id().to_owned())
+                .insert(&mut conn)
+                .await;
+        }
+
+        let host_agent = host.clone();
+        state
+            .event_client_mock()
+            .expect_list_events()
+            .with(pred::eq(event_room_id), pred::eq(HOST_EVENT_TYPE))
+            .returning(move |room_id, _| Ok(host_events(room_id, &host_agent)));
+
+        let expected_segments = segments1.clone();
+        state
+            .event_client_mock()
+            .expect_adjust_room()
+            .withf(move |room_id, started_at, segments, _offset| {
+                *room_id == event_room_id
+                    && started_at.timestamp_millis() == started_at1.timestamp_millis()
+                    && *segments == expected_segments
+            })
+            .times(1)
+            .returning(|_, _, _, _| Ok(()));
+
+        state
+            .event_client_mock()
+            .expect_dump_room()
+            .with(pred::eq(modified_event_room_id))
+            .returning(|_| Ok(()));
+
+        // The main stream gets transcoded alone rather than composed with the host's other one.
+        state
+            .tq_client_mock()
+            .expect_create_task()
+            .withf(move |_class, task, _priority, _job| {
+                matches!(
+                    task,
+                    TqTask::TranscodeStreamToHls { stream_id, .. } if *stream_id == rtc1_id
+                )
+            })
+            .times(1)
+            .returning(|_, _, _, _| Ok(()));
+
+        let state = Arc::new(state);
+        let strategy = WebinarPostprocessingStrategy::new(state.clone(), webinar);
+
+        strategy
+            .handle_stream_upload(
+                UploadedStream {
+                    id: rtc2_id,
+                    parsed_data: Ok(StreamData {
+                        uri: "s3://webinar.origin.dev.example.com/rtc2.webm".to_string(),
+                        started_at: now - Duration::minutes(30),
+                        segments: segments2,
+                    }),
+                },
+                &Progress::new(ClassStatus::Finished),
+            )
+            .await
+            .expect("Failed to handle upload");
+
+        strategy
+            .handle_adjust(
+                RoomAdjustResult::Success {
+                    original_room_id: event_room_id,
+                    modified_room_id: modified_event_room_id,
+                    modified_segments: segments1.clone(),
+                    cut_original_segments: segments1,
+                },
+                &Progress::new(ClassStatus::Adjusted),
+            )
+            .await
+            .expect("Failed to handle adjust");
+
+        let mut conn = state.get_conn().await.expect("Failed to get conn");
+        let recordings = RecordingListQuery::new(webinar_id)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to list recordings");
+        assert_eq!(recordings.len(), 1);
+        assert_eq!(recordings[0].rtc_id(), rtc1_id);
+
+        // The other session is soft-deleted rather than dropped.
+        let deleted = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM recording WHERE class_id = $1 AND deleted_at IS NOT NULL",
+        )
+        .bind(webinar_id)
+        .fetch_one(&mut conn)
+        .await
+        .expect("Failed to count deleted recordings");
+        assert_eq!(deleted, 1);
+    }
+
+    #[tokio::test]
+    async fn handle_adjust_composes_several_publishers() {
+        let now = Utc::now();
+        let agent1 = TestAgent::new("web", "user1", USR_AUDIENCE);
+        let agent2 = TestAgent::new("web", "user2", USR_AUDIENCE);
+        let mut state = TestState::new(TestAuthz::new()).await;
+        state.set_webinar_recordings_mode(USR_AUDIENCE, WebinarRecordingsMode::Composite);
+        let modified_event_room_id = Uuid::new_v4();
+        let webinar = insert_webinar(&state, Uuid::new_v4()).await;
+        let webinar_id = webinar.id();
+        let segments: Segments = vec![(Bound::Included(0), Bound::Excluded(2_700_000))].into();
+        let cut_original_segments: Segments =
+            vec![(Bound::Included(3_000), Bound::Excluded(2_700_000))].into();
+
+        let host_recording = {
+            let mut conn = state.get_conn().await.expect("Failed to get conn");
+
+            let host_recording =
+                factory::Recording::new(webinar_id, Uuid::new_v4(), agent1.agent_id().to_owned())
+                    .stream_uri("s3://webinar.origin.dev.example.com/rtc1.webm".to_string())
+                    .segments(segments.clone())
+                    .started_at(now - Duration::hours(1))
+                    .insert(&mut conn)
+                    .await;
+
+            factory::Recording::new(webinar_id, Uuid::new_v4(), agent2.agent_id().to_owned())
+                .stream_uri("s3://webinar.origin.dev.example.com/rtc2.webm".to_string())
+                .segments(segments.clone())
+                .started_at(now - Duration::minutes(50))
+                .insert(&mut conn)
+                .await;
+
+            host_recording
+        };
+
+        let host = agent1.clone();
+        state
+            .event_client_mock()
+            .expect_list_events()
+            .returning(move |room_id, kind| match kind {
+                HOST_EVENT_TYPE => Ok(host_events(room_id, &host)),
+                _ => Ok(vec![]),
+            });
+
+        state
+            .event_client_mock()
+            .expect_read_room()
+            .with(pred::eq(modified_event_room_id))
+            .returning(move |room_id| {
+                Ok(EventRoomResponse {
+                    id: room_id,
+                    time: (Bound::Included(now - Duration::hours(1)), Bound::Unbounded),
+                    tags: None,
+                })
+            });
+
+        state
+            .event_client_mock()
+            .expect_dump_room()
+            .with(pred::eq(modified_event_room_id))
+            .returning(|_| Ok(()));
+
+        state
+            .conference_client_mock()
+            .expect_read_config_snapshots()
+            .returning(|_| Ok(vec![]));
+
+        let host_rtc_id = host_recording.rtc_id();
+        state
+            .tq_client_mock()
+            .expect_create_task()
+            .withf(move |_class, task, _priority, _job| {
+                matches!(
+                    task,
+                    TqTask::TranscodeMinigroupToHls { streams, host_stream_id }
+                        if streams.len() == 2 && *host_stream_id == host_rtc_id
+                )
+            })
+            .returning(|_, _, _, _| Ok(()));
+
+        let state = Arc::new(state);
+
+        WebinarPostprocessingStrategy::new(state.clone(), webinar)
+            .handle_adjust(
+                RoomAdjustResult::Success {
+                    original_room_id: Uuid::new_v4(),
+                    modified_room_id: modified_event_room_id,
+                    modified_segments: segments,
+                    cut_original_segments,
+                },
+                &Progress::new(ClassStatus::Adjusted),
+            )
+            .await
+            .expect("Failed to handle adjust");
+
+        let mut conn = state.get_conn().await.expect("Failed to get conn");
+        let recordings = RecordingListQuery::new(webinar_id)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to list recordings");
+        assert_eq!(recordings.len(), 2);
+
+        for recording in recordings {
+            let expected = (recording.rtc_id() == host_rtc_id).then_some(HostSelection::HostEvent);
+            assert_eq!(recording.host_selection(), expected);
+        }
+
+        // The status is saved along with the adjusted recordings.
+        let webinar = crate::db::class::ReadQuery::by_id(webinar_id)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to read webinar")
+            .expect("Webinar not found");
+        assert_eq!(webinar.status(), ClassStatus::Adjusted);
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use serde_derive::Serialize;
+use svc_authz::cache::ConnectionPool;
+use utoipa::ToSchema;
+
+use crate::app::AppContext;
+use crate::config::ReadinessConfig;
+
+/// State of the dependencies the pod can't serve without.
+#[derive(Clone)]
+pub struct Readiness {
+    mqtt_connected: Arc<AtomicBool>,
+    authz_cache: Option<ConnectionPool>,
+    http_client: reqwest::Client,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(as = ReadinessReport)]
+pub struct Report {
+    ready: bool,
+    #[schema(value_type = BTreeMap<String, ReadinessComponent>)]
+    components: BTreeMap<&'static str, Component>,
+}
+
+impl Report {
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(as = ReadinessComponent)]
+pub struct Component {
+    #[schema(inline)]
+    status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Up,
+    Down,
+}
+
+impl Readiness {
+    pub fn new(authz_cache: Option<ConnectionPool>, config: &ReadinessConfig) -> Self {
+        let http_client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .expect("Failed to build readiness http client");
+
+        Self {
+            mqtt_connected: Arc::new(AtomicBool::new(false)),
+            authz_cache,
+            http_client,
+        }
+    }
+
+    pub fn set_mqtt_connected(&self, connected: bool) {
+        self.mqtt_connected.store(connected, Ordering::SeqCst);
+    }
+
+    pub async fn check(&self, ctx: &dyn AppContext) -> Report {
+        let config = &ctx.config().readiness;
+        let mut components = BTreeMap::new();
+
+        components.insert("db", component(with_timeout(config, check_db(ctx)).await));
+        components.insert("mqtt", component(self.check_mqtt()));
+
+        if config.check_tq {
+            let url = ctx.config().tq_client.base_url.clone();
+            components.insert("tq", component(self.check_http(&url).await));
+        }
+
+        if config.check_authz_cache {
+            if let Some(pool) = &self.authz_cache {
+                let check = check_authz_cache(pool.clone());
+                components.insert("authz_cache", component(with_timeout(config, check).await));
+            }
+        }
+
+        let ready = components.values().all(|c| c.status == Status::Up);
+        Report { ready, components }
+    }
+
+    fn check_mqtt(&self) -> Result<()> {
+        if self.mqtt_connected.load(Ordering::SeqCst) {
+            Ok(())
+        } else {
+            Err(anyhow!("Not connected to broker"))
+        }
+    }
+
+    // Any response means the service is there, it's up to the service whether the root is served.
+    async fn check_http(&self, url: &str) -> Result<()> {
+        self.http_client
+            .get(url)
+            .send()
+            .await
+            .context("Request failed")?;
+
+        Ok(())
+    }
+}
+
+async fn check_db(ctx: &dyn AppContext) -> Result<()> {
+    ctx.get_conn().await?;
+    Ok(())
+}
+
+// The pool pings redis when a connection is checked out.
+async fn check_authz_cache(pool: ConnectionPool) -> Result<()> {
+    tokio::task::spawn_blocking(move || pool.get().map(|_| ()))
+        .await
+        .context("Authz cache check panicked")?
+        .context("Failed to get authz cache connection")
+}
+
+async fn with_timeout<F>(config: &ReadinessConfig, check: F) -> Result<()>
+where
+    F: Future<Output = Result<()>>,
+{
+    tokio::time::timeout(config.timeout, check)
+        .await
+        .unwrap_or_else(|_| Err(anyhow!("Timed out")))
+}
+
+fn component(result: Result<()>) -> Component {
+    match result {
+        Ok(()) => Component {
+            status: Status::Up,
+            error: None,
+        },
+        Err(e) => Component {
+            status: Status::Down,
+            error: Some(format!("{:#}", e)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value as JsonValue;
+
+    use super::*;
+    use crate::test_helpers::prelude::*;
+
+    #[tokio::test]
+    async fn ready_once_mqtt_is_connected() {
+        let state = TestState::new(TestAuthz::new()).await;
+        let readiness = Readiness::new(None, &state.config().readiness);
+
+        let report = readiness.check(&state).await;
+        assert!(!report.is_ready());
+
+        let v = serde_json::to_value(&report).unwrap();
+        assert_eq!(v["components"]["db"]["status"], "up");
+        assert_eq!(v["components"]["mqtt"]["status"], "down");
+        assert_eq!(v["components"]["mqtt"]["error"], "Not connected to broker");
+        assert_eq!(v["components"].get("tq"), None);
+
+        readiness.set_mqtt_connected(true);
+
+        let report = readiness.check(&state).await;
+        assert!(report.is_ready());
+
+        let v = serde_json::to_value(&report).unwrap();
+        assert_eq!(v["ready"], JsonValue::Bool(true));
+        assert_eq!(v["components"]["mqtt"]["status"], "up");
+    }
+}
@@ -0,0 +1,162 @@
+//! Canary rollouts of frontends. A rollout sends a percent of the scopes of a tenant app that
+//! have no frontend bound to them to another frontend. Scopes are split by their hash so that
+//! each of them always lands on the same frontend and raising the percent only adds scopes.
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use sqlx::PgConnection;
+use url::Url;
+
+use crate::db::frontend::FrontendByScopeQuery;
+use crate::db::frontend_rollout::TargetQuery;
+
+/// Where the frontend a redirect goes to comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Scope,
+    Rollout,
+    Default,
+}
+
+impl Source {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Source::Scope => "scope",
+            Source::Rollout => "rollout",
+            Source::Default => "default",
+        }
+    }
+}
+
+/// The frontend bound to the scope or the one rolled out to it, if any.
+pub async fn find_frontend(
+    conn: &mut PgConnection,
+    tenant: &str,
+    app: &str,
+    scope: &str,
+) -> Result<Option<(Source, Url)>> {
+    let bound = FrontendByScopeQuery::new(scope.to_owned(), app.to_owned())
+        .execute(conn)
+        .await
+        .context("Failed to find frontend by scope")?;
+
+    if let Some(url) = bound.and_then(|frontend| Url::parse(&frontend.url).ok()) {
+        return Ok(Some((Source::Scope, url)));
+    }
+
+    let target = TargetQuery::new(tenant.to_owned(), app.to_owned())
+        .execute(conn)
+        .await
+        .context("Failed to find frontend rollout")?;
+
+    let url = target
+        .filter(|target| includes(scope, target.percent))
+        .and_then(|target| Url::parse(&target.url).ok())
+        .map(|url| (Source::Rollout, url));
+
+    Ok(url)
+}
+
+/// Whether the scope falls into the first `percent` of the scopes.
+pub fn includes(scope: &str, percent: i16) -> bool {
+    i16::from(bucket(scope)) < percent
+}
+
+// Sha256 rather than the std hasher since the latter may change between Rust releases
+// and replicas built with different ones would disagree.
+fn bucket(scope: &str) -> u8 {
+    let hash = Sha256::digest(scope.as_bytes());
+
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&hash[..8]);
+
+    (u64::from_be_bytes(bytes) % 100) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::frontend_rollout::UpsertQuery;
+    use crate::test_helpers::prelude::*;
+
+    #[test]
+    fn splits_scopes_by_percent() {
+        let scopes = (0..10_000)
+            .map(|i| format!("scope-{}", i))
+            .collect::<Vec<_>>();
+        let included = |percent| scopes.iter().filter(|s| includes(s, percent)).count();
+
+        assert_eq!(included(0), 0);
+        assert_eq!(included(100), scopes.len());
+
+        let ten = included(10);
+        assert!((800..1200).contains(&ten), "{} scopes of 10000", ten);
+    }
+
+    #[test]
+    fn keeps_rolled_out_scopes_when_percent_grows() {
+        for i in 0..1000 {
+            let scope = format!("scope-{}", i);
+
+            if includes(&scope, 10) {
+                assert!(includes(&scope, 50));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn prefers_scope_binding_to_rollout() {
+        let db = TestDb::new().await;
+        let mut conn = db.get_conn().await;
+        let tenant = random_string();
+
+        let bound = factory::Frontend::new("https://bound.example.org".into())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to seed frontend");
+        let canary = factory::Frontend::new("https://canary.example.org".into())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to seed frontend");
+
+        let rollout =
+            |percent| UpsertQuery::new(tenant.clone(), "webinar".into(), canary.id, percent);
+
+        rollout(100)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to insert rollout");
+
+        let scope = random_string();
+        let found = find_frontend(&mut conn, &tenant, "webinar", &scope)
+            .await
+            .expect("Failed to find frontend");
+        assert_eq!(
+            found,
+            Some((Source::Rollout, Url::parse(&canary.url).unwrap()))
+        );
+
+        factory::Scope::new(scope.clone(), bound.id, "webinar".into())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to seed scope");
+
+        let found = find_frontend(&mut conn, &tenant, "webinar", &scope)
+            .await
+            .expect("Failed to find frontend");
+        assert_eq!(
+            found,
+            Some((Source::Scope, Url::parse(&bound.url).unwrap()))
+        );
+
+        rollout(0)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to update rollout");
+
+        let found = find_frontend(&mut conn, &tenant, "webinar", &random_string())
+            .await
+            .expect("Failed to find frontend");
+        assert_eq!(found, None);
+    }
+}
@@ -2,18 +2,24 @@ use std::ops::Bound;
 
 use anyhow::Context;
 use chrono::Utc;
+use serde_derive::Serialize;
+use serde_json::Value as JsonValue;
+use sqlx::postgres::PgConnection;
 use tracing::error;
 use uuid::Uuid;
 
 use crate::app::api::v1::AppError;
 use crate::app::error::ErrorExt;
 use crate::app::error::ErrorKind as AppErrorKind;
+use crate::app::outbox;
 use crate::app::AppContext;
 use crate::clients::event::{EventRoomCreatePayload, LockedTypes};
 use crate::clients::{
     conference::RoomUpdate as ConfRoomUpdate, event::RoomUpdate as EventRoomUpdate,
 };
-use crate::db::class::{BoundedDateTimeTuple, ClassType, RtcSharingPolicy};
+use crate::db::class::{
+    BoundedDateTimeTuple, ClassStatus, ClassType, Object as Class, RtcSharingPolicy,
+};
 
 pub async fn update_classroom_id(
     state: &dyn AppContext,
@@ -125,3 +131,56 @@ pub async fn lock_interaction(state: &dyn AppContext, event_room_id: Uuid, types
         );
     }
 }
+
+/// Cuts the class time at the current moment and moves it to the closed status.
+pub async fn close_class(
+    conn: &mut PgConnection,
+    class: &Class,
+    timed_out: bool,
+) -> anyhow::Result<Class> {
+    let class = crate::db::class::RoomCloseQuery::new(class.id(), timed_out)
+        .execute(conn)
+        .await
+        .context("Failed to close class")?;
+
+    crate::db::class::UpdateStatusQuery::new(class.id(), ClassStatus::Closed)
+        .execute(conn)
+        .await
+        .context("Failed to update class status")?;
+
+    Ok(class)
+}
+
+/// Writes the `*.stop` event to the outbox, call it within the class closing transaction.
+pub async fn push_class_stop(
+    conn: &mut PgConnection,
+    class: &Class,
+) -> anyhow::Result<outbox::Event> {
+    let label = match class.kind() {
+        ClassType::P2P => "p2p.stop",
+        ClassType::Minigroup => "minigroup.stop",
+        ClassType::Webinar => "webinar.stop",
+    };
+
+    let path = format!("audiences/{}/events", class.audience());
+
+    outbox::push(conn, label, &path, &ClassStop::new(class)).await
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct ClassStop {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<JsonValue>,
+    scope: String,
+    id: Uuid,
+}
+
+impl ClassStop {
+    pub(crate) fn new(class: &Class) -> Self {
+        Self {
+            tags: class.tags().map(ToOwned::to_owned),
+            scope: class.scope().to_owned(),
+            id: class.id(),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use tokio::sync::Notify;
+
+/// Counts work in flight so that shutdown could wait for it to finish.
+#[derive(Clone, Default)]
+pub struct TaskTracker {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    count: AtomicUsize,
+    closed: AtomicBool,
+    notify: Notify,
+}
+
+/// Keeps the work tracked until dropped.
+pub struct TaskGuard {
+    inner: Arc<Inner>,
+}
+
+impl TaskTracker {
+    pub fn guard(&self) -> TaskGuard {
+        self.inner.count.fetch_add(1, Ordering::SeqCst);
+
+        TaskGuard {
+            inner: self.inner.clone(),
+        }
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.inner.count.load(Ordering::SeqCst)
+    }
+
+    /// Marks that no more work is expected, see [`TaskTracker::wait`].
+    pub fn close(&self) {
+        self.inner.closed.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.inner.closed.load(Ordering::SeqCst)
+    }
+
+    /// Waits until the tracker is closed and all the tracked work is done.
+    pub async fn wait(&self) {
+        loop {
+            let notified = self.inner.notify.notified();
+
+            if self.is_closed() && self.in_flight() == 0 {
+                return;
+            }
+
+            notified.await;
+        }
+    }
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        if self.inner.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.inner.notify.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn wait_returns_once_closed_and_drained() {
+        let tracker = TaskTracker::default();
+        let guard = tracker.guard();
+
+        let waiter = tokio::task::spawn({
+            let tracker = tracker.clone();
+            async move { tracker.wait().await }
+        });
+
+        tracker.close();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished());
+        assert_eq!(tracker.in_flight(), 1);
+
+        drop(guard);
+
+        tokio::time::timeout(Duration::from_secs(5), waiter)
+            .await
+            .expect("Tracker wasn't drained")
+            .unwrap();
+    }
+}
@@ -1,17 +1,20 @@
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
-use serde_derive::{Deserialize, Serialize};
+use serde_derive::Deserialize;
 use serde_json::Value as JsonValue;
-use svc_agent::mqtt::{
-    IncomingEvent, IncomingResponse, IntoPublishableMessage, OutgoingEvent,
-    OutgoingEventProperties, ShortTermTimingProperties,
-};
+use sqlx::Acquire;
+use svc_agent::mqtt::{IncomingEvent, IncomingResponse};
 use svc_agent::request::Dispatcher;
 use tracing::{debug, error, field::display, info, instrument, warn, Span};
 use uuid::Uuid;
 
 use super::AppContext;
+use crate::app::dead_letters;
+use crate::app::dedup;
+use crate::app::outbox;
+use crate::app::postprocessing_strategy::Progress;
+use crate::app::webhooks::{self, WebhookEvent};
 use crate::{
     app::error::{ErrorExt, ErrorKind as AppErrorKind},
     clients::{event::RoomAdjustResult, tq::TaskCompleteResult},
@@ -19,12 +22,21 @@ use crate::{
 };
 use crate::{
     app::metrics::MqttMetrics,
-    db::class::{ClassType, Object as Class},
+    db::class::{ClassStatus, Object as Class},
 };
 use crate::{app::postprocessing_strategy, clients::tq::TaskCompleteSuccess};
 use crate::{app::postprocessing_strategy::TranscodeSuccess, clients::event::RoomAdjust};
 use crate::{app::postprocessing_strategy::UploadedStream, clients::tq::TaskComplete};
 
+const HANDLED_LABELS: &[&str] = &[
+    "room.close",
+    "room.upload",
+    "room.adjust",
+    "task.complete",
+    "room.dump_events",
+    "edition.commit",
+];
+
 pub struct MessageHandler {
     ctx: Arc<dyn AppContext>,
     dispatcher: Arc<Dispatcher>,
@@ -61,12 +73,40 @@ impl MessageHandler {
     pub async fn handle_event(&self, data: IncomingEvent<String>, topic: String) {
         warn!("Incoming event",);
 
-        let topic = topic.split('/').collect::<Vec<&str>>();
-
         let label = data.properties().label().map(|x| x.to_owned());
+
+        let event_key = match label.as_deref() {
+            Some(label) if HANDLED_LABELS.contains(&label) => {
+                Some(dedup::event_key(label, &topic, data.payload()))
+            }
+            _ => None,
+        };
+
+        let mut claimed = false;
+
+        if let (Some(key), Some(label)) = (&event_key, label.as_deref()) {
+            match dedup::claim(self.ctx.as_ref(), key, label).await {
+                Ok(true) => claimed = true,
+                Ok(false) => {
+                    warn!("Duplicate event skipped");
+                    MqttMetrics::observe_duplicate_event(label);
+                    return;
+                }
+                // Better to process a duplicate than to lose the event.
+                Err(e) => error!("Failed to check event for duplicate, err = {:?}", e),
+            }
+        }
+
+        // Handlers consume the event so a copy is kept to store it as a dead letter on failure.
+        let dead_letter = event_key
+            .as_ref()
+            .map(|_| IncomingEvent::new(data.payload().to_owned(), data.properties().clone()));
+
+        let topic_parts = topic.split('/').collect::<Vec<&str>>();
+
         let result = match label.as_deref() {
             Some("room.close") => self
-                .handle_close(data, topic)
+                .handle_close(data, topic_parts)
                 .await
                 .error(AppErrorKind::ClassClosingFailed),
             Some("room.upload") => self
@@ -99,16 +139,73 @@ impl MessageHandler {
         if let Err(e) = result {
             error!("Event handler failed, err = {:?}", e);
             e.notify_sentry();
+
+            if let (Some(key), Some(event)) = (event_key, dead_letter) {
+                if let Err(err) = dead_letters::record(self.ctx(), &key, &event, &topic, &e).await {
+                    error!("Failed to store dead letter, err = {:?}", err);
+                }
+
+                if claimed {
+                    if let Err(err) = dedup::release(self.ctx(), &key).await {
+                        error!("Failed to release failed event, err = {:?}", err);
+                    }
+                }
+            }
         } else {
+            if let Some(key) = event_key {
+                if claimed {
+                    if let Err(e) = dedup::complete(self.ctx(), &key).await {
+                        error!("Failed to complete handled event, err = {:?}", e);
+                    }
+                }
+
+                if let Err(e) = dead_letters::resolve(self.ctx(), &key).await {
+                    error!("Failed to resolve dead letter, err = {:?}", e);
+                }
+            }
+
             info!("Event handler done")
         }
     }
 
+    /// Finds the class the event is about to handle the class events in order.
+    /// Falls back to the room id when there's no such class, `None` means the event has
+    /// nothing to do with classes.
+    pub async fn lane_key(&self, data: &IncomingEvent<String>) -> Option<Uuid> {
+        let payload = serde_json::from_str::<JsonValue>(data.payload()).ok()?;
+
+        let room_id = match data.properties().label()? {
+            "room.close" | "room.upload" => &payload["id"],
+            "room.adjust" => &payload["room_id"],
+            "task.complete" => &payload["tags"]["conference_room_id"],
+            "room.dump_events" => &payload["result"]["room_id"],
+            "edition.commit" => &payload["source_room_id"],
+            _ => return None,
+        };
+
+        let room_id = room_id.as_str()?.parse::<Uuid>().ok()?;
+
+        let class_id = match self.ctx.get_conn().await {
+            Ok(mut conn) => crate::db::class::OriginalIdByRoomQuery::new(room_id)
+                .execute(&mut conn)
+                .await
+                .map_err(Into::into),
+            Err(e) => Err(e),
+        };
+
+        match class_id {
+            Ok(class_id) => Some(class_id.unwrap_or(room_id)),
+            Err(e) => {
+                error!(%room_id, "Failed to find class by room id, err = {:?}", e);
+                Some(room_id)
+            }
+        }
+    }
+
     #[instrument(skip(self, data, topic), fields(payload_id, class_id))]
     async fn handle_close(&self, data: IncomingEvent<String>, topic: Vec<&str>) -> Result<()> {
         let payload = serde_json::from_str::<RoomClose>(&data.extract_payload())?;
         Span::current().record("payload_id", &display(payload.id));
-        let mut conn = self.ctx.get_conn().await?;
 
         warn!("Close event handler started");
 
@@ -122,45 +219,46 @@ impl MessageHandler {
             _ => return Ok(()),
         };
 
-        let class = query
-            .execute(&mut conn)
-            .await?
-            .ok_or_else(|| anyhow!("Class not found by id from payload = {:?}", payload,))?;
+        let (class, event) = {
+            let mut conn = self.ctx.get_conn().await?;
+
+            let class = match query.execute(&mut conn).await? {
+                Some(class) => class,
+                None => {
+                    warn!(
+                        room_id = %payload.id,
+                        "Class not found by room id, probably deleted class",
+                    );
+                    return Ok(());
+                }
+            };
 
-        Span::current().record("class_id", &display(class.id()));
-        warn!("Close event found class",);
+            Span::current().record("class_id", &display(class.id()));
+            warn!("Close event found class",);
 
-        let label = match class.kind() {
-            ClassType::P2P => "p2p.stop",
-            ClassType::Minigroup => "minigroup.stop",
-            ClassType::Webinar => "webinar.stop",
-        };
+            let mut txn = conn
+                .begin()
+                .await
+                .context("Failed to begin sqlx db transaction")?;
 
-        crate::db::class::RoomCloseQuery::new(class.id(), payload.timed_out.unwrap_or(false))
-            .execute(&mut conn)
-            .await?;
+            crate::app::services::close_class(&mut txn, &class, payload.timed_out.unwrap_or(false))
+                .await?;
 
-        warn!("Close event, room close query done");
+            let event = crate::app::services::push_class_stop(&mut txn, &class).await?;
 
-        let timing = ShortTermTimingProperties::new(chrono::Utc::now());
-        let props = OutgoingEventProperties::new(label, timing);
-        let path = format!("audiences/{}/events", class.audience());
+            txn.commit()
+                .await
+                .context("Failed to commit sqlx db transaction")?;
 
-        let payload = ClassStop {
-            tags: class.tags().map(ToOwned::to_owned),
-            scope: class.scope().to_owned(),
-            id: class.id(),
+            (class, event)
         };
 
-        warn!("Close event, sending notification, payload = {:?}", payload);
+        warn!("Close event, room close query done");
 
-        let event = OutgoingEvent::broadcast(payload, props, &path);
-        let boxed_event = Box::new(event) as Box<dyn IntoPublishableMessage + Send>;
+        outbox::publish(self.ctx.as_ref(), vec![event]).await;
+        webhooks::enqueue(self.ctx.as_ref(), &class, WebhookEvent::Stopped).await;
 
-        self.ctx
-            .publisher()
-            .publish(boxed_event)
-            .with_context(|| format!("Failed to publish {} event", label))
+        Ok(())
     }
 
     async fn handle_stream_upload(&self, data: IncomingEvent<String>) -> Result<()> {
@@ -190,9 +288,14 @@ impl MessageHandler {
             return Ok(());
         }
 
-        postprocessing_strategy::get(self.ctx.clone(), class)?
-            .handle_mjr_dumps_upload(room_upload.rtcs)
-            .await
+        let progress = Progress::new(ClassStatus::Finished);
+
+        postprocessing_strategy::get(self.ctx.clone(), class.clone())?
+            .handle_mjr_dumps_upload(room_upload.rtcs, &progress)
+            .await?;
+
+        self.notify_progress(&class, &progress).await;
+        Ok(())
     }
 
     async fn handle_edition_commit(&self, data: IncomingEvent<String>) -> Result<()> {
@@ -203,10 +306,14 @@ impl MessageHandler {
         } else {
             Err(anyhow!("Commit result unsucessful: {:?}", commit))
         }?;
+        let progress = Progress::new(ClassStatus::Adjusted);
+
+        postprocessing_strategy::get(self.ctx.clone(), class.clone())?
+            .handle_adjust(commit.result.into_adjust_result(), &progress)
+            .await?;
 
-        postprocessing_strategy::get(self.ctx.clone(), class)?
-            .handle_adjust(commit.result.into_adjust_result())
-            .await
+        self.notify_progress(&class, &progress).await;
+        Ok(())
     }
 
     async fn handle_adjust(&self, data: IncomingEvent<String>) -> Result<()> {
@@ -217,10 +324,14 @@ impl MessageHandler {
         let class = self
             .get_original_class_by_room_id(room_adjust.room_id())
             .await?;
+        let progress = Progress::new(ClassStatus::Adjusted);
+
+        postprocessing_strategy::get(self.ctx.clone(), class.clone())?
+            .handle_adjust(room_adjust.into(), &progress)
+            .await?;
 
-        postprocessing_strategy::get(self.ctx.clone(), class)?
-            .handle_adjust(room_adjust.into())
-            .await
+        self.notify_progress(&class, &progress).await;
+        Ok(())
     }
 
     async fn handle_tq_task_completion(&self, data: IncomingEvent<String>) -> Result<()> {
@@ -235,32 +346,83 @@ impl MessageHandler {
                     Some(class) => class,
                     None => return Ok(()),
                 };
-                match success {
+                let template = success.template().to_owned();
+                let stream_id = success.stream_id();
+
+                let progress = match success {
                     TaskCompleteSuccess::TranscodeStreamToHls(result) => {
-                        postprocessing_strategy::get(self.ctx.clone(), class)?
-                            .handle_transcoding_completion(TranscodeSuccess::TranscodeStreamToHls(
-                                result,
-                            ))
-                            .await
+                        let progress = Progress::new(ClassStatus::Transcoded)
+                            .completes_job(template, stream_id);
+                        postprocessing_strategy::get(self.ctx.clone(), class.clone())?
+                            .handle_transcoding_completion(
+                                TranscodeSuccess::TranscodeStreamToHls(result),
+                                &progress,
+                            )
+                            .await?;
+                        progress
                     }
                     TaskCompleteSuccess::TranscodeMinigroupToHls(result) => {
-                        postprocessing_strategy::get(self.ctx.clone(), class)?
+                        let progress = Progress::new(ClassStatus::Transcoded)
+                            .completes_job(template, stream_id);
+                        postprocessing_strategy::get(self.ctx.clone(), class.clone())?
                             .handle_transcoding_completion(
                                 TranscodeSuccess::TranscodeMinigroupToHls(result),
+                                &progress,
                             )
-                            .await
+                            .await?;
+                        progress
                     }
                     TaskCompleteSuccess::ConvertMjrDumpsToStream(result) => {
+                        let progress =
+                            Progress::new(ClassStatus::Finished).completes_job(template, stream_id);
                         let stream = UploadedStream::from_convert_result(&result)?;
-                        postprocessing_strategy::get(self.ctx.clone(), class)?
-                            .handle_stream_upload(stream)
-                            .await
+                        postprocessing_strategy::get(self.ctx.clone(), class.clone())?
+                            .handle_stream_upload(stream, &progress)
+                            .await?;
+                        progress
                     }
-                }
+                };
+
+                self.notify_progress(&class, &progress).await;
+                Ok(())
             }
             TaskCompleteResult::Failure { error } => {
                 error!(?error, "Tq task error");
-                Ok(())
+
+                let class = self
+                    .get_class_from_tags_by_conference_id(task.tags.as_ref())
+                    .await?;
+                let class = match class {
+                    Some(class) => class,
+                    None => return Ok(()),
+                };
+
+                let tags = task.tags.as_ref();
+                let tag = |key: &str| tags.and_then(|t| t.get(key)).and_then(|v| v.as_str());
+                let stream_id = tag("stream_id").and_then(|s| Uuid::parse_str(s).ok());
+
+                let template = match tag("template") {
+                    Some(template) => template.to_owned(),
+                    None => {
+                        warn!(
+                            class_id = %class.id(),
+                            ?tags,
+                            "Tq task failure without a template tag, skipping"
+                        );
+                        return Ok(());
+                    }
+                };
+
+                let error = error.unwrap_or_default();
+
+                crate::app::tq_retry::handle_failed_job(
+                    self.ctx.as_ref(),
+                    &class,
+                    template,
+                    stream_id,
+                    &error,
+                )
+                .await
             }
         }
     }
@@ -282,6 +444,22 @@ impl MessageHandler {
         }
     }
 
+    /// The progress is already saved by the strategy, only webhooks are left. They go out
+    /// only when the class has actually moved to the status.
+    async fn notify_progress(&self, class: &Class, progress: &Progress) {
+        if !progress.transitioned() {
+            return;
+        }
+
+        let event = match progress.status() {
+            ClassStatus::Adjusted => WebhookEvent::Adjusted,
+            ClassStatus::Transcoded => WebhookEvent::Transcoded,
+            _ => return,
+        };
+
+        webhooks::enqueue(self.ctx.as_ref(), class, event).await;
+    }
+
     async fn get_class_from_tags_by_conference_id(
         &self,
         tags: Option<&JsonValue>,
@@ -388,14 +566,6 @@ struct RoomUpload {
     rtcs: Vec<postprocessing_strategy::MjrDumpsUploadResult>,
 }
 
-#[derive(Serialize, Debug)]
-struct ClassStop {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    tags: Option<JsonValue>,
-    scope: String,
-    id: Uuid,
-}
-
 #[derive(Deserialize, Debug)]
 #[serde(tag = "status", content = "result")]
 #[serde(rename_all = "snake_case")]
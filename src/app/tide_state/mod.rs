@@ -13,6 +13,7 @@ use url::Url;
 use crate::clients::conference::ConferenceClient;
 use crate::clients::event::EventClient;
 use crate::clients::tq::TqClient;
+use crate::clients::webhook::WebhookClient;
 use crate::config::Config;
 use crate::config::StorageConfig;
 
@@ -27,6 +28,7 @@ pub trait AppContext: Sync + Send {
     fn conference_client(&self) -> &dyn ConferenceClient;
     fn event_client(&self) -> &dyn EventClient;
     fn tq_client(&self) -> &dyn TqClient;
+    fn webhook_client(&self) -> &dyn WebhookClient;
     fn authz(&self) -> &Authz;
     fn storage_config(&self) -> &StorageConfig;
     fn config(&self) -> &Config;
@@ -61,17 +63,20 @@ pub struct TideState {
     conference_client: Arc<dyn ConferenceClient>,
     event_client: Arc<dyn EventClient>,
     tq_client: Arc<dyn TqClient>,
+    webhook_client: Arc<dyn WebhookClient>,
     authz: Authz,
     turn_host_selector: TurnHostSelector,
 }
 
 impl TideState {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         db_pool: PgPool,
         config: Config,
         event_client: Arc<dyn EventClient>,
         conference_client: Arc<dyn ConferenceClient>,
         tq_client: Arc<dyn TqClient>,
+        webhook_client: Arc<dyn WebhookClient>,
         agent: Agent,
         authz: Authz,
     ) -> Self {
@@ -84,6 +89,7 @@ impl TideState {
             conference_client,
             event_client,
             tq_client,
+            webhook_client,
             authz,
             turn_host_selector,
         }
@@ -127,6 +133,10 @@ impl AppContext for TideState {
         self.tq_client.as_ref()
     }
 
+    fn webhook_client(&self) -> &dyn WebhookClient {
+        self.webhook_client.as_ref()
+    }
+
     fn authz(&self) -> &Authz {
         &self.authz
     }
@@ -0,0 +1,550 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde_derive::Serialize;
+use serde_json::{json, Value as JsonValue};
+use sqlx::postgres::PgConnection;
+use sqlx::Acquire;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::app::outbox;
+use crate::app::AppContext;
+use crate::clients::tq::Task as TqTask;
+use crate::config::TqRetryConfig;
+use crate::db::class::Object as Class;
+use crate::db::postprocessing_job::{
+    CompleteQuery, Object as Job, ResubmitFailedQuery, ScheduleRetryQuery, TakeDueRetriesQuery,
+};
+
+const BATCH_SIZE: i64 = 100;
+
+/// Periodically resubmits failed tq tasks whose retry time has come.
+pub fn spawn(ctx: Arc<dyn AppContext>, config: TqRetryConfig) -> JoinHandle<()> {
+    tokio::task::spawn(async move {
+        let mut interval = tokio::time::interval(config.poll_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            match resubmit_due(ctx.as_ref()).await {
+                Ok(0) => {}
+                Ok(resubmitted) => info!(resubmitted, "Resubmitted failed tq tasks"),
+                Err(e) => error!("Failed to resubmit tq tasks, err = {:?}", e),
+            }
+        }
+    })
+}
+
+/// Marks the job failed and schedules a retry or, once the job is out of attempts,
+/// notifies the audience that the class won't get its recording.
+pub async fn handle_failed_job(
+    ctx: &dyn AppContext,
+    class: &Class,
+    template: String,
+    stream_id: Option<Uuid>,
+    error: &JsonValue,
+) -> Result<()> {
+    let config = &ctx.config().tq_client.retry;
+
+    let event = {
+        let mut conn = ctx.get_conn().await?;
+        let mut txn = conn
+            .begin()
+            .await
+            .context("Failed to begin sqlx db transaction")?;
+
+        let job = CompleteQuery::failed(class.id(), template, stream_id, error.to_owned())
+            .execute(&mut txn)
+            .await
+            .context("Failed to save postprocessing job failure")?;
+
+        let job = match job {
+            Some(job) => job,
+            None => return Ok(()),
+        };
+
+        let event = if job.task().is_some()
+            && job.attempts() < config.max_attempts
+            && is_retryable(config, error)
+        {
+            let retry_at = Utc::now() + backoff(config, job.attempts());
+
+            ScheduleRetryQuery::new(job.id(), retry_at)
+                .execute(&mut txn)
+                .await
+                .context("Failed to schedule tq task retry")?;
+
+            None
+        } else {
+            Some(push_transcoding_failed(&mut txn, class, &job, error).await?)
+        };
+
+        txn.commit().await?;
+        event
+    };
+
+    if let Some(event) = event {
+        outbox::publish(ctx, vec![event]).await;
+    }
+
+    Ok(())
+}
+
+async fn resubmit_due(ctx: &dyn AppContext) -> Result<usize> {
+    let jobs = {
+        let mut conn = ctx.get_conn().await?;
+        TakeDueRetriesQuery::new(BATCH_SIZE)
+            .execute(&mut conn)
+            .await
+            .context("Failed to take due tq task retries")?
+    };
+
+    let resubmitted = jobs.len();
+
+    for job in jobs {
+        if let Err(e) = resubmit(ctx, &job).await {
+            error!(
+                class_id = %job.class_id(),
+                template = job.template(),
+                "Failed to resubmit tq task, err = {:?}", e
+            );
+
+            if let Err(e) = handle_failed_resubmit(ctx, &job, &e).await {
+                error!(
+                    class_id = %job.class_id(),
+                    template = job.template(),
+                    "Failed to handle tq task resubmission failure, err = {:?}", e
+                );
+            }
+        }
+    }
+
+    Ok(resubmitted)
+}
+
+async fn resubmit(ctx: &dyn AppContext, job: &Job) -> Result<()> {
+    let class = {
+        let mut conn = ctx.get_conn().await?;
+        crate::db::class::ReadQuery::by_id(job.class_id())
+            .execute(&mut conn)
+            .await?
+            .ok_or_else(|| anyhow!("Class not found, id = {}", job.class_id()))?
+    };
+
+    let task = job
+        .task()
+        .cloned()
+        .ok_or_else(|| anyhow!("Missing tq task to resubmit"))?;
+    let task = serde_json::from_value::<TqTask>(task).context("Failed to parse tq task")?;
+
+    crate::app::postprocessing_strategy::resubmit_tq_task(ctx, &class, task, job.priority().bump())
+        .await
+}
+
+/// The job has been taken off the schedule so unless the failed resubmission has already
+/// been handled as a tq failure it gets rescheduled or, once out of attempts, given up on.
+async fn handle_failed_resubmit(
+    ctx: &dyn AppContext,
+    job: &Job,
+    error: &anyhow::Error,
+) -> Result<()> {
+    let config = &ctx.config().tq_client.retry;
+    let error = json!(error.to_string());
+
+    let event = {
+        let mut conn = ctx.get_conn().await?;
+        let mut txn = conn
+            .begin()
+            .await
+            .context("Failed to begin sqlx db transaction")?;
+
+        let job = ResubmitFailedQuery::new(job.id(), job.updated_at(), error.clone())
+            .execute(&mut txn)
+            .await
+            .context("Failed to save tq task resubmission failure")?;
+
+        let job = match job {
+            Some(job) => job,
+            None => return Ok(()),
+        };
+
+        let event = if job.attempts() < config.max_attempts {
+            let retry_at = Utc::now() + backoff(config, job.attempts());
+
+            ScheduleRetryQuery::new(job.id(), retry_at)
+                .execute(&mut txn)
+                .await
+                .context("Failed to schedule tq task retry")?;
+
+            None
+        } else {
+            let class = crate::db::class::ReadQuery::by_id(job.class_id())
+                .execute(&mut txn)
+                .await?;
+
+            match class {
+                Some(class) => Some(push_transcoding_failed(&mut txn, &class, &job, &error).await?),
+                None => None,
+            }
+        };
+
+        txn.commit().await?;
+        event
+    };
+
+    if let Some(event) = event {
+        outbox::publish(ctx, vec![event]).await;
+    }
+
+    Ok(())
+}
+
+fn is_retryable(config: &TqRetryConfig, error: &JsonValue) -> bool {
+    if config.retryable_errors.is_empty() {
+        return true;
+    }
+
+    let kind = error
+        .get("kind")
+        .and_then(|kind| kind.as_str())
+        .or_else(|| error.as_str());
+
+    match kind {
+        Some(kind) => config.retryable_errors.iter().any(|k| k == kind),
+        None => false,
+    }
+}
+
+fn backoff(config: &TqRetryConfig, attempts: i32) -> chrono::Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1).max(0) as u32);
+    let backoff = config.backoff.saturating_mul(factor);
+
+    chrono::Duration::from_std(backoff).unwrap_or_else(|_| chrono::Duration::max_value())
+}
+
+async fn push_transcoding_failed(
+    conn: &mut PgConnection,
+    class: &Class,
+    job: &Job,
+    error: &JsonValue,
+) -> Result<outbox::Event> {
+    let path = format!("audiences/{}/events", class.audience());
+
+    let payload = TranscodingFailed {
+        tags: class.tags().map(ToOwned::to_owned),
+        scope: class.scope().to_owned(),
+        id: class.id(),
+        template: job.template().to_owned(),
+        stream_id: job.stream_id(),
+        attempts: job.attempts(),
+        error: error.to_owned(),
+    };
+
+    outbox::push(conn, "transcoding.failed", &path, &payload).await
+}
+
+#[derive(Serialize, Debug)]
+struct TranscodingFailed {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<JsonValue>,
+    scope: String,
+    id: Uuid,
+    template: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_id: Option<Uuid>,
+    attempts: i32,
+    error: JsonValue,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use super::*;
+    use crate::clients::tq::Priority;
+    use crate::db::postprocessing_job::{ListQuery, UpsertQuery};
+    use crate::test_helpers::prelude::*;
+
+    fn task(stream_id: Uuid) -> TqTask {
+        TqTask::ConvertMjrDumpsToStream {
+            mjr_dumps_uris: vec!["s3://bucket/dump.mjr".to_owned()],
+            stream_uri: "s3://bucket/stream.webm".to_owned(),
+            stream_id,
+        }
+    }
+
+    const TEMPLATE: &str = "convert-mjr-dumps-to-stream";
+
+    async fn insert_job(conn: &mut sqlx::PgConnection, class: &Class, stream_id: Uuid) {
+        UpsertQuery::new(
+            class.id(),
+            TEMPLATE.to_owned(),
+            Some(stream_id),
+            Priority::Normal,
+        )
+        .task(serde_json::to_value(task(stream_id)).unwrap())
+        .retry()
+        .execute(conn)
+        .await
+        .expect("Failed to insert postprocessing job");
+    }
+
+    #[tokio::test]
+    async fn failed_job_is_retried_until_out_of_attempts() {
+        let db_pool = TestDb::new().await;
+        let stream_id = Uuid::new_v4();
+        let other_stream_id = Uuid::new_v4();
+
+        let webinar = {
+            let mut conn = db_pool.get_conn().await;
+            let webinar = factory::Webinar::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                (Bound::Unbounded, Bound::Unbounded).into(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+            )
+            .insert(&mut conn)
+            .await;
+
+            insert_job(&mut conn, &webinar, other_stream_id).await;
+            webinar
+        };
+
+        let state = TestState::new_with_pool(db_pool, TestAuthz::new());
+        let error = serde_json::json!({"kind": "oops"});
+
+        for attempt in 1..=3 {
+            {
+                let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+                insert_job(&mut conn, &webinar, stream_id).await;
+            }
+
+            handle_failed_job(
+                &state,
+                &webinar,
+                TEMPLATE.to_owned(),
+                Some(stream_id),
+                &error,
+            )
+            .await
+            .expect("Failed to handle failed job");
+
+            let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+            let job = ListQuery::new(webinar.id())
+                .execute(&mut conn)
+                .await
+                .expect("Failed to list postprocessing jobs")
+                .into_iter()
+                .find(|job| job.stream_id() == Some(stream_id))
+                .expect("Missing postprocessing job");
+            assert_eq!(job.attempts(), attempt);
+
+            if attempt < 3 {
+                assert!(job.retry_at().expect("Missing retry_at") > Utc::now());
+                assert!(state.test_publisher().flush().is_empty());
+            } else {
+                assert!(job.retry_at().is_none());
+
+                let messages = state.test_publisher().flush();
+                assert_eq!(messages.len(), 1);
+
+                match messages[0].properties() {
+                    OutgoingEnvelopeProperties::Event(props) => {
+                        assert_eq!(props.label(), "transcoding.failed")
+                    }
+                    _ => panic!("Expected an event"),
+                }
+
+                let payload = messages[0].payload::<JsonValue>();
+                assert_eq!(payload["id"], webinar.id().to_string());
+                assert_eq!(payload["stream_id"], stream_id.to_string());
+                assert_eq!(payload["attempts"], 3);
+                assert_eq!(payload["error"], error);
+            }
+        }
+
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+        let other_status = sqlx::query_scalar::<_, String>(
+            "SELECT status::text FROM postprocessing_job WHERE class_id = $1 AND stream_id = $2",
+        )
+        .bind(webinar.id())
+        .bind(other_stream_id)
+        .fetch_one(&mut conn)
+        .await
+        .expect("Failed to read postprocessing job status");
+        assert_eq!(other_status, "pending");
+    }
+
+    #[tokio::test]
+    async fn due_retry_is_resubmitted_with_bumped_priority() {
+        let db_pool = TestDb::new().await;
+        let stream_id = Uuid::new_v4();
+
+        let webinar = {
+            let mut conn = db_pool.get_conn().await;
+            let webinar = factory::Webinar::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                (Bound::Unbounded, Bound::Unbounded).into(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+            )
+            .insert(&mut conn)
+            .await;
+
+            insert_job(&mut conn, &webinar, stream_id).await;
+
+            let job = CompleteQuery::failed(
+                webinar.id(),
+                TEMPLATE.to_owned(),
+                Some(stream_id),
+                serde_json::json!({"kind": "oops"}),
+            )
+            .execute(&mut conn)
+            .await
+            .expect("Failed to complete postprocessing job")
+            .expect("Missing postprocessing job");
+
+            ScheduleRetryQuery::new(job.id(), Utc::now() - chrono::Duration::seconds(1))
+                .execute(&mut conn)
+                .await
+                .expect("Failed to schedule retry");
+
+            webinar
+        };
+
+        let mut state = TestState::new_with_pool(db_pool, TestAuthz::new());
+
+        // Other tests share the database so their due retries may get resubmitted too.
+        state
+            .tq_client_mock()
+            .expect_create_task()
+            .returning(|_, _, _, _| Ok(()));
+
+        resubmit_due(&state).await.expect("Failed to resubmit");
+
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+        let jobs = ListQuery::new(webinar.id())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to list postprocessing jobs");
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].attempts(), 2);
+        assert_eq!(jobs[0].priority(), Priority::High);
+        assert!(jobs[0].retry_at().is_none());
+    }
+
+    #[tokio::test]
+    async fn failed_resubmission_is_rescheduled() {
+        let db_pool = TestDb::new().await;
+        let stream_id = Uuid::new_v4();
+
+        let webinar = {
+            let mut conn = db_pool.get_conn().await;
+            let webinar = factory::Webinar::new(
+                random_string(),
+                USR_AUDIENCE.to_string(),
+                (Bound::Unbounded, Bound::Unbounded).into(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+            )
+            .insert(&mut conn)
+            .await;
+
+            // The task can't be parsed so the resubmission fails before reaching tq.
+            UpsertQuery::new(
+                webinar.id(),
+                TEMPLATE.to_owned(),
+                Some(stream_id),
+                Priority::Normal,
+            )
+            .task(serde_json::json!({"unknown": "task"}))
+            .execute(&mut conn)
+            .await
+            .expect("Failed to insert postprocessing job");
+
+            let job = CompleteQuery::failed(
+                webinar.id(),
+                TEMPLATE.to_owned(),
+                Some(stream_id),
+                serde_json::json!({"kind": "oops"}),
+            )
+            .execute(&mut conn)
+            .await
+            .expect("Failed to complete postprocessing job")
+            .expect("Missing postprocessing job");
+
+            ScheduleRetryQuery::new(job.id(), Utc::now() - chrono::Duration::seconds(1))
+                .execute(&mut conn)
+                .await
+                .expect("Failed to schedule retry");
+
+            webinar
+        };
+
+        let mut state = TestState::new_with_pool(db_pool, TestAuthz::new());
+
+        // Other tests share the database so their due retries may get resubmitted too.
+        state
+            .tq_client_mock()
+            .expect_create_task()
+            .returning(|_, _, _, _| Ok(()));
+
+        resubmit_due(&state).await.expect("Failed to resubmit");
+
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+        let jobs = ListQuery::new(webinar.id())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to list postprocessing jobs");
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].attempts(), 2);
+        assert!(jobs[0].retry_at().expect("Missing retry_at") > Utc::now());
+    }
+
+    #[tokio::test]
+    async fn new_submission_starts_job_over() {
+        let db_pool = TestDb::new().await;
+        let stream_id = Uuid::new_v4();
+        let mut conn = db_pool.get_conn().await;
+
+        let webinar = factory::Webinar::new(
+            random_string(),
+            USR_AUDIENCE.to_string(),
+            (Bound::Unbounded, Bound::Unbounded).into(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        )
+        .insert(&mut conn)
+        .await;
+
+        insert_job(&mut conn, &webinar, stream_id).await;
+        insert_job(&mut conn, &webinar, stream_id).await;
+
+        let retried = ListQuery::new(webinar.id())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to list postprocessing jobs")
+            .remove(0);
+        assert_eq!(retried.attempts(), 2);
+
+        let restarted = UpsertQuery::new(
+            webinar.id(),
+            TEMPLATE.to_owned(),
+            Some(stream_id),
+            Priority::Normal,
+        )
+        .task(serde_json::to_value(task(stream_id)).unwrap())
+        .execute(&mut conn)
+        .await
+        .expect("Failed to restart postprocessing job");
+
+        assert_eq!(restarted.attempts(), 1);
+        assert_ne!(restarted.id(), retried.id());
+    }
+}
@@ -0,0 +1,417 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde_derive::Serialize;
+use sha2::Sha256;
+use sqlx::Acquire;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::app::services::ClassStop;
+use crate::app::AppContext;
+use crate::clients::webhook::WebhookRequest;
+use crate::config::{WebhookEndpoint, WebhooksConfig};
+use crate::db::class::{ClassType, Object as Class};
+use crate::db::webhook_delivery::{
+    AttemptInsertQuery, InsertQuery, Object as Delivery, TakeDueQuery, UpdateQuery,
+};
+
+const BATCH_SIZE: i64 = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WebhookEvent {
+    Created,
+    Updated,
+    Stopped,
+    Adjusted,
+    Transcoded,
+    Deleted,
+}
+
+impl WebhookEvent {
+    fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::Created => "class.created",
+            WebhookEvent::Updated => "class.updated",
+            WebhookEvent::Stopped => "class.stopped",
+            WebhookEvent::Adjusted => "class.adjusted",
+            WebhookEvent::Transcoded => "class.transcoded",
+            WebhookEvent::Deleted => "class.deleted",
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct WebhookBody {
+    event: &'static str,
+    kind: &'static str,
+    payload: ClassStop,
+    occurred_at: i64,
+}
+
+/// Queues the class lifecycle webhook when the class audience has got an endpoint.
+/// Failures get logged only as webhooks must never break the flow they're sent from.
+pub async fn enqueue(ctx: &dyn AppContext, class: &Class, event: WebhookEvent) {
+    if let Err(e) = try_enqueue(ctx, class, event).await {
+        error!(
+            class_id = %class.id(),
+            ?event,
+            "Failed to enqueue webhook, err = {:?}", e
+        );
+    }
+}
+
+async fn try_enqueue(ctx: &dyn AppContext, class: &Class, event: WebhookEvent) -> Result<()> {
+    if !ctx
+        .config()
+        .webhooks
+        .endpoints
+        .contains_key(class.audience())
+    {
+        return Ok(());
+    }
+
+    let kind = match class.kind() {
+        ClassType::P2P => "p2p",
+        ClassType::Minigroup => "minigroup",
+        ClassType::Webinar => "webinar",
+    };
+
+    let body = WebhookBody {
+        event: event.as_str(),
+        kind,
+        payload: ClassStop::new(class),
+        occurred_at: Utc::now().timestamp(),
+    };
+
+    let payload = serde_json::to_value(&body).context("Failed to serialize webhook")?;
+
+    let mut conn = ctx.get_conn().await?;
+    InsertQuery::new(
+        class.id(),
+        class.audience().to_owned(),
+        event.as_str().to_owned(),
+        payload,
+    )
+    .execute(&mut conn)
+    .await
+    .context("Failed to insert webhook delivery")?;
+
+    Ok(())
+}
+
+/// Periodically sends queued webhooks.
+pub fn spawn(ctx: Arc<dyn AppContext>, config: WebhooksConfig) -> JoinHandle<()> {
+    tokio::task::spawn(async move {
+        let mut interval = tokio::time::interval(config.poll_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            match deliver_due(ctx.as_ref()).await {
+                Ok(0) => {}
+                Ok(sent) => info!(sent, "Sent webhooks"),
+                Err(e) => error!("Failed to send webhooks, err = {:?}", e),
+            }
+        }
+    })
+}
+
+async fn deliver_due(ctx: &dyn AppContext) -> Result<usize> {
+    let config = &ctx.config().webhooks;
+
+    if config.endpoints.is_empty() {
+        return Ok(0);
+    }
+
+    // A delivery stuck in flight, e.g. on a crash, gets taken again once its lease is over.
+    let lease = chrono::Duration::from_std(config.timeout * 2).context("Invalid timeout")?;
+    let audiences = config.endpoints.keys().cloned().collect();
+
+    let deliveries = {
+        let mut conn = ctx.get_conn().await?;
+        TakeDueQuery::new(audiences, BATCH_SIZE, Utc::now() + lease)
+            .execute(&mut conn)
+            .await
+            .context("Failed to take due webhook deliveries")?
+    };
+
+    let sent = deliveries.len();
+
+    for delivery in deliveries {
+        if let Err(e) = deliver(ctx, &delivery).await {
+            error!(
+                class_id = %delivery.class_id(),
+                delivery_id = %delivery.id(),
+                "Failed to send webhook, err = {:?}", e
+            );
+        }
+    }
+
+    Ok(sent)
+}
+
+async fn deliver(ctx: &dyn AppContext, delivery: &Delivery) -> Result<()> {
+    let config = &ctx.config().webhooks;
+
+    let endpoint = config
+        .endpoints
+        .get(delivery.audience())
+        .ok_or_else(|| anyhow!("No webhook endpoint, audience = {}", delivery.audience()))?;
+
+    let (status_code, error) = match ctx
+        .webhook_client()
+        .send(build_request(endpoint, delivery)?)
+        .await
+    {
+        Ok(status) if (200..300).contains(&status) => (Some(status), None),
+        Ok(status) => (
+            Some(status),
+            Some(format!("Unexpected status code {}", status)),
+        ),
+        Err(e) => (None, Some(e.to_string())),
+    };
+
+    let mut conn = ctx.get_conn().await?;
+    let mut txn = conn
+        .begin()
+        .await
+        .context("Failed to begin sqlx db transaction")?;
+
+    let mut q = AttemptInsertQuery::new(delivery.id(), delivery.attempts());
+
+    if let Some(status_code) = status_code {
+        q = q.status_code(status_code as i32);
+    }
+
+    if let Some(error) = error.clone() {
+        q = q.error(error);
+    }
+
+    q.execute(&mut txn)
+        .await
+        .context("Failed to save webhook delivery attempt")?;
+
+    let q = match error {
+        None => UpdateQuery::delivered(delivery.id()),
+        Some(_) if delivery.attempts() < config.max_attempts => {
+            let next_attempt_at = Utc::now() + backoff(config, delivery.attempts());
+            UpdateQuery::retry(delivery.id(), next_attempt_at)
+        }
+        Some(error) => {
+            warn!(
+                class_id = %delivery.class_id(),
+                delivery_id = %delivery.id(),
+                "Webhook is out of attempts, last error = {}", error
+            );
+
+            UpdateQuery::failed(delivery.id())
+        }
+    };
+
+    q.execute(&mut txn)
+        .await
+        .context("Failed to update webhook delivery")?;
+
+    txn.commit()
+        .await
+        .context("Failed to commit sqlx db transaction")?;
+
+    Ok(())
+}
+
+fn build_request(endpoint: &WebhookEndpoint, delivery: &Delivery) -> Result<WebhookRequest> {
+    let body = serde_json::to_string(delivery.payload()).context("Failed to serialize webhook")?;
+    let timestamp = Utc::now().timestamp();
+
+    Ok(WebhookRequest {
+        url: endpoint.url.clone(),
+        delivery_id: delivery.id().to_string(),
+        timestamp,
+        signature: sign(&endpoint.secret, timestamp, &body),
+        body,
+    })
+}
+
+/// Hex encoded HMAC-SHA256 of `{timestamp}.{body}` keyed with the endpoint secret.
+fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes a key of any size");
+
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn backoff(config: &WebhooksConfig, attempts: i32) -> chrono::Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1).max(0) as u32);
+    let backoff = config.backoff.saturating_mul(factor);
+
+    chrono::Duration::from_std(backoff).unwrap_or_else(|_| chrono::Duration::max_value())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::webhook_delivery::{AttemptListQuery, ListQuery, Status};
+    use crate::test_helpers::prelude::*;
+
+    const SECRET: &str = "secret";
+
+    async fn insert_webinar(state: &TestState, audience: &str) -> Class {
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+
+        factory::Webinar::new(
+            random_string(),
+            audience.to_owned(),
+            (Bound::Unbounded, Bound::Unbounded).into(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        )
+        .tags(serde_json::json!({"foo": "bar"}))
+        .insert(&mut conn)
+        .await
+    }
+
+    async fn list_deliveries(state: &TestState, audience: &str) -> Vec<Delivery> {
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+
+        ListQuery::new(audience.to_owned(), 100)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to list webhook deliveries")
+    }
+
+    #[test]
+    fn sign_body() {
+        assert_eq!(
+            sign(SECRET, 1600000000, r#"{"id":1}"#),
+            "49847f6653f3434dc0d5563850815d91e18471282eeccadbf48380236b3ed25f"
+        );
+    }
+
+    #[tokio::test]
+    async fn enqueue_skips_audience_without_endpoint() {
+        let db_pool = TestDb::new().await;
+        let state = TestState::new_with_pool(db_pool, TestAuthz::new());
+        let audience = random_string();
+        let webinar = insert_webinar(&state, &audience).await;
+
+        enqueue(&state, &webinar, WebhookEvent::Created).await;
+
+        assert!(list_deliveries(&state, &audience).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn enqueued_webhook_is_signed_and_delivered() {
+        let db_pool = TestDb::new().await;
+        let mut state = TestState::new_with_pool(db_pool, TestAuthz::new());
+        // A random audience keeps other tests' deliveries out of the way.
+        let audience = random_string();
+        state.set_webhook_endpoint(&audience, "https://hooks.example.com/dispatcher", SECRET);
+
+        state
+            .webhook_client_mock()
+            .expect_send()
+            .withf(|request| {
+                request.url.as_str() == "https://hooks.example.com/dispatcher"
+                    && request.signature == sign(SECRET, request.timestamp, &request.body)
+            })
+            .times(1)
+            .returning(|_| Ok(204));
+
+        let webinar = insert_webinar(&state, &audience).await;
+        enqueue(&state, &webinar, WebhookEvent::Stopped).await;
+
+        let sent = deliver_due(&state).await.expect("Failed to send webhooks");
+        assert_eq!(sent, 1);
+
+        let deliveries = list_deliveries(&state, &audience).await;
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].class_id(), webinar.id());
+        assert_eq!(deliveries[0].event(), "class.stopped");
+        assert_eq!(deliveries[0].status(), Status::Delivered);
+        assert_eq!(deliveries[0].attempts(), 1);
+
+        let payload = deliveries[0].payload();
+        assert_eq!(payload["event"], "class.stopped");
+        assert_eq!(payload["kind"], "webinar");
+        assert_eq!(payload["payload"]["id"], webinar.id().to_string());
+        assert_eq!(payload["payload"]["scope"], webinar.scope());
+        assert_eq!(
+            payload["payload"]["tags"],
+            serde_json::json!({"foo": "bar"})
+        );
+
+        // Nothing is due anymore.
+        let sent = deliver_due(&state).await.expect("Failed to send webhooks");
+        assert_eq!(sent, 0);
+    }
+
+    #[tokio::test]
+    async fn failed_webhook_is_retried_until_out_of_attempts() {
+        let db_pool = TestDb::new().await;
+        let mut state = TestState::new_with_pool(db_pool, TestAuthz::new());
+        let audience = random_string();
+        state.set_webhook_endpoint(&audience, "https://hooks.example.com/dispatcher", SECRET);
+
+        state
+            .webhook_client_mock()
+            .expect_send()
+            .returning(|_| Ok(500));
+
+        let webinar = insert_webinar(&state, &audience).await;
+        enqueue(&state, &webinar, WebhookEvent::Transcoded).await;
+
+        let max_attempts = state.config().webhooks.max_attempts;
+
+        for attempt in 1..=max_attempts {
+            deliver_due(&state).await.expect("Failed to send webhooks");
+
+            let deliveries = list_deliveries(&state, &audience).await;
+            assert_eq!(deliveries.len(), 1);
+            assert_eq!(deliveries[0].attempts(), attempt);
+
+            if attempt < max_attempts {
+                assert_eq!(deliveries[0].status(), Status::Pending);
+                assert!(
+                    deliveries[0]
+                        .next_attempt_at()
+                        .expect("Missing next_attempt_at")
+                        > Utc::now()
+                );
+
+                // Make the retry due right away.
+                let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+                UpdateQuery::retry(
+                    deliveries[0].id(),
+                    Utc::now() - chrono::Duration::seconds(1),
+                )
+                .execute(&mut conn)
+                .await
+                .expect("Failed to schedule retry");
+            } else {
+                assert_eq!(deliveries[0].status(), Status::Failed);
+                assert!(deliveries[0].next_attempt_at().is_none());
+            }
+        }
+
+        let deliveries = list_deliveries(&state, &audience).await;
+        let mut conn = state.get_conn().await.expect("Failed to fetch connection");
+        let history = AttemptListQuery::new(vec![deliveries[0].id()])
+            .execute(&mut conn)
+            .await
+            .expect("Failed to list attempts");
+        assert_eq!(history.len(), max_attempts as usize);
+
+        let history = serde_json::to_value(&history).unwrap();
+        assert_eq!(history[0]["attempt"], 1);
+        assert_eq!(history[0]["status_code"], 500);
+        assert_eq!(history[0]["error"], "Unexpected status code 500");
+    }
+}
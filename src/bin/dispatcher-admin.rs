@@ -0,0 +1,19 @@
+use anyhow::Result;
+use clap::Parser;
+use tracing_subscriber::EnvFilter;
+
+use dispatcher::admin::{self, Cli};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    #[cfg(feature = "dotenv")]
+    dotenv::dotenv()?;
+
+    // Stdout is left for the command output.
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(EnvFilter::from_default_env())
+        .init();
+
+    admin::run(Cli::parse()).await
+}
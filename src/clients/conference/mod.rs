@@ -0,0 +1,69 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::serde::ts_milliseconds;
+use chrono::{DateTime, Utc};
+#[cfg(test)]
+use mockall::{automock, predicate::*};
+use serde_derive::Deserialize;
+use serde_json::Value as JsonValue;
+use svc_agent::AgentId;
+use uuid::Uuid;
+
+pub use self::types::*;
+use super::ClientError;
+use crate::db::class::BoundedDateTimeTuple;
+
+pub struct RoomUpdate {
+    pub time: Option<BoundedDateTimeTuple>,
+    pub reserve: Option<i32>,
+    pub classroom_id: Option<Uuid>,
+    pub host: Option<AgentId>,
+}
+
+impl RoomUpdate {
+    pub fn is_empty_update(&self) -> bool {
+        matches!(
+            self,
+            RoomUpdate {
+                classroom_id: None,
+                reserve: None,
+                host: None,
+                time: None
+            }
+        )
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConfigSnapshot {
+    pub send_video: Option<bool>,
+    pub send_audio: Option<bool>,
+    pub rtc_id: Uuid,
+    #[serde(with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait ConferenceClient: Sync + Send {
+    async fn read_room(&self, id: Uuid) -> Result<ConferenceRoomResponse, ClientError>;
+
+    async fn create_room(
+        &self,
+        time: BoundedDateTimeTuple,
+        audience: String,
+        rtc_sharing_policy: Option<String>,
+        reserve: Option<i32>,
+        tags: Option<JsonValue>,
+        classroom_id: Option<Uuid>,
+    ) -> Result<Uuid, ClientError>;
+
+    async fn update_room(&self, id: Uuid, update: RoomUpdate) -> Result<(), ClientError>;
+
+    async fn read_config_snapshots(&self, id: Uuid) -> Result<Vec<ConfigSnapshot>, ClientError>;
+}
+
+pub use tower_client::TowerClient;
+
+mod tower_client;
+mod types;
@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value as JsonValue;
+use svc_agent::{request::Dispatcher, AgentId};
+use tower::Service;
+use uuid::Uuid;
+
+use super::types::*;
+use super::{ClientError, ConferenceClient, ConfigSnapshot, RoomUpdate};
+use crate::clients::mqtt::{MqttBaseClient, MqttClient, MqttClientLayers};
+use crate::config::MqttServiceConfig;
+use crate::db::class::BoundedDateTimeTuple;
+
+struct TowerClientInner {
+    read_room: MqttClient<ConferenceRoomReadPayload>,
+    create_room: MqttClient<ConferenceRoomPayload>,
+    update_room: MqttClient<ConferenceRoomUpdatePayload>,
+    read_config_snapshots: MqttClient<ConferenceWriterConfigSnapshotReadPayload>,
+}
+
+impl TowerClientInner {
+    fn new(svc: MqttBaseClient, layers: &MqttClientLayers) -> Self {
+        Self {
+            read_room: MqttClient::new(svc.clone(), layers),
+            create_room: MqttClient::new(svc.clone(), layers),
+            update_room: MqttClient::new(svc.clone(), layers),
+            read_config_snapshots: MqttClient::new(svc, layers),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TowerClient {
+    inner: Arc<TowerClientInner>,
+}
+
+impl TowerClient {
+    pub fn new(me: AgentId, dispatcher: Arc<Dispatcher>, config: &MqttServiceConfig) -> Self {
+        let svc = MqttBaseClient::new(
+            me,
+            config.account_id.clone(),
+            dispatcher,
+            &config.api_version,
+        );
+        let layers = MqttClientLayers::new("conference", config);
+        let inner = TowerClientInner::new(svc, &layers);
+
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+}
+
+#[async_trait]
+impl ConferenceClient for TowerClient {
+    async fn read_room(&self, id: Uuid) -> Result<ConferenceRoomResponse, ClientError> {
+        self.inner
+            .read_room
+            .clone()
+            .call(ConferenceRoomReadPayload { id })
+            .await
+    }
+
+    async fn create_room(
+        &self,
+        time: BoundedDateTimeTuple,
+        audience: String,
+        rtc_sharing_policy: Option<String>,
+        reserve: Option<i32>,
+        tags: Option<JsonValue>,
+        classroom_id: Option<Uuid>,
+    ) -> Result<Uuid, ClientError> {
+        let payload = ConferenceRoomPayload {
+            audience,
+            time,
+            rtc_sharing_policy,
+            reserve,
+            tags,
+            classroom_id,
+        };
+
+        self.inner
+            .create_room
+            .clone()
+            .call(payload)
+            .await
+            .map(|v| v.id)
+    }
+
+    async fn update_room(&self, id: Uuid, update: RoomUpdate) -> Result<(), ClientError> {
+        let payload = ConferenceRoomUpdatePayload {
+            id,
+            time: update.time,
+            classroom_id: update.classroom_id,
+            host: update.host,
+            reserve: update.reserve,
+        };
+
+        self.inner
+            .update_room
+            .clone()
+            .call(payload)
+            .await
+            .map(|_v| ())
+    }
+
+    async fn read_config_snapshots(
+        &self,
+        room_id: Uuid,
+    ) -> Result<Vec<ConfigSnapshot>, ClientError> {
+        self.inner
+            .read_config_snapshots
+            .clone()
+            .call(ConferenceWriterConfigSnapshotReadPayload { room_id })
+            .await
+    }
+}
@@ -0,0 +1,101 @@
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use svc_agent::AgentId;
+use uuid::Uuid;
+
+use super::ConfigSnapshot;
+use crate::clients::mqtt::MqttRequest;
+use crate::db::class::BoundedDateTimeTuple;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ConferenceRoomPayload {
+    pub audience: String,
+    #[serde(with = "crate::serde::ts_seconds_bound_tuple")]
+    pub time: BoundedDateTimeTuple,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rtc_sharing_policy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reserve: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub classroom_id: Option<Uuid>,
+}
+
+#[derive(Deserialize)]
+pub struct ConferenceRoomCreateResponse {
+    pub id: Uuid,
+}
+
+impl MqttRequest for ConferenceRoomPayload {
+    type Response = ConferenceRoomCreateResponse;
+
+    fn method(&self) -> &'static str {
+        "room.create"
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ConferenceRoomUpdatePayload {
+    pub id: Uuid,
+    #[serde(with = "crate::serde::ts_seconds_option_bound_tuple")]
+    pub time: Option<BoundedDateTimeTuple>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub classroom_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<AgentId>,
+    pub reserve: Option<i32>,
+}
+
+impl MqttRequest for ConferenceRoomUpdatePayload {
+    type Response = JsonValue;
+
+    fn method(&self) -> &'static str {
+        "room.update"
+    }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ConferenceRoomReadPayload {
+    pub id: Uuid,
+}
+
+#[derive(Deserialize)]
+pub struct ConferenceRoomResponse {
+    pub id: Uuid,
+    #[serde(with = "crate::serde::ts_seconds_bound_tuple")]
+    pub time: BoundedDateTimeTuple,
+}
+
+impl MqttRequest for ConferenceRoomReadPayload {
+    type Response = ConferenceRoomResponse;
+
+    fn method(&self) -> &'static str {
+        "room.read"
+    }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ConferenceWriterConfigSnapshotReadPayload {
+    pub room_id: Uuid,
+}
+
+impl MqttRequest for ConferenceWriterConfigSnapshotReadPayload {
+    type Response = Vec<ConfigSnapshot>;
+
+    fn method(&self) -> &'static str {
+        "writer_config_snapshot.read"
+    }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
+}
@@ -1,11 +0,0 @@
-mod log;
-mod metrics;
-mod serializer;
-
-pub use log::{LogLayer, LogMiddleware};
-pub use metrics::{MetricsLayer, MetricsMiddleware};
-pub use serializer::{ToJsonLayer, ToJsonMiddleware};
-
-use super::mqtt_client::JsonMqttRequest;
-use super::ClientError;
-use super::MqttRequest;
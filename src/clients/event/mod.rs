@@ -76,7 +76,5 @@ pub use client::MqttEventClient;
 pub use tower_client::TowerClient;
 
 mod client;
-mod layer;
-mod mqtt_client;
 mod tower_client;
 mod types;
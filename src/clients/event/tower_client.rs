@@ -1,17 +1,17 @@
 use std::sync::Arc;
-use std::time::Duration;
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde_json::Value as JsonValue;
-use svc_agent::{request::Dispatcher, AccountId, AgentId};
+use svc_agent::{request::Dispatcher, AgentId};
 use tower::Service;
 use uuid::Uuid;
 
-use super::mqtt_client::{MqttBaseClient, MqttClient};
 use super::types::*;
 use super::{ClientError, EventClient};
 use super::{EVENT_LIST_LIMIT, MAX_EVENT_LIST_PAGES};
+use crate::clients::mqtt::{MqttBaseClient, MqttClient, MqttClientLayers};
+use crate::config::MqttServiceConfig;
 use crate::db::recording::Segments;
 
 struct TowerClientInner {
@@ -27,17 +27,17 @@ struct TowerClientInner {
 }
 
 impl TowerClientInner {
-    fn new(timeout: Option<Duration>, svc: MqttBaseClient) -> Self {
+    fn new(svc: MqttBaseClient, layers: &MqttClientLayers) -> Self {
         Self {
-            read_room: MqttClient::new(timeout, svc.clone()),
-            create_room: MqttClient::new(timeout, svc.clone()),
-            update_room: MqttClient::new(timeout, svc.clone()),
-            update_locked_types: MqttClient::new(timeout, svc.clone()),
-            adjust_room: MqttClient::new(timeout, svc.clone()),
-            commit_edition: MqttClient::new(timeout, svc.clone()),
-            create_event: MqttClient::new(timeout, svc.clone()),
-            list_events: MqttClient::new(timeout, svc.clone()),
-            dump_events: MqttClient::new(timeout, svc),
+            read_room: MqttClient::new(svc.clone(), layers),
+            create_room: MqttClient::new(svc.clone(), layers),
+            update_room: MqttClient::new(svc.clone(), layers),
+            update_locked_types: MqttClient::new(svc.clone(), layers),
+            adjust_room: MqttClient::new(svc.clone(), layers),
+            commit_edition: MqttClient::new(svc.clone(), layers),
+            create_event: MqttClient::new(svc.clone(), layers),
+            list_events: MqttClient::new(svc.clone(), layers),
+            dump_events: MqttClient::new(svc, layers),
         }
     }
 
@@ -94,16 +94,15 @@ pub struct TowerClient {
 }
 
 impl TowerClient {
-    #[allow(dead_code)]
-    pub fn new(
-        me: AgentId,
-        event_account_id: AccountId,
-        dispatcher: Arc<Dispatcher>,
-        timeout: Option<Duration>,
-        api_version: &str,
-    ) -> Self {
-        let svc = MqttBaseClient::new(me, event_account_id, dispatcher, api_version);
-        let inner = TowerClientInner::new(timeout, svc);
+    pub fn new(me: AgentId, dispatcher: Arc<Dispatcher>, config: &MqttServiceConfig) -> Self {
+        let svc = MqttBaseClient::new(
+            me,
+            config.account_id.clone(),
+            dispatcher,
+            &config.api_version,
+        );
+        let layers = MqttClientLayers::new("event", config);
+        let inner = TowerClientInner::new(svc, &layers);
 
         Self {
             inner: Arc::new(inner),
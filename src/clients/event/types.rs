@@ -4,6 +4,7 @@ use serde_json::Value as JsonValue;
 use svc_agent::AgentId;
 use uuid::Uuid;
 
+use crate::clients::mqtt::MqttRequest;
 use crate::db::class::{BoundedDateTimeTuple, ClassType};
 use crate::db::recording::Segments;
 
@@ -135,17 +136,6 @@ impl RoomUpdate {
 
 ////////////////////////////////////////////////////////////////////////////////
 
-pub trait MqttRequest: Clone + serde::Serialize {
-    type Response: serde::de::DeserializeOwned;
-
-    fn method(&self) -> &'static str;
-    fn payload(&self) -> JsonValue {
-        serde_json::to_value(self).unwrap()
-    }
-}
-
-////////////////////////////////////////////////////////////////////////////////
-
 #[derive(Clone, Debug, Serialize)]
 pub struct EventRoomCreatePayload {
     pub audience: String,
@@ -183,6 +173,10 @@ impl MqttRequest for EventRoomUpdatePayload {
     fn method(&self) -> &'static str {
         "room.update"
     }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -231,6 +225,10 @@ impl MqttRequest for EventDumpEventsPayload {
     fn method(&self) -> &'static str {
         "room.dump_events"
     }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -254,6 +252,10 @@ impl MqttRequest for EventRoomReadPayload {
     fn method(&self) -> &'static str {
         "room.read"
     }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -272,6 +274,10 @@ impl MqttRequest for EventListPayload {
     fn method(&self) -> &'static str {
         "event.list"
     }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -316,6 +322,10 @@ impl MqttRequest for EventRoomLockedTypesPayload {
     fn method(&self) -> &'static str {
         "room.locked_types"
     }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -12,6 +12,19 @@ pub enum ClientError {
     Payload(String),
     Timeout,
     Http(String),
+    Status(u16, String),
+    CircuitOpen,
+}
+
+impl ClientError {
+    /// Errors a degraded service responds with, those are worth retrying.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ClientError::Timeout => true,
+            ClientError::Status(status, _) => *status >= 500,
+            _ => false,
+        }
+    }
 }
 
 impl From<AgentError> for ClientError {
@@ -27,6 +40,8 @@ impl fmt::Display for ClientError {
             ClientError::Payload(s) => write!(f, "Payload error: {}", s),
             ClientError::Timeout => write!(f, "Timeout"),
             ClientError::Http(s) => write!(f, "Http error: {}", s),
+            ClientError::Status(status, s) => write!(f, "Response status {}: {}", status, s),
+            ClientError::CircuitOpen => write!(f, "Circuit breaker is open"),
         }
     }
 }
@@ -53,4 +68,8 @@ fn generate_correlation_data() -> String {
 
 pub mod conference;
 pub mod event;
+pub mod mqtt;
+#[cfg(feature = "dev-stubs")]
+pub mod stub;
 pub mod tq;
+pub mod webhook;
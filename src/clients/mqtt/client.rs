@@ -20,9 +20,13 @@ use svc_agent::{
 use tower::ServiceBuilder;
 
 use super::layer::{
-    LogLayer, LogMiddleware, MetricsLayer, MetricsMiddleware, ToJsonLayer, ToJsonMiddleware,
+    CircuitBreakerLayer, CircuitBreakerMiddleware, ConcurrencyLimitLayer,
+    ConcurrencyLimitMiddleware, LogLayer, LogMiddleware, MetricsLayer, MetricsMiddleware,
+    RetryLayer, RetryMiddleware, ToJsonLayer, ToJsonMiddleware,
 };
-use super::{generate_correlation_data, ClientError, MqttRequest};
+use super::MqttRequest;
+use crate::clients::{generate_correlation_data, ClientError};
+use crate::config::{MqttServiceConfig, RetryConfig};
 
 pub struct JsonMqttRequest {
     method: &'static str,
@@ -54,7 +58,7 @@ where
 #[derive(Clone)]
 struct ClientSettings {
     me: AgentId,
-    event_account_id: AccountId,
+    account_id: AccountId,
     api_version: String,
 }
 
@@ -67,13 +71,13 @@ pub struct MqttBaseClient {
 impl MqttBaseClient {
     pub fn new(
         me: AgentId,
-        event_account_id: AccountId,
+        account_id: AccountId,
         dispatcher: Arc<Dispatcher>,
         api_version: &str,
     ) -> Self {
         let settings = Arc::new(ClientSettings {
             me,
-            event_account_id,
+            account_id,
             api_version: api_version.to_owned(),
         });
 
@@ -94,7 +98,7 @@ impl MqttBaseClient {
         let msg = if let OutgoingMessage::Request(msg) = OutgoingRequest::multicast(
             payload,
             reqp,
-            &self.settings.event_account_id,
+            &self.settings.account_id,
             &self.settings.api_version,
         ) {
             msg
@@ -108,7 +112,7 @@ impl MqttBaseClient {
     fn response_topic(&self) -> Result<String, ClientError> {
         let me = self.settings.me.clone();
 
-        Subscription::unicast_responses_from(&self.settings.event_account_id)
+        Subscription::unicast_responses_from(&self.settings.account_id)
             .subscription_topic(&me, &self.settings.api_version)
             .map_err(|e| AgentError::new(&e.to_string()).into())
     }
@@ -152,7 +156,13 @@ impl tower::Service<JsonMqttRequest> for MqttBaseClient {
                     let payload =
                         payload_result.map_err(|e| ClientError::Payload(e.to_string()))?;
 
+                    let status = payload.properties().status();
                     let data = payload.extract_payload();
+
+                    if !status.is_success() {
+                        return Err(ClientError::Status(status.as_u16(), data.to_string()));
+                    }
+
                     Ok(data)
                 })
             }
@@ -160,15 +170,50 @@ impl tower::Service<JsonMqttRequest> for MqttBaseClient {
     }
 }
 
+/// Layers shared by all the methods of a remote service client, built once per service
+/// so that the circuit breaker and the concurrency limit account for the whole service.
+#[derive(Clone)]
+pub struct MqttClientLayers {
+    remote_service_label: &'static str,
+    timeout: Duration,
+    retry: RetryConfig,
+    circuit_breaker: CircuitBreakerLayer,
+    concurrency_limit: ConcurrencyLimitLayer,
+}
+
+impl MqttClientLayers {
+    pub fn new(remote_service_label: &'static str, config: &MqttServiceConfig) -> Self {
+        Self {
+            remote_service_label,
+            timeout: Duration::from_secs(config.timeout),
+            retry: config.retry.clone(),
+            circuit_breaker: CircuitBreakerLayer::new(
+                remote_service_label,
+                config.circuit_breaker.clone(),
+            ),
+            concurrency_limit: ConcurrencyLimitLayer::new(
+                config.max_concurrent_requests,
+                Duration::from_secs(config.timeout),
+            ),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct MqttClient<R: MqttRequest> {
     r: PhantomData<R>,
     client: LogMiddleware<
         MetricsMiddleware<
-            ToJsonMiddleware<
-                tower::util::MapErr<
-                    tower::timeout::Timeout<MqttBaseClient>,
-                    fn(tower::BoxError) -> ClientError,
+            CircuitBreakerMiddleware<
+                RetryMiddleware<
+                    ConcurrencyLimitMiddleware<
+                        ToJsonMiddleware<
+                            tower::util::MapErr<
+                                tower::timeout::Timeout<MqttBaseClient>,
+                                fn(tower::BoxError) -> ClientError,
+                            >,
+                        >,
+                    >,
                 >,
             >,
         >,
@@ -176,17 +221,18 @@ pub struct MqttClient<R: MqttRequest> {
 }
 
 impl<R: MqttRequest> MqttClient<R> {
-    pub fn new(timeout: Option<Duration>, svc: MqttBaseClient) -> Self {
+    pub fn new(svc: MqttBaseClient, layers: &MqttClientLayers) -> Self {
         let client = ServiceBuilder::new()
-            .layer(LogLayer::new("event"))
-            .layer(MetricsLayer::new("event"))
+            .layer(LogLayer::new(layers.remote_service_label))
+            .layer(MetricsLayer::new(layers.remote_service_label))
+            .layer(layers.circuit_breaker.clone())
+            .layer(RetryLayer::new(layers.retry.clone()))
+            .layer(layers.concurrency_limit.clone())
             .layer(ToJsonLayer::new())
             .layer(tower::util::MapErrLayer::new(
                 map_err as fn(tower::BoxError) -> ClientError,
             ))
-            .layer(tower::timeout::TimeoutLayer::new(
-                timeout.unwrap_or_else(|| Duration::from_secs(30)),
-            ))
+            .layer(tower::timeout::TimeoutLayer::new(layers.timeout))
             .service(svc);
 
         Self {
@@ -196,13 +242,18 @@ impl<R: MqttRequest> MqttClient<R> {
     }
 }
 
-fn map_err(_e: tower::BoxError) -> ClientError {
-    ClientError::Timeout
+// The timeout layer boxes both its own error and the ones of the inner service.
+fn map_err(e: tower::BoxError) -> ClientError {
+    match e.downcast::<ClientError>() {
+        Ok(e) => *e,
+        Err(_) => ClientError::Timeout,
+    }
 }
 
 impl<R> tower::Service<R> for MqttClient<R>
 where
-    R: MqttRequest + 'static,
+    R: MqttRequest + Send + Sync + 'static,
+    <R as MqttRequest>::Response: Send + Sync + 'static,
 {
     type Response = <R as MqttRequest>::Response;
     type Error = ClientError;
@@ -0,0 +1,259 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use anyhow::Result;
+use parking_lot::Mutex;
+use tower::{Layer, Service};
+
+use super::*;
+use crate::config::CircuitBreakerConfig;
+
+// The state is shared by all the services the layer is applied to
+// so that the whole remote service is considered degraded, not a single method.
+#[derive(Clone)]
+pub struct CircuitBreakerLayer {
+    breaker: Arc<Mutex<Breaker>>,
+}
+
+impl CircuitBreakerLayer {
+    pub fn new(remote_service_label: &'static str, config: CircuitBreakerConfig) -> Self {
+        let breaker = Breaker {
+            remote_service_label,
+            config,
+            failures: 0,
+            opened_at: None,
+            trial_in_flight: false,
+        };
+
+        Self {
+            breaker: Arc::new(Mutex::new(breaker)),
+        }
+    }
+}
+
+impl<S> Layer<S> for CircuitBreakerLayer {
+    type Service = CircuitBreakerMiddleware<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        CircuitBreakerMiddleware {
+            service,
+            breaker: self.breaker.clone(),
+        }
+    }
+}
+
+// Circuit breaker middleware, rejects requests while the remote service keeps failing
+// instead of making every caller wait for the timeout.
+#[derive(Clone)]
+pub struct CircuitBreakerMiddleware<S> {
+    service: S,
+    breaker: Arc<Mutex<Breaker>>,
+}
+
+#[allow(clippy::type_complexity)]
+impl<S, Req> Service<Req> for CircuitBreakerMiddleware<S>
+where
+    S: Service<Req, Error = ClientError>,
+    <S as Service<Req>>::Future: Send + Sync + 'static,
+{
+    type Response = <S as Service<Req>>::Response;
+    type Error = ClientError;
+    type Future = Pin<
+        Box<
+            dyn futures::Future<Output = Result<Self::Response, Self::Error>>
+                + Send
+                + Sync
+                + 'static,
+        >,
+    >;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Req) -> Self::Future {
+        let call = match Breaker::start(&self.breaker) {
+            Some(call) => call,
+            None => return Box::pin(async { Err(ClientError::CircuitOpen) }),
+        };
+
+        let fut = self.service.call(request);
+
+        Box::pin(async move {
+            let response = fut.await;
+            let failed = matches!(&response, Err(e) if e.is_transient());
+            call.finish(failed);
+            response
+        })
+    }
+}
+
+struct Breaker {
+    remote_service_label: &'static str,
+    config: CircuitBreakerConfig,
+    failures: u32,
+    opened_at: Option<Instant>,
+    trial_in_flight: bool,
+}
+
+impl Breaker {
+    // Returns `None` when the request must be rejected. Once `reset_timeout` passes
+    // a single trial request is let through while the circuit is open.
+    fn start(breaker: &Arc<Mutex<Breaker>>) -> Option<Call> {
+        let mut state = breaker.lock();
+
+        let trial = match state.opened_at {
+            None => false,
+            Some(opened_at) => {
+                if state.trial_in_flight || opened_at.elapsed() < state.config.reset_timeout {
+                    return None;
+                }
+
+                state.trial_in_flight = true;
+                true
+            }
+        };
+
+        Some(Call {
+            breaker: breaker.clone(),
+            trial,
+        })
+    }
+
+    fn record(&mut self, failed: bool, trial: bool) {
+        if !failed {
+            if self.opened_at.is_some() {
+                tracing::info!(
+                    remote_service = self.remote_service_label,
+                    "circuit breaker closed"
+                );
+            }
+
+            self.failures = 0;
+            self.opened_at = None;
+            return;
+        }
+
+        self.failures += 1;
+
+        if trial || (self.opened_at.is_none() && self.failures >= self.config.failure_threshold) {
+            tracing::warn!(
+                remote_service = self.remote_service_label,
+                failures = self.failures,
+                "circuit breaker opened"
+            );
+
+            self.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+struct Call {
+    breaker: Arc<Mutex<Breaker>>,
+    trial: bool,
+}
+
+impl Call {
+    fn finish(self, failed: bool) {
+        self.breaker.lock().record(failed, self.trial);
+    }
+}
+
+// Lets another trial through if this one got cancelled before it finished.
+impl Drop for Call {
+    fn drop(&mut self) {
+        if self.trial {
+            self.breaker.lock().trial_in_flight = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    use tower::{service_fn, ServiceExt};
+
+    use super::super::tests::TestRequest;
+    use super::*;
+
+    #[tokio::test]
+    async fn opens_after_failures_and_closes_after_trial() {
+        let failing = Arc::new(AtomicBool::new(true));
+        let service = service_fn({
+            let failing = failing.clone();
+            move |_: TestRequest| {
+                let response = if failing.load(Ordering::SeqCst) {
+                    Err(ClientError::Timeout)
+                } else {
+                    Ok(())
+                };
+
+                futures::future::ready(response)
+            }
+        });
+
+        let layer = CircuitBreakerLayer::new(
+            "test",
+            CircuitBreakerConfig {
+                failure_threshold: 2,
+                reset_timeout: Duration::from_millis(50),
+            },
+        );
+        let service = layer.layer(service);
+        let request = TestRequest { idempotent: true };
+
+        for _ in 0..2 {
+            let result = service.clone().oneshot(request.clone()).await;
+            assert!(matches!(result, Err(ClientError::Timeout)));
+        }
+
+        let result = service.clone().oneshot(request.clone()).await;
+        assert!(matches!(result, Err(ClientError::CircuitOpen)));
+
+        // A failed trial opens the circuit again.
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let result = service.clone().oneshot(request.clone()).await;
+        assert!(matches!(result, Err(ClientError::Timeout)));
+        let result = service.clone().oneshot(request.clone()).await;
+        assert!(matches!(result, Err(ClientError::CircuitOpen)));
+
+        failing.store(false, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        for _ in 0..3 {
+            service
+                .clone()
+                .oneshot(request.clone())
+                .await
+                .expect("Request failed");
+        }
+    }
+
+    #[tokio::test]
+    async fn client_errors_dont_open_circuit() {
+        let service = service_fn(|_: TestRequest| {
+            futures::future::ready(Err::<(), _>(ClientError::Payload("Bad payload".into())))
+        });
+
+        let layer = CircuitBreakerLayer::new(
+            "test",
+            CircuitBreakerConfig {
+                failure_threshold: 1,
+                reset_timeout: Duration::from_secs(60),
+            },
+        );
+        let service = layer.layer(service);
+
+        for _ in 0..3 {
+            let result = service
+                .clone()
+                .oneshot(TestRequest { idempotent: true })
+                .await;
+            assert!(matches!(result, Err(ClientError::Payload(_))));
+        }
+    }
+}
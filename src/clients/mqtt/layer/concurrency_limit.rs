@@ -0,0 +1,163 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::sync::Semaphore;
+use tower::{Layer, Service, ServiceExt};
+
+use super::*;
+
+// The limit is shared by all the services the layer is applied to.
+#[derive(Clone)]
+pub struct ConcurrencyLimitLayer {
+    semaphore: Arc<Semaphore>,
+    timeout: Duration,
+}
+
+impl ConcurrencyLimitLayer {
+    pub fn new(max_concurrent_requests: usize, timeout: Duration) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent_requests)),
+            timeout,
+        }
+    }
+}
+
+impl<S> Layer<S> for ConcurrencyLimitLayer {
+    type Service = ConcurrencyLimitMiddleware<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        ConcurrencyLimitMiddleware {
+            service,
+            semaphore: self.semaphore.clone(),
+            timeout: self.timeout,
+        }
+    }
+}
+
+// Concurrency limit middleware, requests over the limit wait for the earlier ones to finish
+// so that a slow remote service doesn't pile up requests. A request times out when it waits
+// longer than the request timeout.
+#[derive(Clone)]
+pub struct ConcurrencyLimitMiddleware<S> {
+    service: S,
+    semaphore: Arc<Semaphore>,
+    timeout: Duration,
+}
+
+#[allow(clippy::type_complexity)]
+impl<S, Req> Service<Req> for ConcurrencyLimitMiddleware<S>
+where
+    Req: Send + Sync + 'static,
+    S: Service<Req, Error = ClientError> + Clone + Send + Sync + 'static,
+    <S as Service<Req>>::Future: Send + Sync + 'static,
+{
+    type Response = <S as Service<Req>>::Response;
+    type Error = ClientError;
+    type Future = Pin<
+        Box<
+            dyn futures::Future<Output = Result<Self::Response, Self::Error>>
+                + Send
+                + Sync
+                + 'static,
+        >,
+    >;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Req) -> Self::Future {
+        let semaphore = self.semaphore.clone();
+        let timeout = self.timeout;
+        let mut service = self.service.clone();
+
+        Box::pin(async move {
+            let _permit = tokio::time::timeout(timeout, semaphore.acquire_owned())
+                .await
+                .map_err(|_| ClientError::Timeout)?
+                .expect("Concurrency limit semaphore is never closed");
+
+            service.ready().await?.call(request).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use tower::service_fn;
+
+    use super::super::tests::{ReadyCheckService, TestRequest};
+    use super::*;
+
+    #[tokio::test]
+    async fn limits_requests_in_flight() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+        let service = service_fn({
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+
+            move |_: TestRequest| {
+                let in_flight = in_flight.clone();
+                let max_in_flight = max_in_flight.clone();
+
+                async move {
+                    let n = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(n, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    Ok::<_, ClientError>(())
+                }
+            }
+        });
+
+        let service = ConcurrencyLimitLayer::new(2, Duration::from_secs(5)).layer(service);
+
+        let requests = (0..6).map(|_| service.clone().oneshot(TestRequest { idempotent: true }));
+
+        for result in futures::future::join_all(requests).await {
+            result.expect("Request failed");
+        }
+
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn times_out_waiting_for_permit() {
+        let service = service_fn(|_: TestRequest| async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Ok::<_, ClientError>(())
+        });
+
+        let service = ConcurrencyLimitLayer::new(1, Duration::from_millis(20)).layer(service);
+
+        let requests = (0..2).map(|_| service.clone().oneshot(TestRequest { idempotent: true }));
+        let results = futures::future::join_all(requests).await;
+
+        assert!(results.iter().any(|result| result.is_ok()));
+        assert!(results
+            .iter()
+            .any(|result| matches!(result, Err(ClientError::Timeout))));
+    }
+
+    #[tokio::test]
+    async fn polls_inner_service_ready_before_call() {
+        let mut service = ConcurrencyLimitLayer::new(1, Duration::from_secs(5))
+            .layer(ReadyCheckService::default());
+
+        service
+            .ready()
+            .await
+            .unwrap()
+            .call(TestRequest { idempotent: true })
+            .await
+            .expect("Called before ready");
+    }
+}
@@ -0,0 +1,79 @@
+mod circuit_breaker;
+mod concurrency_limit;
+mod log;
+mod metrics;
+mod retry;
+mod serializer;
+
+pub use circuit_breaker::{CircuitBreakerLayer, CircuitBreakerMiddleware};
+pub use concurrency_limit::{ConcurrencyLimitLayer, ConcurrencyLimitMiddleware};
+pub use log::{LogLayer, LogMiddleware};
+pub use metrics::{MetricsLayer, MetricsMiddleware};
+pub use retry::{RetryLayer, RetryMiddleware};
+pub use serializer::{ToJsonLayer, ToJsonMiddleware};
+
+use super::client::JsonMqttRequest;
+use super::MqttRequest;
+use crate::clients::ClientError;
+
+#[cfg(test)]
+mod tests {
+    use std::task::{Context, Poll};
+
+    use serde_derive::Serialize;
+    use tower::Service;
+
+    use super::MqttRequest;
+    use crate::clients::ClientError;
+
+    #[derive(Clone, Serialize)]
+    pub struct TestRequest {
+        pub idempotent: bool,
+    }
+
+    impl MqttRequest for TestRequest {
+        type Response = ();
+
+        fn method(&self) -> &'static str {
+            "test.request"
+        }
+
+        fn is_idempotent(&self) -> bool {
+            self.idempotent
+        }
+    }
+
+    /// Fails a request unless the service has been polled ready right before it, as tower requires.
+    /// A clone starts unready like any fresh service.
+    #[derive(Default)]
+    pub struct ReadyCheckService {
+        ready: bool,
+    }
+
+    impl Clone for ReadyCheckService {
+        fn clone(&self) -> Self {
+            Self::default()
+        }
+    }
+
+    impl Service<TestRequest> for ReadyCheckService {
+        type Response = ();
+        type Error = ClientError;
+        type Future = futures::future::Ready<Result<(), ClientError>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.ready = true;
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _request: TestRequest) -> Self::Future {
+            let ready = std::mem::take(&mut self.ready);
+
+            futures::future::ready(if ready {
+                Ok(())
+            } else {
+                Err(ClientError::Payload("Called before ready".into()))
+            })
+        }
+    }
+}
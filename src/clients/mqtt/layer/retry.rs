@@ -0,0 +1,207 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use anyhow::Result;
+use rand::Rng;
+use tower::{Layer, Service, ServiceExt};
+
+use super::*;
+use crate::config::RetryConfig;
+
+pub struct RetryLayer {
+    config: RetryConfig,
+}
+
+impl RetryLayer {
+    pub fn new(config: RetryConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl<S> Layer<S> for RetryLayer {
+    type Service = RetryMiddleware<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        RetryMiddleware {
+            service,
+            config: self.config.clone(),
+        }
+    }
+}
+
+// Retry middleware, sends idempotent requests again when they fail with a transient error.
+// Non-idempotent ones may have been handled even though the response didn't come,
+// so they aren't retried.
+#[derive(Debug, Clone)]
+pub struct RetryMiddleware<S> {
+    service: S,
+    config: RetryConfig,
+}
+
+#[allow(clippy::type_complexity)]
+impl<S, Req> Service<Req> for RetryMiddleware<S>
+where
+    Req: MqttRequest + Send + Sync + 'static,
+    S: Service<Req, Error = ClientError> + Clone + Send + Sync + 'static,
+    <S as Service<Req>>::Response: Send + Sync + 'static,
+    <S as Service<Req>>::Future: Send + Sync + 'static,
+{
+    type Response = <S as Service<Req>>::Response;
+    type Error = ClientError;
+    type Future = Pin<
+        Box<
+            dyn futures::Future<Output = Result<Self::Response, Self::Error>>
+                + Send
+                + Sync
+                + 'static,
+        >,
+    >;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Req) -> Self::Future {
+        let mut service = self.service.clone();
+        let config = self.config.clone();
+
+        Box::pin(async move {
+            let mut attempt = 0;
+
+            loop {
+                // The clone has never been polled, it must get ready before the call.
+                let response = match service.ready().await {
+                    Ok(service) => service.call(request.clone()).await,
+                    Err(e) => Err(e),
+                };
+
+                let retry = match &response {
+                    Err(e) => {
+                        e.is_transient() && request.is_idempotent() && attempt < config.max_retries
+                    }
+                    Ok(_) => false,
+                };
+
+                if !retry {
+                    return response;
+                }
+
+                tracing::warn!(
+                    method = request.method(),
+                    attempt,
+                    "mqtt request failed, retrying"
+                );
+
+                tokio::time::sleep(backoff(&config, attempt)).await;
+                attempt += 1;
+            }
+        })
+    }
+}
+
+fn backoff(config: &RetryConfig, attempt: u32) -> Duration {
+    let delay = config
+        .base_delay
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(config.max_delay);
+
+    // Spreads the retries of the requests failed at once.
+    let half = delay / 2;
+    half + half.mul_f64(rand::thread_rng().gen::<f64>())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use tower::service_fn;
+
+    use super::super::tests::{ReadyCheckService, TestRequest};
+    use super::*;
+
+    fn config() -> RetryConfig {
+        RetryConfig {
+            max_retries: 2,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        }
+    }
+
+    fn failing_service(
+        calls: Arc<AtomicUsize>,
+        error: fn() -> ClientError,
+    ) -> impl Service<
+        TestRequest,
+        Response = (),
+        Error = ClientError,
+        Future = futures::future::Ready<Result<(), ClientError>>,
+    > + Clone {
+        service_fn(move |_: TestRequest| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            futures::future::ready(Err(error()))
+        })
+    }
+
+    #[tokio::test]
+    async fn retries_idempotent_request_on_timeout() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let service = RetryLayer::new(config())
+            .layer(failing_service(calls.clone(), || ClientError::Timeout));
+
+        let result = service.oneshot(TestRequest { idempotent: true }).await;
+        assert!(matches!(result, Err(ClientError::Timeout)));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn doesnt_retry_non_idempotent_request() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let service = RetryLayer::new(config())
+            .layer(failing_service(calls.clone(), || ClientError::Timeout));
+
+        service
+            .oneshot(TestRequest { idempotent: false })
+            .await
+            .expect_err("Unexpectedly succeeded");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn doesnt_retry_client_errors() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let service = RetryLayer::new(config()).layer(failing_service(calls.clone(), || {
+            ClientError::Status(404, "Not found".into())
+        }));
+
+        service
+            .oneshot(TestRequest { idempotent: true })
+            .await
+            .expect_err("Unexpectedly succeeded");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn polls_inner_service_ready_before_call() {
+        let mut service = RetryLayer::new(config()).layer(ReadyCheckService::default());
+
+        service
+            .ready()
+            .await
+            .unwrap()
+            .call(TestRequest { idempotent: true })
+            .await
+            .expect("Called before ready");
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let config = config();
+
+        for attempt in 0..10 {
+            let delay = backoff(&config, attempt);
+            assert!(delay <= config.max_delay);
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use serde_json::Value as JsonValue;
+
+pub use client::{MqttBaseClient, MqttClient, MqttClientLayers};
+
+mod client;
+mod layer;
+
+pub trait MqttRequest: Clone + serde::Serialize {
+    type Response: serde::de::DeserializeOwned;
+
+    fn method(&self) -> &'static str;
+    fn payload(&self) -> JsonValue {
+        serde_json::to_value(self).unwrap()
+    }
+
+    /// Whether the request can be sent again when its outcome is unknown, e.g. on timeout.
+    fn is_idempotent(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,74 @@
+use async_trait::async_trait;
+use serde_json::Value as JsonValue;
+use uuid::Uuid;
+
+use super::{ConferenceRoom, Stubs};
+use crate::clients::conference::{
+    ConferenceClient, ConferenceRoomResponse, ConfigSnapshot, RoomUpdate,
+};
+use crate::clients::ClientError;
+use crate::db::class::BoundedDateTimeTuple;
+
+pub struct StubConferenceClient {
+    stubs: Stubs,
+}
+
+impl StubConferenceClient {
+    pub(super) fn new(stubs: Stubs) -> Self {
+        Self { stubs }
+    }
+}
+
+#[async_trait]
+impl ConferenceClient for StubConferenceClient {
+    async fn read_room(&self, id: Uuid) -> Result<ConferenceRoomResponse, ClientError> {
+        let world = self.stubs.world.lock();
+        let room = world
+            .conference_rooms
+            .get(&id)
+            .ok_or_else(|| ClientError::Status(404, format!("Room not found, id = {}", id)))?;
+
+        Ok(ConferenceRoomResponse {
+            id,
+            time: room.time,
+        })
+    }
+
+    async fn create_room(
+        &self,
+        time: BoundedDateTimeTuple,
+        audience: String,
+        _rtc_sharing_policy: Option<String>,
+        _reserve: Option<i32>,
+        _tags: Option<JsonValue>,
+        _classroom_id: Option<Uuid>,
+    ) -> Result<Uuid, ClientError> {
+        let id = Uuid::new_v4();
+        let room = ConferenceRoom {
+            audience,
+            time,
+            closed: false,
+        };
+
+        self.stubs.world.lock().conference_rooms.insert(id, room);
+        Ok(id)
+    }
+
+    async fn update_room(&self, id: Uuid, update: RoomUpdate) -> Result<(), ClientError> {
+        let mut world = self.stubs.world.lock();
+        let room = world
+            .conference_rooms
+            .get_mut(&id)
+            .ok_or_else(|| ClientError::Status(404, format!("Room not found, id = {}", id)))?;
+
+        if let Some(time) = update.time {
+            room.time = time;
+        }
+
+        Ok(())
+    }
+
+    async fn read_config_snapshots(&self, _id: Uuid) -> Result<Vec<ConfigSnapshot>, ClientError> {
+        Ok(vec![])
+    }
+}
@@ -0,0 +1,219 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_derive::Serialize;
+use serde_json::{json, Value as JsonValue};
+use svc_agent::{AccountId, AgentId};
+use uuid::Uuid;
+
+use super::{EventRoom, Source, Stubs};
+use crate::clients::event::{
+    Event, EventClient, EventRoomCreatePayload, EventRoomResponse, LockedTypes, RoomUpdate,
+};
+use crate::clients::ClientError;
+use crate::db::recording::Segments;
+
+pub struct StubEventClient {
+    stubs: Stubs,
+}
+
+impl StubEventClient {
+    pub(super) fn new(stubs: Stubs) -> Self {
+        Self { stubs }
+    }
+
+    fn audience(&self, id: Uuid) -> Result<String, ClientError> {
+        self.stubs
+            .world
+            .lock()
+            .event_rooms
+            .get(&id)
+            .map(|room| room.audience.clone())
+            .ok_or_else(|| ClientError::Status(404, format!("Room not found, id = {}", id)))
+    }
+}
+
+#[derive(Serialize)]
+struct RoomAdjust {
+    room_id: Uuid,
+    status: &'static str,
+    original_room_id: Uuid,
+    modified_room_id: Uuid,
+    #[serde(with = "crate::db::recording::serde::segments")]
+    modified_segments: Segments,
+    #[serde(with = "crate::db::recording::serde::segments")]
+    cut_original_segments: Segments,
+}
+
+#[async_trait]
+impl EventClient for StubEventClient {
+    async fn read_room(&self, id: Uuid) -> Result<EventRoomResponse, ClientError> {
+        let world = self.stubs.world.lock();
+        let room = world
+            .event_rooms
+            .get(&id)
+            .ok_or_else(|| ClientError::Status(404, format!("Room not found, id = {}", id)))?;
+
+        Ok(EventRoomResponse {
+            id,
+            time: room.time,
+            tags: room.tags.clone(),
+        })
+    }
+
+    async fn create_room(&self, payload: EventRoomCreatePayload) -> Result<Uuid, ClientError> {
+        let id = Uuid::new_v4();
+        let room = EventRoom {
+            audience: payload.audience,
+            time: payload.time,
+            tags: payload.tags,
+            events: vec![],
+        };
+
+        self.stubs.world.lock().event_rooms.insert(id, room);
+        Ok(id)
+    }
+
+    async fn update_room(&self, id: Uuid, update: RoomUpdate) -> Result<(), ClientError> {
+        let mut world = self.stubs.world.lock();
+        let room = world
+            .event_rooms
+            .get_mut(&id)
+            .ok_or_else(|| ClientError::Status(404, format!("Room not found, id = {}", id)))?;
+
+        if let Some(time) = update.time {
+            room.time = time;
+        }
+
+        Ok(())
+    }
+
+    async fn update_locked_types(
+        &self,
+        _id: Uuid,
+        _locked_types: LockedTypes,
+    ) -> Result<(), ClientError> {
+        Ok(())
+    }
+
+    // Copies the room into the original and the modified ones as event does.
+    async fn adjust_room(
+        &self,
+        event_room_id: Uuid,
+        _started_at: DateTime<Utc>,
+        segments: Segments,
+        _offset: i64,
+    ) -> Result<(), ClientError> {
+        let original_room_id = Uuid::new_v4();
+        let modified_room_id = Uuid::new_v4();
+
+        let audience = {
+            let mut world = self.stubs.world.lock();
+            let room = world.event_rooms.get(&event_room_id).ok_or_else(|| {
+                ClientError::Status(404, format!("Room not found, id = {}", event_room_id))
+            })?;
+
+            let copy = |room: &EventRoom| EventRoom {
+                audience: room.audience.clone(),
+                time: room.time,
+                tags: room.tags.clone(),
+                events: room.events.clone(),
+            };
+
+            let (original, modified) = (copy(room), copy(room));
+            let audience = room.audience.clone();
+            world.event_rooms.insert(original_room_id, original);
+            world.event_rooms.insert(modified_room_id, modified);
+            audience
+        };
+
+        let adjust = RoomAdjust {
+            room_id: event_room_id,
+            status: "success",
+            original_room_id,
+            modified_room_id,
+            modified_segments: segments.clone(),
+            cut_original_segments: segments,
+        };
+        let adjust =
+            serde_json::to_value(adjust).map_err(|e| ClientError::Payload(e.to_string()))?;
+
+        self.stubs
+            .schedule("room.adjust", Source::Event, &audience, adjust);
+        Ok(())
+    }
+
+    async fn commit_edition(&self, edition_id: Uuid, _offset: i64) -> Result<(), ClientError> {
+        Err(ClientError::Payload(format!(
+            "Editions aren't supported by the event stub, edition id = {}",
+            edition_id
+        )))
+    }
+
+    async fn create_event(&self, payload: JsonValue) -> Result<(), ClientError> {
+        let room_id = payload
+            .get("room_id")
+            .and_then(|id| id.as_str())
+            .and_then(|id| id.parse::<Uuid>().ok())
+            .ok_or_else(|| ClientError::Payload("Missing room_id in event".into()))?;
+
+        let mut world = self.stubs.world.lock();
+        let room = world
+            .event_rooms
+            .get_mut(&room_id)
+            .ok_or_else(|| ClientError::Status(404, format!("Room not found, id = {}", room_id)))?;
+
+        let now = Utc::now();
+        let occurred_at = room.events.len() as u64 + 1;
+        let created_by = AgentId::new("alpha", AccountId::new("dispatcher", &room.audience));
+
+        let mut event = json!({
+            "id": Uuid::new_v4(),
+            "occurred_at": occurred_at,
+            "original_occurred_at": occurred_at,
+            "created_by": created_by,
+            "created_at": now.timestamp_millis(),
+        });
+
+        if let (Some(event), Some(payload)) = (event.as_object_mut(), payload.as_object()) {
+            for (key, value) in payload {
+                event.insert(key.to_owned(), value.to_owned());
+            }
+        }
+
+        room.events.push(event);
+        Ok(())
+    }
+
+    async fn list_events(&self, room_id: Uuid, kind: &str) -> Result<Vec<Event>, ClientError> {
+        let world = self.stubs.world.lock();
+        let room = world
+            .event_rooms
+            .get(&room_id)
+            .ok_or_else(|| ClientError::Status(404, format!("Room not found, id = {}", room_id)))?;
+
+        room.events
+            .iter()
+            .filter(|event| event["type"] == kind)
+            .map(|event| {
+                serde_json::from_value::<Event>(event.to_owned())
+                    .map_err(|e| ClientError::Payload(e.to_string()))
+            })
+            .collect()
+    }
+
+    async fn dump_room(&self, event_room_id: Uuid) -> Result<(), ClientError> {
+        let audience = self.audience(event_room_id)?;
+
+        let dump = json!({
+            "status": "success",
+            "result": {
+                "room_id": event_room_id,
+                "s3_uri": format!("s3://stub/{}.json", event_room_id),
+            },
+        });
+
+        self.stubs
+            .schedule("room.dump_events", Source::Event, &audience, dump);
+        Ok(())
+    }
+}
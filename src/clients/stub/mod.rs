@@ -0,0 +1,362 @@
+//! In-memory event, conference and tq services to run the dispatcher against nothing but
+//! Postgres. Instead of responding over the broker they send the events the real services
+//! would right into the event router: conference closes its rooms once their time is over
+//! and uploads a recording, event adjusts rooms, tq completes tasks.
+
+use std::collections::HashMap;
+use std::ops::Bound;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde_derive::Serialize;
+use serde_json::{json, Value as JsonValue};
+use svc_agent::mqtt::IncomingEvent;
+use svc_agent::{AccountId, AgentId};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use super::conference::ConferenceClient;
+use super::event::EventClient;
+use super::tq::TqClient;
+use crate::app::EventSender;
+use crate::config::{Config, DevStubsConfig};
+use crate::db::class::BoundedDateTimeTuple;
+
+const TICK: Duration = Duration::from_secs(1);
+
+/// Builds the stubbed clients and starts sending their events.
+pub fn start(
+    config: &Config,
+    stubs_config: &DevStubsConfig,
+    events: EventSender,
+) -> (
+    Arc<dyn EventClient>,
+    Arc<dyn ConferenceClient>,
+    Arc<dyn TqClient>,
+) {
+    info!("Using in-memory event, conference and tq stubs");
+
+    let stubs = Stubs::new(config, stubs_config);
+    stubs.spawn(events);
+
+    (
+        Arc::new(event::StubEventClient::new(stubs.clone())),
+        Arc::new(conference::StubConferenceClient::new(stubs.clone())),
+        Arc::new(tq::StubTqClient::new(stubs)),
+    )
+}
+
+#[derive(Clone, Copy)]
+enum Source {
+    Conference,
+    Event,
+    Tq,
+}
+
+struct ConferenceRoom {
+    audience: String,
+    time: BoundedDateTimeTuple,
+    closed: bool,
+}
+
+struct EventRoom {
+    audience: String,
+    time: BoundedDateTimeTuple,
+    tags: Option<JsonValue>,
+    events: Vec<JsonValue>,
+}
+
+struct Scheduled {
+    at: Instant,
+    label: &'static str,
+    source: Source,
+    audience: String,
+    payload: JsonValue,
+}
+
+#[derive(Default)]
+struct World {
+    conference_rooms: HashMap<Uuid, ConferenceRoom>,
+    event_rooms: HashMap<Uuid, EventRoom>,
+    scheduled: Vec<Scheduled>,
+}
+
+#[derive(Clone)]
+struct Stubs {
+    world: Arc<Mutex<World>>,
+    config: DevStubsConfig,
+    conference: (AccountId, String),
+    event: (AccountId, String),
+    tq: (AccountId, String),
+}
+
+impl Stubs {
+    fn new(config: &Config, stubs_config: &DevStubsConfig) -> Self {
+        Self {
+            world: Default::default(),
+            config: stubs_config.clone(),
+            conference: (
+                config.conference_client.account_id.clone(),
+                config.conference_client.api_version.clone(),
+            ),
+            event: (
+                config.event_client.account_id.clone(),
+                config.event_client.api_version.clone(),
+            ),
+            tq: (
+                config.tq_client.account_id.clone(),
+                config.tq_client.api_version.clone(),
+            ),
+        }
+    }
+
+    fn schedule(&self, label: &'static str, source: Source, audience: &str, payload: JsonValue) {
+        self.world.lock().scheduled.push(Scheduled {
+            at: Instant::now() + self.config.delay,
+            label,
+            source,
+            audience: audience.to_owned(),
+            payload,
+        });
+    }
+
+    fn spawn(&self, events: EventSender) {
+        let stubs = self.clone();
+
+        tokio::task::spawn(async move {
+            let mut interval = tokio::time::interval(TICK);
+
+            loop {
+                interval.tick().await;
+
+                for scheduled in stubs.due() {
+                    let (event, topic) = stubs.to_event(scheduled);
+
+                    if let Err(e) = events.send(event, topic).await {
+                        error!("Failed to send stub event, err = {:?}", e);
+                        return;
+                    }
+                }
+            }
+        });
+    }
+
+    // Closes the conference rooms whose time is over and takes the events whose time has come.
+    fn due(&self) -> Vec<Scheduled> {
+        let mut world = self.world.lock();
+        let now = Instant::now();
+        let mut due = vec![];
+
+        for (id, room) in world.conference_rooms.iter_mut() {
+            let over = match room.time.1 {
+                Bound::Included(t) | Bound::Excluded(t) => t <= Utc::now(),
+                Bound::Unbounded => false,
+            };
+
+            if room.closed || !over {
+                continue;
+            }
+
+            room.closed = true;
+
+            let close = RoomClose {
+                id: *id,
+                audience: &room.audience,
+                time: room.time,
+            };
+            let close = serde_json::to_value(close).expect("Failed to serialize room.close");
+
+            due.push(Scheduled {
+                at: now,
+                label: "room.close",
+                source: Source::Conference,
+                audience: room.audience.clone(),
+                payload: close,
+            });
+
+            let rtc_id = Uuid::new_v4();
+            let created_by = AgentId::new("web", AccountId::new("stub", &room.audience));
+            let upload = json!({
+                "id": id,
+                "rtcs": [{
+                    "status": "ready",
+                    "id": rtc_id,
+                    "created_by": created_by,
+                    "uri": format!("s3://stub/{}.source.webm", rtc_id),
+                    "mjr_dumps_uris": [format!("s3://stub/{}.mjr", rtc_id)],
+                }],
+            });
+
+            due.push(Scheduled {
+                at: now + self.config.delay,
+                label: "room.upload",
+                source: Source::Conference,
+                audience: room.audience.clone(),
+                payload: upload,
+            });
+        }
+
+        let (ready, later) = std::mem::take(&mut world.scheduled)
+            .into_iter()
+            .chain(due)
+            .partition::<Vec<_>, _>(|s| s.at <= now);
+
+        world.scheduled = later;
+        ready
+    }
+
+    fn to_event(&self, scheduled: Scheduled) -> (IncomingEvent<String>, String) {
+        let (account_id, api_version) = match scheduled.source {
+            Source::Conference => &self.conference,
+            Source::Event => &self.event,
+            Source::Tq => &self.tq,
+        };
+
+        let topic = format!(
+            "apps/{}/api/{}/audiences/{}/events",
+            account_id, api_version, scheduled.audience
+        );
+
+        let now = Utc::now().timestamp_millis().to_string();
+        let properties = json!({
+            "agent_id": AgentId::new("alpha", account_id.to_owned()),
+            "connection_version": "v1",
+            "connection_mode": "service",
+            "label": scheduled.label,
+            "broker_timestamp": now,
+            "broker_processing_timestamp": now,
+            "broker_initial_processing_timestamp": now,
+            "tracking_id": format!("{}.{}.{}", Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()),
+            "session_tracking_label": format!("{}.{}", Uuid::new_v4(), Uuid::new_v4()),
+        });
+
+        let properties =
+            serde_json::from_value(properties).expect("Failed to build stub event properties");
+        let event = IncomingEvent::new(scheduled.payload.to_string(), properties);
+
+        (event, topic)
+    }
+
+    // Start and duration of the recording made in the conference room.
+    fn recording(&self, conference_room_id: Uuid) -> (DateTime<Utc>, Duration) {
+        let world = self.world.lock();
+        let now = Utc::now();
+
+        let (start, end) = match world.conference_rooms.get(&conference_room_id) {
+            Some(room) => (bound_value(room.time.0), bound_value(room.time.1)),
+            None => (None, None),
+        };
+
+        let end = end.unwrap_or(now).min(now);
+        let start = start.unwrap_or(end).min(end);
+        let duration = (end - start)
+            .to_std()
+            .unwrap_or_default()
+            .max(Duration::from_secs(1));
+
+        (start, duration)
+    }
+}
+
+fn bound_value(bound: Bound<DateTime<Utc>>) -> Option<DateTime<Utc>> {
+    match bound {
+        Bound::Included(t) | Bound::Excluded(t) => Some(t),
+        Bound::Unbounded => None,
+    }
+}
+
+#[derive(Serialize)]
+struct RoomClose<'a> {
+    id: Uuid,
+    audience: &'a str,
+    #[serde(with = "crate::serde::ts_seconds_bound_tuple")]
+    time: BoundedDateTimeTuple,
+}
+
+mod conference;
+mod event;
+mod tq;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stubs(delay: Duration) -> Stubs {
+        let account = |label| {
+            (
+                AccountId::new(label, "dev.svc.example.org"),
+                "v1".to_owned(),
+            )
+        };
+
+        Stubs {
+            world: Default::default(),
+            config: DevStubsConfig { delay },
+            conference: account("conference"),
+            event: account("event"),
+            tq: account("tq"),
+        }
+    }
+
+    #[test]
+    fn closes_conference_room_when_its_time_is_over() {
+        let stubs = stubs(Duration::ZERO);
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+
+        stubs.world.lock().conference_rooms.insert(
+            id,
+            ConferenceRoom {
+                audience: "dev.usr.example.org".into(),
+                time: (
+                    Bound::Included(now - chrono::Duration::hours(1)),
+                    Bound::Excluded(now - chrono::Duration::seconds(1)),
+                ),
+                closed: false,
+            },
+        );
+
+        let labels = stubs.due().into_iter().map(|s| s.label).collect::<Vec<_>>();
+        assert_eq!(labels, vec!["room.close", "room.upload"]);
+        assert!(stubs.due().is_empty());
+
+        let (_, duration) = stubs.recording(id);
+        assert_eq!(duration.as_secs(), 3599);
+    }
+
+    #[test]
+    fn holds_scheduled_events_until_delay() {
+        let stubs = stubs(Duration::from_secs(60));
+        stubs.schedule(
+            "room.adjust",
+            Source::Event,
+            "dev.usr.example.org",
+            json!({}),
+        );
+
+        assert!(stubs.due().is_empty());
+        assert_eq!(stubs.world.lock().scheduled.len(), 1);
+    }
+
+    #[test]
+    fn builds_event_from_service_audience_topic() {
+        let stubs = stubs(Duration::ZERO);
+        stubs.schedule(
+            "task.complete",
+            Source::Tq,
+            "dev.usr.example.org",
+            json!({}),
+        );
+
+        let scheduled = stubs.due().pop().expect("No events due");
+        let (event, topic) = stubs.to_event(scheduled);
+
+        assert_eq!(
+            topic,
+            "apps/tq.dev.svc.example.org/api/v1/audiences/dev.usr.example.org/events"
+        );
+        assert_eq!(event.properties().label(), Some("task.complete"));
+    }
+}
@@ -0,0 +1,68 @@
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{Source, Stubs};
+use crate::clients::tq::{task_tags, JobAttempt, Priority, Task, TqClient};
+use crate::clients::ClientError;
+use crate::db::class::Object as Class;
+
+pub struct StubTqClient {
+    stubs: Stubs,
+}
+
+impl StubTqClient {
+    pub(super) fn new(stubs: Stubs) -> Self {
+        Self { stubs }
+    }
+}
+
+#[async_trait]
+impl TqClient for StubTqClient {
+    // Completes the task successfully as if the whole conference room time was recorded.
+    async fn create_task(
+        &self,
+        class: &Class,
+        task: Task,
+        _priority: Priority,
+        job: JobAttempt,
+    ) -> Result<(), ClientError> {
+        let tags = task_tags(class, &task, job);
+        let (started_at, duration) = self.stubs.recording(class.conference_room_id());
+        let duration = duration.as_secs_f64();
+
+        let mut complete = match task {
+            Task::ConvertMjrDumpsToStream {
+                stream_id,
+                stream_uri,
+                ..
+            } => json!({
+                "template": "convert-mjr-dumps-to-stream",
+                "stream_id": stream_id,
+                "stream_uri": stream_uri,
+                "segments": format!("{},{}", started_at.timestamp_millis(), duration),
+            }),
+            Task::TranscodeStreamToHls {
+                stream_id,
+                event_room_id,
+                ..
+            } => json!({
+                "template": "transcode-stream-to-hls",
+                "stream_id": stream_id,
+                "stream_uri": format!("s3://stub/{}.m3u8", stream_id),
+                "stream_duration": duration.to_string(),
+                "event_room_id": event_room_id.unwrap_or_else(|| class.event_room_id()),
+            }),
+            Task::TranscodeMinigroupToHls { .. } => json!({
+                "template": "transcode-minigroup-to-hls",
+                "recording_duration": duration.to_string(),
+            }),
+        };
+
+        complete["status"] = json!("success");
+        complete["tags"] = tags;
+
+        self.stubs
+            .schedule("task.complete", Source::Tq, class.audience(), complete);
+        Ok(())
+    }
+}
@@ -10,6 +10,7 @@ use reqwest::{header, Url};
 use serde_derive::{Deserialize, Serialize};
 use serde_json::{json, Value as JsonValue};
 use tracing::{error, info};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 use super::ClientError;
@@ -17,14 +18,24 @@ use crate::config::TqAudienceSettings;
 use crate::db::class::Object as Class;
 use crate::db::recording::Segments;
 
-#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, sqlx::Type, ToSchema)]
 #[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "tq_priority", rename_all = "lowercase")]
 pub enum Priority {
     Low,
     Normal,
     High,
 }
 
+impl Priority {
+    pub fn bump(self) -> Self {
+        match self {
+            Priority::Low => Priority::Normal,
+            Priority::Normal | Priority::High => Priority::High,
+        }
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 #[derive(Debug, Serialize)]
@@ -73,8 +84,8 @@ impl<'a> TaskWithOptions<'a> {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Serialize)]
-#[serde(untagged)]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
 pub enum Task {
     TranscodeStreamToHls {
         stream_id: Uuid,
@@ -82,7 +93,7 @@ pub enum Task {
         #[serde(skip_serializing_if = "Option::is_none")]
         event_room_id: Option<Uuid>,
         #[serde(skip_serializing_if = "Option::is_none")]
-        #[serde(serialize_with = "crate::db::recording::serde::segments_option")]
+        #[serde(default, with = "crate::db::recording::serde::segments_option")]
         segments: Option<Segments>,
     },
     TranscodeMinigroupToHls {
@@ -97,7 +108,7 @@ pub enum Task {
 }
 
 impl Task {
-    fn template(&self) -> &'static str {
+    pub fn template(&self) -> &'static str {
         match self {
             Self::TranscodeStreamToHls { .. } => "transcode-stream-to-hls",
             Self::TranscodeMinigroupToHls { .. } => "transcode-minigroup-to-hls",
@@ -111,22 +122,32 @@ impl Task {
             None
         }
     }
+
+    /// The stream the task processes, tells apart tasks of the same template within a class.
+    pub fn target_stream_id(&self) -> Option<Uuid> {
+        match self {
+            Self::TranscodeStreamToHls { stream_id, .. }
+            | Self::ConvertMjrDumpsToStream { stream_id, .. } => Some(*stream_id),
+            Self::TranscodeMinigroupToHls { .. } => None,
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct TranscodeMinigroupToHlsStream {
     id: Uuid,
     uri: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     offset: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[serde(serialize_with = "crate::db::recording::serde::segments_option")]
+    #[serde(default, with = "crate::db::recording::serde::segments_option")]
     segments: Option<Segments>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[serde(serialize_with = "crate::db::recording::serde::segments_option")]
+    #[serde(default, with = "crate::db::recording::serde::segments_option")]
     modified_segments: Option<Segments>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[serde(serialize_with = "crate::db::recording::serde::segments_option")]
+    #[serde(default, with = "crate::db::recording::serde::segments_option")]
     pin_segments: Option<Segments>,
     #[serde(with = "crate::db::recording::serde::segments")]
     video_mute_segments: Segments,
@@ -224,6 +245,24 @@ pub enum TaskCompleteSuccess {
     ConvertMjrDumpsToStream(ConvertMjrDumpsToStreamSuccess),
 }
 
+impl TaskCompleteSuccess {
+    pub fn template(&self) -> &'static str {
+        match self {
+            Self::TranscodeStreamToHls(_) => "transcode-stream-to-hls",
+            Self::TranscodeMinigroupToHls(_) => "transcode-minigroup-to-hls",
+            Self::ConvertMjrDumpsToStream(_) => "convert-mjr-dumps-to-stream",
+        }
+    }
+
+    pub fn stream_id(&self) -> Option<Uuid> {
+        match self {
+            Self::TranscodeStreamToHls(result) => Some(result.stream_id),
+            Self::TranscodeMinigroupToHls(_) => None,
+            Self::ConvertMjrDumpsToStream(result) => Some(result.stream_id),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ConvertMjrDumpsToStreamSuccess {
     pub stream_id: Uuid,
@@ -244,6 +283,14 @@ pub struct TranscodeMinigroupToHlsSuccess {
     pub recording_duration: String,
 }
 
+/// The postprocessing job attempt a task is sent for. Tq sends it back in the tags
+/// which tells apart completions of the task resubmissions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JobAttempt {
+    pub job_id: Uuid,
+    pub attempt: i32,
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 #[cfg_attr(test, automock)]
@@ -254,6 +301,7 @@ pub trait TqClient: Sync + Send {
         class: &Class,
         task: Task,
         priority: Priority,
+        job: JobAttempt,
     ) -> Result<(), ClientError>;
 }
 
@@ -325,20 +373,10 @@ impl HttpTqClient {
         class: &'a Class,
         task: Task,
         priority: Priority,
+        job: JobAttempt,
     ) -> TaskPayload<'a, '_> {
         let template = task.template();
-
-        let mut tags = class
-            .tags()
-            .map(ToOwned::to_owned)
-            .unwrap_or_else(|| json!({"scope": class.scope().to_owned()}));
-
-        tags.as_object_mut().and_then(|map| {
-            map.insert(
-                "conference_room_id".to_string(),
-                json!(class.conference_room_id()),
-            )
-        });
+        let tags = task_tags(class, &task, job);
 
         let task_with_options = if let Some(settings) = self.audience_settings.get(class.audience())
         {
@@ -368,6 +406,32 @@ impl HttpTqClient {
     }
 }
 
+/// Tags tq sends back on the task completion.
+pub fn task_tags(class: &Class, task: &Task, job: JobAttempt) -> JsonValue {
+    let mut tags = class
+        .tags()
+        .map(ToOwned::to_owned)
+        .unwrap_or_else(|| json!({"scope": class.scope().to_owned()}));
+
+    if let Some(map) = tags.as_object_mut() {
+        map.insert(
+            "conference_room_id".to_string(),
+            json!(class.conference_room_id()),
+        );
+
+        // Failed task completions carry nothing but tags, these allow to find the task.
+        map.insert("template".to_string(), json!(task.template()));
+        if let Some(stream_id) = task.target_stream_id() {
+            map.insert("stream_id".to_string(), json!(stream_id));
+        }
+
+        map.insert("job_id".to_string(), json!(job.job_id));
+        map.insert("attempt".to_string(), json!(job.attempt));
+    }
+
+    tags
+}
+
 #[derive(Serialize)]
 struct TaskPayload<'a, 'b> {
     audience: &'a str,
@@ -384,10 +448,11 @@ impl TqClient for HttpTqClient {
         class: &Class,
         task: Task,
         priority: Priority,
+        job: JobAttempt,
     ) -> Result<(), ClientError> {
         let url = self.build_url(class, &task)?;
 
-        let task = self.build_task(class, task, priority);
+        let task = self.build_task(class, task, priority, job);
 
         let json = serde_json::to_string(&task).map_err(|e| ClientError::Payload(e.to_string()))?;
 
@@ -425,9 +490,13 @@ impl TqClient for HttpTqClient {
 
 #[cfg(test)]
 mod tests {
+    use std::ops::Bound;
+
     use serde::Serialize;
+    use uuid::Uuid;
 
-    use crate::clients::tq::Priority;
+    use crate::clients::tq::{Priority, Task, TranscodeMinigroupToHlsStream};
+    use crate::db::recording::Segments;
 
     #[test]
     fn test_priority_serialization() {
@@ -443,4 +512,46 @@ mod tests {
         let s = serde_json::to_string(&t).unwrap();
         assert_eq!(s.as_str(), "{\"priority\":\"normal\"}");
     }
+
+    #[test]
+    fn test_priority_bump() {
+        assert_eq!(Priority::Low.bump(), Priority::Normal);
+        assert_eq!(Priority::Normal.bump(), Priority::High);
+        assert_eq!(Priority::High.bump(), Priority::High);
+    }
+
+    #[test]
+    fn test_task_serialization_roundtrip() {
+        let segments = Segments::from(vec![(Bound::Included(0), Bound::Excluded(1000))]);
+
+        let tasks = vec![
+            Task::TranscodeStreamToHls {
+                stream_id: Uuid::new_v4(),
+                stream_uri: "s3://bucket/stream.webm".to_owned(),
+                event_room_id: Some(Uuid::new_v4()),
+                segments: Some(segments.clone()),
+            },
+            Task::TranscodeMinigroupToHls {
+                streams: vec![TranscodeMinigroupToHlsStream::new(
+                    Uuid::new_v4(),
+                    "s3://bucket/1.webm".into(),
+                )
+                .offset(100)
+                .segments(segments.clone())
+                .pin_segments(segments)],
+                host_stream_id: Uuid::new_v4(),
+            },
+            Task::ConvertMjrDumpsToStream {
+                mjr_dumps_uris: vec!["s3://bucket/dump.mjr".to_owned()],
+                stream_uri: "s3://bucket/stream.webm".to_owned(),
+                stream_id: Uuid::new_v4(),
+            },
+        ];
+
+        for task in tasks {
+            let value = serde_json::to_value(&task).unwrap();
+            let parsed = serde_json::from_value::<Task>(value).unwrap();
+            assert_eq!(parsed, task);
+        }
+    }
 }
@@ -0,0 +1,77 @@
+use std::convert::TryInto;
+use std::time::Duration;
+
+use async_trait::async_trait;
+#[cfg(test)]
+use mockall::{automock, predicate::*};
+use reqwest::{header, Url};
+
+use super::ClientError;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WebhookRequest {
+    pub url: Url,
+    pub delivery_id: String,
+    pub timestamp: i64,
+    pub signature: String,
+    pub body: String,
+}
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait WebhookClient: Sync + Send {
+    /// Posts the webhook and returns the response status code whatever it is.
+    async fn send(&self, request: WebhookRequest) -> Result<u16, ClientError>;
+}
+
+pub struct HttpWebhookClient {
+    client: reqwest::Client,
+}
+
+impl HttpWebhookClient {
+    pub fn new(timeout: Duration) -> Self {
+        let mut headers = header::HeaderMap::new();
+        headers.insert(
+            http::header::CONTENT_TYPE,
+            "application/json".try_into().unwrap(),
+        );
+        headers.insert(
+            http::header::USER_AGENT,
+            format!("dispatcher-{}", crate::APP_VERSION)
+                .try_into()
+                .unwrap(),
+        );
+
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .default_headers(headers)
+            .build()
+            .expect("Failed to build HttpWebhookClient");
+
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl WebhookClient for HttpWebhookClient {
+    async fn send(&self, request: WebhookRequest) -> Result<u16, ClientError> {
+        let resp = self
+            .client
+            .post(request.url)
+            .header("X-Dispatcher-Delivery", request.delivery_id)
+            .header("X-Dispatcher-Timestamp", request.timestamp.to_string())
+            .header("X-Dispatcher-Signature", request.signature)
+            .body(request.body)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_timeout() {
+                    ClientError::Timeout
+                } else {
+                    ClientError::Http(e.to_string())
+                }
+            })?;
+
+        Ok(resp.status().as_u16())
+    }
+}
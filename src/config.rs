@@ -8,6 +8,7 @@ use svc_authz::ConfigMap as Authz;
 use svc_error::extension::sentry::Config as SentryConfig;
 
 use crate::app::turn_host::TurnHost;
+use crate::db::recording::HostSelection;
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct Config {
@@ -29,6 +30,26 @@ pub struct Config {
     pub retry_delay: Duration,
     pub turn_hosts: vec1::Vec1<TurnHost>,
     pub frontend: HashMap<String, FrontendConfig>,
+    #[serde(default)]
+    pub class_sweeper: ClassSweeperConfig,
+    #[serde(default)]
+    pub minigroup_postprocessing: MinigroupPostprocessingConfig,
+    #[serde(default)]
+    pub webinar_postprocessing: WebinarPostprocessingConfig,
+    #[serde(default)]
+    pub webhooks: WebhooksConfig,
+    #[serde(default)]
+    pub outbox: OutboxConfig,
+    #[serde(default)]
+    pub dedup: DedupConfig,
+    #[serde(default)]
+    pub event_lanes: EventLanesConfig,
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
+    #[serde(default)]
+    pub readiness: ReadinessConfig,
+    #[cfg(feature = "dev-stubs")]
+    pub dev_stubs: Option<DevStubsConfig>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -61,6 +82,32 @@ pub struct TqClientConfig {
     pub api_version: String,
     #[serde(default)]
     pub audience_settings: HashMap<String, TqAudienceSettings>,
+    #[serde(default)]
+    pub retry: TqRetryConfig,
+}
+
+/// Failed tq tasks get resubmitted after `backoff`, doubled on every next attempt.
+/// Errors are matched by their `kind`, an empty `retryable_errors` retries any error.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct TqRetryConfig {
+    pub max_attempts: i32,
+    #[serde(with = "humantime_serde")]
+    pub backoff: Duration,
+    #[serde(with = "humantime_serde")]
+    pub poll_interval: Duration,
+    pub retryable_errors: Vec<String>,
+}
+
+impl Default for TqRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Duration::from_secs(60),
+            poll_interval: Duration::from_secs(10),
+            retryable_errors: vec![],
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -77,6 +124,59 @@ pub struct MqttServiceConfig {
     pub account_id: AccountId,
     pub timeout: u64,
     pub api_version: String,
+    #[serde(default = "MqttServiceConfig::default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+}
+
+impl MqttServiceConfig {
+    fn default_max_concurrent_requests() -> usize {
+        100
+    }
+}
+
+/// Idempotent requests failed with a transient error are retried up to `max_retries` times.
+/// The delay doubles from `base_delay` up to `max_delay` and is randomized by half.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    pub max_retries: u32,
+    #[serde(with = "humantime_serde")]
+    pub base_delay: Duration,
+    #[serde(with = "humantime_serde")]
+    pub max_delay: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(2),
+        }
+    }
+}
+
+/// After `failure_threshold` transient failures in a row requests are rejected right away
+/// for `reset_timeout`, then a single trial request decides whether to close the circuit.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct CircuitBreakerConfig {
+    pub failure_threshold: u32,
+    #[serde(with = "humantime_serde")]
+    pub reset_timeout: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            reset_timeout: Duration::from_secs(30),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -88,3 +188,243 @@ pub struct StorageConfig {
 pub struct FrontendConfig {
     pub base_url: url::Url,
 }
+
+/// Every `interval` up to `batch_size` classes past their end get closed.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ClassSweeperConfig {
+    #[serde(with = "humantime_serde")]
+    pub interval: Duration,
+    pub batch_size: i64,
+}
+
+impl Default for ClassSweeperConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(60),
+            batch_size: 100,
+        }
+    }
+}
+
+/// Events are published right after their transaction is committed. Those that failed get
+/// republished by the relay once they're older than `relay_after`. Sent events are kept for `retention`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct OutboxConfig {
+    #[serde(with = "humantime_serde")]
+    pub poll_interval: Duration,
+    #[serde(with = "humantime_serde")]
+    pub relay_after: Duration,
+    #[serde(with = "humantime_serde")]
+    pub retention: Duration,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(1),
+            relay_after: Duration::from_secs(5),
+            retention: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+/// Incoming events are remembered for `ttl` to skip their redeliveries. An event being
+/// handled is held for `lease` only so that it's handled again if the handler never finishes.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct DedupConfig {
+    #[serde(with = "humantime_serde")]
+    pub ttl: Duration,
+    #[serde(with = "humantime_serde")]
+    pub lease: Duration,
+    #[serde(with = "humantime_serde")]
+    pub cleanup_interval: Duration,
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(24 * 60 * 60),
+            lease: Duration::from_secs(5 * 60),
+            cleanup_interval: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+/// Incoming events of the same class are handled one by one in one of `count` lanes.
+/// A lane holds up to `capacity` events, the following ones wait in its overflow not to hold
+/// the other lanes. Up to `intake_capacity` events wait for their lanes to be picked.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct EventLanesConfig {
+    pub count: usize,
+    pub capacity: usize,
+    pub intake_capacity: usize,
+}
+
+impl Default for EventLanesConfig {
+    fn default() -> Self {
+        Self {
+            count: 16,
+            capacity: 100,
+            intake_capacity: 1000,
+        }
+    }
+}
+
+/// On shutdown the events being handled are waited for up to `timeout`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ShutdownConfig {
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// `/readyz` always checks the db and the broker connection, tq and the authz cache are
+/// checked when enabled. Each check gives up after `timeout`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ReadinessConfig {
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    pub check_tq: bool,
+    pub check_authz_cache: bool,
+}
+
+impl Default for ReadinessConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(2),
+            check_tq: false,
+            check_authz_cache: false,
+        }
+    }
+}
+
+/// Replaces event, conference and tq with in-memory stubs when present,
+/// the stubbed services respond with events `delay` after being asked.
+#[cfg(feature = "dev-stubs")]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct DevStubsConfig {
+    #[serde(with = "humantime_serde")]
+    pub delay: Duration,
+}
+
+#[cfg(feature = "dev-stubs")]
+impl Default for DevStubsConfig {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Minigroup recordings are composed around the host's one. When the room has no host event
+/// or the host has no recording the host is chosen by `host_fallback` strategies in order.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct MinigroupPostprocessingConfig {
+    pub host_fallback: Vec<HostSelection>,
+}
+
+impl Default for MinigroupPostprocessingConfig {
+    fn default() -> Self {
+        Self {
+            host_fallback: vec![
+                HostSelection::ClassHost,
+                HostSelection::LongestRecording,
+                HostSelection::FirstRecording,
+            ],
+        }
+    }
+}
+
+/// Webinars with several publishers get either their recordings composed around the host's one
+/// or the host's recording alone transcoded. `audience_modes` overrides `mode` per audience.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct WebinarPostprocessingConfig {
+    pub mode: WebinarRecordingsMode,
+    pub audience_modes: HashMap<String, WebinarRecordingsMode>,
+    pub host_fallback: Vec<HostSelection>,
+}
+
+impl WebinarPostprocessingConfig {
+    pub fn mode(&self, audience: &str) -> WebinarRecordingsMode {
+        self.audience_modes
+            .get(audience)
+            .copied()
+            .unwrap_or(self.mode)
+    }
+}
+
+impl Default for WebinarPostprocessingConfig {
+    fn default() -> Self {
+        Self {
+            mode: WebinarRecordingsMode::MainStream,
+            audience_modes: HashMap::new(),
+            host_fallback: MinigroupPostprocessingConfig::default().host_fallback,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebinarRecordingsMode {
+    MainStream,
+    Composite,
+}
+
+/// Class lifecycle webhooks get posted to the audience endpoint. Failed deliveries are retried
+/// after `backoff`, doubled on every next attempt, until `max_attempts` is reached.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct WebhooksConfig {
+    pub endpoints: HashMap<String, WebhookEndpoint>,
+    pub max_attempts: i32,
+    #[serde(with = "humantime_serde")]
+    pub backoff: Duration,
+    #[serde(with = "humantime_serde")]
+    pub poll_interval: Duration,
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: HashMap::new(),
+            max_attempts: 5,
+            backoff: Duration::from_secs(30),
+            poll_interval: Duration::from_secs(5),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct WebhookEndpoint {
+    pub url: url::Url,
+    pub secret: String,
+}
+
+// The config gets logged on start, keep the secret out of there.
+impl std::fmt::Debug for WebhookEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookEndpoint")
+            .field("url", &self.url)
+            .field("secret", &"<hidden>")
+            .finish()
+    }
+}
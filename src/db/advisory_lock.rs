@@ -0,0 +1,22 @@
+use sqlx::postgres::PgConnection;
+
+/// Tries to take a transaction level advisory lock, returns whether it was taken.
+/// The lock is released automatically on commit or rollback.
+pub struct TryXactLockQuery {
+    key: i64,
+}
+
+impl TryXactLockQuery {
+    pub fn new(key: i64) -> Self {
+        Self { key }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<bool> {
+        sqlx::query_scalar!(
+            r#"SELECT pg_try_advisory_xact_lock($1) AS "locked!""#,
+            self.key
+        )
+        .fetch_one(conn)
+        .await
+    }
+}
@@ -0,0 +1,197 @@
+use chrono::{serde::ts_seconds, DateTime, Utc};
+use serde_derive::Serialize;
+use serde_json::Value as JsonValue;
+use sqlx::postgres::PgConnection;
+use svc_agent::AccountId;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A mutating API request. `route` is the path pattern the request was routed by,
+/// `class_id` and `scope` are set when the request targets a class or a scope.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[schema(as = AuditLogEntry)]
+pub struct Object {
+    id: i64,
+    #[schema(value_type = Option<String>)]
+    account_id: Option<AccountId>,
+    method: String,
+    route: String,
+    path: String,
+    class_id: Option<Uuid>,
+    scope: Option<String>,
+    #[schema(value_type = Option<Object>)]
+    payload: Option<JsonValue>,
+    status: i16,
+    error: Option<String>,
+    #[serde(with = "ts_seconds")]
+    #[schema(value_type = i64)]
+    created_at: DateTime<Utc>,
+}
+
+impl Object {
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct InsertQuery {
+    account_id: Option<AccountId>,
+    method: String,
+    route: String,
+    path: String,
+    class_id: Option<Uuid>,
+    scope: Option<String>,
+    payload: Option<JsonValue>,
+    status: i16,
+    error: Option<String>,
+}
+
+impl InsertQuery {
+    pub fn new(method: String, route: String, path: String, status: i16) -> Self {
+        Self {
+            account_id: None,
+            method,
+            route,
+            path,
+            class_id: None,
+            scope: None,
+            payload: None,
+            status,
+            error: None,
+        }
+    }
+
+    pub fn account_id(self, account_id: AccountId) -> Self {
+        Self {
+            account_id: Some(account_id),
+            ..self
+        }
+    }
+
+    pub fn class_id(self, class_id: Uuid) -> Self {
+        Self {
+            class_id: Some(class_id),
+            ..self
+        }
+    }
+
+    pub fn scope(self, scope: String) -> Self {
+        Self {
+            scope: Some(scope),
+            ..self
+        }
+    }
+
+    pub fn payload(self, payload: JsonValue) -> Self {
+        Self {
+            payload: Some(payload),
+            ..self
+        }
+    }
+
+    pub fn error(self, error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..self
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO audit_log (
+                account_id, method, route, path, class_id, scope, payload, status, error
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            self.account_id as Option<AccountId>,
+            self.method,
+            self.route,
+            self.path,
+            self.class_id,
+            self.scope,
+            self.payload,
+            self.status,
+            self.error,
+        )
+        .execute(conn)
+        .await
+        .map(|_| ())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct ListQuery {
+    class_id: Option<Uuid>,
+    account_id: Option<AccountId>,
+    before: Option<i64>,
+    limit: i64,
+}
+
+impl ListQuery {
+    pub fn new(limit: i64) -> Self {
+        Self {
+            class_id: None,
+            account_id: None,
+            before: None,
+            limit,
+        }
+    }
+
+    pub fn class_id(self, class_id: Uuid) -> Self {
+        Self {
+            class_id: Some(class_id),
+            ..self
+        }
+    }
+
+    pub fn account_id(self, account_id: AccountId) -> Self {
+        Self {
+            account_id: Some(account_id),
+            ..self
+        }
+    }
+
+    /// Continues listing with the entries older than the one with the given id.
+    pub fn before(self, id: i64) -> Self {
+        Self {
+            before: Some(id),
+            ..self
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            SELECT
+                id,
+                account_id AS "account_id: _",
+                method,
+                route,
+                path,
+                class_id,
+                scope,
+                payload,
+                status,
+                error,
+                created_at
+            FROM audit_log
+            WHERE ($1::uuid IS NULL OR class_id = $1)
+            AND ($2::account_id IS NULL OR account_id = $2)
+            AND ($3::bigint IS NULL OR id < $3)
+            ORDER BY id DESC
+            LIMIT $4
+            "#,
+            self.class_id,
+            self.account_id as Option<AccountId>,
+            self.before,
+            self.limit,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
@@ -58,9 +58,12 @@ impl AuthzReadQuery {
                         SELECT
                             id::text AS "id!: String"
                         FROM class
-                        WHERE event_room_id = $1
+                        WHERE (
+                            event_room_id = $1
                             OR original_event_room_id = $1
                             OR modified_event_room_id = $1
+                        )
+                        AND deleted_at IS NULL
                     "#,
                     id,
                 )
@@ -75,6 +78,7 @@ impl AuthzReadQuery {
                             id::text AS "id!: String"
                         FROM class
                         WHERE conference_room_id = $1
+                        AND deleted_at IS NULL
                     "#,
                     id,
                 )
@@ -91,6 +95,7 @@ impl AuthzReadQuery {
                         INNER JOIN recording r
                         ON r.class_id = class.id
                         WHERE rtc_id = $1
+                        AND class.deleted_at IS NULL
                     "#,
                     id,
                 )
@@ -106,6 +111,7 @@ impl AuthzReadQuery {
                         FROM class
                         WHERE audience = $1
                         AND scope = $2
+                        AND deleted_at IS NULL
                     "#,
                     audience,
                     scope
@@ -121,6 +127,7 @@ impl AuthzReadQuery {
                             id::text AS "id!: String"
                         FROM class
                         WHERE id = $1
+                        AND deleted_at IS NULL
                     "#,
                     id,
                 )
@@ -1,17 +1,21 @@
 use super::*;
 
-#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+#[derive(Clone, Debug, Serialize, sqlx::FromRow, ToSchema)]
+#[schema(as = CreatedClass)]
 pub struct Dummy {
     id: Uuid,
     #[serde(skip)]
     kind: ClassType,
     scope: String,
     #[serde(with = "serde::time")]
+    #[schema(value_type = BoundedTime)]
     time: Time,
     audience: String,
     #[serde(with = "ts_seconds")]
+    #[schema(value_type = i64)]
     created_at: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Value>)]
     tags: Option<JsonValue>,
     properties: KeyValueProperties,
     preserve_history: bool,
@@ -163,7 +167,7 @@ impl InsertQuery {
                 established, properties, original_class_id
             )
             VALUES ($1, $2, $3, $4, $5, $6::class_type, $7, $8, $9, $10, $11, $12, $13, $14, $15)
-            ON CONFLICT (scope, audience)
+            ON CONFLICT (scope, audience) WHERE deleted_at IS NULL
             DO UPDATE
             SET time = EXCLUDED.time,
                 tags = EXCLUDED.tags,
@@ -63,7 +63,7 @@ impl std::convert::TryFrom<Object> for Minigroup {
 }
 
 #[cfg(test)]
-use super::{GenericReadQuery, KeyValueProperties, MinigroupType};
+use super::{ClassStatus, GenericReadQuery, KeyValueProperties, MinigroupType};
 
 #[cfg(test)]
 pub type MinigroupReadQuery = GenericReadQuery<MinigroupType>;
@@ -166,7 +166,8 @@ impl MinigroupInsertQuery {
                 timed_out,
                 properties AS "properties: _",
                 original_class_id,
-                content_id
+                content_id,
+                status AS "status!: ClassStatus"
             "#,
             self.scope,
             self.audience,
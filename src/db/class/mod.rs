@@ -8,10 +8,12 @@ use uuid::Uuid;
 
 use serde_derive::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use utoipa::ToSchema;
 
 pub type BoundedDateTimeTuple = (Bound<DateTime<Utc>>, Bound<DateTime<Utc>>);
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq, ToSchema)]
+#[schema(value_type = Object)]
 pub struct KeyValueProperties(serde_json::Map<String, JsonValue>);
 
 impl KeyValueProperties {
@@ -73,14 +75,31 @@ impl sqlx::Type<sqlx::Postgres> for KeyValueProperties {
     }
 }
 
-#[derive(Clone, Copy, Debug, sqlx::Type, PartialEq, Eq, Serialize)]
+#[derive(Clone, Copy, Debug, sqlx::Type, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[sqlx(type_name = "class_type", rename_all = "lowercase")]
+#[serde(rename_all(deserialize = "lowercase"))]
+#[schema(rename_all = "lowercase")]
 pub enum ClassType {
     Webinar,
     P2P,
     Minigroup,
 }
 
+/// Class lifecycle state. Variants are declared in the order the class moves through them
+/// so that transitions can only go forward.
+#[derive(
+    Clone, Copy, Debug, sqlx::Type, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema,
+)]
+#[sqlx(type_name = "class_status", rename_all = "snake_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum ClassStatus {
+    RealTime,
+    Closed,
+    Finished,
+    Adjusted,
+    Transcoded,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum RtcSharingPolicy {
     Shared,
@@ -145,18 +164,22 @@ impl AsClassType for MinigroupType {
     }
 }
 
-#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+#[derive(Clone, Debug, Serialize, sqlx::FromRow, ToSchema)]
+#[schema(as = Class)]
 pub struct Object {
     id: Uuid,
     #[serde(skip)]
     kind: ClassType,
     scope: String,
     #[serde(with = "serde::time")]
+    #[schema(value_type = BoundedTime)]
     time: Time,
     audience: String,
     #[serde(with = "ts_seconds")]
+    #[schema(value_type = i64)]
     created_at: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Value>)]
     tags: Option<JsonValue>,
     properties: KeyValueProperties,
     conference_room_id: Uuid,
@@ -168,12 +191,14 @@ pub struct Object {
     preserve_history: bool,
     reserve: Option<i32>,
     room_events_uri: Option<String>,
+    #[schema(value_type = Option<String>)]
     host: Option<AgentId>,
     timed_out: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     original_class_id: Option<Uuid>,
     #[serde(skip_serializing_if = "Option::is_none")]
     content_id: Option<String>,
+    status: ClassStatus,
 }
 
 pub fn default_locked_chat() -> bool {
@@ -245,7 +270,6 @@ impl Object {
         self.timed_out
     }
 
-    #[cfg(test)]
     pub fn host(&self) -> Option<&AgentId> {
         self.host.as_ref()
     }
@@ -261,6 +285,10 @@ impl Object {
     pub fn content_id(&self) -> Option<&String> {
         self.content_id.as_ref()
     }
+
+    pub fn status(&self) -> ClassStatus {
+        self.status
+    }
 }
 
 impl crate::app::services::Creatable for Object {
@@ -427,7 +455,7 @@ impl ReadQuery {
 
         let q = Select::from_table("class");
 
-        let mut q = match self.condition {
+        let q = match self.condition {
             ReadQueryPredicate::Id(_) => q.and_where("id".equals("_placeholder_")),
             ReadQueryPredicate::Scope { .. } => q
                 .and_where("audience".equals("_placeholder_"))
@@ -443,6 +471,8 @@ impl ReadQuery {
             }
         };
 
+        let mut q = q.and_where("deleted_at".is_null());
+
         if self.original {
             q = q.and_where("original_class_id".is_null())
         }
@@ -512,7 +542,9 @@ impl<T: AsClassType> GenericReadQuery<T> {
             }
         };
 
-        let q = q.and_where("kind".equals("_placeholder_"));
+        let q = q
+            .and_where("deleted_at".is_null())
+            .and_where("kind".equals("_placeholder_"));
 
         let (sql, _bindings) = Postgres::build(q);
         let query = sqlx::query_as(&sql);
@@ -533,6 +565,317 @@ impl<T: AsClassType> GenericReadQuery<T> {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+pub struct ListQuery {
+    audience: String,
+    kind: Option<ClassType>,
+    time: Option<PgRange<DateTime<Utc>>>,
+    timed_out: Option<bool>,
+    original_class_id: Option<Uuid>,
+    content_id: Option<String>,
+    tags: Option<JsonValue>,
+    status: Option<ClassStatus>,
+    after: Option<Uuid>,
+    limit: i64,
+}
+
+impl ListQuery {
+    pub fn new(audience: String, limit: i64) -> Self {
+        Self {
+            audience,
+            kind: None,
+            time: None,
+            timed_out: None,
+            original_class_id: None,
+            content_id: None,
+            tags: None,
+            status: None,
+            after: None,
+            limit,
+        }
+    }
+
+    pub fn kind(self, kind: ClassType) -> Self {
+        Self {
+            kind: Some(kind),
+            ..self
+        }
+    }
+
+    /// Keeps only classes whose time overlaps with the given range.
+    pub fn time(self, time: BoundedDateTimeTuple) -> Self {
+        Self {
+            time: Some(PgRange::from(time)),
+            ..self
+        }
+    }
+
+    pub fn timed_out(self, timed_out: bool) -> Self {
+        Self {
+            timed_out: Some(timed_out),
+            ..self
+        }
+    }
+
+    pub fn original_class_id(self, original_class_id: Uuid) -> Self {
+        Self {
+            original_class_id: Some(original_class_id),
+            ..self
+        }
+    }
+
+    pub fn content_id(self, content_id: String) -> Self {
+        Self {
+            content_id: Some(content_id),
+            ..self
+        }
+    }
+
+    /// Keeps only classes whose tags contain the given JSON.
+    pub fn tags(self, tags: JsonValue) -> Self {
+        Self {
+            tags: Some(tags),
+            ..self
+        }
+    }
+
+    pub fn status(self, status: ClassStatus) -> Self {
+        Self {
+            status: Some(status),
+            ..self
+        }
+    }
+
+    /// Continues listing after the class with the given id.
+    pub fn after(self, id: Uuid) -> Self {
+        Self {
+            after: Some(id),
+            ..self
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            SELECT
+                id,
+                scope,
+                kind AS "kind!: ClassType",
+                audience,
+                time AS "time!: Time",
+                tags,
+                properties AS "properties: _",
+                preserve_history,
+                created_at,
+                event_room_id AS "event_room_id!: Uuid",
+                conference_room_id AS "conference_room_id!: Uuid",
+                original_event_room_id,
+                modified_event_room_id,
+                reserve,
+                room_events_uri,
+                host AS "host: AgentId",
+                timed_out,
+                original_class_id,
+                content_id,
+                status AS "status!: ClassStatus"
+            FROM class
+            WHERE audience = $1
+            AND established = 't'
+            AND deleted_at IS NULL
+            AND ($2::class_type IS NULL OR kind = $2)
+            AND ($3::tstzrange IS NULL OR time && $3)
+            AND ($4::boolean IS NULL OR timed_out = $4)
+            AND ($5::uuid IS NULL OR original_class_id = $5)
+            AND ($6::text IS NULL OR content_id = $6)
+            AND ($7::jsonb IS NULL OR tags::jsonb @> $7)
+            AND ($8::class_status IS NULL OR status = $8)
+            AND (
+                $9::uuid IS NULL
+                OR (created_at, id) < (SELECT created_at, id FROM class WHERE id = $9)
+            )
+            ORDER BY created_at DESC, id DESC
+            LIMIT $10
+            "#,
+            self.audience,
+            self.kind as Option<ClassType>,
+            self.time,
+            self.timed_out,
+            self.original_class_id,
+            self.content_id,
+            self.tags,
+            self.status as Option<ClassStatus>,
+            self.after,
+            self.limit,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Finds classes whose time has ended while they still haven't been closed.
+/// Found rows are locked until the end of the transaction.
+pub struct EndedListQuery {
+    audience: Option<String>,
+    limit: i64,
+}
+
+impl EndedListQuery {
+    pub fn new(limit: i64) -> Self {
+        Self {
+            audience: None,
+            limit,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn audience(self, audience: String) -> Self {
+        Self {
+            audience: Some(audience),
+            ..self
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            SELECT
+                id,
+                scope,
+                kind AS "kind!: ClassType",
+                audience,
+                time AS "time!: Time",
+                tags,
+                properties AS "properties: _",
+                preserve_history,
+                created_at,
+                event_room_id AS "event_room_id!: Uuid",
+                conference_room_id AS "conference_room_id!: Uuid",
+                original_event_room_id,
+                modified_event_room_id,
+                reserve,
+                room_events_uri,
+                host AS "host: AgentId",
+                timed_out,
+                original_class_id,
+                content_id,
+                status AS "status!: ClassStatus"
+            FROM class
+            WHERE status = 'real_time'
+            AND established = 't'
+            AND deleted_at IS NULL
+            AND UPPER(time) < NOW()
+            AND ($2::text IS NULL OR audience = $2)
+            ORDER BY UPPER(time)
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+            "#,
+            self.limit,
+            self.audience,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Finds the class any of the rooms belongs to. Replicas resolve to their original class.
+pub struct OriginalIdByRoomQuery {
+    room_id: Uuid,
+}
+
+impl OriginalIdByRoomQuery {
+    pub fn new(room_id: Uuid) -> Self {
+        Self { room_id }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Option<Uuid>> {
+        let r = sqlx::query!(
+            r#"
+            SELECT COALESCE(original_class_id, id) AS "id!: Uuid"
+            FROM class
+            WHERE (
+                event_room_id = $1
+                OR conference_room_id = $1
+                OR original_event_room_id = $1
+                OR modified_event_room_id = $1
+            )
+            AND deleted_at IS NULL
+            ORDER BY original_class_id NULLS FIRST
+            LIMIT 1
+            "#,
+            self.room_id,
+        )
+        .fetch_optional(conn)
+        .await?;
+
+        Ok(r.map(|r| r.id))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Moves the class to the given status and records the transition.
+/// Backward transitions are ignored unless `force` is set.
+pub struct UpdateStatusQuery {
+    id: Uuid,
+    status: ClassStatus,
+    force: bool,
+}
+
+impl UpdateStatusQuery {
+    pub fn new(id: Uuid, status: ClassStatus) -> Self {
+        Self {
+            id,
+            status,
+            force: false,
+        }
+    }
+
+    pub fn force(self) -> Self {
+        Self {
+            force: true,
+            ..self
+        }
+    }
+
+    /// Returns whether the status has actually changed.
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<bool> {
+        sqlx::query!(
+            r#"
+            WITH old AS (
+                SELECT id, status
+                FROM class
+                WHERE id = $1
+                FOR UPDATE
+            ), updated AS (
+                UPDATE class
+                SET status = $2
+                FROM old
+                WHERE class.id = old.id
+                AND old.status <> $2
+                AND ($3 OR old.status < $2)
+                RETURNING class.id, old.status AS from_status
+            )
+            INSERT INTO class_status_transition (class_id, from_status, to_status)
+            SELECT id, from_status, $2
+            FROM updated
+            "#,
+            self.id,
+            self.status as ClassStatus,
+            self.force,
+        )
+        .execute(conn)
+        .await
+        .map(|r| r.rows_affected() > 0)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 pub struct UpdateDumpEventsQuery {
     modified_event_room_id: Uuid,
     room_events_uri: String,
@@ -604,7 +947,8 @@ impl UpdateAdjustedRoomsQuery {
                 host AS "host: AgentId",
                 timed_out,
                 original_class_id,
-                content_id
+                content_id,
+                status AS "status!: ClassStatus"
             "#,
             self.id,
             self.original_event_room_id,
@@ -658,7 +1002,8 @@ impl EstablishQuery {
                 host AS "host: AgentId",
                 timed_out,
                 original_class_id,
-                content_id
+                content_id,
+                status AS "status!: ClassStatus"
             "#,
             self.id,
             self.event_room_id,
@@ -720,7 +1065,8 @@ impl RecreateQuery {
                 host AS "host: AgentId",
                 timed_out,
                 original_class_id,
-                content_id
+                content_id,
+                status AS "status!: ClassStatus"
             "#,
             self.id,
             time,
@@ -806,7 +1152,8 @@ impl ClassUpdateQuery {
                 host AS "host: AgentId",
                 timed_out,
                 original_class_id,
-                content_id
+                content_id,
+                status AS "status!: ClassStatus"
             "#,
             self.id,
             time,
@@ -859,7 +1206,8 @@ impl RoomCloseQuery {
                 host AS "host: AgentId",
                 timed_out,
                 original_class_id,
-                content_id
+                content_id,
+                status AS "status!: ClassStatus"
             "#,
             self.id,
             self.timed_out
@@ -871,6 +1219,8 @@ impl RoomCloseQuery {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+/// Marks the class deleted. Read queries skip deleted classes while the rows referencing them
+/// are kept.
 pub struct DeleteQuery {
     id: Uuid,
 }
@@ -884,8 +1234,10 @@ impl DeleteQuery {
         sqlx::query_as!(
             Object,
             r#"
-            DELETE FROM class
+            UPDATE class
+            SET deleted_at = NOW()
             WHERE id = $1
+            AND deleted_at IS NULL
             "#,
             self.id,
         )
@@ -930,3 +1282,119 @@ pub use insert_query::{Dummy, InsertQuery};
 pub use minigroup::*;
 pub use p2p::*;
 pub use webinar::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::prelude::*;
+
+    async fn transitions(
+        conn: &mut PgConnection,
+        class_id: Uuid,
+    ) -> Vec<(ClassStatus, ClassStatus)> {
+        sqlx::query_as(
+            "SELECT from_status, to_status FROM class_status_transition WHERE class_id = $1 ORDER BY created_at",
+        )
+        .bind(class_id)
+        .fetch_all(conn)
+        .await
+        .expect("Failed to fetch transitions")
+    }
+
+    #[tokio::test]
+    async fn update_status_moves_forward_only() {
+        let db = TestDb::new().await;
+        let mut conn = db.get_conn().await;
+
+        let webinar = factory::Webinar::new(
+            random_string(),
+            USR_AUDIENCE.to_string(),
+            (Bound::Unbounded, Bound::Unbounded).into(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        )
+        .insert(&mut conn)
+        .await;
+        assert_eq!(webinar.status(), ClassStatus::RealTime);
+
+        let updated = UpdateStatusQuery::new(webinar.id(), ClassStatus::Adjusted)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to update status");
+        assert!(updated);
+
+        let updated = UpdateStatusQuery::new(webinar.id(), ClassStatus::Finished)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to update status");
+        assert!(!updated);
+
+        let updated = UpdateStatusQuery::new(webinar.id(), ClassStatus::Adjusted)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to update status");
+        assert!(!updated);
+
+        let class = ReadQuery::by_id(webinar.id())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to read class")
+            .expect("Class not found");
+        assert_eq!(class.status(), ClassStatus::Adjusted);
+
+        let updated = UpdateStatusQuery::new(webinar.id(), ClassStatus::RealTime)
+            .force()
+            .execute(&mut conn)
+            .await
+            .expect("Failed to update status");
+        assert!(updated);
+
+        assert_eq!(
+            transitions(&mut conn, webinar.id()).await,
+            vec![
+                (ClassStatus::RealTime, ClassStatus::Adjusted),
+                (ClassStatus::Adjusted, ClassStatus::RealTime),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn original_id_by_room_finds_class_by_any_room() {
+        let db = TestDb::new().await;
+        let mut conn = db.get_conn().await;
+
+        let (conference_room_id, event_room_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let (original_event_room_id, modified_event_room_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let minigroup = factory::Minigroup::new(
+            random_string(),
+            USR_AUDIENCE.to_string(),
+            (Bound::Unbounded, Bound::Unbounded).into(),
+            conference_room_id,
+            event_room_id,
+        )
+        .original_event_room_id(original_event_room_id)
+        .modified_event_room_id(modified_event_room_id)
+        .insert(&mut conn)
+        .await;
+
+        for room_id in &[
+            conference_room_id,
+            event_room_id,
+            original_event_room_id,
+            modified_event_room_id,
+        ] {
+            let class_id = OriginalIdByRoomQuery::new(*room_id)
+                .execute(&mut conn)
+                .await
+                .expect("Failed to find class id");
+            assert_eq!(class_id, Some(minigroup.id()));
+        }
+
+        let class_id = OriginalIdByRoomQuery::new(Uuid::new_v4())
+            .execute(&mut conn)
+            .await
+            .expect("Failed to find class id");
+        assert_eq!(class_id, None);
+    }
+}
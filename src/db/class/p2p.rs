@@ -5,7 +5,7 @@ use serde_json::Value as JsonValue;
 use sqlx::postgres::{types::PgRange, PgConnection};
 use uuid::Uuid;
 
-use super::{AgentId, ClassType, KeyValueProperties, Object, Time};
+use super::{AgentId, ClassStatus, ClassType, KeyValueProperties, Object, Time};
 
 pub struct P2PInsertQuery {
     scope: String,
@@ -77,7 +77,8 @@ impl P2PInsertQuery {
                 host AS "host: AgentId",
                 timed_out,
                 original_class_id,
-                content_id
+                content_id,
+                status AS "status!: ClassStatus"
             "#,
             self.scope,
             self.audience,
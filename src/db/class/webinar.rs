@@ -5,7 +5,7 @@ use serde_json::Value as JsonValue;
 use sqlx::postgres::{types::PgRange, PgConnection};
 use uuid::Uuid;
 
-use super::{AgentId, ClassType, KeyValueProperties, Object, Time, WrongKind};
+use super::{AgentId, ClassStatus, ClassType, KeyValueProperties, Object, Time, WrongKind};
 #[cfg(test)]
 use super::{GenericReadQuery, WebinarType};
 
@@ -78,6 +78,7 @@ pub struct WebinarInsertQuery {
     modified_event_room_id: Option<Uuid>,
     reserve: Option<i32>,
     room_events_uri: Option<String>,
+    status: ClassStatus,
 }
 
 impl WebinarInsertQuery {
@@ -101,6 +102,7 @@ impl WebinarInsertQuery {
             modified_event_room_id: None,
             reserve: None,
             room_events_uri: None,
+            status: ClassStatus::RealTime,
         }
     }
 
@@ -132,6 +134,10 @@ impl WebinarInsertQuery {
         }
     }
 
+    pub fn status(self, status: ClassStatus) -> Self {
+        Self { status, ..self }
+    }
+
     #[cfg(test)]
     pub fn reserve(self, reserve: i32) -> Self {
         Self {
@@ -150,9 +156,9 @@ impl WebinarInsertQuery {
                 scope, audience, time, tags, preserve_history, kind,
                 conference_room_id, event_room_id,
                 original_event_room_id, modified_event_room_id, reserve, room_events_uri,
-                properties
+                properties, status
             )
-            VALUES ($1, $2, $3, $4, $5, $6::class_type, $7, $8, $9, $10, $11, $12, $13)
+            VALUES ($1, $2, $3, $4, $5, $6::class_type, $7, $8, $9, $10, $11, $12, $13, $14)
             RETURNING
                 id,
                 scope,
@@ -172,7 +178,8 @@ impl WebinarInsertQuery {
                 host AS "host: AgentId",
                 timed_out,
                 original_class_id,
-                content_id
+                content_id,
+                status AS "status!: ClassStatus"
             "#,
             self.scope,
             self.audience,
@@ -187,6 +194,7 @@ impl WebinarInsertQuery {
             self.reserve,
             self.room_events_uri,
             self.properties.unwrap_or_default() as KeyValueProperties,
+            self.status as ClassStatus,
         )
         .fetch_one(conn)
         .await
@@ -0,0 +1,290 @@
+use chrono::serde::{ts_seconds, ts_seconds_option};
+use chrono::{DateTime, Utc};
+use serde_derive::Serialize;
+use serde_json::Value as JsonValue;
+use sqlx::postgres::PgConnection;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// An incoming event whose handler failed. `payload` and `properties` are kept as they came
+/// so that the event can be replayed.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[schema(as = DeadLetter)]
+pub struct Object {
+    id: Uuid,
+    label: String,
+    topic: String,
+    payload: String,
+    #[schema(value_type = Object)]
+    properties: JsonValue,
+    error: String,
+    attempts: i32,
+    #[serde(with = "ts_seconds")]
+    #[schema(value_type = i64)]
+    created_at: DateTime<Utc>,
+    #[serde(with = "ts_seconds")]
+    #[schema(value_type = i64)]
+    updated_at: DateTime<Utc>,
+    #[serde(with = "ts_seconds_option", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<i64>)]
+    replayed_at: Option<DateTime<Utc>>,
+    #[serde(with = "ts_seconds_option", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<i64>)]
+    resolved_at: Option<DateTime<Utc>>,
+}
+
+impl Object {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    pub fn payload(&self) -> &str {
+        &self.payload
+    }
+
+    pub fn properties(&self) -> &JsonValue {
+        &self.properties
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        self.resolved_at.is_some()
+    }
+
+    #[cfg(test)]
+    pub fn error(&self) -> &str {
+        &self.error
+    }
+
+    #[cfg(test)]
+    pub fn attempts(&self) -> i32 {
+        self.attempts
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Stores the failed event or bumps `attempts` when the same event has already failed.
+pub struct UpsertQuery {
+    key: String,
+    label: String,
+    topic: String,
+    payload: String,
+    properties: JsonValue,
+    error: String,
+}
+
+impl UpsertQuery {
+    pub fn new(
+        key: String,
+        label: String,
+        topic: String,
+        payload: String,
+        properties: JsonValue,
+        error: String,
+    ) -> Self {
+        Self {
+            key,
+            label,
+            topic,
+            payload,
+            properties,
+            error,
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Object> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            INSERT INTO dead_letter (key, label, topic, payload, properties, error)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (key) DO UPDATE
+            SET error = EXCLUDED.error,
+                attempts = dead_letter.attempts + 1,
+                updated_at = NOW(),
+                resolved_at = NULL
+            RETURNING
+                id,
+                label,
+                topic,
+                payload,
+                properties,
+                error,
+                attempts,
+                created_at,
+                updated_at,
+                replayed_at,
+                resolved_at
+            "#,
+            self.key,
+            self.label,
+            self.topic,
+            self.payload,
+            self.properties,
+            self.error,
+        )
+        .fetch_one(conn)
+        .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Marks the dead letter of the event resolved if there's one.
+pub struct ResolveQuery {
+    key: String,
+}
+
+impl ResolveQuery {
+    pub fn new(key: String) -> Self {
+        Self { key }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE dead_letter
+            SET resolved_at = NOW(),
+                updated_at = NOW()
+            WHERE key = $1
+            AND resolved_at IS NULL
+            "#,
+            self.key,
+        )
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct ReadQuery {
+    id: Uuid,
+}
+
+impl ReadQuery {
+    pub fn by_id(id: Uuid) -> Self {
+        Self { id }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Option<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            SELECT
+                id,
+                label,
+                topic,
+                payload,
+                properties,
+                error,
+                attempts,
+                created_at,
+                updated_at,
+                replayed_at,
+                resolved_at
+            FROM dead_letter
+            WHERE id = $1
+            "#,
+            self.id,
+        )
+        .fetch_optional(conn)
+        .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct ListQuery {
+    label: Option<String>,
+    resolved: Option<bool>,
+    limit: i64,
+}
+
+impl ListQuery {
+    pub fn new(limit: i64) -> Self {
+        Self {
+            label: None,
+            resolved: None,
+            limit,
+        }
+    }
+
+    pub fn label(self, label: String) -> Self {
+        Self {
+            label: Some(label),
+            ..self
+        }
+    }
+
+    pub fn resolved(self, resolved: bool) -> Self {
+        Self {
+            resolved: Some(resolved),
+            ..self
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            SELECT
+                id,
+                label,
+                topic,
+                payload,
+                properties,
+                error,
+                attempts,
+                created_at,
+                updated_at,
+                replayed_at,
+                resolved_at
+            FROM dead_letter
+            WHERE ($1::text IS NULL OR label = $1)
+            AND ($2::boolean IS NULL OR (resolved_at IS NOT NULL) = $2)
+            ORDER BY updated_at DESC
+            LIMIT $3
+            "#,
+            self.label,
+            self.resolved,
+            self.limit,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct MarkReplayedQuery {
+    id: Uuid,
+}
+
+impl MarkReplayedQuery {
+    pub fn new(id: Uuid) -> Self {
+        Self { id }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE dead_letter
+            SET replayed_at = NOW(),
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+            self.id,
+        )
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,23 +1,44 @@
 use chrono::{serde::ts_seconds, DateTime, Utc};
 use serde_derive::{Deserialize, Serialize};
 use sqlx::postgres::PgConnection;
+use utoipa::ToSchema;
 
 ///////////////////////////////////////////////////////////////////////////////
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[schema(as = Frontend)]
 pub struct Object {
     pub id: i64,
     pub url: String,
     #[serde(with = "ts_seconds")]
+    #[schema(value_type = i64)]
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug)]
-pub struct ListQuery {}
+#[derive(Debug, Default)]
+pub struct ListQuery {
+    after: Option<i64>,
+    limit: Option<i64>,
+}
 
 impl ListQuery {
     pub fn new() -> Self {
-        Self {}
+        Default::default()
+    }
+
+    /// Continues listing after the frontend with the given id.
+    pub fn after(self, id: i64) -> Self {
+        Self {
+            after: Some(id),
+            ..self
+        }
+    }
+
+    pub fn limit(self, limit: i64) -> Self {
+        Self {
+            limit: Some(limit),
+            ..self
+        }
     }
 
     pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
@@ -26,7 +47,12 @@ impl ListQuery {
             r#"
             SELECT *
             FROM frontend
+            WHERE ($1::bigint IS NULL OR id > $1)
+            ORDER BY id
+            LIMIT $2
             "#,
+            self.after,
+            self.limit,
         )
         .fetch_all(conn)
         .await
@@ -61,3 +87,82 @@ impl FrontendByScopeQuery {
         .await
     }
 }
+
+#[derive(Debug)]
+pub struct InsertQuery {
+    url: String,
+}
+
+impl InsertQuery {
+    pub fn new(url: String) -> Self {
+        Self { url }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Object> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            INSERT INTO frontend (url)
+            VALUES ($1)
+            RETURNING id, url, created_at
+            "#,
+            self.url,
+        )
+        .fetch_one(conn)
+        .await
+    }
+}
+
+#[derive(Debug)]
+pub struct UpdateQuery {
+    id: i64,
+    url: String,
+}
+
+impl UpdateQuery {
+    pub fn new(id: i64, url: String) -> Self {
+        Self { id, url }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Option<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            UPDATE frontend
+            SET url = $2
+            WHERE id = $1
+            RETURNING id, url, created_at
+            "#,
+            self.id,
+            self.url,
+        )
+        .fetch_optional(conn)
+        .await
+    }
+}
+
+/// Deletes the frontend along with the scopes bound to it.
+#[derive(Debug)]
+pub struct DeleteQuery {
+    id: i64,
+}
+
+impl DeleteQuery {
+    pub fn new(id: i64) -> Self {
+        Self { id }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Option<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            DELETE FROM frontend
+            WHERE id = $1
+            RETURNING id, url, created_at
+            "#,
+            self.id,
+        )
+        .fetch_optional(conn)
+        .await
+    }
+}
@@ -0,0 +1,147 @@
+use chrono::{serde::ts_seconds, DateTime, Utc};
+use serde_derive::Serialize;
+use sqlx::postgres::PgConnection;
+use utoipa::ToSchema;
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[schema(as = FrontendRollout)]
+pub struct Object {
+    pub id: i64,
+    pub tenant: String,
+    pub app: String,
+    pub frontend_id: i64,
+    pub percent: i16,
+    #[serde(with = "ts_seconds")]
+    #[schema(value_type = i64)]
+    pub created_at: DateTime<Utc>,
+}
+
+/// The rollout of the tenant app along with the url of the frontend it rolls out.
+#[derive(Debug)]
+pub struct Target {
+    pub percent: i16,
+    pub url: String,
+}
+
+#[derive(Debug)]
+pub struct ListQuery {}
+
+impl ListQuery {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            SELECT id, tenant, app, frontend_id, percent, created_at
+            FROM frontend_rollout
+            ORDER BY tenant, app
+            "#,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
+
+#[derive(Debug)]
+pub struct TargetQuery {
+    tenant: String,
+    app: String,
+}
+
+impl TargetQuery {
+    pub fn new(tenant: String, app: String) -> Self {
+        Self { tenant, app }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Option<Target>> {
+        sqlx::query_as!(
+            Target,
+            r#"
+            SELECT r.percent, fe.url
+            FROM frontend_rollout r
+            INNER JOIN frontend fe
+            ON fe.id = r.frontend_id
+            WHERE r.tenant = $1 AND r.app = $2
+            "#,
+            self.tenant,
+            self.app,
+        )
+        .fetch_optional(conn)
+        .await
+    }
+}
+
+/// Sets the rollout of the tenant app replacing the current one if any.
+/// Returns nothing when there's no such frontend.
+#[derive(Debug)]
+pub struct UpsertQuery {
+    tenant: String,
+    app: String,
+    frontend_id: i64,
+    percent: i16,
+}
+
+impl UpsertQuery {
+    pub fn new(tenant: String, app: String, frontend_id: i64, percent: i16) -> Self {
+        Self {
+            tenant,
+            app,
+            frontend_id,
+            percent,
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Option<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            INSERT INTO frontend_rollout (tenant, app, frontend_id, percent)
+            SELECT $1, $2, id, $4
+            FROM frontend
+            WHERE id = $3
+            ON CONFLICT (tenant, app) DO UPDATE
+            SET frontend_id = EXCLUDED.frontend_id,
+                percent = EXCLUDED.percent
+            RETURNING id, tenant, app, frontend_id, percent, created_at
+            "#,
+            self.tenant,
+            self.app,
+            self.frontend_id,
+            self.percent,
+        )
+        .fetch_optional(conn)
+        .await
+    }
+}
+
+#[derive(Debug)]
+pub struct DeleteQuery {
+    tenant: String,
+    app: String,
+}
+
+impl DeleteQuery {
+    pub fn new(tenant: String, app: String) -> Self {
+        Self { tenant, app }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Option<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            DELETE FROM frontend_rollout
+            WHERE tenant = $1 AND app = $2
+            RETURNING id, tenant, app, frontend_id, percent, created_at
+            "#,
+            self.tenant,
+            self.app,
+        )
+        .fetch_optional(conn)
+        .await
+    }
+}
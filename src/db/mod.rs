@@ -20,9 +20,17 @@ pub(crate) async fn create_pool(
 }
 
 pub(crate) mod account;
+pub(crate) mod advisory_lock;
+pub(crate) mod audit_log;
 pub(crate) mod authz;
 pub(crate) mod class;
+pub(crate) mod dead_letter;
 pub(crate) mod frontend;
+pub(crate) mod frontend_rollout;
+pub(crate) mod outbox;
+pub(crate) mod postprocessing_job;
+pub(crate) mod processed_event;
 pub(crate) mod record_timestamp;
 pub(crate) mod recording;
 pub(crate) mod scope;
+pub(crate) mod webhook_delivery;
@@ -0,0 +1,209 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value as JsonValue;
+use sqlx::postgres::PgConnection;
+
+/// An outgoing event written in the same transaction as the state change it is about.
+#[derive(Clone, Debug)]
+pub struct Object {
+    id: i64,
+    label: String,
+    path: String,
+    payload: JsonValue,
+}
+
+impl Object {
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn payload(&self) -> &JsonValue {
+        &self.payload
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct InsertQuery {
+    label: String,
+    path: String,
+    payload: JsonValue,
+}
+
+impl InsertQuery {
+    pub fn new(label: String, path: String, payload: JsonValue) -> Self {
+        Self {
+            label,
+            path,
+            payload,
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Object> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            INSERT INTO outbox (label, path, payload)
+            VALUES ($1, $2, $3)
+            RETURNING
+                id,
+                label,
+                path,
+                payload
+            "#,
+            self.label,
+            self.path,
+            self.payload,
+        )
+        .fetch_one(conn)
+        .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Locks unsent events created before `created_before` in their creation order.
+/// Concurrent callers skip the locked ones so must be run in a transaction.
+pub struct TakeUnsentQuery {
+    created_before: DateTime<Utc>,
+    limit: i64,
+}
+
+impl TakeUnsentQuery {
+    pub fn new(created_before: DateTime<Utc>, limit: i64) -> Self {
+        Self {
+            created_before,
+            limit,
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            SELECT
+                id,
+                label,
+                path,
+                payload
+            FROM outbox
+            WHERE sent_at IS NULL
+            AND created_at < $1
+            ORDER BY created_at, id
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+            "#,
+            self.created_before,
+            self.limit,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct MarkSentQuery {
+    ids: Vec<i64>,
+}
+
+impl MarkSentQuery {
+    pub fn new(ids: Vec<i64>) -> Self {
+        Self { ids }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE outbox
+            SET sent_at = NOW()
+            WHERE id = ANY($1)
+            AND sent_at IS NULL
+            "#,
+            &self.ids,
+        )
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct DeleteSentQuery {
+    sent_before: DateTime<Utc>,
+}
+
+impl DeleteSentQuery {
+    pub fn new(sent_before: DateTime<Utc>) -> Self {
+        Self { sent_before }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<u64> {
+        let r = sqlx::query!(
+            r#"
+            DELETE FROM outbox
+            WHERE sent_at < $1
+            "#,
+            self.sent_before,
+        )
+        .execute(conn)
+        .await?;
+
+        Ok(r.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::prelude::*;
+
+    #[tokio::test]
+    async fn take_unsent_skips_sent_and_fresh_events() {
+        let db_pool = TestDb::new().await;
+        let mut conn = db_pool.get_conn().await;
+
+        let insert = |label: &str| {
+            InsertQuery::new(
+                label.to_owned(),
+                "audiences/example.org/events".to_owned(),
+                serde_json::json!({ "id": random_string() }),
+            )
+        };
+
+        let sent = insert("webinar.stop").execute(&mut conn).await.unwrap();
+        let unsent = insert("webinar.stop").execute(&mut conn).await.unwrap();
+
+        MarkSentQuery::new(vec![sent.id()])
+            .execute(&mut conn)
+            .await
+            .expect("Failed to mark event sent");
+
+        let taken = TakeUnsentQuery::new(Utc::now() - chrono::Duration::minutes(1), 1000)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to take unsent events");
+        assert!(taken.iter().all(|e| e.id() != unsent.id()));
+
+        let taken = TakeUnsentQuery::new(Utc::now() + chrono::Duration::seconds(1), 1000)
+            .execute(&mut conn)
+            .await
+            .expect("Failed to take unsent events");
+        assert!(taken.iter().any(|e| e.id() == unsent.id()));
+        assert!(taken.iter().all(|e| e.id() != sent.id()));
+
+        MarkSentQuery::new(vec![unsent.id()])
+            .execute(&mut conn)
+            .await
+            .expect("Failed to mark event sent");
+    }
+}
@@ -0,0 +1,434 @@
+use chrono::serde::{ts_seconds, ts_seconds_option};
+use chrono::{DateTime, Utc};
+use serde_derive::Serialize;
+use serde_json::Value as JsonValue;
+use sqlx::postgres::PgConnection;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::clients::tq::Priority;
+
+#[derive(Clone, Copy, Debug, sqlx::Type, PartialEq, Eq, Serialize, ToSchema)]
+#[sqlx(type_name = "postprocessing_job_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[schema(as = PostprocessingJobStatus)]
+pub enum Status {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+/// A tq task sent for a class. Retrying the task bumps `attempts`.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[schema(as = PostprocessingJob)]
+pub struct Object {
+    id: Uuid,
+    class_id: Uuid,
+    template: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_id: Option<Uuid>,
+    priority: Priority,
+    #[schema(value_type = PostprocessingJobStatus)]
+    status: Status,
+    attempts: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Value>)]
+    last_error: Option<JsonValue>,
+    #[serde(with = "ts_seconds")]
+    #[schema(value_type = i64)]
+    created_at: DateTime<Utc>,
+    #[serde(with = "ts_seconds")]
+    #[schema(value_type = i64)]
+    updated_at: DateTime<Utc>,
+    #[serde(with = "ts_seconds_option", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<i64>)]
+    completed_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    task: Option<JsonValue>,
+    #[serde(with = "ts_seconds_option", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<i64>)]
+    retry_at: Option<DateTime<Utc>>,
+}
+
+impl Object {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn class_id(&self) -> Uuid {
+        self.class_id
+    }
+
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    pub fn stream_id(&self) -> Option<Uuid> {
+        self.stream_id
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    pub fn attempts(&self) -> i32 {
+        self.attempts
+    }
+
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+
+    /// Serialized `clients::tq::Task` to resubmit the job with.
+    pub fn task(&self) -> Option<&JsonValue> {
+        self.task.as_ref()
+    }
+
+    #[cfg(test)]
+    pub fn retry_at(&self) -> Option<DateTime<Utc>> {
+        self.retry_at
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Saves the job of a task being sent. Sending the task anew, e.g. on an operator restart,
+/// starts the job over with a new id and a fresh retry budget so that its completions
+/// never look like the ones of the previous run. A retry keeps the id and bumps `attempts`.
+pub struct UpsertQuery {
+    class_id: Uuid,
+    template: String,
+    stream_id: Option<Uuid>,
+    priority: Priority,
+    task: Option<JsonValue>,
+    retry: bool,
+}
+
+impl UpsertQuery {
+    pub fn new(
+        class_id: Uuid,
+        template: String,
+        stream_id: Option<Uuid>,
+        priority: Priority,
+    ) -> Self {
+        Self {
+            class_id,
+            template,
+            stream_id,
+            priority,
+            task: None,
+            retry: false,
+        }
+    }
+
+    pub fn task(self, task: JsonValue) -> Self {
+        Self {
+            task: Some(task),
+            ..self
+        }
+    }
+
+    pub fn retry(self) -> Self {
+        Self {
+            retry: true,
+            ..self
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Object> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            INSERT INTO postprocessing_job (class_id, template, stream_id, priority, task)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (
+                class_id,
+                template,
+                COALESCE(stream_id, '00000000-0000-0000-0000-000000000000'::uuid)
+            )
+            DO UPDATE SET
+                id = CASE WHEN $6 THEN postprocessing_job.id ELSE EXCLUDED.id END,
+                priority = EXCLUDED.priority,
+                status = 'pending',
+                attempts = CASE WHEN $6 THEN postprocessing_job.attempts + 1 ELSE 1 END,
+                task = COALESCE(EXCLUDED.task, postprocessing_job.task),
+                completed_at = NULL,
+                retry_at = NULL,
+                updated_at = NOW()
+            RETURNING
+                id,
+                class_id,
+                template,
+                stream_id,
+                priority AS "priority!: Priority",
+                status AS "status!: Status",
+                attempts,
+                last_error,
+                created_at,
+                updated_at,
+                completed_at,
+                task,
+                retry_at
+            "#,
+            self.class_id,
+            self.template,
+            self.stream_id,
+            self.priority as Priority,
+            self.task,
+            self.retry,
+        )
+        .fetch_one(conn)
+        .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Completes the pending job of the class with the given template and stream id.
+pub struct CompleteQuery {
+    class_id: Uuid,
+    template: String,
+    stream_id: Option<Uuid>,
+    status: Status,
+    error: Option<JsonValue>,
+}
+
+impl CompleteQuery {
+    pub fn succeeded(class_id: Uuid, template: String, stream_id: Option<Uuid>) -> Self {
+        Self {
+            class_id,
+            template,
+            stream_id,
+            status: Status::Succeeded,
+            error: None,
+        }
+    }
+
+    pub fn failed(
+        class_id: Uuid,
+        template: String,
+        stream_id: Option<Uuid>,
+        error: JsonValue,
+    ) -> Self {
+        Self {
+            class_id,
+            template,
+            stream_id,
+            status: Status::Failed,
+            error: Some(error),
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Option<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            UPDATE postprocessing_job
+            SET status = $4,
+                last_error = COALESCE($5, last_error),
+                completed_at = NOW(),
+                updated_at = NOW()
+            WHERE class_id = $1
+            AND template = $2
+            AND stream_id IS NOT DISTINCT FROM $3
+            AND status = 'pending'
+            RETURNING
+                id,
+                class_id,
+                template,
+                stream_id,
+                priority AS "priority!: Priority",
+                status AS "status!: Status",
+                attempts,
+                last_error,
+                created_at,
+                updated_at,
+                completed_at,
+                task,
+                retry_at
+            "#,
+            self.class_id,
+            self.template,
+            self.stream_id,
+            self.status as Status,
+            self.error,
+        )
+        .fetch_optional(conn)
+        .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct ListQuery {
+    class_id: Uuid,
+}
+
+impl ListQuery {
+    pub fn new(class_id: Uuid) -> Self {
+        Self { class_id }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            SELECT
+                id,
+                class_id,
+                template,
+                stream_id,
+                priority AS "priority!: Priority",
+                status AS "status!: Status",
+                attempts,
+                last_error,
+                created_at,
+                updated_at,
+                completed_at,
+                task,
+                retry_at
+            FROM postprocessing_job
+            WHERE class_id = $1
+            ORDER BY created_at
+            "#,
+            self.class_id,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct ScheduleRetryQuery {
+    id: Uuid,
+    retry_at: DateTime<Utc>,
+}
+
+impl ScheduleRetryQuery {
+    pub fn new(id: Uuid, retry_at: DateTime<Utc>) -> Self {
+        Self { id, retry_at }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE postprocessing_job
+            SET retry_at = $2,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+            self.id,
+            self.retry_at,
+        )
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Counts a resubmission that failed before reaching tq against the job attempts.
+/// Skips the job when it has been updated since `updated_at`, e.g. by the failed tq request.
+pub struct ResubmitFailedQuery {
+    id: Uuid,
+    updated_at: DateTime<Utc>,
+    error: JsonValue,
+}
+
+impl ResubmitFailedQuery {
+    pub fn new(id: Uuid, updated_at: DateTime<Utc>, error: JsonValue) -> Self {
+        Self {
+            id,
+            updated_at,
+            error,
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Option<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            UPDATE postprocessing_job
+            SET attempts = attempts + 1,
+                last_error = $2,
+                updated_at = NOW()
+            WHERE id = $1
+            AND updated_at = $3
+            AND status = 'failed'
+            RETURNING
+                id,
+                class_id,
+                template,
+                stream_id,
+                priority AS "priority!: Priority",
+                status AS "status!: Status",
+                attempts,
+                last_error,
+                created_at,
+                updated_at,
+                completed_at,
+                task,
+                retry_at
+            "#,
+            self.id,
+            self.error,
+            self.updated_at,
+        )
+        .fetch_optional(conn)
+        .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Picks failed jobs whose retry time has come and unschedules them
+/// so that concurrent callers never get the same job.
+pub struct TakeDueRetriesQuery {
+    limit: i64,
+}
+
+impl TakeDueRetriesQuery {
+    pub fn new(limit: i64) -> Self {
+        Self { limit }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            UPDATE postprocessing_job
+            SET retry_at = NULL,
+                updated_at = NOW()
+            WHERE id IN (
+                SELECT id
+                FROM postprocessing_job
+                WHERE status = 'failed'
+                AND retry_at <= NOW()
+                ORDER BY retry_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING
+                id,
+                class_id,
+                template,
+                stream_id,
+                priority AS "priority!: Priority",
+                status AS "status!: Status",
+                attempts,
+                last_error,
+                created_at,
+                updated_at,
+                completed_at,
+                task,
+                retry_at
+            "#,
+            self.limit,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
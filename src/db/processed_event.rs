@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgConnection;
+
+/// Claims the event key until `expires_at`. Returns `false` when the key has already been
+/// claimed and is not expired yet which means the event is a duplicate.
+pub struct ClaimQuery {
+    key: String,
+    label: String,
+    expires_at: DateTime<Utc>,
+}
+
+impl ClaimQuery {
+    pub fn new(key: String, label: String, expires_at: DateTime<Utc>) -> Self {
+        Self {
+            key,
+            label,
+            expires_at,
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<bool> {
+        let r = sqlx::query!(
+            r#"
+            INSERT INTO processed_event (key, label, expires_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (key) DO UPDATE
+            SET label = EXCLUDED.label,
+                created_at = NOW(),
+                expires_at = EXCLUDED.expires_at
+            WHERE processed_event.expires_at < NOW()
+            "#,
+            self.key,
+            self.label,
+            self.expires_at,
+        )
+        .execute(conn)
+        .await?;
+
+        Ok(r.rows_affected() > 0)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Moves the expiration of a claimed event key.
+pub struct ProlongQuery {
+    key: String,
+    expires_at: DateTime<Utc>,
+}
+
+impl ProlongQuery {
+    pub fn new(key: String, expires_at: DateTime<Utc>) -> Self {
+        Self { key, expires_at }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<()> {
+        sqlx::query!(
+            "UPDATE processed_event SET expires_at = $2 WHERE key = $1",
+            self.key,
+            self.expires_at,
+        )
+        .execute(conn)
+        .await
+        .map(|_| ())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct DeleteQuery {
+    key: String,
+}
+
+impl DeleteQuery {
+    pub fn new(key: String) -> Self {
+        Self { key }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<()> {
+        sqlx::query!("DELETE FROM processed_event WHERE key = $1", self.key)
+            .execute(conn)
+            .await
+            .map(|_| ())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct DeleteExpiredQuery {
+    expired_before: DateTime<Utc>,
+}
+
+impl DeleteExpiredQuery {
+    pub fn new(expired_before: DateTime<Utc>) -> Self {
+        Self { expired_before }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<u64> {
+        let r = sqlx::query!(
+            "DELETE FROM processed_event WHERE expires_at < $1",
+            self.expired_before
+        )
+        .execute(conn)
+        .await?;
+
+        Ok(r.rows_affected())
+    }
+}
@@ -86,3 +86,23 @@ impl FindQuery {
         .await
     }
 }
+
+pub struct DeleteQuery {
+    class_id: Uuid,
+}
+
+impl DeleteQuery {
+    pub fn new(class_id: Uuid) -> Self {
+        Self { class_id }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<usize> {
+        sqlx::query!(
+            "DELETE FROM record_timestamp WHERE class_id = $1",
+            self.class_id
+        )
+        .execute(conn)
+        .await
+        .map(|r| r.rows_affected() as usize)
+    }
+}
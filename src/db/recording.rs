@@ -1,5 +1,6 @@
 use std::ops::Bound;
 
+use chrono::serde::{ts_seconds, ts_seconds_option};
 use chrono::{DateTime, Utc};
 use sqlx::postgres::{types::PgRange, PgConnection};
 use svc_agent::AgentId;
@@ -9,25 +10,31 @@ use serde_derive::{Deserialize, Serialize};
 
 ////////////////////////////////////////////////////////////////////////////////
 
-#[allow(dead_code)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct Object {
     id: Uuid,
     class_id: Uuid,
     rtc_id: Uuid,
     stream_uri: Option<String>,
+    #[serde(with = "serde::segments_option")]
     segments: Option<Segments>,
+    #[serde(with = "serde::segments_option")]
     modified_segments: Option<Segments>,
+    #[serde(with = "ts_seconds_option")]
     started_at: Option<DateTime<Utc>>,
+    #[serde(with = "ts_seconds")]
     created_at: DateTime<Utc>,
+    #[serde(with = "ts_seconds_option")]
     adjusted_at: Option<DateTime<Utc>>,
+    #[serde(with = "ts_seconds_option")]
     transcoded_at: Option<DateTime<Utc>>,
     created_by: AgentId,
+    #[serde(with = "ts_seconds_option")]
     deleted_at: Option<DateTime<Utc>>,
+    host_selection: Option<HostSelection>,
 }
 
 impl Object {
-    #[cfg(test)]
     pub fn id(&self) -> Uuid {
         self.id
     }
@@ -56,6 +63,7 @@ impl Object {
         self.modified_segments.as_ref().or_else(|| self.segments())
     }
 
+    #[cfg(test)]
     pub fn adjusted_at(&self) -> Option<DateTime<Utc>> {
         self.adjusted_at
     }
@@ -64,9 +72,43 @@ impl Object {
         self.transcoded_at
     }
 
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
     pub fn created_by(&self) -> &AgentId {
         &self.created_by
     }
+
+    #[cfg(test)]
+    pub fn host_selection(&self) -> Option<HostSelection> {
+        self.host_selection
+    }
+}
+
+/// The recording the class is presented by: the host's one once the host has been selected,
+/// the earliest one otherwise. Ties go to the least rtc id so the choice never depends
+/// on the order the recordings are listed in.
+pub fn main_recording(recordings: &[Object]) -> Option<&Object> {
+    recordings.iter().min_by_key(|recording| {
+        (
+            recording.host_selection.is_none(),
+            recording.started_at.is_none(),
+            recording.started_at,
+            recording.rtc_id,
+        )
+    })
+}
+
+/// How the host the class recordings get composed around has been chosen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+#[sqlx(type_name = "recording_host_selection", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum HostSelection {
+    HostEvent,
+    ClassHost,
+    LongestRecording,
+    FirstRecording,
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -87,6 +129,57 @@ impl Segments {
     pub fn empty() -> Segments {
         Segments(vec![])
     }
+
+    /// Total length of the bounded segments in milliseconds.
+    pub fn duration(&self) -> i64 {
+        self.0
+            .iter()
+            .map(|range| match (range.start, range.end) {
+                (
+                    Bound::Included(start) | Bound::Excluded(start),
+                    Bound::Included(end) | Bound::Excluded(end),
+                ) => end - start,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Moves the segments `offset` milliseconds later.
+    pub fn shift(&self, offset: i64) -> Segments {
+        let shift = |bound: Bound<i64>| match bound {
+            Bound::Included(t) => Bound::Included(t + offset),
+            Bound::Excluded(t) => Bound::Excluded(t + offset),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+
+        self.0
+            .iter()
+            .map(|range| (shift(range.start), shift(range.end)))
+            .collect::<BoundedOffsetTuples>()
+            .into()
+    }
+
+    /// Cuts out the `[from, to)` part of the segments making `from` their origin.
+    pub fn slice(&self, from: i64, to: i64) -> Segments {
+        self.0
+            .iter()
+            .filter_map(|range| {
+                let start = match range.start {
+                    Bound::Included(t) | Bound::Excluded(t) => t.max(from),
+                    Bound::Unbounded => from,
+                };
+
+                let end = match range.end {
+                    Bound::Included(t) | Bound::Excluded(t) => t.min(to),
+                    Bound::Unbounded => to,
+                };
+
+                (start < end)
+                    .then_some((Bound::Included(start - from), Bound::Excluded(end - from)))
+            })
+            .collect::<BoundedOffsetTuples>()
+            .into()
+    }
 }
 
 impl From<BoundedOffsetTuples> for Segments {
@@ -134,7 +227,8 @@ impl RecordingListQuery {
                 adjusted_at,
                 transcoded_at,
                 created_by AS "created_by: AgentId",
-                deleted_at
+                deleted_at,
+                host_selection AS "host_selection: HostSelection"
             FROM recording
             WHERE class_id = $1 AND deleted_at IS NULL
             "#,
@@ -200,7 +294,7 @@ impl RecordingInsertQuery {
                 transcoded_at, created_by
             )
             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-            ON CONFLICT (class_id, created_by)
+            ON CONFLICT (class_id, rtc_id)
             WHERE deleted_at IS NULL
             DO UPDATE
             SET (rtc_id, stream_uri, segments, modified_segments,
@@ -219,7 +313,8 @@ impl RecordingInsertQuery {
                 adjusted_at,
                 transcoded_at,
                 created_by AS "created_by: AgentId",
-                deleted_at
+                deleted_at,
+                host_selection AS "host_selection: HostSelection"
             "#,
             self.class_id,
             self.rtc_id,
@@ -271,7 +366,8 @@ impl AdjustWebinarUpdateQuery {
                 adjusted_at,
                 transcoded_at,
                 created_by AS "created_by: AgentId",
-                deleted_at
+                deleted_at,
+                host_selection AS "host_selection: HostSelection"
             "#,
             self.webinar_id,
             self.modified_segments as Segments,
@@ -287,6 +383,7 @@ pub struct AdjustMinigroupUpdateQuery {
     minigroup_id: Uuid,
     modified_segments: Segments,
     host: AgentId,
+    host_selection: Option<HostSelection>,
 }
 
 impl AdjustMinigroupUpdateQuery {
@@ -295,6 +392,14 @@ impl AdjustMinigroupUpdateQuery {
             minigroup_id,
             modified_segments,
             host,
+            host_selection: None,
+        }
+    }
+
+    pub fn host_selection(self, host_selection: HostSelection) -> Self {
+        Self {
+            host_selection: Some(host_selection),
+            ..self
         }
     }
 
@@ -308,6 +413,11 @@ impl AdjustMinigroupUpdateQuery {
                     WHEN created_by = $3 THEN $2
                     ELSE segments
                 END,
+                host_selection =
+                    CASE
+                        WHEN created_by = $3 THEN $4::recording_host_selection
+                        ELSE NULL
+                    END,
                 adjusted_at = NOW()
             WHERE class_id = $1 AND deleted_at IS NULL
             RETURNING
@@ -322,11 +432,13 @@ impl AdjustMinigroupUpdateQuery {
                 adjusted_at,
                 transcoded_at,
                 created_by AS "created_by: AgentId",
-                deleted_at
+                deleted_at,
+                host_selection AS "host_selection: HostSelection"
             "#,
             self.minigroup_id,
             self.modified_segments as Segments,
-            self.host as AgentId
+            self.host as AgentId,
+            self.host_selection as Option<HostSelection>,
         )
         .fetch_all(conn)
         .await
@@ -335,6 +447,51 @@ impl AdjustMinigroupUpdateQuery {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+pub struct ModifiedSegmentsUpdateQuery {
+    id: Uuid,
+    modified_segments: Segments,
+}
+
+impl ModifiedSegmentsUpdateQuery {
+    pub fn new(id: Uuid, modified_segments: Segments) -> Self {
+        Self {
+            id,
+            modified_segments,
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Object> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            UPDATE recording
+            SET modified_segments = $2
+            WHERE id = $1
+            RETURNING
+                id,
+                class_id,
+                rtc_id,
+                stream_uri,
+                segments AS "segments!: Option<Segments>",
+                started_at,
+                modified_segments AS "modified_segments!: Option<Segments>",
+                created_at,
+                adjusted_at,
+                transcoded_at,
+                created_by AS "created_by: AgentId",
+                deleted_at,
+                host_selection AS "host_selection: HostSelection"
+            "#,
+            self.id,
+            self.modified_segments as Segments,
+        )
+        .fetch_one(conn)
+        .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 pub struct TranscodingUpdateQuery {
     class_id: Uuid,
 }
@@ -363,7 +520,8 @@ impl TranscodingUpdateQuery {
                 adjusted_at,
                 transcoded_at,
                 created_by AS "created_by: AgentId",
-                deleted_at
+                deleted_at,
+                host_selection AS "host_selection: HostSelection"
             "#,
             self.class_id,
         )
@@ -420,7 +578,8 @@ impl RecordingConvertInsertQuery {
                 adjusted_at,
                 transcoded_at,
                 created_by AS "created_by: AgentId",
-                deleted_at
+                deleted_at,
+                host_selection AS "host_selection: HostSelection"
             "#,
             self.class_id,
             self.rtc_id,
@@ -483,7 +642,8 @@ impl StreamUploadUpdateQuery {
                 adjusted_at,
                 transcoded_at,
                 created_by AS "created_by: AgentId",
-                deleted_at
+                deleted_at,
+                host_selection AS "host_selection: HostSelection"
             "#,
             self.class_id,
             self.rtc_id,
@@ -500,11 +660,23 @@ impl StreamUploadUpdateQuery {
 
 pub struct DeleteQuery {
     class_id: Uuid,
+    except: Option<Uuid>,
 }
 
 impl DeleteQuery {
     pub fn new(class_id: Uuid) -> Self {
-        Self { class_id }
+        Self {
+            class_id,
+            except: None,
+        }
+    }
+
+    /// Keeps the recording with the given rtc id.
+    pub fn except(self, rtc_id: Uuid) -> Self {
+        Self {
+            except: Some(rtc_id),
+            ..self
+        }
     }
 
     pub(crate) async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<usize> {
@@ -514,8 +686,10 @@ impl DeleteQuery {
             UPDATE recording
             SET deleted_at = NOW()
             WHERE class_id = $1 AND deleted_at IS NULL
+            AND ($2::uuid IS NULL OR rtc_id <> $2)
             "#,
             self.class_id,
+            self.except,
         )
         .execute(conn)
         .await
@@ -547,16 +721,28 @@ pub(crate) mod serde {
         }
     }
 
-    pub(crate) fn segments_option<S>(
-        opt: &Option<super::Segments>,
-        serializer: S,
-    ) -> Result<S::Ok, S::Error>
-    where
-        S: serde::ser::Serializer,
-    {
-        match opt {
-            Some(value) => segments::serialize(value, serializer),
-            None => serializer.serialize_none(),
+    pub(crate) mod segments_option {
+        use super::super::Segments;
+        use serde::{de, ser, Deserialize};
+
+        pub(crate) fn serialize<S>(opt: &Option<Segments>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: ser::Serializer,
+        {
+            match opt {
+                Some(value) => super::segments::serialize(value, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub(crate) fn deserialize<'de, D>(d: D) -> Result<Option<Segments>, D::Error>
+        where
+            D: de::Deserializer<'de>,
+        {
+            #[derive(Deserialize)]
+            struct Wrapper(#[serde(with = "super::segments")] Segments);
+
+            Option::<Wrapper>::deserialize(d).map(|w| w.map(|w| w.0))
         }
     }
 }
@@ -608,6 +794,35 @@ pub mod tests {
         assert_eq!(recordings[0].rtc_id(), recording.rtc_id());
     }
 
+    #[test]
+    fn shift_and_slice_segments() {
+        let segments: Segments = vec![
+            (Bound::Included(0), Bound::Excluded(600_000)),
+            (Bound::Included(900_000), Bound::Excluded(2_100_000)),
+        ]
+        .into();
+
+        assert_eq!(
+            segments.shift(1_000),
+            vec![
+                (Bound::Included(1_000), Bound::Excluded(601_000)),
+                (Bound::Included(901_000), Bound::Excluded(2_101_000)),
+            ]
+            .into()
+        );
+
+        assert_eq!(
+            segments.slice(500_000, 1_000_000),
+            vec![
+                (Bound::Included(0), Bound::Excluded(100_000)),
+                (Bound::Included(400_000), Bound::Excluded(500_000)),
+            ]
+            .into()
+        );
+
+        assert_eq!(segments.slice(600_000, 900_000), Segments::empty());
+    }
+
     pub struct RecordingInsertQuery {
         class_id: Uuid,
         rtc_id: Uuid,
@@ -707,7 +922,8 @@ pub mod tests {
                     adjusted_at,
                     transcoded_at,
                     created_by AS "created_by: AgentId",
-                    deleted_at
+                    deleted_at,
+                    host_selection AS "host_selection: HostSelection"
                 "#,
                 self.class_id,
                 self.rtc_id,
@@ -1,25 +1,70 @@
 use chrono::{serde::ts_seconds, DateTime, Utc};
 use serde_derive::{Deserialize, Serialize};
 use sqlx::postgres::PgConnection;
+use utoipa::ToSchema;
 
 ///////////////////////////////////////////////////////////////////////////////
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[schema(as = Scope)]
 pub struct Object {
     pub id: i64,
     pub scope: String,
     pub app: String,
     pub frontend_id: i64,
     #[serde(with = "ts_seconds")]
+    #[schema(value_type = i64)]
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug)]
-pub struct ListQuery {}
+#[derive(Debug, Default)]
+pub struct ListQuery {
+    frontend_id: Option<i64>,
+    app: Option<String>,
+    scope_prefix: Option<String>,
+    after: Option<i64>,
+    limit: Option<i64>,
+}
 
 impl ListQuery {
     pub fn new() -> Self {
-        Self {}
+        Default::default()
+    }
+
+    pub fn frontend_id(self, frontend_id: i64) -> Self {
+        Self {
+            frontend_id: Some(frontend_id),
+            ..self
+        }
+    }
+
+    pub fn app(self, app: String) -> Self {
+        Self {
+            app: Some(app),
+            ..self
+        }
+    }
+
+    pub fn scope_prefix(self, scope_prefix: String) -> Self {
+        Self {
+            scope_prefix: Some(scope_prefix),
+            ..self
+        }
+    }
+
+    /// Continues listing after the scope with the given id.
+    pub fn after(self, id: i64) -> Self {
+        Self {
+            after: Some(id),
+            ..self
+        }
+    }
+
+    pub fn limit(self, limit: i64) -> Self {
+        Self {
+            limit: Some(limit),
+            ..self
+        }
     }
 
     pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
@@ -28,27 +73,94 @@ impl ListQuery {
             r#"
             SELECT *
             FROM scope
+            WHERE ($1::bigint IS NULL OR frontend_id = $1)
+            AND ($2::text IS NULL OR app = $2)
+            AND ($3::text IS NULL OR starts_with(scope, $3))
+            AND ($4::bigint IS NULL OR id > $4)
+            ORDER BY id
+            LIMIT $5
             "#,
+            self.frontend_id,
+            self.app,
+            self.scope_prefix,
+            self.after,
+            self.limit,
         )
         .fetch_all(conn)
         .await
     }
 }
 
+/// Binds the app of the scope to the frontend replacing the current binding if any.
+/// Returns nothing when there's no such frontend.
+#[derive(Debug)]
+pub struct UpsertQuery {
+    scope: String,
+    app: String,
+    frontend_id: i64,
+}
+
+impl UpsertQuery {
+    pub fn new(scope: String, app: String, frontend_id: i64) -> Self {
+        Self {
+            scope,
+            app,
+            frontend_id,
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Option<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            INSERT INTO scope (scope, app, frontend_id)
+            SELECT $1, $2, id
+            FROM frontend
+            WHERE id = $3
+            ON CONFLICT (scope, app) DO UPDATE
+            SET frontend_id = EXCLUDED.frontend_id
+            RETURNING id, scope, frontend_id, created_at, app
+            "#,
+            self.scope,
+            self.app,
+            self.frontend_id,
+        )
+        .fetch_optional(conn)
+        .await
+    }
+}
+
 #[derive(Debug)]
 pub struct DeleteQuery {
     scope: String,
+    app: Option<String>,
 }
 
 impl DeleteQuery {
     pub fn new(scope: String) -> Self {
-        Self { scope }
+        Self { scope, app: None }
+    }
+
+    pub fn app(self, app: String) -> Self {
+        Self {
+            app: Some(app),
+            ..self
+        }
     }
 
-    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<()> {
-        sqlx::query!("DELETE FROM scope WHERE scope = $1", self.scope)
-            .execute(conn)
-            .await
-            .map(|_| ())
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            DELETE FROM scope
+            WHERE scope = $1
+            AND ($2::text IS NULL OR app = $2)
+            RETURNING id, scope, frontend_id, created_at, app
+            "#,
+            self.scope,
+            self.app,
+        )
+        .fetch_all(conn)
+        .await
     }
 }
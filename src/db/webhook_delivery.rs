@@ -0,0 +1,413 @@
+use chrono::serde::{ts_seconds, ts_seconds_option};
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sqlx::postgres::PgConnection;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug, sqlx::Type, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[sqlx(type_name = "webhook_delivery_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[schema(as = WebhookDeliveryStatus)]
+pub enum Status {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+/// A webhook to be sent to the audience endpoint. `payload` is the exact body to sign and send.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[schema(as = WebhookDelivery)]
+pub struct Object {
+    id: Uuid,
+    class_id: Uuid,
+    audience: String,
+    event: String,
+    #[schema(value_type = Object)]
+    payload: JsonValue,
+    #[schema(value_type = WebhookDeliveryStatus)]
+    status: Status,
+    attempts: i32,
+    #[serde(with = "ts_seconds_option", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<i64>)]
+    next_attempt_at: Option<DateTime<Utc>>,
+    #[serde(with = "ts_seconds")]
+    #[schema(value_type = i64)]
+    created_at: DateTime<Utc>,
+    #[serde(with = "ts_seconds")]
+    #[schema(value_type = i64)]
+    updated_at: DateTime<Utc>,
+    #[serde(with = "ts_seconds_option", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<i64>)]
+    delivered_at: Option<DateTime<Utc>>,
+}
+
+impl Object {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn class_id(&self) -> Uuid {
+        self.class_id
+    }
+
+    pub fn audience(&self) -> &str {
+        &self.audience
+    }
+
+    pub fn payload(&self) -> &JsonValue {
+        &self.payload
+    }
+
+    pub fn attempts(&self) -> i32 {
+        self.attempts
+    }
+
+    #[cfg(test)]
+    pub fn event(&self) -> &str {
+        &self.event
+    }
+
+    #[cfg(test)]
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    #[cfg(test)]
+    pub fn next_attempt_at(&self) -> Option<DateTime<Utc>> {
+        self.next_attempt_at
+    }
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[schema(as = WebhookDeliveryAttempt)]
+pub struct Attempt {
+    #[serde(skip)]
+    delivery_id: Uuid,
+    attempt: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(with = "ts_seconds")]
+    #[schema(value_type = i64)]
+    created_at: DateTime<Utc>,
+}
+
+impl Attempt {
+    pub fn delivery_id(&self) -> Uuid {
+        self.delivery_id
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct InsertQuery {
+    class_id: Uuid,
+    audience: String,
+    event: String,
+    payload: JsonValue,
+}
+
+impl InsertQuery {
+    pub fn new(class_id: Uuid, audience: String, event: String, payload: JsonValue) -> Self {
+        Self {
+            class_id,
+            audience,
+            event,
+            payload,
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Object> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            INSERT INTO webhook_delivery (class_id, audience, event, payload)
+            VALUES ($1, $2, $3, $4)
+            RETURNING
+                id,
+                class_id,
+                audience,
+                event,
+                payload,
+                status AS "status!: Status",
+                attempts,
+                next_attempt_at,
+                created_at,
+                updated_at,
+                delivered_at
+            "#,
+            self.class_id,
+            self.audience,
+            self.event,
+            self.payload,
+        )
+        .fetch_one(conn)
+        .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Picks pending deliveries of the audiences whose time has come and leases them
+/// until `lease_until` so that concurrent callers never get the same delivery.
+/// Bumps `attempts`.
+pub struct TakeDueQuery {
+    audiences: Vec<String>,
+    limit: i64,
+    lease_until: DateTime<Utc>,
+}
+
+impl TakeDueQuery {
+    pub fn new(audiences: Vec<String>, limit: i64, lease_until: DateTime<Utc>) -> Self {
+        Self {
+            audiences,
+            limit,
+            lease_until,
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            UPDATE webhook_delivery
+            SET attempts = attempts + 1,
+                next_attempt_at = $2,
+                updated_at = NOW()
+            WHERE id IN (
+                SELECT id
+                FROM webhook_delivery
+                WHERE status = 'pending'
+                AND next_attempt_at <= NOW()
+                AND audience = ANY($3)
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING
+                id,
+                class_id,
+                audience,
+                event,
+                payload,
+                status AS "status!: Status",
+                attempts,
+                next_attempt_at,
+                created_at,
+                updated_at,
+                delivered_at
+            "#,
+            self.limit,
+            self.lease_until,
+            &self.audiences,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct UpdateQuery {
+    id: Uuid,
+    status: Status,
+    next_attempt_at: Option<DateTime<Utc>>,
+}
+
+impl UpdateQuery {
+    pub fn delivered(id: Uuid) -> Self {
+        Self {
+            id,
+            status: Status::Delivered,
+            next_attempt_at: None,
+        }
+    }
+
+    pub fn retry(id: Uuid, next_attempt_at: DateTime<Utc>) -> Self {
+        Self {
+            id,
+            status: Status::Pending,
+            next_attempt_at: Some(next_attempt_at),
+        }
+    }
+
+    pub fn failed(id: Uuid) -> Self {
+        Self {
+            id,
+            status: Status::Failed,
+            next_attempt_at: None,
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE webhook_delivery
+            SET status = $2,
+                next_attempt_at = $3,
+                delivered_at = CASE WHEN $2::webhook_delivery_status = 'delivered' THEN NOW() END,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+            self.id,
+            self.status as Status,
+            self.next_attempt_at,
+        )
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct ListQuery {
+    audience: String,
+    class_id: Option<Uuid>,
+    status: Option<Status>,
+    limit: i64,
+}
+
+impl ListQuery {
+    pub fn new(audience: String, limit: i64) -> Self {
+        Self {
+            audience,
+            class_id: None,
+            status: None,
+            limit,
+        }
+    }
+
+    pub fn class_id(self, class_id: Uuid) -> Self {
+        Self {
+            class_id: Some(class_id),
+            ..self
+        }
+    }
+
+    pub fn status(self, status: Status) -> Self {
+        Self {
+            status: Some(status),
+            ..self
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Object>> {
+        sqlx::query_as!(
+            Object,
+            r#"
+            SELECT
+                id,
+                class_id,
+                audience,
+                event,
+                payload,
+                status AS "status!: Status",
+                attempts,
+                next_attempt_at,
+                created_at,
+                updated_at,
+                delivered_at
+            FROM webhook_delivery
+            WHERE audience = $1
+            AND ($2::uuid IS NULL OR class_id = $2)
+            AND ($3::webhook_delivery_status IS NULL OR status = $3)
+            ORDER BY created_at DESC
+            LIMIT $4
+            "#,
+            self.audience,
+            self.class_id,
+            self.status as Option<Status>,
+            self.limit,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct AttemptInsertQuery {
+    delivery_id: Uuid,
+    attempt: i32,
+    status_code: Option<i32>,
+    error: Option<String>,
+}
+
+impl AttemptInsertQuery {
+    pub fn new(delivery_id: Uuid, attempt: i32) -> Self {
+        Self {
+            delivery_id,
+            attempt,
+            status_code: None,
+            error: None,
+        }
+    }
+
+    pub fn status_code(self, status_code: i32) -> Self {
+        Self {
+            status_code: Some(status_code),
+            ..self
+        }
+    }
+
+    pub fn error(self, error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..self
+        }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO webhook_delivery_attempt (delivery_id, attempt, status_code, error)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            self.delivery_id,
+            self.attempt,
+            self.status_code,
+            self.error,
+        )
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct AttemptListQuery {
+    delivery_ids: Vec<Uuid>,
+}
+
+impl AttemptListQuery {
+    pub fn new(delivery_ids: Vec<Uuid>) -> Self {
+        Self { delivery_ids }
+    }
+
+    pub async fn execute(self, conn: &mut PgConnection) -> sqlx::Result<Vec<Attempt>> {
+        sqlx::query_as!(
+            Attempt,
+            r#"
+            SELECT
+                delivery_id,
+                attempt,
+                status_code,
+                error,
+                created_at
+            FROM webhook_delivery_attempt
+            WHERE delivery_id = ANY($1)
+            ORDER BY delivery_id, attempt
+            "#,
+            &self.delivery_ids,
+        )
+        .fetch_all(conn)
+        .await
+    }
+}
@@ -0,0 +1,56 @@
+#[macro_use]
+extern crate anyhow;
+
+use std::env::var;
+
+use sqlx::postgres::PgPool;
+
+pub const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const APP: &str = env!("CARGO_PKG_NAME");
+
+pub use app::{admin, run};
+
+pub async fn create_db() -> PgPool {
+    let url = var("DATABASE_URL").expect("DATABASE_URL must be specified");
+
+    let size = var("DATABASE_POOL_SIZE")
+        .map(|val| {
+            val.parse::<u32>()
+                .expect("Error converting DATABASE_POOL_SIZE variable into u32")
+        })
+        .unwrap_or(5);
+
+    let idle_size = var("DATABASE_POOL_IDLE_SIZE")
+        .map(|val| {
+            val.parse::<u32>()
+                .expect("Error converting DATABASE_POOL_IDLE_SIZE variable into u32")
+        })
+        .ok();
+
+    let timeout = var("DATABASE_POOL_TIMEOUT")
+        .map(|val| {
+            val.parse::<u64>()
+                .expect("Error converting DATABASE_POOL_TIMEOUT variable into u64")
+        })
+        .unwrap_or(5);
+
+    let max_lifetime = var("DATABASE_POOL_MAX_LIFETIME")
+        .map(|val| {
+            val.parse::<u64>()
+                .expect("Error converting DATABASE_POOL_MAX_LIFETIME variable into u64")
+        })
+        .unwrap_or(1800);
+
+    db::create_pool(&url, size, idle_size, timeout, max_lifetime).await
+}
+
+mod app;
+mod clients;
+mod config;
+mod db;
+#[allow(unused_imports)]
+#[allow(dead_code)]
+mod serde;
+#[cfg(test)]
+mod test_helpers;
+mod utils;
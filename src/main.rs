@@ -1,17 +1,12 @@
-#[macro_use]
-extern crate anyhow;
-
 use std::env::var;
 
 use anyhow::Result;
-use sqlx::postgres::PgPool;
-use svc_authz::cache::{create_pool, AuthzCache, RedisCache};
+use svc_authz::cache::{create_pool, AuthzCache, ConnectionPool, RedisCache};
 use tracing::warn;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::EnvFilter;
 
-const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
-const APP: &str = env!("CARGO_PKG_NAME");
+use dispatcher::{APP, APP_VERSION};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -32,46 +27,15 @@ async fn main() -> Result<()> {
     tracing::subscriber::set_global_default(subscriber)?;
     warn!("Launching {}, version: {}", APP, APP_VERSION);
 
-    let db = create_db().await;
-    let authz_cache = create_redis();
-    app::run(db, authz_cache).await
-}
-
-async fn create_db() -> PgPool {
-    let url = var("DATABASE_URL").expect("DATABASE_URL must be specified");
-
-    let size = var("DATABASE_POOL_SIZE")
-        .map(|val| {
-            val.parse::<u32>()
-                .expect("Error converting DATABASE_POOL_SIZE variable into u32")
-        })
-        .unwrap_or(5);
-
-    let idle_size = var("DATABASE_POOL_IDLE_SIZE")
-        .map(|val| {
-            val.parse::<u32>()
-                .expect("Error converting DATABASE_POOL_IDLE_SIZE variable into u32")
-        })
-        .ok();
-
-    let timeout = var("DATABASE_POOL_TIMEOUT")
-        .map(|val| {
-            val.parse::<u64>()
-                .expect("Error converting DATABASE_POOL_TIMEOUT variable into u64")
-        })
-        .unwrap_or(5);
-
-    let max_lifetime = var("DATABASE_POOL_MAX_LIFETIME")
-        .map(|val| {
-            val.parse::<u64>()
-                .expect("Error converting DATABASE_POOL_MAX_LIFETIME variable into u64")
-        })
-        .unwrap_or(1800);
-
-    crate::db::create_pool(&url, size, idle_size, timeout, max_lifetime).await
+    let db = dispatcher::create_db().await;
+    let authz_cache_pool = create_redis_pool();
+    let authz_cache = authz_cache_pool.clone().map(|pool| {
+        Box::new(RedisCache::new(pool, cache_expiration_time())) as Box<dyn AuthzCache>
+    });
+    dispatcher::run(db, authz_cache, authz_cache_pool).await
 }
 
-fn create_redis() -> Option<Box<dyn AuthzCache>> {
+fn create_redis_pool() -> Option<ConnectionPool> {
     if let Some("1") = var("CACHE_ENABLED").ok().as_deref() {
         let url = var("CACHE_URL").expect("CACHE_URL must be specified");
 
@@ -96,28 +60,17 @@ fn create_redis() -> Option<Box<dyn AuthzCache>> {
             })
             .unwrap_or_else(|_| 5);
 
-        let expiration_time = var("CACHE_EXPIRATION_TIME")
-            .map(|val| {
-                val.parse::<usize>()
-                    .expect("Error converting CACHE_EXPIRATION_TIME variable into u64")
-            })
-            .unwrap_or_else(|_| 300);
-
-        let pool = create_pool(&url, size, idle_size, timeout);
-        let cache = Box::new(RedisCache::new(pool, expiration_time)) as Box<dyn AuthzCache>;
-        Some(cache)
+        Some(create_pool(&url, size, idle_size, timeout))
     } else {
         None
     }
 }
 
-mod app;
-mod clients;
-mod config;
-mod db;
-#[allow(unused_imports)]
-#[allow(dead_code)]
-mod serde;
-#[cfg(test)]
-mod test_helpers;
-mod utils;
+fn cache_expiration_time() -> usize {
+    var("CACHE_EXPIRATION_TIME")
+        .map(|val| {
+            val.parse::<usize>()
+                .expect("Error converting CACHE_EXPIRATION_TIME variable into u64")
+        })
+        .unwrap_or_else(|_| 300)
+}
@@ -270,3 +270,30 @@ pub(crate) mod milliseconds_bound_tuples {
         deserializer.deserialize_seq(MillisecondsBoundTupleVisitor)
     }
 }
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// OpenAPI schema of a bounded time tuple as written by `ts_seconds_bound_tuple`:
+/// `[start, end)` in unix seconds with `null` for an unbounded side.
+pub(crate) struct BoundedTime;
+
+impl<'s> utoipa::ToSchema<'s> for BoundedTime {
+    fn schema() -> (
+        &'s str,
+        utoipa::openapi::RefOr<utoipa::openapi::schema::Schema>,
+    ) {
+        use utoipa::openapi::schema::{ArrayBuilder, ObjectBuilder, SchemaType};
+
+        let bound = ObjectBuilder::new()
+            .schema_type(SchemaType::Integer)
+            .nullable(true);
+
+        let schema = ArrayBuilder::new()
+            .items(bound)
+            .min_items(Some(2))
+            .max_items(Some(2))
+            .description(Some("[start, end) in unix seconds, null when unbounded"));
+
+        ("BoundedTime", schema.into())
+    }
+}
@@ -155,6 +155,7 @@ pub struct Webinar {
     modified_event_room_id: Option<Uuid>,
     reserve: Option<usize>,
     properties: Option<KeyValueProperties>,
+    status: Option<db::class::ClassStatus>,
 }
 
 impl Webinar {
@@ -176,6 +177,21 @@ impl Webinar {
             modified_event_room_id: None,
             reserve: None,
             properties: None,
+            status: None,
+        }
+    }
+
+    pub fn tags(self, tags: JsonValue) -> Self {
+        Self {
+            tags: Some(tags),
+            ..self
+        }
+    }
+
+    pub fn status(self, status: db::class::ClassStatus) -> Self {
+        Self {
+            status: Some(status),
+            ..self
         }
     }
 
@@ -222,6 +238,10 @@ impl Webinar {
             q = q.reserve(reserve as i32);
         }
 
+        if let Some(status) = self.status {
+            q = q.status(status);
+        }
+
         q.execute(conn).await.expect("Failed to insert webinar")
     }
 }
@@ -18,3 +18,23 @@ pub fn random_string() -> String {
 pub fn datetimes_almost_eq<Tz: TimeZone>(dt1: DateTime<Tz>, dt2: DateTime<Tz>) -> bool {
     dt1.trunc_subsecs(3) == dt2.trunc_subsecs(3)
 }
+
+pub fn incoming_event(
+    label: &str,
+    payload: &serde_json::Value,
+) -> svc_agent::mqtt::IncomingEvent<String> {
+    let properties = serde_json::json!({
+        "agent_id": format!("alpha.conference.{}", super::SVC_AUDIENCE),
+        "connection_version": "v1",
+        "connection_mode": "service",
+        "label": label,
+        "broker_timestamp": "1600000000000",
+        "broker_processing_timestamp": "1600000000000",
+        "broker_initial_processing_timestamp": "1600000000000",
+        "tracking_id": format!("{}.{}.{}", uuid::Uuid::new_v4(), uuid::Uuid::new_v4(), uuid::Uuid::new_v4()),
+        "session_tracking_label": format!("{}.{}", uuid::Uuid::new_v4(), uuid::Uuid::new_v4()),
+    });
+
+    let properties = serde_json::from_value(properties).expect("Failed to build event properties");
+    svc_agent::mqtt::IncomingEvent::new(payload.to_string(), properties)
+}
@@ -20,7 +20,10 @@ use crate::app::{AppContext, Publisher};
 use crate::clients::conference::{ConferenceClient, MockConferenceClient};
 use crate::clients::event::{EventClient, MockEventClient};
 use crate::clients::tq::{MockTqClient, TqClient};
-use crate::config::{Config, StorageConfig, TqAudienceSettings};
+use crate::clients::webhook::{MockWebhookClient, WebhookClient};
+use crate::config::{
+    Config, StorageConfig, TqAudienceSettings, WebhookEndpoint, WebinarRecordingsMode,
+};
 
 use super::agent::TestAgent;
 use super::authz::TestAuthz;
@@ -39,6 +42,7 @@ pub struct TestState {
     conference_client: Arc<MockConferenceClient>,
     event_client: Arc<MockEventClient>,
     tq_client: Arc<MockTqClient>,
+    webhook_client: Arc<MockWebhookClient>,
     authz: Authz,
     turn_host_selector: TurnHostSelector,
 }
@@ -114,6 +118,7 @@ impl TestState {
             conference_client: Arc::new(MockConferenceClient::new()),
             event_client: Arc::new(MockEventClient::new()),
             tq_client: Arc::new(MockTqClient::new()),
+            webhook_client: Arc::new(MockWebhookClient::new()),
             authz: authz.into(),
         }
     }
@@ -133,6 +138,7 @@ impl TestState {
             conference_client: Arc::new(MockConferenceClient::new()),
             event_client: Arc::new(MockEventClient::new()),
             tq_client: Arc::new(MockTqClient::new()),
+            webhook_client: Arc::new(MockWebhookClient::new()),
             authz: authz.into(),
             turn_host_selector: TurnHostSelector::new(&vec1!["turn.example.org".into()]),
         }
@@ -150,6 +156,23 @@ impl TestState {
             });
     }
 
+    pub fn set_webinar_recordings_mode(&mut self, audience: &str, mode: WebinarRecordingsMode) {
+        self.config
+            .webinar_postprocessing
+            .audience_modes
+            .insert(audience.to_owned(), mode);
+    }
+
+    pub fn set_webhook_endpoint(&mut self, audience: &str, url: &str, secret: &str) {
+        self.config.webhooks.endpoints.insert(
+            audience.to_owned(),
+            WebhookEndpoint {
+                url: url.parse().expect("Failed to parse webhook url"),
+                secret: secret.to_owned(),
+            },
+        );
+    }
+
     pub fn set_turn_hosts(&mut self, hosts: &[&str]) {
         let hosts = hosts.into_iter().map(|c| (*c).into()).collect::<Vec<_>>();
         let hosts = Vec1::try_from_vec(hosts).unwrap();
@@ -177,6 +200,10 @@ impl TestState {
     pub fn tq_client_mock(&mut self) -> &mut MockTqClient {
         Arc::get_mut(&mut self.tq_client).expect("Failed to get tq client mock")
     }
+
+    pub fn webhook_client_mock(&mut self) -> &mut MockWebhookClient {
+        Arc::get_mut(&mut self.webhook_client).expect("Failed to get webhook client mock")
+    }
 }
 
 #[async_trait]
@@ -210,6 +237,10 @@ impl AppContext for TestState {
         self.tq_client.as_ref()
     }
 
+    fn webhook_client(&self) -> &dyn WebhookClient {
+        self.webhook_client.as_ref()
+    }
+
     fn authz(&self) -> &Authz {
         &self.authz
     }